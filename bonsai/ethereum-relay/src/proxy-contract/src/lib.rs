@@ -204,10 +204,10 @@ mod tests {
             callback_image_id: Secret::zero().0,
         };
 
-        let fake_receipt = Receipt {
-            inner: InnerReceipt::Fake,
-            journal: call_me_call.encode()[4..4 + 32 + 32].to_vec(),
-        };
+        let fake_receipt = Receipt::new(
+            InnerReceipt::Fake,
+            call_me_call.encode()[4..4 + 32 + 32].to_vec(),
+        );
 
         // Create dummy responses
         let dummy_proof = SnarkProof {
@@ -0,0 +1,277 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum ABI encoding of receipt claims, journals and seals, matching the
+//! types of `IRiscZeroVerifier.sol` and `RiscZeroGroth16Verifier.sol`.
+//!
+//! The verifier contract checks a SNARK over the digest of the claim it is
+//! given, so a claim or seal that is packed differently than the contract
+//! expects fails verification with no indication of what went wrong. These
+//! helpers produce exactly the encoding the contract decodes.
+
+use anyhow::{bail, ensure, Context, Result};
+use bonsai_sdk::alpha::responses::SnarkProof;
+use ethers::{
+    abi::{self, Token},
+    types::U256,
+    utils::id,
+};
+use risc0_zkvm::{
+    sha::{Digest, Impl, Sha256},
+    ExitCode, ReceiptMetadata,
+};
+
+/// The signature of the `verify` function of `IRiscZeroVerifier` taking an
+/// image ID, a post-state digest and a journal digest.
+pub const VERIFY_SIGNATURE: &str = "verify(bytes,bytes32,bytes32,bytes32)";
+
+fn digest_token(digest: &Digest) -> Token {
+    Token::FixedBytes(digest.as_bytes().to_vec())
+}
+
+/// Return the claim as a token of the Solidity `ReceiptMetadata` struct.
+///
+/// Fails if the exit code cannot be represented on-chain: the session limit
+/// is not a valid exit code of a receipt, and the contract stores the user
+/// exit code as a `uint8`.
+pub fn claim_token(meta: &ReceiptMetadata) -> Result<Token> {
+    let (system, user) = match meta.exit_code {
+        ExitCode::Halted(user) => (0u8, user),
+        ExitCode::Paused(user) => (1, user),
+        ExitCode::SystemSplit => (2, 0),
+        ExitCode::SessionLimit => bail!("the session limit is not a valid receipt exit code"),
+    };
+    let user = u8::try_from(user).context("user exit code does not fit in a uint8")?;
+    Ok(Token::Tuple(vec![
+        digest_token(&meta.pre.digest()),
+        digest_token(&meta.post.digest()),
+        Token::Tuple(vec![
+            Token::Uint(U256::from(system)),
+            Token::Uint(U256::from(user)),
+        ]),
+        digest_token(&meta.input),
+        digest_token(&meta.output),
+    ]))
+}
+
+/// ABI-encode the claim as the Solidity `ReceiptMetadata` struct.
+pub fn abi_encode_claim(meta: &ReceiptMetadata) -> Result<Vec<u8>> {
+    Ok(abi::encode(&[claim_token(meta)?]))
+}
+
+/// Return the SHA-256 digest of the journal, as passed as the `journalHash`
+/// of [VERIFY_SIGNATURE].
+///
+/// This is the `output` of the claim when the guest did not commit a
+/// metadata digest or enable the journal chain.
+pub fn journal_digest(journal: &[u8]) -> [u8; 32] {
+    (*Impl::hash_bytes(journal)).into()
+}
+
+/// Return the SNARK proof as a token of the Solidity `Seal` struct.
+pub fn seal_token(proof: &SnarkProof) -> Result<Token> {
+    fn uint_array(words: &[String]) -> Result<Token> {
+        ensure!(words.len() == 2, "SNARK proof is not well formed");
+        Ok(Token::FixedArray(
+            words
+                .iter()
+                .map(|word| {
+                    Ok(Token::Uint(U256::from_str_radix(word, 16).with_context(
+                        || format!("invalid SNARK proof word: {word}"),
+                    )?))
+                })
+                .collect::<Result<_>>()?,
+        ))
+    }
+
+    ensure!(proof.b.len() == 2, "SNARK proof is not well formed");
+    Ok(Token::Tuple(vec![
+        uint_array(&proof.a)?,
+        Token::FixedArray(vec![uint_array(&proof.b[0])?, uint_array(&proof.b[1])?]),
+        uint_array(&proof.c)?,
+    ]))
+}
+
+/// ABI-encode the SNARK proof as the Solidity `Seal` struct, as passed as the
+/// `seal` bytes of the verifier contract.
+pub fn seal_to_calldata(proof: &SnarkProof) -> Result<Vec<u8>> {
+    Ok(abi::encode(&[seal_token(proof)?]))
+}
+
+/// ABI-encode a seal and its claim as the Solidity `Receipt` struct.
+pub fn abi_encode_receipt(seal: &[u8], meta: &ReceiptMetadata) -> Result<Vec<u8>> {
+    Ok(abi::encode(&[Token::Tuple(vec![
+        Token::Bytes(seal.to_vec()),
+        claim_token(meta)?,
+    ])]))
+}
+
+/// Return the calldata of a call to [VERIFY_SIGNATURE], checking the seal
+/// against the given image ID, post-state digest and journal.
+pub fn verify_calldata(
+    proof: &SnarkProof,
+    image_id: impl Into<Digest>,
+    post_state_digest: &Digest,
+    journal: &[u8],
+) -> Result<Vec<u8>> {
+    let mut calldata = id(VERIFY_SIGNATURE).to_vec();
+    calldata.extend(abi::encode(&[
+        Token::Bytes(seal_to_calldata(proof)?),
+        digest_token(&image_id.into()),
+        digest_token(post_state_digest),
+        Token::FixedBytes(journal_digest(journal).to_vec()),
+    ]));
+    Ok(calldata)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::ParamType;
+    use risc0_zkvm::SystemState;
+
+    use super::*;
+
+    // The known-good seal of `RiscZeroGroth16Verifier.t.sol`.
+    const TEST_SEAL: &str = "0ca9d14477c5ac35d4bcc3562f3e2b70f52696d82af496fea385fc4c997ffbf013696413e37acb3c6ec156135f3083fd4238644a237b95e6702f222df5c012082d5e0e6e86f557a2c5385af338f81b75c33b18e93ebf152eaaf3639ee3edc24815070be3b40806c5ed815ea0d58a1eb3d44ae2c56a91a0122dd006ad030129d126d852777e5e418bad8dc374c5743350206f0b0d1d79adfeca47636788aad94d2b4a78e87ac2ae3d7bc291a6f2c08047ea0534936ca3891fc6ff068e0ccd5c2a25f6f43e7ffecaf29e8fc6a872a98c2b7a273d3d3b987e589ca9830def18491f2f27576b784b8de4020edbbc1c2fb560e17f7bf622ca543336b29eefae33d0a5";
+
+    fn test_meta() -> ReceiptMetadata {
+        ReceiptMetadata {
+            pre: SystemState {
+                pc: 0x1000,
+                merkle_root: Digest::from([1, 2, 3, 4, 5, 6, 7, 8]),
+            },
+            post: SystemState {
+                pc: 0x2000,
+                merkle_root: Digest::from([8, 7, 6, 5, 4, 3, 2, 1]),
+            },
+            exit_code: ExitCode::Halted(3),
+            input: Digest::default(),
+            output: Digest::from(journal_digest(b"journal")),
+        }
+    }
+
+    #[test]
+    fn seal_matches_contract() {
+        let words: Vec<String> = hex::decode(TEST_SEAL)
+            .unwrap()
+            .chunks(32)
+            .map(hex::encode)
+            .collect();
+        let proof = SnarkProof {
+            a: words[0..2].to_vec(),
+            b: vec![words[2..4].to_vec(), words[4..6].to_vec()],
+            c: words[6..8].to_vec(),
+            public: vec![],
+        };
+        assert_eq!(hex::encode(seal_to_calldata(&proof).unwrap()), TEST_SEAL);
+
+        let malformed = SnarkProof {
+            b: vec![words[2..4].to_vec()],
+            ..proof
+        };
+        assert!(seal_to_calldata(&malformed).is_err());
+    }
+
+    #[test]
+    fn claim_round_trip() {
+        let meta = test_meta();
+        let encoded = abi_encode_claim(&meta).unwrap();
+        let bytes32 = || ParamType::FixedBytes(32);
+        let uint8 = || ParamType::Uint(8);
+        let decoded = abi::decode(
+            &[ParamType::Tuple(vec![
+                bytes32(),
+                bytes32(),
+                ParamType::Tuple(vec![uint8(), uint8()]),
+                bytes32(),
+                bytes32(),
+            ])],
+            &encoded,
+        )
+        .unwrap();
+        let Token::Tuple(fields) = &decoded[0] else {
+            panic!("claim is not a tuple");
+        };
+        let bytes = |token: &Token| token.clone().into_fixed_bytes().unwrap();
+        let Token::Tuple(exit_code) = &fields[2] else {
+            panic!("exit code is not a tuple");
+        };
+        let exit_code: Vec<u32> = exit_code
+            .iter()
+            .map(|x| x.clone().into_uint().unwrap().as_u32())
+            .collect();
+
+        // Recompute the digest as `ReceiptMetadataLib.digest` does, from the
+        // decoded fields, and check that it matches the claim.
+        let mut packed = Vec::new();
+        packed.extend_from_slice(Impl::hash_bytes(b"risc0.ReceiptMeta").as_bytes());
+        packed.extend(bytes(&fields[3]));
+        packed.extend(bytes(&fields[0]));
+        packed.extend(bytes(&fields[1]));
+        packed.extend(bytes(&fields[4]));
+        packed.extend_from_slice(&(exit_code[0] << 24).to_be_bytes());
+        packed.extend_from_slice(&(exit_code[1] << 24).to_be_bytes());
+        packed.extend_from_slice(&(4u16 << 8).to_be_bytes());
+        assert_eq!(*Impl::hash_bytes(&packed), meta.digest().unwrap());
+
+        let invalid = ReceiptMetadata {
+            exit_code: ExitCode::Halted(256),
+            ..meta.clone()
+        };
+        assert!(abi_encode_claim(&invalid).is_err());
+        let invalid = ReceiptMetadata {
+            exit_code: ExitCode::SessionLimit,
+            ..meta
+        };
+        assert!(abi_encode_claim(&invalid).is_err());
+    }
+
+    #[test]
+    fn receipt_and_verify_calldata() {
+        let meta = test_meta();
+        let seal = hex::decode(TEST_SEAL).unwrap();
+        let encoded = abi_encode_receipt(&seal, &meta).unwrap();
+        // The head of the dynamic struct, the offset and length of the seal,
+        // and the six words of the claim precede the seal itself.
+        assert_eq!(encoded.len(), 32 * 9 + seal.len());
+        assert_eq!(&encoded[32 * 9..], seal.as_slice());
+        assert_eq!(&encoded[32 * 2..32 * 8], abi_encode_claim(&meta).unwrap());
+
+        let words: Vec<String> = seal.chunks(32).map(hex::encode).collect();
+        let proof = SnarkProof {
+            a: words[0..2].to_vec(),
+            b: vec![words[2..4].to_vec(), words[4..6].to_vec()],
+            c: words[6..8].to_vec(),
+            public: vec![],
+        };
+        let calldata =
+            verify_calldata(&proof, meta.pre.digest(), &meta.post.digest(), b"journal").unwrap();
+        assert_eq!(&calldata[..4], &id(VERIFY_SIGNATURE));
+        let decoded = abi::decode(
+            &[
+                ParamType::Bytes,
+                ParamType::FixedBytes(32),
+                ParamType::FixedBytes(32),
+                ParamType::FixedBytes(32),
+            ],
+            &calldata[4..],
+        )
+        .unwrap();
+        assert_eq!(decoded[0], Token::Bytes(seal));
+        assert_eq!(
+            decoded[3],
+            Token::FixedBytes(meta.output.as_bytes().to_vec())
+        );
+    }
+}
@@ -14,5 +14,6 @@
 
 //! An asynchronous Client API.
 
+pub mod abi;
 pub mod client;
 pub mod utils;
@@ -41,10 +41,7 @@ pub(crate) mod tests {
             receipt_url: Some(format!("{}/fake/receipt/path", server.uri())),
         };
 
-        let receipt_data_response = Receipt {
-            journal: vec![],
-            inner: InnerReceipt::Fake,
-        };
+        let receipt_data_response = Receipt::new(InnerReceipt::Fake, vec![]);
 
         let create_snark_res = CreateSessRes {
             uuid: receipt_id.to_string(),
@@ -0,0 +1,90 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Optional IndexedDB cache of receipt verification results.
+//
+// Results are keyed by the digest of the encoded receipt, the image ID it was
+// checked against, and the ID of the verifier, which is derived from the
+// control IDs of the circuits it accepts. A cache hit therefore only ever
+// returns the result of verifying the exact same bytes with the same
+// circuits. Entries written by a verifier with other circuits are never read.
+
+const DB_NAME = "risc0-receipt-cache";
+const STORE = "results";
+
+function toHex(bytes) {
+  return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+function request(req) {
+  return new Promise((resolve, reject) => {
+    req.onsuccess = () => resolve(req.result);
+    req.onerror = () => reject(req.error);
+  });
+}
+
+export class ReceiptCache {
+  constructor(db, wasm) {
+    this.db = db;
+    this.wasm = wasm;
+  }
+
+  // Open the cache. `wasm` is the module generated by wasm-pack, and
+  // `factory` the IndexedDB implementation to use.
+  static async open(wasm, factory = globalThis.indexedDB) {
+    const req = factory.open(DB_NAME, 2);
+    req.onupgradeneeded = () => {
+      // Entries of version 1 were keyed by the crate version of the verifier.
+      if (req.result.objectStoreNames.contains(STORE)) {
+        req.result.deleteObjectStore(STORE);
+      }
+      req.result.createObjectStore(STORE);
+    };
+    return new ReceiptCache(await request(req), wasm);
+  }
+
+  // Return the key of the result of verifying `receipt` against `imageId`.
+  key(receipt, imageId) {
+    return [receipt.digest, imageId, this.wasm.verifierId()].map(toHex).join("");
+  }
+
+  // Verify `receipt` (a `SessionReceipt`) against `imageId`, reusing a cached
+  // result if one exists. Returns whether the receipt is valid. If given,
+  // `onProgress(current, total)` is called after each segment is verified.
+  async validate(receipt, imageId, onProgress) {
+    const key = this.key(receipt, imageId);
+    const store = this.db.transaction(STORE, "readonly").objectStore(STORE);
+    const cached = await request(store.get(key));
+    if (cached !== undefined) {
+      return cached.valid;
+    }
+
+    let valid = true;
+    try {
+      receipt.validate(imageId, onProgress);
+    } catch (e) {
+      valid = false;
+    }
+
+    const tx = this.db.transaction(STORE, "readwrite");
+    await request(tx.objectStore(STORE).put({ valid }, key));
+    return valid;
+  }
+
+  // Remove all cached results.
+  async clear() {
+    const tx = this.db.transaction(STORE, "readwrite");
+    await request(tx.objectStore(STORE).clear());
+  }
+}
@@ -0,0 +1,138 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import assert from "node:assert/strict";
+import { test } from "node:test";
+
+import { ReceiptCache } from "./receipt-cache.js";
+
+// A request that completes asynchronously, as in IndexedDB.
+function fakeRequest(fn) {
+  const req = {};
+  setTimeout(() => {
+    req.result = fn();
+    req.onsuccess();
+  });
+  return req;
+}
+
+// An in-memory stand-in for the parts of IndexedDB used by the cache. The
+// stores persist across calls to `open`, like a browser profile.
+class FakeIndexedDB {
+  constructor() {
+    this.stores = new Map();
+    this.version = 0;
+  }
+
+  open(name, version) {
+    const req = {};
+    const db = {
+      objectStoreNames: { contains: (store) => this.stores.has(store) },
+      createObjectStore: (store) => this.stores.set(store, new Map()),
+      deleteObjectStore: (store) => this.stores.delete(store),
+      transaction: (store) => ({
+        objectStore: () => {
+          const map = this.stores.get(store);
+          return {
+            get: (key) => fakeRequest(() => map.get(key)),
+            put: (value, key) => fakeRequest(() => map.set(key, value)),
+            clear: () => fakeRequest(() => map.clear()),
+          };
+        },
+      }),
+    };
+    setTimeout(() => {
+      req.result = db;
+      if (version > this.version) {
+        this.version = version;
+        req.onupgradeneeded();
+      }
+      req.onsuccess();
+    });
+    return req;
+  }
+}
+
+// A stand-in for the wasm module, with a verifier ID that can be changed.
+function fakeWasm(id) {
+  return { verifierId: () => Uint8Array.of(id) };
+}
+
+// A stand-in for a `SessionReceipt` that counts its verifications.
+function fakeReceipt(digest, valid) {
+  return {
+    digest: Uint8Array.of(digest),
+    validations: 0,
+    validate() {
+      this.validations += 1;
+      if (!valid) {
+        throw new Error("invalid receipt");
+      }
+    },
+  };
+}
+
+const IMAGE_ID = Uint8Array.of(0xaa);
+const OTHER_IMAGE_ID = Uint8Array.of(0xbb);
+
+test("results are cached per receipt and image ID", async () => {
+  const cache = await ReceiptCache.open(fakeWasm(1), new FakeIndexedDB());
+  const good = fakeReceipt(1, true);
+  const bad = fakeReceipt(2, false);
+
+  assert.equal(await cache.validate(good, IMAGE_ID), true);
+  assert.equal(await cache.validate(good, IMAGE_ID), true);
+  assert.equal(good.validations, 1);
+
+  assert.equal(await cache.validate(bad, IMAGE_ID), false);
+  assert.equal(await cache.validate(bad, IMAGE_ID), false);
+  assert.equal(bad.validations, 1);
+
+  await cache.validate(good, OTHER_IMAGE_ID);
+  assert.equal(good.validations, 2);
+});
+
+test("results of a verifier with other control IDs are not reused", async () => {
+  const factory = new FakeIndexedDB();
+  const receipt = fakeReceipt(1, true);
+  const cache = await ReceiptCache.open(fakeWasm(1), factory);
+  await cache.validate(receipt, IMAGE_ID);
+
+  const upgraded = await ReceiptCache.open(fakeWasm(2), factory);
+  await upgraded.validate(receipt, IMAGE_ID);
+  assert.equal(receipt.validations, 2);
+
+  // The results of both verifiers are kept.
+  await cache.validate(receipt, IMAGE_ID);
+  await upgraded.validate(receipt, IMAGE_ID);
+  assert.equal(receipt.validations, 2);
+});
+
+test("entries keyed by the crate version are dropped", async () => {
+  const factory = new FakeIndexedDB();
+  factory.version = 1;
+  factory.stores.set("results", new Map([["stale", { valid: true, version: "0.1.0" }]]));
+
+  await ReceiptCache.open(fakeWasm(1), factory);
+  assert.equal(factory.stores.get("results").size, 0);
+});
+
+test("clear removes all results", async () => {
+  const cache = await ReceiptCache.open(fakeWasm(1), new FakeIndexedDB());
+  const receipt = fakeReceipt(1, true);
+  await cache.validate(receipt, IMAGE_ID);
+  await cache.clear();
+  await cache.validate(receipt, IMAGE_ID);
+  assert.equal(receipt.validations, 2);
+});
@@ -12,4 +12,111 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-// This is intentionally blank, there may be content here later.
+use js_sys::{Array, Function, Uint8Array};
+use risc0_zkvm::{
+    recursion::valid_control_ids,
+    sha::{Digest, Impl, Sha256},
+    Receipt, BLAKE2B_CONTROL_ID, SHA256_CONTROL_ID,
+};
+use wasm_bindgen::prelude::*;
+
+/// The identity of this verifier: the SHA-256 digest of the control IDs of
+/// the circuits whose receipts it accepts.
+///
+/// Receipts valid under one set of circuits may not be valid under another,
+/// so cached verification results produced by a verifier with a different ID
+/// should not be trusted.
+#[wasm_bindgen(js_name = verifierId)]
+pub fn verifier_id() -> Vec<u8> {
+    let mut ids: Vec<u8> = SHA256_CONTROL_ID
+        .iter()
+        .chain(BLAKE2B_CONTROL_ID.iter())
+        .flat_map(|id| id.as_bytes().to_vec())
+        .collect();
+    for id in valid_control_ids() {
+        ids.extend_from_slice(id.as_bytes());
+    }
+    Impl::hash_bytes(&ids).as_bytes().to_vec()
+}
+
+/// A receipt deserialized from its bincode encoding, for verification from JS.
+#[wasm_bindgen]
+pub struct SessionReceipt {
+    receipt: Receipt,
+    digest: Digest,
+}
+
+#[wasm_bindgen]
+impl SessionReceipt {
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<SessionReceipt, JsError> {
+        Ok(Self {
+            receipt: bincode::deserialize(bytes)?,
+            digest: *Impl::hash_bytes(bytes),
+        })
+    }
+
+    /// The journal committed by the guest.
+    #[wasm_bindgen(getter)]
+    pub fn journal(&self) -> Vec<u8> {
+        self.receipt.journal.clone()
+    }
+
+    /// The SHA-256 digest of the encoded receipt, used to key cached results.
+    #[wasm_bindgen(getter)]
+    pub fn digest(&self) -> Vec<u8> {
+        self.digest.as_bytes().to_vec()
+    }
+
+    /// Verify this receipt against the given image ID.
+    ///
+    /// If given, `on_progress` is called as `onProgress(current, total)` after
+    /// each segment is verified, e.g. to drive a progress bar while a receipt
+    /// of many segments is verified.
+    pub fn validate(&self, image_id: &[u8], on_progress: Option<Function>) -> Result<(), JsError> {
+        let image_id = Digest::try_from(image_id)?;
+        Ok(self
+            .receipt
+            .verify_with_progress(image_id, |current, total| {
+                if let Some(on_progress) = &on_progress {
+                    // Errors thrown by the callback do not affect verification.
+                    let _ = on_progress.call2(
+                        &JsValue::NULL,
+                        &JsValue::from(current as u32),
+                        &JsValue::from(total as u32),
+                    );
+                }
+            })?)
+    }
+
+    /// Verify this receipt against any of the given image IDs, returning the
+    /// index of the one that matched.
+    ///
+    /// This allows accepting receipts from several versions of a guest, e.g.
+    /// during a rolling upgrade. The image ID the receipt claims is looked up
+    /// among the given IDs, and the seal is verified once, against that ID.
+    #[wasm_bindgen(js_name = validateAny)]
+    pub fn validate_any(&self, image_ids: Array) -> Result<usize, JsError> {
+        let image_ids = image_ids
+            .iter()
+            .enumerate()
+            .map(|(idx, image_id)| {
+                Digest::try_from(Uint8Array::new(&image_id).to_vec().as_slice())
+                    .map_err(|err| JsError::new(&format!("image ID {idx}: {err}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if image_ids.is_empty() {
+            return Err(JsError::new("no image IDs given"));
+        }
+        // The pre-state digest is read from the seal, and is only trusted once
+        // the receipt verifies against it.
+        let pre = self.receipt.inner.get_metadata()?.pre.digest();
+        let idx = image_ids.iter().position(|id| *id == pre).ok_or_else(|| {
+            JsError::new(&format!(
+                "receipt image ID {pre} does not match any of the image IDs"
+            ))
+        })?;
+        self.receipt.verify(pre)?;
+        Ok(idx)
+    }
+}
@@ -12,8 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use risc0_zkvm::Receipt;
-use risc0_zkvm_receipts::{FIB_ID, FIB_RECEIPT};
+use browser_verify::SessionReceipt;
+use js_sys::{Array, Function, Uint8Array};
+use risc0_zkvm::{sha::Digest, Receipt};
+use risc0_zkvm_receipts::{FIB_ID, FIB_RECEIPT, FIB_RECEIPT_BAD_JOURNAL, FIB_RECEIPT_BAD_SEAL};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
 
 wasm_bindgen_test_configure!(run_in_browser);
@@ -24,3 +27,77 @@ fn test_verify() {
     let receipt: Receipt = bincode::deserialize(FIB_RECEIPT).unwrap();
     receipt.verify(FIB_ID).unwrap();
 }
+
+#[wasm_bindgen_test]
+fn test_verify_corrupted() {
+    for bytes in [FIB_RECEIPT_BAD_SEAL, FIB_RECEIPT_BAD_JOURNAL] {
+        let receipt = SessionReceipt::new(bytes).unwrap();
+        assert!(receipt
+            .validate(Digest::from(FIB_ID).as_bytes(), None)
+            .is_err());
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_validate_any() {
+    let receipt = SessionReceipt::new(FIB_RECEIPT).unwrap();
+    let fib_id = Digest::from(FIB_ID);
+    let other_id = Digest::default();
+
+    let image_ids = Array::of2(
+        &Uint8Array::from(other_id.as_bytes()),
+        &Uint8Array::from(fib_id.as_bytes()),
+    );
+    assert_eq!(receipt.validate_any(image_ids).unwrap(), 1);
+
+    // Malformed image IDs are reported by index.
+    let image_ids = Array::of2(
+        &Uint8Array::from(fib_id.as_bytes()),
+        &Uint8Array::from(&[0u8; 4][..]),
+    );
+    let err = receipt.validate_any(image_ids).unwrap_err();
+    let message = String::from(js_sys::Error::from(JsValue::from(err)).message());
+    assert!(message.contains("image ID 1: "), "{message}");
+
+    // A receipt of another image is rejected.
+    let image_ids = Array::of1(&Uint8Array::from(other_id.as_bytes()));
+    let err = receipt.validate_any(image_ids).unwrap_err();
+    let message = String::from(js_sys::Error::from(JsValue::from(err)).message());
+    assert!(message.contains("does not match any"), "{message}");
+
+    assert!(receipt.validate_any(Array::new()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_validate_progress() {
+    let receipt = SessionReceipt::new(FIB_RECEIPT).unwrap();
+    let segments = bincode::deserialize::<Receipt>(FIB_RECEIPT)
+        .unwrap()
+        .inner
+        .flat()
+        .len();
+
+    let calls = Array::new();
+    let on_progress = {
+        let calls = calls.clone();
+        Closure::<dyn FnMut(u32, u32)>::new(move |current: u32, total: u32| {
+            calls.push(&Array::of2(&current.into(), &total.into()));
+        })
+    };
+    let on_progress: &Function = on_progress.as_ref().unchecked_ref();
+    receipt
+        .validate(Digest::from(FIB_ID).as_bytes(), Some(on_progress.clone()))
+        .unwrap();
+
+    assert_eq!(calls.length() as usize, segments);
+    let last = Array::from(&calls.get(calls.length() - 1));
+    assert_eq!(last.get(0).as_f64(), Some(segments as f64));
+    assert_eq!(last.get(1).as_f64(), Some(segments as f64));
+}
+
+#[wasm_bindgen_test]
+fn test_verifier_id() {
+    let id = browser_verify::verifier_id();
+    assert_eq!(id.len(), 32);
+    assert_eq!(id, browser_verify::verifier_id());
+}
@@ -19,9 +19,6 @@ use risc0_build::GuestOptions;
 fn main() {
     risc0_build::embed_methods_with_options(HashMap::from([(
         "image_crop",
-        GuestOptions {
-            features: vec![],
-            std: true,
-        },
+        GuestOptions::default(),
     )]));
 }
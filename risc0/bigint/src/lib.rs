@@ -0,0 +1,146 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../README.md")]
+#![no_std]
+#![deny(missing_docs)]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::ptr::null_mut;
+
+use risc0_zkvm_platform::syscall::{bigint, nr::SYS_BIGINT_DIVREM, sys_bigint, syscall_2};
+
+/// Width, in words, of the operands of [modmul_384].
+pub const WIDTH_384_WORDS: usize = 12;
+
+/// Width, in words, of the operands of [modmul_512].
+pub const WIDTH_512_WORDS: usize = 16;
+
+// The width, in words, of the limbs multiplied by the accelerator. The
+// product of two limbs fits in the 256-bit result.
+const LIMB_WORDS: usize = bigint::WIDTH_WORDS / 2;
+
+/// Compute `x * y % modulus` for 384-bit integers.
+///
+/// # Panics
+///
+/// Panics if `x` or `y` is not less than `modulus`.
+pub fn modmul_384(
+    x: &[u32; WIDTH_384_WORDS],
+    y: &[u32; WIDTH_384_WORDS],
+    modulus: &[u32; WIDTH_384_WORDS],
+) -> [u32; WIDTH_384_WORDS] {
+    let mut result = [0u32; WIDTH_384_WORDS];
+    modmul(x, y, modulus, &mut result);
+    result
+}
+
+/// Compute `x * y % modulus` for 512-bit integers.
+///
+/// # Panics
+///
+/// Panics if `x` or `y` is not less than `modulus`.
+pub fn modmul_512(
+    x: &[u32; WIDTH_512_WORDS],
+    y: &[u32; WIDTH_512_WORDS],
+    modulus: &[u32; WIDTH_512_WORDS],
+) -> [u32; WIDTH_512_WORDS] {
+    let mut result = [0u32; WIDTH_512_WORDS];
+    modmul(x, y, modulus, &mut result);
+    result
+}
+
+fn modmul(x: &[u32], y: &[u32], modulus: &[u32], result: &mut [u32]) {
+    assert!(
+        less_than(x, modulus) && less_than(y, modulus),
+        "bigint operands must be less than the modulus"
+    );
+    let width = modulus.len();
+    let product = mul_wide(x, y);
+
+    let mut request: Vec<u32> = Vec::with_capacity(3 * width);
+    request.extend_from_slice(&product);
+    request.extend_from_slice(modulus);
+    let mut response = vec![0u32; 2 * width];
+    let request: &[u8] = bytemuck::cast_slice(&request);
+    // SAFETY: the response is a buffer of the given number of words.
+    unsafe {
+        syscall_2(
+            SYS_BIGINT_DIVREM,
+            response.as_mut_ptr(),
+            response.len(),
+            request.as_ptr() as u32,
+            request.len() as u32,
+        )
+    };
+    let (quotient, remainder) = response.split_at(width);
+
+    // The host is untrusted: check that product = quotient * modulus +
+    // remainder, with the remainder reduced.
+    assert!(
+        less_than(remainder, modulus),
+        "host returned an unreduced remainder"
+    );
+    let mut check = mul_wide(quotient, modulus);
+    let carry = add_at(&mut check, 0, remainder);
+    assert!(
+        carry == 0 && check == product,
+        "host returned a wrong quotient"
+    );
+    result.copy_from_slice(remainder);
+}
+
+// Multiply `x` and `y`, which have the same width, into an integer of twice
+// their width, using the accelerator for each pair of limbs.
+fn mul_wide(x: &[u32], y: &[u32]) -> Vec<u32> {
+    let zero = [0u32; bigint::WIDTH_WORDS];
+    let mut out = vec![0u32; 2 * x.len()];
+    for (i, x_limb) in x.chunks(LIMB_WORDS).enumerate() {
+        let mut a = [0u32; bigint::WIDTH_WORDS];
+        a[..x_limb.len()].copy_from_slice(x_limb);
+        for (j, y_limb) in y.chunks(LIMB_WORDS).enumerate() {
+            let mut b = [0u32; bigint::WIDTH_WORDS];
+            b[..y_limb.len()].copy_from_slice(y_limb);
+            let mut product = [0u32; bigint::WIDTH_WORDS];
+            // SAFETY: all arguments are arrays of the width of the
+            // accelerator. A zero modulus selects a plain multiplication,
+            // which cannot overflow for operands of one limb each.
+            unsafe { sys_bigint(&mut product, bigint::OP_MULTIPLY, &a, &b, &zero) };
+            add_at(&mut out, (i + j) * LIMB_WORDS, &product);
+        }
+    }
+    out
+}
+
+// Add `value` to `out`, starting at word `offset`, and return the carry out
+// of the most significant word.
+fn add_at(out: &mut [u32], offset: usize, value: &[u32]) -> u32 {
+    let mut carry = 0u64;
+    for (idx, word) in out.iter_mut().enumerate().skip(offset) {
+        let addend = value.get(idx - offset).copied().unwrap_or(0);
+        if addend == 0 && carry == 0 && idx - offset >= value.len() {
+            break;
+        }
+        let sum = *word as u64 + addend as u64 + carry;
+        *word = sum as u32;
+        carry = sum >> 32;
+    }
+    carry as u32
+}
+
+fn less_than(lhs: &[u32], rhs: &[u32]) -> bool {
+    lhs.iter().rev().cmp(rhs.iter().rev()).is_lt()
+}
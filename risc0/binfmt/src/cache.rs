@@ -0,0 +1,107 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+use anyhow::Result;
+use risc0_zkp::core::{
+    digest::Digest,
+    hash::sha::{cpu::Impl, Sha256},
+};
+use risc0_zkvm_platform::{memory::MEM_SIZE, PAGE_SIZE};
+
+use crate::{elf::Program, image::MemoryImage};
+
+/// The number of images kept by [MemoryImage::from_elf_cached].
+pub const IMAGE_CACHE_CAPACITY: usize = 16;
+
+struct CacheEntry {
+    elf_digest: Digest,
+    image_id: Digest,
+    image: MemoryImage,
+}
+
+/// Least recently used images, with the most recently used at the back.
+static CACHE: Mutex<VecDeque<CacheEntry>> = Mutex::new(VecDeque::new());
+
+impl MemoryImage {
+    /// Construct the initial memory image for the given ELF binary, reusing a
+    /// previously constructed image if the same ELF was loaded recently.
+    ///
+    /// Loading an image parses the ELF and hashes every page, which dominates
+    /// the cost of starting a job for small guests. Images are cached in a
+    /// process-wide LRU of [IMAGE_CACHE_CAPACITY] entries, keyed by the
+    /// SHA-256 digest of the ELF.
+    pub fn from_elf_cached(elf: &[u8]) -> Result<Self> {
+        let elf_digest = *Impl::hash_bytes(elf);
+        let mut cache = CACHE.lock().unwrap();
+        if let Some(idx) = cache.iter().position(|x| x.elf_digest == elf_digest) {
+            let entry = cache.remove(idx).unwrap();
+            let image = entry.image.clone();
+            cache.push_back(entry);
+            return Ok(image);
+        }
+        drop(cache);
+
+        let program = Program::load_elf(elf, MEM_SIZE as u32)?;
+        let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
+        let entry = CacheEntry {
+            elf_digest,
+            image_id: image.compute_id(),
+            image: image.clone(),
+        };
+
+        let mut cache = CACHE.lock().unwrap();
+        cache.retain(|x| x.elf_digest != elf_digest);
+        cache.push_back(entry);
+        if cache.len() > IMAGE_CACHE_CAPACITY {
+            cache.pop_front();
+        }
+        Ok(image)
+    }
+
+    /// Remove the image with the given ID from the cache used by
+    /// [MemoryImage::from_elf_cached]. Returns whether it was cached.
+    pub fn evict_cached(image_id: &Digest) -> bool {
+        let mut cache = CACHE.lock().unwrap();
+        let len = cache.len();
+        cache.retain(|x| x.image_id != *image_id);
+        cache.len() != len
+    }
+
+    /// Remove all images from the cache used by [MemoryImage::from_elf_cached].
+    pub fn clear_cached() {
+        CACHE.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm_methods::{MULTI_TEST_ELF, MULTI_TEST_ID};
+    use test_log::test;
+
+    use crate::MemoryImage;
+
+    #[test]
+    fn from_elf_cached() {
+        let image = MemoryImage::from_elf_cached(MULTI_TEST_ELF).unwrap();
+        assert_eq!(image.compute_id(), MULTI_TEST_ID.into());
+
+        let cached = MemoryImage::from_elf_cached(MULTI_TEST_ELF).unwrap();
+        assert_eq!(cached.compute_id(), MULTI_TEST_ID.into());
+
+        assert!(MemoryImage::evict_cached(&MULTI_TEST_ID.into()));
+        assert!(!MemoryImage::evict_cached(&MULTI_TEST_ID.into()));
+    }
+}
@@ -80,3 +80,17 @@ impl Program {
         Ok(Program { entry, image })
     }
 }
+
+/// Read the contents of the named section of an ELF file, or `None` if the
+/// file has no such section.
+pub fn read_elf_section(input: &[u8], name: &str) -> Result<Option<Vec<u8>>> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(input)?;
+    let Some(header) = elf.section_header_by_name(name)? else {
+        return Ok(None);
+    };
+    let (data, compression) = elf.section_data(&header)?;
+    if compression.is_some() {
+        bail!("Compressed section {name} is not supported");
+    }
+    Ok(Some(data.to_vec()))
+}
@@ -14,7 +14,7 @@
 
 use std::collections::{BTreeMap, VecDeque};
 
-use anyhow::Result;
+use anyhow::{bail, ensure, Result};
 use risc0_zkp::core::{
     digest::Digest,
     hash::sha::{cpu::Impl, Sha256, BLOCK_BYTES, SHA256_INIT},
@@ -249,6 +249,12 @@ impl MemoryImage {
             .unwrap_or_else(|| vec![0; self.info.page_size as usize])
     }
 
+    /// Return true if the page specified by page_idx has been stored in this
+    /// image, even if it only holds zeros.
+    pub fn has_page(&self, page_idx: u32) -> bool {
+        self.pages.contains_key(&page_idx)
+    }
+
     /// Writes the given byte array in this memory image at the given
     /// address.  The caller is responsible for ensuring the bytes do
     /// not overlap a page boundry.
@@ -358,6 +364,73 @@ impl MemoryImage {
     pub fn compute_id(&self) -> Digest {
         compute_image_id(&self.compute_root_hash(), self.pc)
     }
+
+    /// Construct a [PageProof] for the page containing `addr`.
+    pub fn page_proof(&self, addr: u32) -> PageProof {
+        let first_idx = self.info.get_page_index(addr);
+        let mut page_idx = first_idx;
+        let mut pages = vec![self.load_page(page_idx)];
+        while page_idx < self.info.root_idx {
+            let entry_addr = self.info.get_page_entry_addr(page_idx);
+            page_idx = self.info.get_page_index(entry_addr);
+            pages.push(self.load_page(page_idx));
+        }
+        PageProof {
+            page_idx: first_idx,
+            pages,
+        }
+    }
+}
+
+/// A proof that a page is part of a [MemoryImage] with a given merkle root.
+///
+/// The proof holds the page itself, followed by each page of the page table on
+/// the path from that page to the root page.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PageProof {
+    /// The index of the proven page.
+    pub page_idx: u32,
+
+    /// The proven page, followed by the page table pages up to and including
+    /// the root page.
+    pub pages: Vec<Vec<u8>>,
+}
+
+impl PageProof {
+    /// Verify this proof against the merkle root of a [MemoryImage] with the
+    /// given page table, returning the contents of the proven page.
+    pub fn verify(&self, info: &PageTableInfo, merkle_root: &Digest) -> Result<&[u8]> {
+        let mut page_idx = self.page_idx;
+        for (i, page) in self.pages.iter().enumerate() {
+            ensure!(
+                page.len() == info.page_size as usize,
+                "Invalid page size: {}",
+                page.len()
+            );
+            if page_idx >= info.root_idx {
+                ensure!(page_idx == info.root_idx, "Invalid page index: {page_idx}");
+                ensure!(i == self.pages.len() - 1, "Page proof is too long");
+                let root =
+                    hash_page_bytes(&page[..(info.root_addr - info.root_page_addr) as usize]);
+                ensure!(root == *merkle_root, "Invalid root hash: {root}");
+                return Ok(&self.pages[0]);
+            }
+            let entry_addr = info.get_page_entry_addr(page_idx);
+            page_idx = info.get_page_index(entry_addr);
+            let Some(parent) = self.pages.get(i + 1) else {
+                bail!("Page proof does not reach the root page");
+            };
+            let offset = (entry_addr - info.get_page_addr(page_idx)) as usize;
+            let entry = parent
+                .get(offset..offset + DIGEST_BYTES)
+                .ok_or_else(|| anyhow::anyhow!("Invalid page size: {}", parent.len()))?;
+            ensure!(
+                entry == hash_page_bytes(page).as_bytes(),
+                "Invalid page table entry for page {page_idx}"
+            );
+        }
+        bail!("Page proof is empty")
+    }
 }
 
 fn hash_page_bytes(page: &[u8]) -> Digest {
@@ -373,6 +446,7 @@ fn hash_page_bytes(page: &[u8]) -> Digest {
 
 #[cfg(test)]
 mod tests {
+    use risc0_zkp::core::digest::Digest;
     use risc0_zkvm_methods::MULTI_TEST_ELF;
     use risc0_zkvm_platform::{
         memory::{MEM_SIZE, PAGE_TABLE, STACK_TOP, SYSTEM, TEXT_START},
@@ -380,7 +454,7 @@ mod tests {
     };
     use test_log::test;
 
-    use crate::{elf::Program, image::PageTableInfo, tagged_struct, MemoryImage};
+    use crate::{elf::Program, image::PageTableInfo, tagged_struct, MemoryImage, PageProof};
 
     fn page_table_size(max_mem: u32, page_size: u32) -> u32 {
         PageTableInfo::new(max_mem, page_size)._page_table_size
@@ -403,6 +477,27 @@ mod tests {
         image.check(image.info.root_page_addr).unwrap();
     }
 
+    #[test]
+    fn page_proof() {
+        const PAGE_SIZE: u32 = 1024;
+        let program = Program::load_elf(MULTI_TEST_ELF, MEM_SIZE as u32).unwrap();
+        let image = MemoryImage::new(&program, PAGE_SIZE).unwrap();
+        let root = image.compute_root_hash();
+
+        for addr in [TEXT_START, STACK_TOP, SYSTEM.start() as u32] {
+            let proof = image.page_proof(addr);
+            let page = proof.verify(&image.info, &root).unwrap();
+            assert_eq!(page, image.load_page(image.info.get_page_index(addr)));
+        }
+
+        let mut proof = image.page_proof(TEXT_START);
+        proof.pages[0][0] ^= 1;
+        proof.verify(&image.info, &root).unwrap_err();
+
+        let proof: PageProof = image.page_proof(TEXT_START);
+        proof.verify(&image.info, &Digest::default()).unwrap_err();
+    }
+
     #[test]
     fn page_table_info() {
         const PAGE_SIZE_1K: u32 = 1024;
@@ -14,10 +14,15 @@
 
 //! Manages formatted binaries used by the RISC Zero zkVM
 
+mod cache;
 mod elf;
 mod image;
 
 pub use crate::{
-    elf::Program,
-    image::{read_sha_halfs, tagged_struct, write_sha_halfs, MemoryImage, SystemState},
+    cache::IMAGE_CACHE_CAPACITY,
+    elf::{read_elf_section, Program},
+    image::{
+        read_sha_halfs, tagged_struct, write_sha_halfs, MemoryImage, PageProof, PageTableInfo,
+        SystemState,
+    },
 };
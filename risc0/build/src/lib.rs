@@ -36,6 +36,8 @@ use sha2::{Digest as ShaDigest, Sha256};
 use tempfile::tempdir_in;
 use zip::ZipArchive;
 
+mod size;
+
 const TARGET_JSON: &str = include_str!("../riscv32im-risc0-zkvm-elf.json");
 
 #[derive(Debug, Deserialize)]
@@ -85,6 +87,18 @@ impl Risc0Method {
         image.compute_id()
     }
 
+    fn write_size_report(&self) {
+        let elf = fs::read(&self.elf_path).unwrap();
+        let report = size::size_report(&self.name, &elf).unwrap();
+        let report_path = self.elf_path.with_extension("size.txt");
+        fs::write(&report_path, &report).unwrap();
+        println!("{report}");
+        println!(
+            "cargo:warning=Wrote size report to {}",
+            report_path.display()
+        );
+    }
+
     fn rust_def(&self) -> String {
         let elf_path = self.elf_path.display();
 
@@ -351,6 +365,7 @@ fn build_guest_package<P>(
     guest_build_env: &GuestBuildEnv,
     features: Vec<String>,
     std: bool,
+    optimize_size: bool,
 ) where
     P: AsRef<Path>,
 {
@@ -398,31 +413,35 @@ fn build_guest_package<P>(
 
     println!("Using rust standard library root: {}", risc0_standard_lib);
 
+    let text_arg = format!("link-arg=-Ttext=0x{:08X}", memory::TEXT_START);
+    let mut rustflags = vec![
+        // Replace atomic ops with nonatomic versions since the guest is single threaded.
+        "-C",
+        "passes=loweratomic",
+        // Remap absolute pathnames in compiled ELFs for builds that are more reproducible.
+        "-Z",
+        "remap-cwd-prefix=.",
+        // Specify where to start loading the program in
+        // memory.  The clang linker understands the same
+        // command line arguments as the GNU linker does; see
+        // https://ftp.gnu.org/old-gnu/Manuals/ld-2.9.1/html_mono/ld.html#SEC3
+        // for details.
+        "-C",
+        &text_arg,
+        // Apparently not having an entry point is only a linker warning(!), so
+        // error out in this case.
+        "-C",
+        "link-arg=--fatal-warnings",
+    ];
+    if optimize_size {
+        for arg in size::LINK_ARGS {
+            rustflags.extend(["-C", *arg]);
+        }
+    }
+
     let mut cmd = Command::new(cargo);
     let mut child = cmd
-        .env(
-            "CARGO_ENCODED_RUSTFLAGS",
-            [
-                // Replace atomic ops with nonatomic versions since the guest is single threaded.
-                "-C",
-                "passes=loweratomic",
-                // Remap absolute pathnames in compiled ELFs for builds that are more reproducible.
-                "-Z",
-                "remap-cwd-prefix=.",
-                // Specify where to start loading the program in
-                // memory.  The clang linker understands the same
-                // command line arguments as the GNU linker does; see
-                // https://ftp.gnu.org/old-gnu/Manuals/ld-2.9.1/html_mono/ld.html#SEC3
-                // for details.
-                "-C",
-                &format!("link-arg=-Ttext=0x{:08X}", memory::TEXT_START),
-                // Apparently not having an entry point is only a linker warning(!), so
-                // error out in this case.
-                "-C",
-                "link-arg=--fatal-warnings",
-            ]
-            .join("\x1f"),
-        )
+        .env("CARGO_ENCODED_RUSTFLAGS", rustflags.join("\x1f"))
         .env("__CARGO_TESTS_ONLY_SRC_ROOT", risc0_standard_lib)
         .args(args)
         .stderr(Stdio::piped())
@@ -467,12 +486,34 @@ fn build_guest_package<P>(
 
 /// Options defining how to embed a guest package in
 /// [`embed_methods_with_options`].
+///
+/// Options are built from [GuestOptions::default] with the `with_*` setters,
+/// so that new options can be added without breaking callers:
+///
+/// ```
+/// use risc0_build::GuestOptions;
+///
+/// let options = GuestOptions::default()
+///     .with_std(false)
+///     .with_optimize_size(true);
+/// ```
+///
+/// This struct is `#[non_exhaustive]` as of this release, which is a breaking
+/// change: struct literals, including those ending in `..Default::default()`,
+/// no longer compile outside this crate and must use the setters instead.
+#[non_exhaustive]
 pub struct GuestOptions {
     /// Features for cargo to build the guest with.
     pub features: Vec<String>,
 
     /// Enable standard library support
     pub std: bool,
+
+    /// Link with dead code elimination and debug info stripped, and write a
+    /// report of the size each crate contributes to the guest image next to
+    /// the ELF. Guest image size drives the number of cycles spent paging in
+    /// memory.
+    pub optimize_size: bool,
 }
 
 impl Default for GuestOptions {
@@ -480,10 +521,33 @@ impl Default for GuestOptions {
         GuestOptions {
             features: vec![],
             std: true,
+            optimize_size: false,
         }
     }
 }
 
+impl GuestOptions {
+    /// Return these [GuestOptions] with the given features.
+    pub fn with_features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Return these [GuestOptions] with standard library support enabled or
+    /// disabled.
+    pub fn with_std(mut self, std: bool) -> Self {
+        self.std = std;
+        self
+    }
+
+    /// Return these [GuestOptions] with size optimization enabled or
+    /// disabled, see [GuestOptions::optimize_size].
+    pub fn with_optimize_size(mut self, optimize_size: bool) -> Self {
+        self.optimize_size = optimize_size;
+        self
+    }
+}
+
 /// Embeds methods built for RISC-V for use by host-side dependencies.
 /// Specify custom options for a guest package by defining its [GuestOptions].
 /// See [embed_methods].
@@ -528,9 +592,14 @@ pub fn embed_methods_with_options(mut guest_pkg_to_options: HashMap<&str, GuestO
             &guest_build_env,
             guest_options.features,
             guest_options.std,
+            guest_options.optimize_size,
         );
 
         for method in guest_methods(&guest_pkg, &guest_dir) {
+            if guest_options.optimize_size && method.elf_path.exists() {
+                method.write_size_report();
+            }
+
             methods_file
                 .write_all(method.rust_def().as_bytes())
                 .unwrap();
@@ -0,0 +1,85 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Size reporting for guest ELFs built with [GuestOptions::optimize_size].
+//!
+//! [GuestOptions::optimize_size]: crate::GuestOptions::optimize_size
+
+use std::{collections::HashMap, fmt::Write};
+
+use elf::{endian::LittleEndian, ElfBytes};
+
+/// Linker arguments used to drop unreachable code and data from a guest.
+///
+/// Sections that are never referenced from the entry point are garbage
+/// collected, identical functions are folded together, and debug info is
+/// stripped since it is never loaded into the zkVM.
+pub(crate) const LINK_ARGS: &[&str] = &[
+    "link-arg=--gc-sections",
+    "link-arg=--icf=all",
+    "link-arg=--strip-debug",
+];
+
+/// Returns the name of the crate a symbol belongs to, based on its
+/// legacy-mangled name. Unmangled symbols are attributed to `[other]`.
+fn crate_name(symbol: &str) -> &str {
+    let Some(rest) = symbol.strip_prefix("_ZN") else {
+        return "[other]";
+    };
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let Ok(len) = rest[..digits].parse::<usize>() else {
+        return "[other]";
+    };
+    match rest.get(digits..digits + len) {
+        Some(name) => name,
+        None => "[other]",
+    }
+}
+
+/// Builds a report of how many bytes each crate contributes to the loadable
+/// part of the given ELF, largest first.
+pub(crate) fn size_report(name: &str, input: &[u8]) -> Result<String, elf::ParseError> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(input)?;
+
+    let mut loaded = 0;
+    if let Some(headers) = elf.section_headers() {
+        loaded = headers
+            .iter()
+            .filter(|x| x.sh_flags & elf::abi::SHF_ALLOC as u64 != 0)
+            .map(|x| x.sh_size)
+            .sum();
+    }
+
+    let mut crates: HashMap<&str, u64> = HashMap::new();
+    if let Some((symbols, strtab)) = elf.symbol_table()? {
+        for symbol in symbols.iter().filter(|x| x.st_size != 0) {
+            let symbol_name = strtab.get(symbol.st_name as usize)?;
+            *crates.entry(crate_name(symbol_name)).or_default() += symbol.st_size;
+        }
+    }
+    let mut crates: Vec<_> = crates.into_iter().collect();
+    crates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut report = String::new();
+    writeln!(report, "{name}: {loaded} bytes loaded").unwrap();
+    for (krate, size) in crates {
+        let percent = if loaded == 0 {
+            0.0
+        } else {
+            size as f64 * 100.0 / loaded as f64
+        };
+        writeln!(report, "{size:>10} {percent:>5.1}% {krate}").unwrap();
+    }
+    Ok(report)
+}
@@ -27,5 +27,23 @@ fn main() {
         RisczeroCmd::New(new) => {
             new.run();
         }
+        RisczeroCmd::Image(image) => {
+            if let Err(err) = image.run() {
+                eprintln!("{err:#}");
+                std::process::exit(1);
+            }
+        }
+        RisczeroCmd::Receipt(receipt) => {
+            if let Err(err) = receipt.run() {
+                eprintln!("{err:#}");
+                std::process::exit(1);
+            }
+        }
+        RisczeroCmd::Deploy(deploy) => {
+            if let Err(err) = deploy.run() {
+                eprintln!("{err:#}");
+                std::process::exit(1);
+            }
+        }
     }
 }
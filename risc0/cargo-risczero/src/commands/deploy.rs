@@ -0,0 +1,165 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use bonsai_sdk::alpha::{Client, SdkErr};
+use clap::Parser;
+use risc0_zkvm::{MemoryImage, Program, MEM_SIZE, PAGE_SIZE};
+
+#[derive(Parser)]
+/// `cargo risczero deploy`
+pub struct DeployCommand {
+    /// The guest ELF file to deploy.
+    pub elf: PathBuf,
+
+    /// The URL of the remote proving service.
+    #[arg(long, env = "BONSAI_API_URL")]
+    pub endpoint: String,
+
+    /// The API key for the remote proving service.
+    #[arg(long, env = "BONSAI_API_KEY", hide_env_values = true)]
+    pub api_key: String,
+
+    /// The lockfile recording the image ID deployed for each guest.
+    #[arg(long, default_value = "risc0.lock")]
+    pub lockfile: PathBuf,
+
+    /// The name of the guest in the lockfile. Defaults to the ELF file name.
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Replace a pinned image ID that differs from the local image ID.
+    #[arg(long)]
+    pub update: bool,
+}
+
+/// Image IDs of deployed guests, keyed by guest name.
+///
+/// The lockfile contains one `name = "image id"` line per guest, in hex, and
+/// should be checked in so that builds producing a different image ID are
+/// caught before they are deployed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Lockfile {
+    /// The pinned image ID of each guest.
+    pub images: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    /// Parse the contents of a lockfile.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut images = BTreeMap::new();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, image_id) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid lockfile entry on line {}", idx + 1))?;
+            let image_id = image_id.trim().trim_matches('"');
+            images.insert(name.trim().to_string(), image_id.to_string());
+        }
+        Ok(Self { images })
+    }
+
+    /// Pin `name` to `image_id`.
+    ///
+    /// Fails if `name` is already pinned to a different image ID, unless
+    /// `update` is set.
+    pub fn pin(&mut self, name: &str, image_id: &str, update: bool) -> Result<()> {
+        if let Some(pinned) = self.images.get(name) {
+            if pinned != image_id && !update {
+                bail!(
+                    "image ID mismatch for `{name}`: locked to {pinned}, but the local build is \
+                     {image_id} (use --update to replace it)"
+                );
+            }
+        }
+        self.images.insert(name.to_string(), image_id.to_string());
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Generated by `cargo risczero deploy`.")?;
+        for (name, image_id) in self.images.iter() {
+            writeln!(f, "{name} = \"{image_id}\"")?;
+        }
+        Ok(())
+    }
+}
+
+impl DeployCommand {
+    /// Execute this command
+    pub fn run(&self) -> Result<()> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => self
+                .elf
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid ELF path: {}", self.elf.display()))?
+                .to_string_lossy()
+                .into_owned(),
+        };
+        let elf = fs::read(&self.elf)
+            .with_context(|| format!("Failed to read {}", self.elf.display()))?;
+        let program = Program::load_elf(&elf, MEM_SIZE as u32)?;
+        let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
+        let image_id = hex::encode(image.compute_id());
+
+        let mut lockfile = match fs::read_to_string(&self.lockfile) {
+            Ok(contents) => Lockfile::parse(&contents)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Lockfile::default(),
+            Err(err) => return Err(err.into()),
+        };
+        // Check the pin before uploading, so a mismatched build is never deployed.
+        lockfile.pin(&name, &image_id, self.update)?;
+
+        let client = Client::from_parts(self.endpoint.clone(), self.api_key.clone())?;
+        match client.upload_img(&image_id, bincode::serialize(&image)?) {
+            Ok(()) => println!("uploaded {name}: {image_id}"),
+            Err(SdkErr::ImageIdExists) => println!("already deployed {name}: {image_id}"),
+            Err(err) => return Err(err.into()),
+        }
+
+        fs::write(&self.lockfile, lockfile.to_string())
+            .with_context(|| format!("Failed to write {}", self.lockfile.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockfile_round_trip() {
+        let mut lockfile = Lockfile::default();
+        lockfile.pin("guest", "abcd", false).unwrap();
+        assert_eq!(Lockfile::parse(&lockfile.to_string()).unwrap(), lockfile);
+    }
+
+    #[test]
+    fn lockfile_pin_mismatch() {
+        let mut lockfile = Lockfile::parse("guest = \"abcd\"\n").unwrap();
+        lockfile.pin("guest", "abcd", false).unwrap();
+        lockfile.pin("other", "1234", false).unwrap();
+        assert!(lockfile.pin("guest", "1234", false).is_err());
+        lockfile.pin("guest", "1234", true).unwrap();
+        assert_eq!(lockfile.images["guest"], "1234");
+    }
+}
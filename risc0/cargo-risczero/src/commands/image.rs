@@ -0,0 +1,294 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use elf::{endian::LittleEndian, ElfBytes};
+use risc0_zkvm::{sha::Digest, MemoryImage, Program, MEM_SIZE, PAGE_SIZE};
+use risc0_zkvm_platform::memory::{RESERVED_STACK, STACK_TOP, TEXT_START};
+
+#[derive(Parser)]
+/// `cargo risczero image`
+pub struct ImageCommand {
+    #[clap(subcommand)]
+    /// Which `image` command to run
+    pub command: ImageCmd,
+}
+
+#[derive(Subcommand)]
+/// Commands of `cargo risczero image`.
+pub enum ImageCmd {
+    /// Print the image ID, memory layout and potential problems of a guest ELF.
+    Info {
+        /// The guest ELF file.
+        elf: PathBuf,
+    },
+}
+
+/// The sizes of the loadable parts of a guest ELF, in bytes.
+#[derive(Debug, Default, PartialEq)]
+pub struct SectionSizes {
+    /// Executable sections.
+    pub text: u64,
+
+    /// Read-only data sections.
+    pub rodata: u64,
+
+    /// Initialized writable data sections.
+    pub data: u64,
+
+    /// Zero-initialized sections.
+    pub bss: u64,
+}
+
+/// An instruction that the zkVM does not implement.
+#[derive(Debug, PartialEq)]
+pub struct UnsupportedInsn {
+    /// The address of the first occurrence.
+    pub addr: u32,
+
+    /// The number of occurrences.
+    pub count: usize,
+}
+
+/// Information about a guest ELF, gathered without executing it.
+pub struct ImageInfo {
+    /// The image ID of the guest.
+    pub image_id: Digest,
+
+    /// The entry point of the guest.
+    pub entry: u32,
+
+    /// The sizes of the loadable sections.
+    pub sizes: SectionSizes,
+
+    /// The end address of the loaded program, where the heap begins.
+    pub program_end: u32,
+
+    /// Symbols referenced but not defined by the ELF.
+    pub undefined_symbols: Vec<String>,
+
+    /// Instructions the zkVM does not implement, grouped by extension.
+    pub unsupported_insns: BTreeMap<&'static str, UnsupportedInsn>,
+}
+
+/// The highest address the program may occupy while leaving room for the
+/// stack.
+const PROGRAM_LIMIT: u32 = STACK_TOP - RESERVED_STACK;
+
+impl ImageInfo {
+    /// Inspect the given guest ELF.
+    pub fn from_elf(input: &[u8]) -> Result<Self> {
+        let program = Program::load_elf(input, MEM_SIZE as u32)?;
+        let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
+        let image_id = image.compute_id();
+
+        let elf = ElfBytes::<LittleEndian>::minimal_parse(input)?;
+        let headers = elf
+            .section_headers()
+            .ok_or(anyhow!("Missing section header table"))?;
+
+        let mut sizes = SectionSizes::default();
+        let mut program_end = TEXT_START;
+        let mut unsupported_insns = BTreeMap::new();
+        for header in headers.iter() {
+            let flags = header.sh_flags;
+            if flags & elf::abi::SHF_ALLOC as u64 == 0 {
+                continue;
+            }
+            let end: u32 = (header.sh_addr + header.sh_size)
+                .try_into()
+                .context("Invalid section address")?;
+            program_end = program_end.max(end);
+            if header.sh_type == elf::abi::SHT_NOBITS {
+                sizes.bss += header.sh_size;
+            } else if flags & elf::abi::SHF_EXECINSTR as u64 != 0 {
+                sizes.text += header.sh_size;
+                let (data, _) = elf.section_data(&header)?;
+                let addr: u32 = header.sh_addr.try_into()?;
+                scan_insns(addr, data, &mut unsupported_insns);
+            } else if flags & elf::abi::SHF_WRITE as u64 != 0 {
+                sizes.data += header.sh_size;
+            } else {
+                sizes.rodata += header.sh_size;
+            }
+        }
+        let mut undefined_symbols = Vec::new();
+        if let Some((symbols, strtab)) = elf.symbol_table()? {
+            for symbol in symbols.iter() {
+                if symbol.st_name == 0 || !symbol.is_undefined() {
+                    continue;
+                }
+                undefined_symbols.push(strtab.get(symbol.st_name as usize)?.to_string());
+            }
+        }
+        undefined_symbols.sort();
+        undefined_symbols.dedup();
+
+        Ok(Self {
+            image_id,
+            entry: program.entry,
+            sizes,
+            program_end,
+            undefined_symbols,
+            unsupported_insns,
+        })
+    }
+
+    /// Returns true if the guest is likely to fail at runtime.
+    pub fn has_problems(&self) -> bool {
+        self.program_end > PROGRAM_LIMIT
+            || !self.undefined_symbols.is_empty()
+            || !self.unsupported_insns.is_empty()
+    }
+}
+
+impl std::fmt::Display for ImageInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sizes = &self.sizes;
+        writeln!(f, "image id:     {}", self.image_id)?;
+        writeln!(f, "entry:        {:#010x}", self.entry)?;
+        writeln!(f, "text:         {} bytes", sizes.text)?;
+        writeln!(f, "rodata:       {} bytes", sizes.rodata)?;
+        writeln!(f, "data:         {} bytes", sizes.data)?;
+        writeln!(f, "bss:          {} bytes", sizes.bss)?;
+        writeln!(
+            f,
+            "program end:  {:#010x} (limit {PROGRAM_LIMIT:#010x})",
+            self.program_end
+        )?;
+        if self.program_end > PROGRAM_LIMIT {
+            writeln!(
+                f,
+                "error: program exceeds the guest memory limit by {} bytes",
+                self.program_end - PROGRAM_LIMIT
+            )?;
+        } else {
+            writeln!(
+                f,
+                "heap + stack: {} bytes available",
+                STACK_TOP - self.program_end
+            )?;
+        }
+        for name in self.undefined_symbols.iter() {
+            writeln!(f, "warning: undefined symbol `{name}`")?;
+        }
+        for (ext, insn) in self.unsupported_insns.iter() {
+            writeln!(
+                f,
+                "warning: {} unsupported {ext} instruction(s), first at {:#010x}",
+                insn.count, insn.addr
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Classify an instruction as belonging to an extension that the zkVM does not
+/// implement. The zkVM only supports RV32IM without compressed instructions.
+fn unsupported_extension(insn: u32) -> Option<&'static str> {
+    if insn & 0b11 != 0b11 {
+        return Some("compressed (C)");
+    }
+    let opcode = insn & 0x7f;
+    let funct3 = (insn >> 12) & 0x7;
+    match opcode {
+        0b0101111 => Some("atomic (A)"),
+        0b0000111 | 0b0100111 | 0b1000011 | 0b1000111 | 0b1001011 | 0b1001111 | 0b1010011 => {
+            Some("floating point (F/D)")
+        }
+        0b0001111 => Some("fence"),
+        0b1110011 if funct3 != 0 => Some("CSR (Zicsr)"),
+        _ => None,
+    }
+}
+
+fn scan_insns(addr: u32, data: &[u8], found: &mut BTreeMap<&'static str, UnsupportedInsn>) {
+    for (idx, chunk) in data.chunks_exact(4).enumerate() {
+        let insn = u32::from_le_bytes(chunk.try_into().unwrap());
+        // Padding between functions is zero filled.
+        if insn == 0 {
+            continue;
+        }
+        if let Some(ext) = unsupported_extension(insn) {
+            found
+                .entry(ext)
+                .or_insert(UnsupportedInsn {
+                    addr: addr + idx as u32 * 4,
+                    count: 0,
+                })
+                .count += 1;
+        }
+    }
+}
+
+impl ImageCommand {
+    /// Execute this command
+    pub fn run(&self) -> Result<()> {
+        match &self.command {
+            ImageCmd::Info { elf } => {
+                let input =
+                    fs::read(elf).with_context(|| format!("Failed to read {}", elf.display()))?;
+                let info = ImageInfo::from_elf(&input)?;
+                print!("{info}");
+                if info.has_problems() {
+                    bail!("{} is likely to fail in the zkVM", elf.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_insns() {
+        // addi a0, a0, 1
+        assert_eq!(unsupported_extension(0x00150513), None);
+        // ecall
+        assert_eq!(unsupported_extension(0x00000073), None);
+        // c.addi a0, 1
+        assert_eq!(unsupported_extension(0x0505), Some("compressed (C)"));
+        // amoadd.w a0, a1, (a2)
+        assert_eq!(unsupported_extension(0x00b6252f), Some("atomic (A)"));
+        // fadd.s fa0, fa0, fa1
+        assert_eq!(
+            unsupported_extension(0x00b57553),
+            Some("floating point (F/D)")
+        );
+        // csrr a0, cycle
+        assert_eq!(unsupported_extension(0xc0002573), Some("CSR (Zicsr)"));
+    }
+
+    #[test]
+    fn scan_counts() {
+        let mut found = BTreeMap::new();
+        let insns: [u32; 4] = [0x00150513, 0x00b6252f, 0, 0x00b6252f];
+        let data: Vec<u8> = insns.iter().flat_map(|x| x.to_le_bytes()).collect();
+        scan_insns(0x1000, &data, &mut found);
+        assert_eq!(
+            found.get("atomic (A)"),
+            Some(&UnsupportedInsn {
+                addr: 0x1004,
+                count: 2
+            })
+        );
+        assert_eq!(found.len(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use hex::FromHex;
+use risc0_zkvm::{
+    receipt::InnerReceipt, serde::from_slice, sha::Digest, ExitCode, Receipt, ReceiptMetadata,
+    VerifierContext, BLAKE2B_CONTROL_ID, POSEIDON_CONTROL_ID, SHA256_CONTROL_ID,
+};
+
+#[derive(Parser)]
+/// `cargo risczero receipt`
+pub struct ReceiptCommand {
+    #[clap(subcommand)]
+    /// Which `receipt` command to run
+    pub command: ReceiptCmd,
+}
+
+#[derive(Subcommand)]
+/// Commands of `cargo risczero receipt`.
+pub enum ReceiptCmd {
+    /// Print a human-readable summary of a receipt.
+    Info {
+        /// The receipt file, as written by `r0vm --receipt`.
+        receipt: PathBuf,
+    },
+
+    /// Verify a receipt against an image ID.
+    Verify {
+        /// The receipt file, as written by `r0vm --receipt`.
+        receipt: PathBuf,
+
+        /// The expected image ID, hex encoded.
+        #[clap(long)]
+        image_id: String,
+
+        /// The domain separator the receipt was proven with, if any.
+        #[clap(long)]
+        domain: Option<String>,
+    },
+
+    /// Write the journal of a receipt to a file.
+    ExtractJournal {
+        /// The receipt file, as written by `r0vm --receipt`.
+        receipt: PathBuf,
+
+        /// The file to write the journal to.
+        #[clap(long, short)]
+        out: PathBuf,
+    },
+}
+
+/// A summary of the contents of a [Receipt].
+pub struct ReceiptSummary {
+    /// The kind of the inner receipt.
+    pub kind: String,
+
+    /// The image ID the receipt claims to start from, if it can be decoded.
+    pub image_id: Option<Digest>,
+
+    /// The exit code of the session, if it can be decoded.
+    pub exit_code: Option<ExitCode>,
+
+    /// The names of the hash functions used in the seal, sorted and without
+    /// duplicates.
+    pub hashfns: Vec<String>,
+
+    /// The circuit the seal(s) were proven with, and the control IDs that
+    /// identify its version, sorted and without duplicates.
+    pub circuit: Option<(String, Vec<Digest>)>,
+
+    /// The total size of the seal(s) in bytes.
+    pub seal_size: usize,
+
+    /// The journal committed by the guest.
+    pub journal: Vec<u8>,
+}
+
+impl ReceiptSummary {
+    /// Summarize the given [Receipt].
+    pub fn new(receipt: &Receipt) -> Result<Self> {
+        let ctx = VerifierContext::default();
+        let (kind, first, last, hashfns, circuit, seal_size) = match &receipt.inner {
+            InnerReceipt::Flat(segments) => {
+                let segments = &segments.0;
+                let first = segments.first().map(|x| x.get_metadata()).transpose()?;
+                let last = segments.last().map(|x| x.get_metadata()).transpose()?;
+                let hashfns: BTreeSet<String> = segments.iter().map(|x| x.hashfn.clone()).collect();
+                let control_ids = segments
+                    .iter()
+                    .map(|x| x.get_control_id(&ctx))
+                    .collect::<Result<BTreeSet<Digest>, _>>()?;
+                let seal_size = segments.iter().map(|x| x.get_seal_bytes().len()).sum();
+                (
+                    format!("flat ({} segments)", segments.len()),
+                    first,
+                    last,
+                    hashfns.into_iter().collect(),
+                    Some(("rv32im".to_string(), control_ids.into_iter().collect())),
+                    seal_size,
+                )
+            }
+            InnerReceipt::Succinct(succinct) => (
+                "succinct".to_string(),
+                Some(succinct.meta.clone()),
+                Some(succinct.meta.clone()),
+                vec![succinct.get_hashfn().to_string()],
+                Some(("recursion".to_string(), vec![succinct.control_id])),
+                succinct.seal.len() * std::mem::size_of::<u32>(),
+            ),
+            InnerReceipt::Fake => ("fake".to_string(), None, None, Vec::new(), None, 0),
+        };
+        Ok(Self {
+            kind,
+            image_id: first.map(|meta: ReceiptMetadata| meta.pre.digest()),
+            exit_code: last.map(|meta| meta.exit_code),
+            hashfns,
+            circuit,
+            seal_size,
+            journal: receipt.journal.clone(),
+        })
+    }
+}
+
+impl std::fmt::Display for ReceiptSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unknown = || "unknown".to_string();
+        writeln!(f, "kind:            {}", self.kind)?;
+        writeln!(
+            f,
+            "image id:        {}",
+            self.image_id.map_or_else(unknown, |x| x.to_string())
+        )?;
+        writeln!(
+            f,
+            "exit code:       {}",
+            self.exit_code.map_or_else(unknown, |x| format!("{x:?}"))
+        )?;
+        writeln!(f, "hash functions:  {}", self.hashfns.join(", "))?;
+        match &self.circuit {
+            Some((name, control_ids)) => {
+                writeln!(f, "circuit:         {name}")?;
+                for control_id in control_ids.iter() {
+                    let known = match is_known_control_id(control_id) {
+                        true => "",
+                        false => " (not supported by this verifier)",
+                    };
+                    writeln!(f, "  control id:    {control_id}{known}")?;
+                }
+            }
+            None => writeln!(f, "circuit:         unknown")?,
+        }
+        writeln!(f, "seal size:       {} bytes", self.seal_size)?;
+        writeln!(f, "journal size:    {} bytes", self.journal.len())?;
+        writeln!(f, "journal (hex):   {}", hex::encode(&self.journal))?;
+        match std::str::from_utf8(&self.journal) {
+            Ok(text) => write!(f, "journal (utf-8): {text:?}"),
+            Err(_) => write!(f, "journal (utf-8): <not valid utf-8>"),
+        }
+    }
+}
+
+// Return whether the control ID is one of a circuit this verifier supports.
+fn is_known_control_id(control_id: &Digest) -> bool {
+    POSEIDON_CONTROL_ID
+        .into_iter()
+        .chain(SHA256_CONTROL_ID)
+        .chain(BLAKE2B_CONTROL_ID)
+        .filter_map(|x| Digest::from_hex(x).ok())
+        .chain(risc0_zkvm::recursion::valid_control_ids())
+        .any(|x| x == *control_id)
+}
+
+/// Load a [Receipt] from a file written by `r0vm --receipt`.
+pub fn load_receipt(path: &PathBuf) -> Result<Receipt> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    // Receipts are serialized as words, and decoding bytes that are not
+    // would panic.
+    if bytes.len() % std::mem::size_of::<u32>() != 0 {
+        bail!(
+            "Failed to decode {}: length of {} bytes is not a multiple of 4",
+            path.display(),
+            bytes.len()
+        );
+    }
+    from_slice(&bytes).map_err(|err| anyhow!("Failed to decode {}: {err}", path.display()))
+}
+
+impl ReceiptCommand {
+    /// Execute this command
+    pub fn run(&self) -> Result<()> {
+        match &self.command {
+            ReceiptCmd::Info { receipt } => {
+                let receipt = load_receipt(receipt)?;
+                println!("{}", ReceiptSummary::new(&receipt)?);
+            }
+            ReceiptCmd::Verify {
+                receipt,
+                image_id,
+                domain,
+            } => {
+                let receipt = load_receipt(receipt)?;
+                let image_id = Digest::from_hex(image_id.trim_start_matches("0x"))
+                    .map_err(|err| anyhow!("Invalid image ID: {err}"))?;
+                let mut ctx = VerifierContext::default();
+                if let Some(domain) = domain {
+                    ctx = ctx.with_domain(domain);
+                }
+                receipt
+                    .verify_with_context(&ctx, image_id)
+                    .map_err(|err| anyhow!("Verification failed: {err}"))?;
+                println!("Receipt verified for image ID {image_id}");
+            }
+            ReceiptCmd::ExtractJournal { receipt, out } => {
+                let receipt = load_receipt(receipt)?;
+                fs::write(out, &receipt.journal)
+                    .with_context(|| format!("Failed to write {}", out.display()))?;
+                println!(
+                    "Wrote {} bytes of journal to {}",
+                    receipt.journal.len(),
+                    out.display()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_verify() {
+        let cmd = ReceiptCommand::parse_from([
+            "receipt",
+            "verify",
+            "receipt.bin",
+            "--image-id",
+            "00112233",
+        ]);
+        match cmd.command {
+            ReceiptCmd::Verify {
+                receipt, image_id, ..
+            } => {
+                assert_eq!(receipt, PathBuf::from("receipt.bin"));
+                assert_eq!(image_id, "00112233");
+            }
+            _ => panic!("Expected verify command"),
+        }
+    }
+
+    #[test]
+    fn load_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("receipt.bin");
+        let receipt = Receipt::new(InnerReceipt::Fake, b"hello".to_vec());
+        let bytes: Vec<u8> = risc0_zkvm::serde::to_vec(&receipt)
+            .unwrap()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let err = load_receipt(&path).err().unwrap();
+        assert!(err.to_string().contains("Failed to decode"), "{err}");
+    }
+
+    #[test]
+    fn summarize_fake() {
+        let receipt = Receipt::new(InnerReceipt::Fake, b"hello".to_vec());
+        let summary = ReceiptSummary::new(&receipt).unwrap();
+        assert_eq!(summary.kind, "fake");
+        assert_eq!(summary.image_id, None);
+        assert_eq!(summary.seal_size, 0);
+        assert_eq!(summary.circuit, None);
+        let text = summary.to_string();
+        assert!(text.contains("68656c6c6f"));
+        assert!(text.contains("\"hello\""));
+    }
+}
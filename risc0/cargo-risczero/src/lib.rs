@@ -17,12 +17,20 @@
 
 use clap::{Parser, Subcommand};
 
-use crate::commands::new::NewCommand;
+use crate::commands::{
+    deploy::DeployCommand, image::ImageCommand, new::NewCommand, receipt::ReceiptCommand,
+};
 
 /// Implementations of the commands
 pub mod commands {
+    /// Deploy guest images to a remote proving service
+    pub mod deploy;
+    /// Inspect guest ELF files
+    pub mod image;
     /// Create a new RISC Zero project
     pub mod new;
+    /// Inspect and verify receipt files
+    pub mod receipt;
 }
 
 #[derive(Parser)]
@@ -47,6 +55,15 @@ pub struct Risczero {
 pub enum RisczeroCmd {
     /// Creates a new risczero starter project.
     New(NewCommand),
+
+    /// Inspects guest ELF files before they are run in the zkVM.
+    Image(ImageCommand),
+
+    /// Inspects, verifies, and extracts the journal of receipt files.
+    Receipt(ReceiptCommand),
+
+    /// Uploads a guest image to a remote proving service and pins its image ID.
+    Deploy(DeployCommand),
 }
 
 #[cfg(test)]
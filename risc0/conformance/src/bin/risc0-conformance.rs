@@ -0,0 +1,94 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+use risc0_conformance::Suite;
+use risc0_zkvm::prove::{default_prover, get_prover};
+
+/// Checks that a zkVM prover reproduces the claims of a conformance suite.
+#[derive(Parser)]
+#[clap(about, version, author)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate the reference suite with the CPU prover.
+    Generate {
+        /// The file to write the suite to.
+        #[clap(long, default_value = "fixtures/suite.bin")]
+        out: PathBuf,
+
+        /// The file to write the digests of the expected claims to.
+        #[clap(long, default_value = "fixtures/claims.txt")]
+        claims: PathBuf,
+    },
+
+    /// Check a suite with a prover.
+    Check {
+        /// The suite to check.
+        #[clap(long, default_value = "fixtures/suite.bin")]
+        suite: PathBuf,
+
+        /// The name of the prover to check, e.g. `cpu:poseidon` or `metal`.
+        /// Defaults to the prover selected by `RISC0_PROVER`.
+        #[clap(long)]
+        prover: Option<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+
+    match Args::parse().command {
+        Command::Generate { out, claims } => {
+            let suite = Suite::generate(get_prover("cpu").as_ref()).unwrap();
+            suite.save(&out).expect("Unable to write suite");
+            fs::write(&claims, suite.claim_digests().unwrap()).expect("Unable to write claims");
+            println!(
+                "Wrote {} cases to {} and {}",
+                suite.cases.len(),
+                out.display(),
+                claims.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Command::Check { suite, prover } => {
+            let suite = Suite::load(&suite).unwrap();
+            let prover = match prover {
+                Some(name) => get_prover(&name),
+                None => default_prover(),
+            };
+            let mut failed = 0;
+            for case in suite.cases.iter() {
+                match case.check(prover.as_ref()) {
+                    Ok(()) => println!("{}: ok", case.name),
+                    Err(err) => {
+                        println!("{}: FAILED: {err:#}", case.name);
+                        failed += 1;
+                    }
+                }
+            }
+            println!("{} passed, {failed} failed", suite.cases.len() - failed);
+            match failed {
+                0 => ExitCode::SUCCESS,
+                _ => ExitCode::FAILURE,
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A conformance suite for RISC Zero zkVM provers.
+//!
+//! A [Suite] is a list of [Case]s, each holding a guest ELF, its input, and
+//! the claims proven about each of its segments by the reference CPU prover.
+//! Since the ELF is stored in the suite rather than built from source, the
+//! expected claims do not depend on the toolchain used to build the guests.
+//!
+//! Packagers and authors of alternative backends can check that a prover
+//! reproduces the same claims with [Suite::check], or with the
+//! `risc0-conformance check` command. Seals are randomized for zero
+//! knowledge, so they are not compared byte for byte; instead every seal must
+//! verify, and the claims it proves must match the suite exactly.
+//!
+//! The digests of the expected claims are also written out as text with
+//! [Suite::claim_digests], so that a change to the claims of a suite kept
+//! under version control shows up in review, and can be checked with
+//! [Suite::check_claim_digests].
+
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+use risc0_zkvm::{
+    prove::Prover, receipt::InnerReceipt, serde::to_vec, sha::Digest, ExecutorEnv, ExecutorPool,
+    Receipt, ReceiptMetadata,
+};
+use risc0_zkvm_methods::{multi_test::MultiTestSpec, HELLO_COMMIT_ELF, MULTI_TEST_ELF};
+use serde::{Deserialize, Serialize};
+
+/// Magic bytes at the start of an encoded [Suite], see [Suite::encode].
+const SUITE_MAGIC: &[u8; 8] = b"R0CONF\0\0";
+
+/// The version of the encoding produced by [Suite::encode].
+const SUITE_FORMAT_VERSION: u32 = 1;
+
+/// A guest execution and the claims expected to be proven about it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Case {
+    /// The name of the case, used in reports.
+    pub name: String,
+
+    /// The guest ELF binary.
+    pub elf: Vec<u8>,
+
+    /// The input words passed to the guest.
+    pub input: Vec<u32>,
+
+    /// The segment limit the guest is executed with, as a power of 2 cycles.
+    pub segment_limit_po2: usize,
+
+    /// The image ID of the guest.
+    pub image_id: Digest,
+
+    /// The journal committed by the guest.
+    pub journal: Vec<u8>,
+
+    /// The claim proven about each segment, in order.
+    pub claims: Vec<ReceiptMetadata>,
+}
+
+impl Case {
+    /// Construct a case by proving the given guest with the given prover,
+    /// which should be the reference CPU prover.
+    pub fn generate(
+        name: &str,
+        elf: &[u8],
+        input: &[u32],
+        segment_limit_po2: usize,
+        prover: &dyn Prover,
+    ) -> Result<Self> {
+        let image_id = ExecutorPool::from_elf(elf)?.image_id();
+        let mut case = Self {
+            name: name.to_string(),
+            elf: elf.to_vec(),
+            input: input.to_vec(),
+            segment_limit_po2,
+            image_id,
+            journal: Vec::new(),
+            claims: Vec::new(),
+        };
+        let receipt = case.prove(prover)?;
+        case.claims = claims(&receipt)?;
+        case.journal = receipt.journal;
+        Ok(case)
+    }
+
+    /// Return the [ReceiptMetadata::digest] of each expected claim.
+    pub fn claim_digests(&self) -> Result<Vec<Digest>> {
+        Ok(self
+            .claims
+            .iter()
+            .map(|claim| claim.digest())
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Check that the given prover reproduces the claims of this case.
+    pub fn check(&self, prover: &dyn Prover) -> Result<()> {
+        let receipt = self.prove(prover)?;
+        ensure!(
+            receipt.journal == self.journal,
+            "journal differs from the expected journal"
+        );
+        let claims = claims(&receipt)?;
+        ensure!(
+            claims.len() == self.claims.len(),
+            "proved {} segments, expected {}",
+            claims.len(),
+            self.claims.len()
+        );
+        for (idx, (claim, expected)) in claims.iter().zip(self.claims.iter()).enumerate() {
+            ensure!(
+                claim == expected,
+                "claim of segment {idx} differs: {claim:?}, expected {expected:?}"
+            );
+        }
+        Ok(())
+    }
+
+    // Prove and verify this case with the given prover.
+    fn prove(&self, prover: &dyn Prover) -> Result<Receipt> {
+        let env = ExecutorEnv::builder()
+            .add_input(&self.input)
+            .segment_limit_po2(self.segment_limit_po2)
+            .build()?;
+        let receipt = prover.prove_elf(env, &self.elf)?;
+        receipt
+            .verify(self.image_id)
+            .context("receipt failed to verify")?;
+        Ok(receipt)
+    }
+}
+
+// Return the claim proven about each segment of a receipt.
+fn claims(receipt: &Receipt) -> Result<Vec<ReceiptMetadata>> {
+    match &receipt.inner {
+        InnerReceipt::Flat(segments) => Ok(segments
+            .0
+            .iter()
+            .map(|segment| segment.get_metadata())
+            .collect::<Result<_, _>>()?),
+        InnerReceipt::Succinct(receipt) => Ok(vec![receipt.meta.clone()]),
+        InnerReceipt::Fake => bail!("fake receipts cannot be checked"),
+    }
+}
+
+/// A list of conformance [Case]s.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Suite {
+    /// The cases of the suite, checked in order.
+    pub cases: Vec<Case>,
+}
+
+impl Suite {
+    /// Generate the reference suite from the test guests of this repository,
+    /// with the given prover, which should be the reference CPU prover.
+    ///
+    /// The cases cover a trivial guest, the SHA-256 accelerator, a guest
+    /// split into several segments, and a guest halting with an application
+    /// exit code.
+    pub fn generate(prover: &dyn Prover) -> Result<Self> {
+        let multi_test = |name, spec: MultiTestSpec, po2| {
+            Case::generate(name, MULTI_TEST_ELF, &to_vec(&spec)?, po2, prover)
+        };
+        Ok(Self {
+            cases: vec![
+                Case::generate("hello_commit", HELLO_COMMIT_ELF, &[], 16, prover)?,
+                multi_test("do_nothing", MultiTestSpec::DoNothing, 16)?,
+                multi_test(
+                    "sha_digest",
+                    MultiTestSpec::ShaDigest {
+                        data: b"risc0-conformance".to_vec(),
+                    },
+                    16,
+                )?,
+                multi_test("busy_loop", MultiTestSpec::BusyLoop { cycles: 3 << 14 }, 14)?,
+                multi_test("exit_code", MultiTestSpec::Exit { status: 3 }, 16)?,
+            ],
+        })
+    }
+
+    /// Check every case of this suite with the given prover.
+    ///
+    /// All cases are checked, and the error lists every case that failed.
+    pub fn check(&self, prover: &dyn Prover) -> Result<()> {
+        let mut failed = Vec::new();
+        for case in self.cases.iter() {
+            if let Err(err) = case.check(prover) {
+                log::error!("{}: {err:#}", case.name);
+                failed.push(case.name.as_str());
+            }
+        }
+        if !failed.is_empty() {
+            bail!(
+                "{} of {} cases failed: {}",
+                failed.len(),
+                self.cases.len(),
+                failed.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Render the digests of the expected claims of every case, one line of
+    /// `<case> <segment> <digest>` per claim.
+    pub fn claim_digests(&self) -> Result<String> {
+        let mut out = String::new();
+        for case in self.cases.iter() {
+            for (idx, digest) in case.claim_digests()?.iter().enumerate() {
+                writeln!(out, "{} {idx} {digest}", case.name)?;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Check that the expected claims of this suite have the digests listed
+    /// in `expected`, as rendered by [Suite::claim_digests].
+    pub fn check_claim_digests(&self, expected: &str) -> Result<()> {
+        let actual = self.claim_digests()?;
+        for (line, (actual, expected)) in actual.lines().zip(expected.lines()).enumerate() {
+            ensure!(
+                actual == expected,
+                "claim digest on line {} differs: {actual}, expected {expected}",
+                line + 1
+            );
+        }
+        ensure!(
+            actual.lines().count() == expected.lines().count(),
+            "suite has {} claims, expected {}",
+            actual.lines().count(),
+            expected.lines().count()
+        );
+        Ok(())
+    }
+
+    /// Encode this [Suite] as the 8 magic bytes `R0CONF\0\0`, a little-endian
+    /// u32 format version, and a bincode body.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = SUITE_MAGIC.to_vec();
+        bytes.extend_from_slice(&SUITE_FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Decode a [Suite] produced by [Suite::encode].
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let header_len = SUITE_MAGIC.len() + 4;
+        ensure!(
+            bytes.len() >= header_len && bytes.starts_with(SUITE_MAGIC),
+            "not a conformance suite"
+        );
+        let version = u32::from_le_bytes(bytes[SUITE_MAGIC.len()..header_len].try_into()?);
+        if version != SUITE_FORMAT_VERSION {
+            bail!("unsupported suite format version: {version}");
+        }
+        Ok(bincode::deserialize(&bytes[header_len..])?)
+    }
+
+    /// Load a [Suite] from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Self::decode(&bytes)
+    }
+
+    /// Save this [Suite] to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(fs::write(path, self.encode()?)?)
+    }
+}
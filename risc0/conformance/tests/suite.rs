@@ -0,0 +1,37 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use risc0_conformance::{Case, Suite};
+use risc0_zkvm::{prove::get_prover, serde::to_vec, ExitCode};
+use risc0_zkvm_methods::{multi_test::MultiTestSpec, MULTI_TEST_ELF};
+
+#[test]
+fn reproduce() {
+    let prover = get_prover("cpu");
+    let input = to_vec(&MultiTestSpec::Exit { status: 3 }).unwrap();
+    let case = Case::generate("exit_code", MULTI_TEST_ELF, &input, 16, prover.as_ref()).unwrap();
+    assert_eq!(case.claims.last().unwrap().exit_code, ExitCode::Halted(3));
+
+    let suite = Suite::decode(&Suite { cases: vec![case] }.encode().unwrap()).unwrap();
+    suite.check(prover.as_ref()).unwrap();
+    suite.check(get_prover("cpu:poseidon").as_ref()).unwrap();
+
+    let claims = suite.claim_digests().unwrap();
+    suite.check_claim_digests(&claims).unwrap();
+
+    let mut forged = suite.clone();
+    forged.cases[0].claims[0].exit_code = ExitCode::Halted(0);
+    assert!(forged.check(prover.as_ref()).is_err());
+    assert!(forged.check_claim_digests(&claims).is_err());
+}
@@ -0,0 +1,146 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Table-driven base64 encoding and decoding, with the standard alphabet and
+//! padding of RFC 4648.
+//!
+//! Three bytes are encoded and four characters decoded at a time, through a
+//! single 24-bit word, with a lookup table for the alphabet.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::Error;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// The value of each character of the alphabet, or 0xff for other characters.
+const DECODE: [u8; 256] = {
+    let mut table = [0xffu8; 256];
+    let mut idx = 0;
+    while idx < 64 {
+        table[ALPHABET[idx] as usize] = idx as u8;
+        idx += 1;
+    }
+    table
+};
+
+/// Encode bytes as padded base64.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = Vec::with_capacity((bytes.len() + 2) / 3 * 4);
+    let chunks = bytes.chunks_exact(3);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        let word = (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32;
+        out.extend_from_slice(&[
+            ALPHABET[(word >> 18) as usize],
+            ALPHABET[((word >> 12) & 0x3f) as usize],
+            ALPHABET[((word >> 6) & 0x3f) as usize],
+            ALPHABET[(word & 0x3f) as usize],
+        ]);
+    }
+    match *rest {
+        [a] => {
+            let word = (a as u32) << 16;
+            out.extend_from_slice(&[
+                ALPHABET[(word >> 18) as usize],
+                ALPHABET[((word >> 12) & 0x3f) as usize],
+                b'=',
+                b'=',
+            ]);
+        }
+        [a, b] => {
+            let word = (a as u32) << 16 | (b as u32) << 8;
+            out.extend_from_slice(&[
+                ALPHABET[(word >> 18) as usize],
+                ALPHABET[((word >> 12) & 0x3f) as usize],
+                ALPHABET[((word >> 6) & 0x3f) as usize],
+                b'=',
+            ]);
+        }
+        _ => {}
+    }
+    // Every byte of `out` is an ASCII character.
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decode padded base64 into bytes.
+///
+/// Only the canonical encoding is accepted: text whose padded digit has
+/// non-zero trailing bits, such as `Zh==` for `Zg==`, is rejected.
+pub fn decode(text: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    let text = text.as_ref();
+    if text.len() % 4 != 0 {
+        return Err(Error::UnexpectedEof);
+    }
+    let padding = match text {
+        [.., b'=', b'='] => 2,
+        [.., b'='] => 1,
+        _ => 0,
+    };
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (idx, group) in text.chunks_exact(4).enumerate() {
+        let last = (idx + 1) * 4 == text.len();
+        let digits = match last {
+            true => 4 - padding,
+            false => 4,
+        };
+        let mut word = 0u32;
+        let mut invalid = 0u8;
+        for (pos, ch) in group.iter().enumerate() {
+            let value = match pos < digits {
+                true => DECODE[*ch as usize],
+                false => 0,
+            };
+            invalid |= value;
+            word = word << 6 | (value & 0x3f) as u32;
+        }
+        // The bits of the last digit that are not part of a byte must be
+        // zero, so that each byte string has a single encoding.
+        let unused = 24 - 8 * (digits as u32 - 1);
+        if invalid == 0xff || word & ((1 << unused) - 1) != 0 {
+            return Err(Error::InvalidInput);
+        }
+        let bytes = [(word >> 16) as u8, (word >> 8) as u8, word as u8];
+        out.extend_from_slice(&bytes[..digits - 1]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for (bytes, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode(bytes), text);
+            assert_eq!(decode(text).unwrap(), bytes);
+        }
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(encode(&bytes)).unwrap(), bytes);
+        assert_eq!(decode("Zm9"), Err(Error::UnexpectedEof));
+        assert_eq!(decode("Zm9*"), Err(Error::InvalidInput));
+        assert_eq!(decode("Z=9v"), Err(Error::InvalidInput));
+        assert_eq!(decode("Zh=="), Err(Error::InvalidInput));
+        assert_eq!(decode("Zm9="), Err(Error::InvalidInput));
+    }
+}
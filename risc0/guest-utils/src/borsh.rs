@@ -0,0 +1,127 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A zero-copy reader for the [borsh](https://borsh.io) binary format.
+//!
+//! Borsh encodes integers as little-endian, which rv32im loads natively, and
+//! prefixes byte strings with a u32 length, so [Reader::bytes] and
+//! [Reader::string] borrow from the input instead of copying it.
+
+use crate::Error;
+
+/// A cursor reading borsh-encoded values from a byte slice.
+pub struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+macro_rules! read_int {
+    ($($name:ident: $ty:ty),*) => {
+        $(
+            #[doc = concat!("Read a little-endian `", stringify!($ty), "`.")]
+            pub fn $name(&mut self) -> Result<$ty, Error> {
+                let bytes = self.take(core::mem::size_of::<$ty>())?;
+                Ok(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+            }
+        )*
+    };
+}
+
+impl<'a> Reader<'a> {
+    /// Construct a reader at the start of `input`.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// Return the number of bytes not yet read.
+    pub fn remaining(&self) -> usize {
+        self.input.len() - self.pos
+    }
+
+    read_int!(
+        read_u8: u8,
+        read_u16: u16,
+        read_u32: u32,
+        read_u64: u64,
+        read_u128: u128,
+        read_i32: i32,
+        read_i64: i64
+    );
+
+    /// Read a `bool`, encoded as a single byte of 0 or 1.
+    pub fn read_bool(&mut self) -> Result<bool, Error> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+
+    /// Read the u32 length prefix of a sequence.
+    pub fn read_len(&mut self) -> Result<usize, Error> {
+        Ok(self.read_u32()? as usize)
+    }
+
+    /// Read a length-prefixed byte string, borrowed from the input.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.read_len()?;
+        self.take(len)
+    }
+
+    /// Read a length-prefixed UTF-8 string, borrowed from the input.
+    pub fn read_string(&mut self) -> Result<&'a str, Error> {
+        core::str::from_utf8(self.read_bytes()?).map_err(|_| Error::InvalidInput)
+    }
+
+    /// Read a fixed number of bytes, borrowed from the input.
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.remaining() < len {
+            return Err(Error::UnexpectedEof);
+        }
+        let bytes = &self.input[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn read() {
+        let mut input = Vec::new();
+        input.push(7u8);
+        input.extend_from_slice(&0x1234u16.to_le_bytes());
+        input.extend_from_slice(&(-5i64).to_le_bytes());
+        input.extend_from_slice(&u128::MAX.to_le_bytes());
+        input.push(1);
+        input.extend_from_slice(&5u32.to_le_bytes());
+        input.extend_from_slice(b"risc0");
+        input.push(2);
+
+        let mut reader = Reader::new(&input);
+        assert_eq!(reader.read_u8(), Ok(7));
+        assert_eq!(reader.read_u16(), Ok(0x1234));
+        assert_eq!(reader.read_i64(), Ok(-5));
+        assert_eq!(reader.read_u128(), Ok(u128::MAX));
+        assert_eq!(reader.read_bool(), Ok(true));
+        assert_eq!(reader.read_string(), Ok("risc0"));
+        assert_eq!(reader.remaining(), 1);
+        assert_eq!(reader.read_bool(), Err(Error::InvalidInput));
+        assert_eq!(reader.read_u32(), Err(Error::UnexpectedEof));
+    }
+}
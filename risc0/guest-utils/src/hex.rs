@@ -0,0 +1,90 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Table-driven hexadecimal encoding and decoding.
+//!
+//! Each byte is encoded with a single lookup of its two digits, and each pair
+//! of digits is decoded with two lookups and no branches on the digit ranges.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::Error;
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+// The two lowercase digits of each byte.
+const ENCODE: [[u8; 2]; 256] = {
+    let mut table = [[0u8; 2]; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        table[idx] = [DIGITS[idx >> 4], DIGITS[idx & 0xf]];
+        idx += 1;
+    }
+    table
+};
+
+// The value of each digit, in either case, or 0xff for other characters.
+const DECODE: [u8; 256] = {
+    let mut table = [0xffu8; 256];
+    let mut idx = 0;
+    while idx < 16 {
+        table[DIGITS[idx] as usize] = idx as u8;
+        table[DIGITS[idx].to_ascii_uppercase() as usize] = idx as u8;
+        idx += 1;
+    }
+    table
+};
+
+/// Encode bytes as lowercase hexadecimal.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = Vec::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.extend_from_slice(&ENCODE[*byte as usize]);
+    }
+    // Every byte of `out` is an ASCII digit.
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decode hexadecimal digits of either case into bytes.
+pub fn decode(hex: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    let hex = hex.as_ref();
+    if hex.len() % 2 != 0 {
+        return Err(Error::UnexpectedEof);
+    }
+    let mut out = Vec::with_capacity(hex.len() / 2);
+    for pair in hex.chunks_exact(2) {
+        let (hi, lo) = (DECODE[pair[0] as usize], DECODE[pair[1] as usize]);
+        if (hi | lo) == 0xff {
+            return Err(Error::InvalidInput);
+        }
+        out.push(hi << 4 | lo);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let hex = encode(&bytes);
+        assert_eq!(&hex[..8], "00010203");
+        assert_eq!(decode(&hex).unwrap(), bytes);
+        assert_eq!(decode("DeadBeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(decode("abc"), Err(Error::UnexpectedEof));
+        assert_eq!(decode("0g"), Err(Error::InvalidInput));
+    }
+}
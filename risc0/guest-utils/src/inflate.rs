@@ -0,0 +1,642 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! DEFLATE decompression ([RFC 1951]), with the zlib ([RFC 1950]) and gzip
+//! ([RFC 1952]) wrappers.
+//!
+//! Rather than decoding Huffman codes a bit at a time and copying
+//! back-references a byte at a time, codes of up to [FAST_BITS] bits, which
+//! cover nearly all symbols in practice, are decoded with a single table
+//! lookup, and back-references are copied as whole runs with
+//! [Vec::extend_from_within], which lowers to a `memcpy` that the zkVM runs a
+//! word at a time. Bits are buffered in a u64 so that a refill is needed at
+//! most once per symbol.
+//!
+//! [RFC 1950]: https://www.rfc-editor.org/rfc/rfc1950
+//! [RFC 1951]: https://www.rfc-editor.org/rfc/rfc1951
+//! [RFC 1952]: https://www.rfc-editor.org/rfc/rfc1952
+
+use alloc::vec::Vec;
+
+use crate::Error;
+
+/// The number of bits of the codes decoded with a single table lookup.
+pub const FAST_BITS: u32 = 9;
+
+const MAX_BITS: usize = 15;
+const MAX_LIT_CODES: usize = 286;
+const MAX_DIST_CODES: usize = 30;
+const FIXED_LIT_CODES: usize = 288;
+
+// The base and number of extra bits of length symbols 257..=285.
+const LBASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEXT: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// The base and number of extra bits of distance symbols 0..=29.
+const DBASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DEXT: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// The order in which the lengths of the code length code are stored.
+const ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompress raw DEFLATE data.
+pub fn inflate(input: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    inflate_into(input, &mut out)?;
+    Ok(out)
+}
+
+/// Decompress zlib data, checking its Adler-32 checksum.
+///
+/// Streams that depend on a preset dictionary are not supported.
+pub fn zlib_decompress(input: &[u8]) -> Result<Vec<u8>, Error> {
+    let [cmf, flg, ..] = *input else {
+        return Err(Error::UnexpectedEof);
+    };
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 || (cmf as u16 * 256 + flg as u16) % 31 != 0 {
+        return Err(Error::InvalidHeader);
+    }
+    if flg & 0x20 != 0 {
+        return Err(Error::InvalidHeader);
+    }
+    let mut out = Vec::new();
+    let len = 2 + inflate_into(&input[2..], &mut out)?;
+    let trailer = input.get(len..len + 4).ok_or(Error::UnexpectedEof)?;
+    if u32::from_be_bytes(trailer.try_into().unwrap()) != adler32(&out) {
+        return Err(Error::ChecksumMismatch);
+    }
+    Ok(out)
+}
+
+/// Decompress the first member of gzip data, checking its CRC-32 and size,
+/// and the CRC of its header if it has one.
+///
+/// The output is allocated up front from the size in the trailer of the
+/// input, which is not checked until the end, so at most `max_capacity`
+/// bytes are allocated from it. The output may still grow beyond that.
+pub fn gunzip(input: &[u8], max_capacity: usize) -> Result<Vec<u8>, Error> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    let header = input.get(..10).ok_or(Error::UnexpectedEof)?;
+    let flags = header[3];
+    if header[..3] != [0x1f, 0x8b, 8] || flags & 0xe0 != 0 {
+        return Err(Error::InvalidHeader);
+    }
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        let len = input.get(pos..pos + 2).ok_or(Error::UnexpectedEof)?;
+        pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let rest = input.get(pos..).ok_or(Error::UnexpectedEof)?;
+            pos += 1 + rest
+                .iter()
+                .position(|x| *x == 0)
+                .ok_or(Error::UnexpectedEof)?;
+        }
+    }
+    if flags & FHCRC != 0 {
+        // The header CRC is the low 16 bits of the CRC-32 of the header.
+        let crc = input.get(pos..pos + 2).ok_or(Error::UnexpectedEof)?;
+        if u16::from_le_bytes([crc[0], crc[1]]) != crc32(&input[..pos]) as u16 {
+            return Err(Error::ChecksumMismatch);
+        }
+        pos += 2;
+    }
+    let body = input.get(pos..).ok_or(Error::UnexpectedEof)?;
+
+    let mut out = output_buffer(input, pos, max_capacity);
+    let len = pos + inflate_into(body, &mut out)?;
+    let trailer = input.get(len..len + 8).ok_or(Error::UnexpectedEof)?;
+    let crc = u32::from_le_bytes(trailer[..4].try_into().unwrap());
+    let size = u32::from_le_bytes(trailer[4..].try_into().unwrap());
+    if crc != crc32(&out) || size != out.len() as u32 {
+        return Err(Error::ChecksumMismatch);
+    }
+    Ok(out)
+}
+
+// Allocate the output of gzip data with a header of `header_len` bytes. The
+// size in the trailer is only known after decompressing, but it is usually at
+// the end of the input, so use it, bounded by `max_capacity` and by the
+// largest ratio of DEFLATE, to size the output up front and avoid copying the
+// output as it grows.
+fn output_buffer(input: &[u8], header_len: usize, max_capacity: usize) -> Vec<u8> {
+    match input.len().checked_sub(4) {
+        Some(end) if end >= header_len => {
+            let size = u32::from_le_bytes(input[end..].try_into().unwrap()) as usize;
+            Vec::with_capacity(size.min(max_capacity).min(input.len().saturating_mul(1032)))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Decompress raw DEFLATE data, appending it to `out`, and return the number
+/// of bytes of `input` consumed.
+pub fn inflate_into(input: &[u8], out: &mut Vec<u8>) -> Result<usize, Error> {
+    let mut bits = BitReader::new(input);
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => stored(&mut bits, out)?,
+            1 => {
+                let (lit, dist) = fixed_tables();
+                codes(&mut bits, out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut bits)?;
+                codes(&mut bits, out, &lit, &dist)?;
+            }
+            _ => return Err(Error::InvalidData),
+        }
+        if last {
+            return Ok(bits.consumed());
+        }
+    }
+}
+
+// Reads the bits of a DEFLATE stream, least significant bit first.
+struct BitReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    buf: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            pos: 0,
+            buf: 0,
+            count: 0,
+        }
+    }
+
+    // Fill the buffer with as many whole bytes as fit.
+    #[inline]
+    fn refill(&mut self) {
+        while self.count <= 56 && self.pos < self.input.len() {
+            self.buf |= (self.input[self.pos] as u64) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+    }
+
+    // Return the next `n` bits without consuming them, padded with zeros past
+    // the end of the input.
+    #[inline]
+    fn peek(&mut self, n: u32) -> u32 {
+        if self.count < n {
+            self.refill();
+        }
+        (self.buf & ((1 << n) - 1)) as u32
+    }
+
+    #[inline]
+    fn consume(&mut self, n: u32) -> Result<(), Error> {
+        if self.count < n {
+            return Err(Error::UnexpectedEof);
+        }
+        self.buf >>= n;
+        self.count -= n;
+        Ok(())
+    }
+
+    #[inline]
+    fn read(&mut self, n: u32) -> Result<u32, Error> {
+        let value = self.peek(n);
+        self.consume(n)?;
+        Ok(value)
+    }
+
+    // Discard the bits up to the next byte boundary, and return the buffered
+    // whole bytes to the input.
+    fn align(&mut self) {
+        self.pos -= (self.count / 8) as usize;
+        self.buf = 0;
+        self.count = 0;
+    }
+
+    // Return the number of bytes consumed, counting a partly read byte.
+    fn consumed(&self) -> usize {
+        self.pos - (self.count / 8) as usize
+    }
+}
+
+// A canonical Huffman code, with a lookup table for the codes of up to
+// FAST_BITS bits.
+struct Huffman<const N: usize> {
+    // For each FAST_BITS-bit prefix of the input, the length of its code in
+    // bits 12..16 and its symbol in bits 0..12, or 0 if the code is longer.
+    fast: [u16; 1 << FAST_BITS],
+    // The number of codes of each length.
+    count: [u16; MAX_BITS + 1],
+    // The symbols, ordered by code.
+    symbol: [u16; N],
+}
+
+impl<const N: usize> Huffman<N> {
+    fn new(lengths: &[u8]) -> Result<Self, Error> {
+        let mut huffman = Self {
+            fast: [0; 1 << FAST_BITS],
+            count: [0; MAX_BITS + 1],
+            symbol: [0; N],
+        };
+        for len in lengths {
+            huffman.count[*len as usize] += 1;
+        }
+        huffman.count[0] = 0;
+
+        // Reject over-subscribed codes. Incomplete codes are allowed, as the
+        // distance code may have a single symbol, and decoding fails if the
+        // input uses a missing code.
+        let mut left = 1i32;
+        for len in 1..=MAX_BITS {
+            left = (left << 1) - huffman.count[len] as i32;
+            if left < 0 {
+                return Err(Error::InvalidData);
+            }
+        }
+
+        let mut offset = [0u16; MAX_BITS + 1];
+        let mut next = [0u32; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offset[len + 1] = offset[len] + huffman.count[len];
+            next[len + 1] = (next[len] + huffman.count[len] as u32) << 1;
+        }
+        for (sym, len) in lengths.iter().enumerate() {
+            let len = *len as usize;
+            if len == 0 {
+                continue;
+            }
+            huffman.symbol[offset[len] as usize] = sym as u16;
+            offset[len] += 1;
+            let code = next[len];
+            next[len] += 1;
+            if len <= FAST_BITS as usize {
+                // Codes are stored most significant bit first, so reverse
+                // them to index by the bits as read.
+                let rev = code.reverse_bits() >> (32 - len);
+                let entry = (len as u16) << 12 | sym as u16;
+                for fill in (rev as usize..1 << FAST_BITS).step_by(1 << len) {
+                    huffman.fast[fill] = entry;
+                }
+            }
+        }
+        Ok(huffman)
+    }
+
+    #[inline]
+    fn decode(&self, bits: &mut BitReader) -> Result<usize, Error> {
+        let entry = self.fast[bits.peek(FAST_BITS) as usize];
+        if entry != 0 {
+            bits.consume((entry >> 12) as u32)?;
+            return Ok((entry & 0xfff) as usize);
+        }
+        self.decode_slow(bits)
+    }
+
+    // Decode a code longer than FAST_BITS, or a missing code, one bit at a
+    // time.
+    fn decode_slow(&self, bits: &mut BitReader) -> Result<usize, Error> {
+        let input = bits.peek(MAX_BITS as u32);
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= ((input >> (len - 1)) & 1) as i32;
+            let count = self.count[len] as i32;
+            if code - first < count {
+                bits.consume(len as u32)?;
+                return Ok(self.symbol[(index + code - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::InvalidData)
+    }
+}
+
+type LitTable = Huffman<FIXED_LIT_CODES>;
+type DistTable = Huffman<MAX_DIST_CODES>;
+
+fn fixed_tables() -> (LitTable, DistTable) {
+    let mut lengths = [0u8; FIXED_LIT_CODES];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    // The fixed codes are complete, so building them cannot fail.
+    (
+        Huffman::new(&lengths).unwrap(),
+        Huffman::new(&[5; MAX_DIST_CODES]).unwrap(),
+    )
+}
+
+fn dynamic_tables(bits: &mut BitReader) -> Result<(LitTable, DistTable), Error> {
+    let nlen = bits.read(5)? as usize + 257;
+    let ndist = bits.read(5)? as usize + 1;
+    let ncode = bits.read(4)? as usize + 4;
+    if nlen > MAX_LIT_CODES || ndist > MAX_DIST_CODES {
+        return Err(Error::InvalidData);
+    }
+
+    let mut lengths = [0u8; MAX_LIT_CODES + MAX_DIST_CODES];
+    for idx in ORDER.iter().take(ncode) {
+        lengths[*idx] = bits.read(3)? as u8;
+    }
+    let lencode = Huffman::<19>::new(&lengths[..19])?;
+
+    let mut idx = 0;
+    lengths[..19].fill(0);
+    while idx < nlen + ndist {
+        let sym = lencode.decode(bits)?;
+        if sym < 16 {
+            lengths[idx] = sym as u8;
+            idx += 1;
+            continue;
+        }
+        let (value, repeat) = match sym {
+            16 => {
+                let prev = *idx
+                    .checked_sub(1)
+                    .and_then(|prev| lengths.get(prev))
+                    .ok_or(Error::InvalidData)?;
+                (prev, 3 + bits.read(2)? as usize)
+            }
+            17 => (0, 3 + bits.read(3)? as usize),
+            _ => (0, 11 + bits.read(7)? as usize),
+        };
+        let end = idx + repeat;
+        if end > nlen + ndist {
+            return Err(Error::InvalidData);
+        }
+        lengths[idx..end].fill(value);
+        idx = end;
+    }
+
+    // A block without an end-of-block code cannot end.
+    if lengths[256] == 0 {
+        return Err(Error::InvalidData);
+    }
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..nlen + ndist])?,
+    ))
+}
+
+fn stored(bits: &mut BitReader, out: &mut Vec<u8>) -> Result<(), Error> {
+    bits.align();
+    let input = &bits.input[bits.pos..];
+    let header = input.get(..4).ok_or(Error::UnexpectedEof)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(Error::InvalidData);
+    }
+    let data = input.get(4..4 + len as usize).ok_or(Error::UnexpectedEof)?;
+    out.extend_from_slice(data);
+    bits.pos += 4 + len as usize;
+    Ok(())
+}
+
+fn codes(
+    bits: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &LitTable,
+    dist: &DistTable,
+) -> Result<(), Error> {
+    loop {
+        let sym = lit.decode(bits)?;
+        if sym < 256 {
+            out.push(sym as u8);
+            continue;
+        }
+        if sym == 256 {
+            return Ok(());
+        }
+
+        let sym = sym - 257;
+        if sym >= LBASE.len() {
+            return Err(Error::InvalidData);
+        }
+        let len = LBASE[sym] as usize + bits.read(LEXT[sym] as u32)? as usize;
+        let sym = dist.decode(bits)?;
+        if sym >= DBASE.len() {
+            return Err(Error::InvalidData);
+        }
+        let dist = DBASE[sym] as usize + bits.read(DEXT[sym] as u32)? as usize;
+        if dist > out.len() {
+            return Err(Error::InvalidData);
+        }
+        copy_match(out, dist, len);
+    }
+}
+
+// Append `len` bytes starting `dist` bytes before the end of `out`.
+//
+// When the source and destination overlap, the repeated pattern is copied a
+// period at a time, so each copy is a single non-overlapping `memcpy`.
+#[inline]
+fn copy_match(out: &mut Vec<u8>, dist: usize, len: usize) {
+    if dist == 1 {
+        let byte = out[out.len() - 1];
+        out.resize(out.len() + len, byte);
+        return;
+    }
+    let mut remaining = len;
+    while remaining > 0 {
+        let start = out.len() - dist;
+        let chunk = remaining.min(dist);
+        out.extend_from_within(start..start + chunk);
+        remaining -= chunk;
+    }
+}
+
+/// Compute the Adler-32 checksum of `data`, as used by zlib.
+pub fn adler32(data: &[u8]) -> u32 {
+    // The largest number of bytes that can be summed before `b` may overflow.
+    const NMAX: usize = 5552;
+    const MOD: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(NMAX) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
+
+/// Compute the CRC-32 checksum of `data`, as used by gzip.
+pub fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut idx = 0;
+        while idx < 256 {
+            let mut crc = idx as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = match crc & 1 {
+                    1 => 0xedb88320 ^ (crc >> 1),
+                    _ => crc >> 1,
+                };
+                bit += 1;
+            }
+            table[idx] = crc;
+            idx += 1;
+        }
+        table
+    };
+
+    let mut crc = !0u32;
+    for byte in data {
+        crc = TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{io::Write, vec};
+
+    use flate2::{
+        write::{DeflateEncoder, GzEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    use super::*;
+
+    // Data with both literals and long, overlapping back-references.
+    fn sample() -> Vec<u8> {
+        let mut data = Vec::new();
+        for idx in 0..20000u32 {
+            data.extend_from_slice(&(idx % 251).to_le_bytes()[..(idx % 3) as usize + 1]);
+            if idx % 97 == 0 {
+                data.extend_from_slice(&[b'a'; 300]);
+            }
+        }
+        data
+    }
+
+    fn compress<W: Write>(mut encoder: W, data: &[u8]) -> W {
+        encoder.write_all(data).unwrap();
+        encoder
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = sample();
+        for level in [
+            Compression::none(),
+            Compression::fast(),
+            Compression::best(),
+        ] {
+            let raw = compress(DeflateEncoder::new(Vec::new(), level), &data)
+                .finish()
+                .unwrap();
+            assert_eq!(inflate(&raw).unwrap(), data);
+
+            let zlib = compress(ZlibEncoder::new(Vec::new(), level), &data)
+                .finish()
+                .unwrap();
+            assert_eq!(zlib_decompress(&zlib).unwrap(), data);
+
+            let gzip = compress(GzEncoder::new(Vec::new(), level), &data)
+                .finish()
+                .unwrap();
+            assert_eq!(gunzip(&gzip, usize::MAX).unwrap(), data);
+            assert_eq!(gunzip(&gzip, 16).unwrap(), data);
+        }
+        assert_eq!(inflate(&[0x03, 0x00]).unwrap(), b"");
+    }
+
+    #[test]
+    fn corrupted() {
+        let data = sample();
+        let mut zlib = compress(ZlibEncoder::new(Vec::new(), Compression::best()), &data)
+            .finish()
+            .unwrap();
+        let len = zlib.len();
+        assert_eq!(zlib_decompress(&zlib[..len - 1]), Err(Error::UnexpectedEof));
+        zlib[len - 1] ^= 1;
+        assert_eq!(zlib_decompress(&zlib), Err(Error::ChecksumMismatch));
+        zlib[0] = 0x79;
+        assert_eq!(zlib_decompress(&zlib), Err(Error::InvalidHeader));
+        assert_eq!(inflate(&[0x07]), Err(Error::InvalidData));
+    }
+
+    #[test]
+    fn gzip_capacity() {
+        let data = sample();
+        let mut gzip = compress(GzEncoder::new(Vec::new(), Compression::best()), &data)
+            .finish()
+            .unwrap();
+        assert!(output_buffer(&gzip, 10, usize::MAX).capacity() >= data.len());
+
+        // A forged size in the trailer is bounded by the limit of the caller.
+        let len = gzip.len();
+        gzip[len - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(output_buffer(&gzip, 10, 1024).capacity() < 2048);
+        assert_eq!(gunzip(&gzip, 1024), Err(Error::ChecksumMismatch));
+    }
+
+    #[test]
+    fn gzip_header_crc() {
+        let data = sample();
+        let raw = compress(DeflateEncoder::new(Vec::new(), Compression::best()), &data)
+            .finish()
+            .unwrap();
+        // A header with FHCRC set, followed by its CRC.
+        let mut gzip = vec![0x1f, 0x8b, 8, 0x02, 0, 0, 0, 0, 0, 0xff];
+        let crc = crc32(&gzip) as u16;
+        gzip.extend_from_slice(&crc.to_le_bytes());
+        gzip.extend_from_slice(&raw);
+        gzip.extend_from_slice(&crc32(&data).to_le_bytes());
+        gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        assert_eq!(gunzip(&gzip, usize::MAX).unwrap(), data);
+
+        gzip[10] ^= 1;
+        assert_eq!(gunzip(&gzip, usize::MAX), Err(Error::ChecksumMismatch));
+        assert_eq!(gunzip(&gzip[..11], usize::MAX), Err(Error::UnexpectedEof));
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+}
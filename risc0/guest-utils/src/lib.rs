@@ -0,0 +1,65 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(missing_docs)]
+
+extern crate alloc;
+
+pub mod base64;
+pub mod borsh;
+pub mod hex;
+pub mod inflate;
+pub mod varint;
+
+use core::fmt;
+
+/// An error decoding or decompressing data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The input ended before the data was complete.
+    UnexpectedEof,
+
+    /// The input has a character or byte that is not valid in its encoding.
+    InvalidInput,
+
+    /// The header of a compressed stream is not valid.
+    InvalidHeader,
+
+    /// The compressed data is not valid DEFLATE data.
+    InvalidData,
+
+    /// The checksum of decompressed data does not match its trailer.
+    ChecksumMismatch,
+
+    /// A decoded integer does not fit in its type.
+    Overflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEof => write!(f, "unexpected end of input"),
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::InvalidHeader => write!(f, "invalid header"),
+            Error::InvalidData => write!(f, "invalid compressed data"),
+            Error::ChecksumMismatch => write!(f, "checksum mismatch"),
+            Error::Overflow => write!(f, "integer overflow"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
@@ -0,0 +1,106 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! LEB128 variable-length integers, as used by protobuf and WebAssembly.
+//!
+//! Decoding returns the value along with the number of bytes consumed, so
+//! that a caller can walk a buffer without copying it. Values that fit in a
+//! single byte, the common case, take one load and one branch.
+
+use alloc::vec::Vec;
+
+use crate::Error;
+
+/// Decode an unsigned LEB128 integer of at most 32 bits, returning the value
+/// and the number of bytes consumed.
+pub fn decode_u32(input: &[u8]) -> Result<(u32, usize), Error> {
+    let (value, len) = decode_u64(input)?;
+    let value = u32::try_from(value).map_err(|_| Error::Overflow)?;
+    Ok((value, len))
+}
+
+/// Decode an unsigned LEB128 integer of at most 64 bits, returning the value
+/// and the number of bytes consumed.
+pub fn decode_u64(input: &[u8]) -> Result<(u64, usize), Error> {
+    match input.first() {
+        Some(byte) if byte & 0x80 == 0 => return Ok((*byte as u64, 1)),
+        None => return Err(Error::UnexpectedEof),
+        _ => {}
+    }
+    let mut value = 0u64;
+    for (idx, byte) in input.iter().enumerate() {
+        let shift = idx as u32 * 7;
+        let bits = (byte & 0x7f) as u64;
+        // The tenth byte may only hold the top bit of a u64.
+        if shift == 63 && bits > 1 {
+            return Err(Error::Overflow);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, idx + 1));
+        }
+        if shift == 63 {
+            return Err(Error::Overflow);
+        }
+    }
+    Err(Error::UnexpectedEof)
+}
+
+/// Decode a zigzag-encoded signed LEB128 integer, as used by the protobuf
+/// `sint64` type, returning the value and the number of bytes consumed.
+pub fn decode_i64(input: &[u8]) -> Result<(i64, usize), Error> {
+    let (value, len) = decode_u64(input)?;
+    Ok(((value >> 1) as i64 ^ -((value & 1) as i64), len))
+}
+
+/// Append an unsigned LEB128 integer to `out`.
+pub fn encode_u64(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Append a zigzag-encoded signed LEB128 integer to `out`.
+pub fn encode_i64(value: i64, out: &mut Vec<u8>) {
+    encode_u64(((value << 1) ^ (value >> 63)) as u64, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut out = Vec::new();
+            encode_u64(value, &mut out);
+            assert_eq!(decode_u64(&out).unwrap(), (value, out.len()));
+        }
+        for value in [0, -1, 1, -64, 64, i64::MIN, i64::MAX] {
+            let mut out = Vec::new();
+            encode_i64(value, &mut out);
+            assert_eq!(decode_i64(&out).unwrap(), (value, out.len()));
+        }
+        assert_eq!(decode_u64(&[0xac, 0x02, 0xff]).unwrap(), (300, 2));
+        assert_eq!(
+            decode_u32(&[0x80, 0x80, 0x80, 0x80, 0x10]),
+            Err(Error::Overflow)
+        );
+        assert_eq!(decode_u64(&[0xff; 10]), Err(Error::Overflow));
+        assert_eq!(decode_u64(&[0x80, 0x80]), Err(Error::UnexpectedEof));
+        assert_eq!(decode_u64(&[]), Err(Error::UnexpectedEof));
+    }
+}
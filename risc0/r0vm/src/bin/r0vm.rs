@@ -15,15 +15,23 @@
 use std::{fs, path::PathBuf};
 
 use clap::Parser;
-use risc0_zkvm::{serde::to_vec, Executor, ExecutorEnv};
+use risc0_zkvm::{cost::CostModel, prove::default_prover, serde::to_vec, Executor, ExecutorEnv};
+
+/// The segment sizes proven by `--calibrate`, as powers of 2 cycles.
+const CALIBRATION_PO2S: std::ops::RangeInclusive<usize> = 14..=18;
 
 /// Runs a RISC-V ELF binary within the RISC Zero ZKVM.
 #[derive(Parser)]
 #[clap(about, version, author)]
 struct Args {
     /// The ELF file to run
-    #[clap(long)]
-    elf: PathBuf,
+    #[clap(long, required_unless_present = "calibrate")]
+    elf: Option<PathBuf>,
+
+    /// Benchmark the prover on this machine, and save a cost profile to this
+    /// file instead of running an ELF.
+    #[clap(long, conflicts_with = "elf")]
+    calibrate: Option<PathBuf>,
 
     /// Receipt output file.
     #[clap(long)]
@@ -53,13 +61,23 @@ fn main() {
     env_logger::init();
 
     let args = Args::parse();
-    let elf_contents = fs::read(&args.elf).unwrap();
+
+    if let Some(profile) = args.calibrate.as_ref() {
+        let prover = default_prover();
+        let model = CostModel::calibrate(prover.as_ref(), CALIBRATION_PO2S).unwrap();
+        model.save(profile).expect("Unable to write cost profile");
+        print!("{model}");
+        return;
+    }
+
+    let elf = args.elf.as_ref().unwrap();
+    let elf_contents = fs::read(elf).unwrap();
 
     if args.verbose > 0 {
         eprintln!(
             "Read {} bytes of ELF from {}",
             elf_contents.len(),
-            args.elf.display()
+            elf.display()
         );
     }
 
@@ -67,8 +85,7 @@ fn main() {
     let mut guest_prof: Option<risc0_zkvm::Profiler> = None;
     #[cfg(feature = "profiler")]
     if args.pprof_out.is_some() {
-        guest_prof =
-            Some(risc0_zkvm::Profiler::new(args.elf.to_str().unwrap(), &elf_contents).unwrap());
+        guest_prof = Some(risc0_zkvm::Profiler::new(elf.to_str().unwrap(), &elf_contents).unwrap());
     }
 
     let session = {
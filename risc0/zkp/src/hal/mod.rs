@@ -58,6 +58,10 @@ pub trait Hal {
         TRACKER.lock().unwrap().peak
     }
 
+    fn get_memory_in_use(&self) -> usize {
+        TRACKER.lock().unwrap().total
+    }
+
     fn get_hash_suite(&self) -> &HashSuite<Self::Field>;
 
     fn alloc_digest(&self, name: &'static str, size: usize) -> Self::Buffer<Digest>;
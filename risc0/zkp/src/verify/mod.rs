@@ -42,6 +42,7 @@ pub enum VerificationError {
     JournalDigestMismatch,
     UnexpectedExitCode,
     InvalidHashSuite,
+    InputDigestMismatch,
 }
 
 impl fmt::Display for VerificationError {
@@ -60,6 +61,7 @@ impl fmt::Display for VerificationError {
             }
             VerificationError::UnexpectedExitCode => write!(f, "Unexpected exit_code"),
             VerificationError::InvalidHashSuite => write!(f, "Invalid hash suite"),
+            VerificationError::InputDigestMismatch => write!(f, "Input digest mismatch detected"),
         }
     }
 }
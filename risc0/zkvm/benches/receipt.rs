@@ -0,0 +1,82 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of proving and verifying receipts across segment counts.
+//!
+//! Unlike the `fib` benchmark, which varies the length of the computation,
+//! these benchmarks fix the segment size and vary the number of segments, to
+//! measure the cost of a session as a whole: proving every segment, and
+//! verifying the chain of segment receipts. The throughput of the `prove`
+//! jobs is reported in segments, and that of the `verify` jobs in bytes of
+//! the serialized receipt, so the size of each receipt is recorded with its
+//! measurements.
+
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use risc0_zkvm::{
+    prove::default_prover, serde::to_vec, Executor, ExecutorEnv, Receipt, VerifierContext,
+};
+use risc0_zkvm_methods::{multi_test::MultiTestSpec, MULTI_TEST_ELF, MULTI_TEST_ID};
+
+const SEGMENT_LIMIT_PO2: usize = 14;
+
+fn setup(segments: u32) -> Executor<'static> {
+    // Leave room in the last segment for the guest to start up and halt.
+    let cycles = (segments - 1) * (1 << SEGMENT_LIMIT_PO2) + (1 << (SEGMENT_LIMIT_PO2 - 1));
+    let spec = to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(SEGMENT_LIMIT_PO2)
+        .build()
+        .unwrap();
+    Executor::from_elf(env, MULTI_TEST_ELF).unwrap()
+}
+
+pub fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("receipt");
+    group.sample_size(10);
+
+    let prover = default_prover();
+    let ctx = VerifierContext::default();
+
+    for segments in [1, 2, 4, 8] {
+        let session = setup(segments).run().unwrap();
+        let receipt = prover.prove_session(&ctx, &session).unwrap();
+        let size = bincode::serialize(&receipt).unwrap().len();
+
+        group.throughput(Throughput::Elements(session.segments.len() as u64));
+        let id = BenchmarkId::from_parameter(format!("{segments}/prove"));
+        group.bench_with_input(id, &segments, |b, &segments| {
+            b.iter_batched(
+                || setup(segments).run().unwrap(),
+                |session| black_box(prover.prove_session(&ctx, &session).unwrap()),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.throughput(Throughput::Bytes(size as u64));
+        let id = BenchmarkId::from_parameter(format!("{segments}/verify"));
+        group.bench_with_input(id, &receipt, |b, receipt: &Receipt| {
+            b.iter(|| black_box(receipt.verify(MULTI_TEST_ID).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(name = benches;
+    config = Criterion::default();
+    targets = bench);
+criterion_main!(benches);
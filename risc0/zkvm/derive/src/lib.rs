@@ -0,0 +1,237 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, ItemFn, LitInt, ReturnType};
+
+/// Derive `risc0_zkvm::serde::JournalCommit` for a type that also implements
+/// `Serialize` and `Deserialize`.
+///
+/// The schema version defaults to 1, and is set with
+/// `#[journal(version = N)]`. A schema ID is derived from the names and types
+/// of the fields, so that a host decoding a journal written by a guest built
+/// with a different definition of the type fails even if the version was not
+/// bumped.
+#[proc_macro_derive(JournalCommit, attributes(journal))]
+pub fn derive_journal_commit(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut version = 1u32;
+    for attr in input.attrs.iter().filter(|x| x.path().is_ident("journal")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("version") {
+                version = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported journal attribute"))
+            }
+        })?;
+    }
+
+    let name = &input.ident;
+    let schema_id = fnv1a(schema(&input)?.as_bytes());
+    let name_str = name.to_string();
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::risc0_zkvm::serde::JournalCommit for #name #ty_generics #where_clause {
+            const NAME: &'static str = #name_str;
+            const VERSION: u32 = #version;
+            const SCHEMA_ID: u32 = #schema_id;
+        }
+    })
+}
+
+/// Mark a function of a guest as a test to run in the zkVM with
+/// `risc0_zkvm::testing`.
+///
+/// The function takes no arguments and returns nothing, and fails the test by
+/// panicking, as a `#[test]` function does. It is recorded under its module
+/// path in the ELF of the guest, for the host to find and run it.
+#[proc_macro_attribute]
+pub fn risc0_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "risc0_test does not take arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let item = parse_macro_input!(item as ItemFn);
+    match expand_test(item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_test(item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &item.sig;
+    if !sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "risc0_test functions cannot take arguments",
+        ));
+    }
+    if !matches!(sig.output, ReturnType::Default) {
+        return Err(syn::Error::new_spanned(
+            &sig.output,
+            "risc0_test functions cannot return a value",
+        ));
+    }
+    if sig.asyncness.is_some() || sig.unsafety.is_some() || !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "risc0_test functions must be plain `fn()` functions",
+        ));
+    }
+
+    let name = &sig.ident;
+    Ok(quote! {
+        #item
+
+        const _: () = {
+            const NAME: &str = concat!(module_path!(), "::", stringify!(#name));
+            #[used]
+            #[link_section = ".risc0.tests"]
+            static TEST: ::risc0_zkvm::testing::TestRecord<{ NAME.len() }> =
+                ::risc0_zkvm::testing::TestRecord::new(#name, NAME);
+        };
+    })
+}
+
+// Describe the shape of the given type, from which its schema ID is derived.
+fn schema(input: &DeriveInput) -> syn::Result<String> {
+    let name = &input.ident;
+    match &input.data {
+        Data::Struct(data) => Ok(format!("{name}{}", fields_schema(&data.fields))),
+        Data::Enum(data) => {
+            let variants: Vec<String> = data
+                .variants
+                .iter()
+                .map(|x| format!("{}{}", x.ident, fields_schema(&x.fields)))
+                .collect();
+            Ok(format!("{name}{{{}}}", variants.join(",")))
+        }
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "JournalCommit cannot be derived for unions",
+        )),
+    }
+}
+
+// Describe the given fields as `{name:Type,...}` or `(Type,...)`, with all
+// whitespace removed so that the description does not depend on how the
+// compiler formats tokens.
+fn fields_schema(fields: &Fields) -> String {
+    let describe = |ty: &syn::Type| -> String {
+        ty.to_token_stream()
+            .to_string()
+            .chars()
+            .filter(|x| !x.is_whitespace())
+            .collect()
+    };
+    match fields {
+        Fields::Named(fields) => {
+            let fields: Vec<String> = fields
+                .named
+                .iter()
+                .map(|x| format!("{}:{}", x.ident.as_ref().unwrap(), describe(&x.ty)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Fields::Unnamed(fields) => {
+            let fields: Vec<String> = fields.unnamed.iter().map(|x| describe(&x.ty)).collect();
+            format!("({})", fields.join(","))
+        }
+        Fields::Unit => String::new(),
+    }
+}
+
+// 32-bit FNV-1a, which is stable across compiler versions, unlike
+// `std::hash`.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn schema_id(input: DeriveInput) -> u32 {
+        fnv1a(schema(&input).unwrap().as_bytes())
+    }
+
+    #[test]
+    fn schema_id_is_pinned() {
+        // Changing this value breaks decoding journals written by existing
+        // guests, so it must stay the same across releases.
+        let id = schema_id(parse_quote! {
+            struct TypedCommit {
+                value: u32,
+                data: Vec<u8>,
+            }
+        });
+        assert_eq!(id, 0xf67d3c14);
+
+        // Formatting and attributes do not change the schema.
+        let reformatted = schema_id(parse_quote! {
+            #[journal(version = 2)]
+            struct TypedCommit { value : u32, data : Vec < u8 > }
+        });
+        assert_eq!(reformatted, id);
+    }
+
+    #[test]
+    fn schema_id_tracks_fields() {
+        let changes = [
+            schema_id(parse_quote! { struct TypedCommit { value: u64, data: Vec<u8> } }),
+            schema_id(parse_quote! { struct TypedCommit { data: Vec<u8>, value: u32 } }),
+            schema_id(parse_quote! { struct TypedCommit { value: u32, bytes: Vec<u8> } }),
+            schema_id(parse_quote! { struct TypedCommit { value: u32 } }),
+            schema_id(parse_quote! { struct Renamed { value: u32, data: Vec<u8> } }),
+            schema_id(parse_quote! { struct TypedCommit(u32, Vec<u8>); }),
+        ];
+        assert_eq!(changes[0], 0xbc451357);
+        assert_eq!(changes[1], 0x1b8ef41e);
+        assert_eq!(changes[2], 0xd2709239);
+        for (idx, changed) in changes.iter().enumerate() {
+            assert_ne!(*changed, 0xf67d3c14, "change {idx}");
+        }
+
+        let variants = schema_id(parse_quote! { enum Event { A, B(u32) } });
+        assert_ne!(
+            variants,
+            schema_id(parse_quote! { enum Event { A, B(u64) } })
+        );
+        assert_ne!(
+            variants,
+            schema_id(parse_quote! { enum Event { B(u32), A } })
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the guest cycles spent decompressing DEFLATE data with
+//! `risc0-guest-utils` and with `miniz_oxide`.
+//!
+//! Each decoder is run once with no iterations and once with one, so that
+//! the difference in cycles is the cost of a single decompression, excluding
+//! loading the guest and reading its input.
+
+use std::io::Write;
+
+use flate2::{write::DeflateEncoder, Compression};
+use risc0_zkvm::{serde::to_vec, Executor, ExecutorEnv};
+use risc0_zkvm_methods::{
+    bench::{BenchmarkSpec, SpecWithIters},
+    BENCH_ELF,
+};
+
+// Run the bench guest and return the number of instruction cycles it took.
+fn run_guest(spec: SpecWithIters) -> usize {
+    let env = ExecutorEnv::builder()
+        .add_input(&to_vec(&spec).unwrap())
+        .build()
+        .unwrap();
+    let mut exec = Executor::from_elf(env, BENCH_ELF).unwrap();
+    let session = exec.run().unwrap();
+    session
+        .resolve()
+        .unwrap()
+        .iter()
+        .map(|segment| segment.insn_cycles)
+        .sum()
+}
+
+// The cycles spent by a single run of the given benchmark.
+fn cycles(spec: BenchmarkSpec) -> usize {
+    run_guest(SpecWithIters(spec.clone(), 1)) - run_guest(SpecWithIters(spec, 0))
+}
+
+// Text-like data, with literals and back-references of all lengths.
+fn sample(len: usize) -> Vec<u8> {
+    let words = [
+        "risc", "zero", "guest", "journal", "receipt", "seal", "segment", "proof", "image",
+    ];
+    let mut data = Vec::new();
+    let mut idx = 0usize;
+    while data.len() < len {
+        data.extend_from_slice(words[idx * 7 % words.len()].as_bytes());
+        data.push(if idx % 13 == 0 { b'\n' } else { b' ' });
+        data.extend_from_slice(&(idx as u32).to_le_bytes()[..idx % 3]);
+        idx += 1;
+    }
+    data.truncate(len);
+    data
+}
+
+fn main() {
+    println!(
+        "| {:>10} | {:>10} | {:>12} | {:>12} |",
+        "Size", "Compressed", "guest-utils", "miniz_oxide"
+    );
+    for len in [1024, 4 * 1024, 16 * 1024, 64 * 1024] {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&sample(len)).unwrap();
+        let data = encoder.finish().unwrap();
+
+        let ours = cycles(BenchmarkSpec::Inflate { data: data.clone() });
+        let miniz = cycles(BenchmarkSpec::MinizInflate { data: data.clone() });
+        println!(
+            "| {:>10} | {:>10} | {:>12} | {:>12} |",
+            len,
+            data.len(),
+            ours,
+            miniz
+        );
+    }
+}
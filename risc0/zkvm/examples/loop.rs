@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{process::Command, rc::Rc, time::Instant};
+use std::{
+    process::Command,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use clap::Parser;
 use human_repr::{HumanCount, HumanDuration};
@@ -34,56 +38,128 @@ struct Args {
     #[arg(long, short)]
     iterations: Option<u64>,
 
+    /// Number of untimed runs to perform before sampling.
+    #[arg(long, default_value_t = 1)]
+    warmup: u64,
+
+    /// Number of timed runs used to compute the reported statistics.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    samples: u64,
+
     #[arg(long, short)]
     quiet: bool,
 }
 
-fn main() {
-    let args = Args::parse();
-    if let Some(iterations) = args.iterations {
-        tracing_subscriber::registry()
-            .with(EnvFilter::from_default_env())
-            .with(tracing_forest::ForestLayer::default())
-            .init();
-
-        let prover = default_prover();
+/// A single timed run of the loop benchmark.
+struct Benchmark {
+    duration: Duration,
+    cycles: u64,
+    seal: usize,
+}
 
+impl Benchmark {
+    fn run(prover: Rc<dyn Prover>, iterations: u64) -> Self {
         let start = Instant::now();
-        let (session, receipt) = top(prover.clone(), iterations);
-        let segments = session.resolve().unwrap();
+        let (session, receipt) = top(prover, iterations);
         let duration = start.elapsed();
 
-        let cycles = segments
+        let cycles = session
+            .resolve()
+            .unwrap()
             .iter()
             .fold(0, |acc, segment| acc + (1 << segment.po2));
-
         let seal = receipt
             .inner
             .flat()
             .iter()
             .fold(0, |acc, segment| acc + segment.get_seal_bytes().len());
+        Self {
+            duration,
+            cycles,
+            seal,
+        }
+    }
+}
+
+/// Summary statistics over the sampled runs of the loop benchmark.
+struct BenchmarkAverage {
+    cycles: u64,
+    seal: usize,
+    mean: Duration,
+    median: Duration,
+    stddev: Duration,
+}
+
+impl BenchmarkAverage {
+    fn new(samples: &[Benchmark]) -> Self {
+        let last = samples.last().expect("at least one sample is required");
+        let mut secs: Vec<f64> = samples.iter().map(|x| x.duration.as_secs_f64()).collect();
+        secs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let len = secs.len();
+        let mean = secs.iter().sum::<f64>() / len as f64;
+        let median = if len % 2 == 0 {
+            (secs[len / 2 - 1] + secs[len / 2]) / 2.0
+        } else {
+            secs[len / 2]
+        };
+        let variance = if len > 1 {
+            secs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (len - 1) as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            cycles: last.cycles,
+            seal: last.seal,
+            mean: Duration::from_secs_f64(mean),
+            median: Duration::from_secs_f64(median),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Some(iterations) = args.iterations {
+        tracing_subscriber::registry()
+            .with(EnvFilter::from_default_env())
+            .with(tracing_forest::ForestLayer::default())
+            .init();
+
+        let prover = default_prover();
+
+        for _ in 0..args.warmup {
+            top(prover.clone(), iterations);
+        }
+
+        let samples: Vec<Benchmark> = (0..args.samples)
+            .map(|_| Benchmark::run(prover.clone(), iterations))
+            .collect();
+        let stats = BenchmarkAverage::new(&samples);
 
         let usage = prover.get_peak_memory_usage();
-        let throughput = (cycles as f64) / duration.as_secs_f64();
+        let throughput = (stats.cycles as f64) / stats.mean.as_secs_f64();
 
         if !args.quiet {
             println!(
-                "| {:>9}k | {:>10} | {:>10} | {:>10} | {:>8}hz |",
-                cycles / 1024,
-                duration.human_duration().to_string(),
+                "| {:>9}k | {:>10} | {:>10} | {:>10} | {:>10} | {:>10} | {:>8}hz |",
+                stats.cycles / 1024,
+                stats.mean.human_duration().to_string(),
+                stats.median.human_duration().to_string(),
+                stats.stddev.human_duration().to_string(),
                 usage.human_count_bytes().to_string(),
-                seal.human_count_bytes().to_string(),
+                stats.seal.human_count_bytes().to_string(),
                 throughput.human_count_bare().to_string()
             );
         }
     } else {
         println!(
-            "| {:>10} | {:>10} | {:>10} | {:>10} | {:>10} |",
-            "Cycles", "Duration", "RAM", "Seal", "Speed"
+            "| {:>10} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10} |",
+            "Cycles", "Mean", "Median", "StdDev", "RAM", "Seal", "Speed"
         );
 
         for iterations in [
-            0,           // warm-up
             1,           // 16, 64K
             4 * 1024,    // 17, 128K
             16 * 1024,   // 18, 256K
@@ -94,19 +170,19 @@ fn main() {
             900 * 1024,  // 23, 8M
             1400 * 1024, // 24, 16M
         ] {
-            run_with_iterations(iterations);
+            run_with_iterations(iterations, args.warmup, args.samples);
         }
     }
 }
 
-fn run_with_iterations(iterations: usize) {
-    let mut cmd = Command::new(std::env::current_exe().unwrap());
-    if iterations == 0 {
-        cmd.arg("--quiet");
-    }
-    let ok = cmd
+fn run_with_iterations(iterations: usize, warmup: u64, samples: u64) {
+    let ok = Command::new(std::env::current_exe().unwrap())
         .arg("--iterations")
         .arg(iterations.to_string())
+        .arg("--warmup")
+        .arg(warmup.to_string())
+        .arg("--samples")
+        .arg(samples.to_string())
         .status()
         .unwrap()
         .success();
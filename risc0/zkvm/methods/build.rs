@@ -27,17 +27,14 @@ fn main() {
     let map = HashMap::from([
         (
             "risc0-zkvm-methods-guest",
-            GuestOptions {
-                features: Vec::new(),
-                std: false,
-            },
+            GuestOptions::default().with_std(false),
         ),
         (
             "risc0-zkvm-methods-std",
-            GuestOptions {
-                features: vec!["test_feature1".to_string(), "test_feature2".to_string()],
-                std: true,
-            },
+            GuestOptions::default().with_features(vec![
+                "test_feature1".to_string(),
+                "test_feature2".to_string(),
+            ]),
         ),
     ]);
 
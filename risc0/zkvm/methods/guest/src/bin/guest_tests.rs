@@ -0,0 +1,75 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest unit tests, run in the zkVM by `tests/guest_tests.rs` of risc0-zkvm.
+
+#![no_main]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::format;
+
+use risc0_zkvm::{
+    guest::{env, memory_barrier, sha},
+    sha::{Digest, Sha256},
+    testing::risc0_test,
+};
+
+risc0_zkvm::test_main!();
+
+#[risc0_test]
+fn sha_cycle_count() {
+    // Time the simulated sha so that it estimates what we'd
+    // see when it's a custom circuit.
+    let a: &Digest = &Digest::from([1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let count1 = env::get_cycle_count();
+    memory_barrier(&count1);
+    let count2 = env::get_cycle_count();
+    memory_barrier(&count2);
+    let result = sha::Impl::hash_pair(a, a);
+    memory_barrier(&result);
+    let count3 = env::get_cycle_count();
+    memory_barrier(&count3);
+
+    let overhead = count2 - count1;
+    let total = (count3 - count2) - overhead;
+
+    // We expect our acceleration circuit to use 72 cycles, so make sure that
+    // our simulation doesn't run faster.
+    assert!(total >= 72, "total: {total}");
+}
+
+#[risc0_test]
+fn libm_build() {
+    use core::hint::black_box;
+    let f = black_box(1.0_f32);
+    black_box(f.min(1.0));
+}
+
+#[risc0_test]
+fn rsa_compat() {
+    // This test comes from: https://github.com/RustCrypto/RSA/blob/master/tests/pkcs1v15.rs
+    use risc0_zkvm::sha::rust_crypto::Sha256;
+    use rsa::{pkcs1v15::SigningKey, pkcs8::DecodePrivateKey, signature::Signer, RsaPrivateKey};
+
+    let pem = include_str!("rsa2048-priv.pem");
+    let private_key = RsaPrivateKey::from_pkcs8_pem(pem).unwrap();
+    let signing_key = SigningKey::<Sha256>::new(private_key);
+
+    let signature = signing_key.sign(b"rsa4096");
+    let expected = "029E365B60971D5A499FF5E1C288B954D3A5DCF52482CEE46DB90DC860B725A8D6CA031146FA156E9F17579BE6122FFB11DAC35E59B2193D75F7B31CE1442DDE7F4FF7885AD5D6080266E9A33BB4CEC93FCC2B6B885457A0ABF19E2DAA00876F694B37F535F119925CCCF9A17B90AE6CF39F07D7FEFBEECDF1B344C14B728196DDD154230BADDEDA5A7EFF373F6CD3EF6D41789572A7A068E3A252D3B7D5D706C6170D8CFDB48C8E738A4B3BFEA3E15716805E376EBD99EA09C6E82F3CFA13CEB23CD289E8F95C27F489ADC05AAACE8A9276EE7CED3B7A5C7264F0D34FF18CEDC3E91D667FCF9992A8CFDE8562F65FDDE1E06595C27E0F82063839A358C927B2";
+    assert_eq!(format!("{}", signature), expected);
+}
@@ -19,14 +19,16 @@
 
 extern crate alloc;
 
-use alloc::{format, vec};
+use alloc::{string::ToString, vec, vec::Vec};
 use core::arch::asm;
 
 use getrandom::getrandom;
+use rand_core::RngCore;
 use risc0_zkp::core::hash::sha::testutil::test_sha_impl;
 use risc0_zkvm::{
-    guest::{env, memory_barrier, sha},
-    sha::{Digest, Sha256},
+    guest::{env, memory_barrier, oracle, sha},
+    oracle::OracleError,
+    sha::Sha256,
 };
 use risc0_zkvm_methods::multi_test::{MultiTestSpec, SYS_MULTI_TEST};
 use risc0_zkvm_platform::{
@@ -36,6 +38,9 @@ use risc0_zkvm_platform::{
 
 risc0_zkvm::entry!(main);
 
+risc0_zkvm::io_port!(input "multi_test.values" = 20, schema "multi_test.values.v1");
+risc0_zkvm::io_port!(output "multi_test.sum" = 21, schema "multi_test.sum.v1");
+
 #[inline(never)]
 #[no_mangle]
 fn profile_test_func1() {
@@ -53,27 +58,6 @@ pub fn main() {
     match impl_select {
         MultiTestSpec::DoNothing => {}
         MultiTestSpec::ShaConforms => test_sha_impl::<sha::Impl>(),
-        MultiTestSpec::ShaCycleCount => {
-            // Time the simulated sha so that it estimates what we'd
-            // see when it's a custom circuit.
-            let a: &Digest = &Digest::from([1, 2, 3, 4, 5, 6, 7, 8]);
-
-            let count1 = env::get_cycle_count();
-            memory_barrier(&count1);
-            let count2 = env::get_cycle_count();
-            memory_barrier(&count2);
-            let result = sha::Impl::hash_pair(a, a);
-            memory_barrier(&result);
-            let count3 = env::get_cycle_count();
-            memory_barrier(&count3);
-
-            let overhead = count2 - count1;
-            let total = (count3 - count2) - overhead;
-
-            // We expect our acceleration circuit to use 72 cycles, so make sure that
-            // our simulation doesn't run faster.
-            assert!(total >= 72, "total: {total}");
-        }
         MultiTestSpec::EventTrace => unsafe {
             // Execute some instructions with distinctive arguments
             // that are easy to find in the event trace.
@@ -111,6 +95,18 @@ pub fn main() {
             let digest = sha::Impl::hash_bytes(&data);
             env::commit(&digest);
         }
+        MultiTestSpec::ShaBatch { msgs } => {
+            let msgs: Vec<&[u8]> = msgs.iter().map(|msg| msg.as_slice()).collect();
+            let start = env::get_cycle_count();
+            let digests = sha::hash_batch(&msgs);
+            let batch_cycles = env::get_cycle_count() - start;
+            let start = env::get_cycle_count();
+            for (msg, digest) in msgs.iter().zip(digests.iter()) {
+                assert_eq!(sha::Impl::hash_bytes(msg), digest);
+            }
+            let single_cycles = env::get_cycle_count() - start;
+            env::commit(&(digests, batch_cycles, single_cycles));
+        }
         MultiTestSpec::Syscall { count } => {
             let mut input: &[u8] = &[];
             let mut input_len: usize = 0;
@@ -191,10 +187,110 @@ pub fn main() {
             }
             env::commit_slice(&result);
         }
-        MultiTestSpec::LibM => {
-            use core::hint::black_box;
-            let f = black_box(1.0_f32);
-            black_box(f.min(1.0));
+        MultiTestSpec::BigIntWide { x, y, modulus } => match modulus.len() {
+            risc0_bigint::WIDTH_384_WORDS => {
+                let result = risc0_bigint::modmul_384(
+                    x.as_slice().try_into().unwrap(),
+                    y.as_slice().try_into().unwrap(),
+                    modulus.as_slice().try_into().unwrap(),
+                );
+                env::commit_slice(&result);
+            }
+            risc0_bigint::WIDTH_512_WORDS => {
+                let result = risc0_bigint::modmul_512(
+                    x.as_slice().try_into().unwrap(),
+                    y.as_slice().try_into().unwrap(),
+                    modulus.as_slice().try_into().unwrap(),
+                );
+                env::commit_slice(&result);
+            }
+            width => panic!("unsupported bigint width: {width}"),
+        },
+        MultiTestSpec::KvOracle { key } => {
+            assert_eq!(oracle::get(&key), Err(OracleError::Uninitialized));
+            let root = oracle::init();
+            // A second call must not commit the root again.
+            assert_eq!(oracle::init(), root);
+            let value = oracle::get(&key);
+            assert_eq!(oracle::get(&key), value);
+            env::commit(&value.map_err(|err| err.to_string()));
+        }
+        MultiTestSpec::LogicalTime => {
+            let first = env::logical_time();
+            let second = env::logical_time();
+            assert!(second > first);
+            env::commit(&second);
+            env::commit_logical_time_at_exit();
+        }
+        MultiTestSpec::FreeMemory => {
+            let before = env::free_memory();
+            let buf = vec![1u8; 1 << 20];
+            memory_barrier(buf.as_ptr());
+            env::commit(&(before, env::free_memory()));
+        }
+        MultiTestSpec::MetadataDigest { digest } => {
+            env::commit(&1u32);
+            env::commit_metadata_digest(&digest);
+        }
+        MultiTestSpec::YieldOutputs { outputs } => {
+            for output in outputs.iter() {
+                env::yield_output(output);
+            }
+        }
+        MultiTestSpec::HashStream { fd } => {
+            risc0_zkvm::guest::hash_stream(fd);
+        }
+        MultiTestSpec::CommitTyped { commit } => {
+            env::commit_typed(&commit);
+        }
+        MultiTestSpec::ExtSyscall { input } => {
+            let mut from_host = vec![0u32; (input.len() + 3) / 4];
+            let len = risc0_zkvm::ext_syscall!("multi_test.reverse", &input, &mut from_host).0;
+            let bytes: &[u8] = bytemuck::cast_slice(&from_host);
+            env::commit_slice(&bytes[..len as usize]);
+        }
+        MultiTestSpec::JournalChain { commits } => {
+            env::enable_journal_chain();
+            for commit in commits.iter() {
+                env::commit_slice(commit);
+            }
+        }
+        MultiTestSpec::Rng { len } => {
+            risc0_zkvm::guest::commit_rng_seed();
+            for _ in 0..2 {
+                let mut bytes = vec![0u8; len];
+                risc0_zkvm::guest::rng().fill_bytes(&mut bytes);
+                env::commit_slice(&bytes);
+            }
+        }
+        MultiTestSpec::Ports => {
+            let values: Vec<u32> = env::read_port(20);
+            env::write_port(21, &values.iter().sum::<u32>());
+        }
+        MultiTestSpec::Exit { status } => env::exit(status, &status),
+        MultiTestSpec::ReadonlyRegion { idx } => {
+            env::commit(&env::readonly_region(idx).map(|region| region.to_vec()));
+        }
+        MultiTestSpec::WriteReadonlyRegion => {
+            let region = env::readonly_region(0).unwrap();
+            unsafe { (region.as_ptr() as *mut u8).write_volatile(1) };
+        }
+        MultiTestSpec::AllocBelowReadonly { extra } => {
+            let region = env::readonly_region(0).unwrap();
+            let len = env::free_memory() + extra as usize;
+            let mut buf = Vec::<u8>::with_capacity(len);
+            // Write the last byte of the allocation, which must not be read-only.
+            unsafe { buf.as_mut_ptr().add(len - 1).write_volatile(1) };
+            let heap_end = buf.as_ptr() as u32 + len as u32;
+            // Committing a slice does not allocate.
+            env::commit_slice(&[region.as_ptr() as u32, heap_end]);
+        }
+        MultiTestSpec::ReadFrames => {
+            let mut frames = Vec::new();
+            while let Some(frame) = env::try_read_frame().unwrap() {
+                frames.push(frame);
+            }
+            env::commit(&frames);
         }
         MultiTestSpec::Oom => {
             use core::hint::black_box;
@@ -205,21 +301,6 @@ pub fn main() {
             let len = (memory::STACK_TOP - memory::RESERVED_STACK) as usize;
             let _data = black_box(vec![0_u8; len]);
         }
-        MultiTestSpec::RsaCompat => {
-            // This test comes from: https://github.com/RustCrypto/RSA/blob/master/tests/pkcs1v15.rs
-            use risc0_zkvm::sha::rust_crypto::Sha256;
-            use rsa::{
-                pkcs1v15::SigningKey, pkcs8::DecodePrivateKey, signature::Signer, RsaPrivateKey,
-            };
-
-            let pem = include_str!("rsa2048-priv.pem");
-            let private_key = RsaPrivateKey::from_pkcs8_pem(pem).unwrap();
-            let signing_key = SigningKey::<Sha256>::new(private_key);
-
-            let signature = signing_key.sign(b"rsa4096");
-            let expected = "029E365B60971D5A499FF5E1C288B954D3A5DCF52482CEE46DB90DC860B725A8D6CA031146FA156E9F17579BE6122FFB11DAC35E59B2193D75F7B31CE1442DDE7F4FF7885AD5D6080266E9A33BB4CEC93FCC2B6B885457A0ABF19E2DAA00876F694B37F535F119925CCCF9A17B90AE6CF39F07D7FEFBEECDF1B344C14B728196DDD154230BADDEDA5A7EFF373F6CD3EF6D41789572A7A068E3A252D3B7D5D706C6170D8CFDB48C8E738A4B3BFEA3E15716805E376EBD99EA09C6E82F3CFA13CEB23CD289E8F95C27F489ADC05AAACE8A9276EE7CED3B7A5C7264F0D34FF18CEDC3E91D667FCF9992A8CFDE8562F65FDDE1E06595C27E0F82063839A358C927B2";
-            assert_eq!(format!("{}", signature), expected);
-        }
         MultiTestSpec::OutOfBounds => unsafe {
             let addr: u32 = env::read();
             // Access memory outside of allowed boundaries. This is intended to cause a
@@ -0,0 +1,37 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recurses to the requested depth with a small, fixed-size stack.
+
+#![no_std]
+#![no_main]
+
+use risc0_zkvm::guest::{env, memory_barrier};
+
+risc0_zkvm::entry!(main, stack_size = 64 * 1024);
+
+#[inline(never)]
+fn recurse(depth: u32) -> u32 {
+    let frame = [depth; 64];
+    memory_barrier(&frame);
+    match depth {
+        0 => 0,
+        _ => recurse(depth - 1) + frame[0],
+    }
+}
+
+pub fn main() {
+    let depth: u32 = env::read();
+    env::commit(&(recurse(depth), env::free_memory() as u32));
+}
@@ -33,6 +33,14 @@ pub enum BenchmarkSpec {
     Memset {
         len: usize,
     },
+    /// Decompress raw DEFLATE data with risc0-guest-utils.
+    Inflate {
+        data: Vec<u8>,
+    },
+    /// Decompress raw DEFLATE data with miniz_oxide, for comparison.
+    MinizInflate {
+        data: Vec<u8>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
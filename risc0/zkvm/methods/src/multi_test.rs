@@ -17,21 +17,64 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 
-use risc0_zkvm::declare_syscall;
+use risc0_zkvm::{declare_syscall, serde::JournalCommit, sha::Digest};
 use risc0_zkvm_platform::syscall::bigint;
 use serde::{Deserialize, Serialize};
 
+/// A value committed with `env::commit_typed`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, JournalCommit)]
+#[journal(version = 2)]
+pub struct TypedCommit {
+    pub value: u32,
+    pub data: Vec<u8>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum MultiTestSpec {
     DoNothing,
+    CommitTyped {
+        commit: TypedCommit,
+    },
     ShaConforms,
-    ShaCycleCount,
     ShaDigest {
         data: Vec<u8>,
     },
+    ShaBatch {
+        msgs: Vec<Vec<u8>>,
+    },
     EventTrace,
+    ExtSyscall {
+        input: Vec<u8>,
+    },
+    JournalChain {
+        commits: Vec<Vec<u8>>,
+    },
+    /// Commit bytes drawn from two streams of the guest RNG.
+    Rng {
+        len: usize,
+    },
+    /// Read the values of the `multi_test.values` input port and write their
+    /// sum to the `multi_test.sum` output port.
+    Ports,
+    /// Commit `status` and halt with it as the exit code.
+    Exit {
+        status: u8,
+    },
+    /// Commit the read-only region with the given index, if it is mapped.
+    ReadonlyRegion {
+        idx: usize,
+    },
+    /// Write to the first read-only region, which faults.
+    WriteReadonlyRegion,
+    /// Allocate the rest of the heap below the first read-only region, plus
+    /// `extra` bytes, and commit the address of the region and the end of the
+    /// heap.
+    AllocBelowReadonly {
+        extra: u32,
+    },
     Profiler,
     Fail,
+    FreeMemory,
     ReadWriteMem {
         /// Tuples of (address, value). Zero means read the value and
         /// output it; nonzero means write that value.
@@ -61,15 +104,34 @@ pub enum MultiTestSpec {
         y: [u32; bigint::WIDTH_WORDS],
         modulus: [u32; bigint::WIDTH_WORDS],
     },
+    /// Commit the product of `x` and `y` modulo `modulus`, which are 384-bit
+    /// or 512-bit integers.
+    BigIntWide {
+        x: Vec<u32>,
+        y: Vec<u32>,
+        modulus: Vec<u32>,
+    },
     PauseContinue,
     BusyLoop {
         /// Busy loop until the guest has run for at least this number of cycles
         cycles: u32,
     },
-    LibM,
+    KvOracle {
+        key: Vec<u8>,
+    },
+    HashStream {
+        fd: u32,
+    },
+    LogicalTime,
+    MetadataDigest {
+        digest: Digest,
+    },
     Oom,
     OutOfBounds,
-    RsaCompat,
+    ReadFrames,
+    YieldOutputs {
+        outputs: Vec<Vec<u8>>,
+    },
 }
 
 declare_syscall!(pub SYS_MULTI_TEST);
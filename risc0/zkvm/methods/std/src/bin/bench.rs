@@ -57,5 +57,15 @@ pub fn main() {
                 memory_barrier(&dst_slice);
             }
         }
+        BenchmarkSpec::Inflate { data } => {
+            for _ in 0..iters {
+                memory_barrier(&risc0_guest_utils::inflate::inflate(&data).unwrap());
+            }
+        }
+        BenchmarkSpec::MinizInflate { data } => {
+            for _ in 0..iters {
+                memory_barrier(&miniz_oxide::inflate::decompress_to_vec(&data).unwrap());
+            }
+        }
     }
 }
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::WORD_SIZE;
+use super::{PAGE_SIZE, WORD_SIZE};
 
 pub const MEM_BITS: usize = 28;
 pub const MEM_SIZE: usize = 1 << MEM_BITS;
@@ -60,6 +60,21 @@ pub const TEXT_START: u32 = 0x0000_0400;
 pub const STACK_TOP: u32 = 0x0BF_FFC00;
 /// Minimum mount of room to leave for the stack when allocating from the heap.
 pub const RESERVED_STACK: u32 = mb(1) as u32;
+/// Table of the read-only regions mapped into the initial memory image by the
+/// host, in the page above the stack, which neither the stack nor the heap
+/// use. The regions themselves are placed below the memory reserved for the
+/// stack, unless the host places them elsewhere, and the heap stops below any
+/// region above it.
+///
+/// The table is the [READONLY_TABLE_MAGIC] word, followed by a word holding
+/// the number of regions, at most [READONLY_MAX_REGIONS], and the address and
+/// length in bytes of each region.
+pub const READONLY_TABLE: u32 = STACK_TOP;
+/// The first word of the [READONLY_TABLE] when the host has mapped read-only
+/// regions. Otherwise the table is left as zeros.
+pub const READONLY_TABLE_MAGIC: u32 = u32::from_le_bytes(*b"R0RO");
+/// The largest number of regions that fit in the [READONLY_TABLE].
+pub const READONLY_MAX_REGIONS: usize = (PAGE_SIZE - 2 * WORD_SIZE) / (2 * WORD_SIZE);
 pub const SYSTEM: Region = Region::new(0x0C00_0000, mb(16));
 pub const PAGE_TABLE: Region = Region::new(0x0D00_0000, mb(16));
 pub const PRE_LOAD: Region = Region::new(0x0D70_0000, mb(9));
@@ -16,7 +16,10 @@
 use core::arch::asm;
 use core::{cmp::min, ptr::null_mut};
 
-use crate::{memory::RESERVED_STACK, WORD_SIZE};
+use crate::{
+    memory::{RESERVED_STACK, STACK_TOP},
+    WORD_SIZE,
+};
 
 pub mod ecall {
     pub const HALT: u32 = 0;
@@ -122,15 +125,42 @@ macro_rules! declare_syscall {
     };
 }
 
+/// The namespace of syscalls registered by the host at runtime, which keeps
+/// them from colliding with built-in syscalls.
+///
+/// This must match the prefix used by [ext_syscall_name].
+pub const EXT_SYSCALL_PREFIX: &str = "risc0_ext::";
+
+/// Construct the name of a syscall registered by the host with
+/// `ExecutorEnvBuilder::register_syscall`.
+///
+/// ```rust
+/// use risc0_zkvm_platform::{ext_syscall_name, syscall::SyscallName};
+///
+/// const SYS_MY_SERVICE: SyscallName = ext_syscall_name!("my_service");
+/// ```
+#[macro_export]
+macro_rules! ext_syscall_name {
+    ($name:literal) => {
+        $crate::syscall::SyscallName::from_bytes_with_nul(
+            concat!("risc0_ext::", $name, "\0").as_ptr(),
+        )
+    };
+}
+
 pub mod nr {
+    declare_syscall!(pub SYS_BIGINT_DIVREM);
     declare_syscall!(pub SYS_CYCLE_COUNT);
     declare_syscall!(pub SYS_GETENV);
+    declare_syscall!(pub SYS_LAYOUT);
     declare_syscall!(pub SYS_LOG);
     declare_syscall!(pub SYS_PANIC);
     declare_syscall!(pub SYS_RANDOM);
     declare_syscall!(pub SYS_READ_AVAIL);
+    declare_syscall!(pub SYS_READ_FRAME);
     declare_syscall!(pub SYS_READ);
     declare_syscall!(pub SYS_WRITE);
+    declare_syscall!(pub SYS_YIELD_OUTPUT);
 }
 
 impl SyscallName {
@@ -587,28 +617,136 @@ pub extern "C" fn sys_alloc_words(nwords: usize) -> *mut u32 {
     sys_alloc_aligned(WORD_SIZE * nwords, WORD_SIZE) as *mut u32
 }
 
-#[no_mangle]
-pub extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8 {
-    extern "C" {
-        // This symbol is defined by the loader and marks the end
-        // of all elf sections, so this is where we start our
-        // heap.
-        //
-        // This is generated automatically by the linker; see
-        // https://lld.llvm.org/ELF/linker_script.html#sections-command
-        static _end: u8;
+extern "C" {
+    // This symbol is defined by the loader and marks the end
+    // of all elf sections, so this is where we start our
+    // heap.
+    //
+    // This is generated automatically by the linker; see
+    // https://lld.llvm.org/ELF/linker_script.html#sections-command
+    static _end: u8;
+}
+
+// Pointer to next heap address to use, or 0 if the heap has not yet been
+// initialized.
+static mut HEAP_POS: usize = 0;
+
+// The heap start and stack size configured with sys_set_layout, or 0 to use
+// the defaults.
+static mut HEAP_START: usize = 0;
+static mut STACK_SIZE: usize = 0;
+
+// The start of the lowest read-only region above the start of the heap, or 0
+// if it has not been looked up yet.
+static mut READONLY_FLOOR: usize = 0;
+
+// Returns the next heap address to use.
+fn heap_pos() -> usize {
+    // SAFETY: Single threaded, so nothing else can touch this while we're working.
+    unsafe {
+        match (HEAP_POS, HEAP_START) {
+            (0, 0) => (&_end) as *const u8 as usize,
+            (0, heap_start) => heap_start,
+            (heap_pos, _) => heap_pos,
+        }
     }
+}
 
-    // Pointer to next heap address to use, or 0 if the heap has not yet been
-    // initialized.
-    static mut HEAP_POS: usize = 0;
+// Returns the start of the lowest read-only region mapped above the start of
+// the heap, or usize::MAX if there is none. The regions are listed in the table
+// at READONLY_TABLE, which the host writes into the initial memory image.
+#[cfg(target_os = "zkvm")]
+fn readonly_floor() -> usize {
+    use crate::memory::{READONLY_MAX_REGIONS, READONLY_TABLE, READONLY_TABLE_MAGIC};
 
+    let table = READONLY_TABLE as usize as *const u32;
+    // SAFETY: Single threaded, so nothing else can touch this while we're
+    // working. The page of the table is above the stack, so the guest never
+    // writes to it, and it holds zeros unless the host mapped regions.
+    unsafe {
+        if READONLY_FLOOR == 0 {
+            let word = |offset: usize| table.add(offset).read_volatile() as usize;
+            let count = match word(0) as u32 {
+                READONLY_TABLE_MAGIC => min(word(1), READONLY_MAX_REGIONS),
+                _ => 0,
+            };
+            let heap_start = match HEAP_START {
+                0 => (&_end) as *const u8 as usize,
+                heap_start => heap_start,
+            };
+            READONLY_FLOOR = (0..count)
+                .map(|idx| word(2 + 2 * idx))
+                .filter(|addr| *addr >= heap_start)
+                .min()
+                .unwrap_or(usize::MAX);
+        }
+        READONLY_FLOOR
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+fn readonly_floor() -> usize {
+    usize::MAX
+}
+
+// Returns the lowest address the heap may grow to, leaving room for the stack
+// and stopping below any read-only region.
+fn heap_limit() -> usize {
     // SAFETY: Single threaded, so nothing else can touch this while we're working.
-    let mut heap_pos = unsafe { HEAP_POS };
+    let stack_size = unsafe { STACK_SIZE };
+    let limit = if stack_size != 0 {
+        STACK_TOP as usize - stack_size
+    } else {
+        let mut stack_pointer: usize;
+        #[cfg(target_os = "zkvm")]
+        unsafe {
+            asm!("add {stack_pointer}, sp, zero", stack_pointer = out(reg) stack_pointer)
+        };
+        #[cfg(not(target_os = "zkvm"))]
+        let stack_pointer: usize = STACK_TOP as usize;
+        stack_pointer - (RESERVED_STACK as usize)
+    };
+    limit.min(readonly_floor())
+}
 
-    if heap_pos == 0 {
-        heap_pos = unsafe { (&_end) as *const u8 as usize };
+/// Configure the start of the heap and the size of the stack.
+///
+/// A `heap_start` of zero starts the heap right after the program. A
+/// `stack_size` of zero lets the heap grow until [RESERVED_STACK] bytes below
+/// the current stack pointer; otherwise the heap may grow until the bottom of a
+/// stack of `stack_size` bytes, and the host faults the guest if the stack
+/// grows beyond it. Either way, the heap stops below any read-only region
+/// mapped above its start, see
+/// [READONLY_TABLE](crate::memory::READONLY_TABLE).
+///
+/// # Safety
+///
+/// Must be called before the first allocation. The host validates the layout.
+#[no_mangle]
+pub unsafe extern "C" fn sys_set_layout(heap_start: usize, stack_size: usize) {
+    if HEAP_POS != 0 {
+        const MSG: &[u8] = "Layout must be set before the first allocation".as_bytes();
+        sys_panic(MSG.as_ptr(), MSG.len());
     }
+    if heap_start != 0 && heap_start < (&_end) as *const u8 as usize {
+        const MSG: &[u8] = "Heap start overlaps the program".as_bytes();
+        sys_panic(MSG.as_ptr(), MSG.len());
+    }
+    syscall_2(
+        nr::SYS_LAYOUT,
+        null_mut(),
+        0,
+        heap_start as u32,
+        stack_size as u32,
+    );
+    HEAP_START = heap_start;
+    STACK_SIZE = stack_size;
+    READONLY_FLOOR = 0;
+}
+
+#[no_mangle]
+pub extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8 {
+    let mut heap_pos = heap_pos();
 
     let offset = heap_pos & (align - 1);
     if offset != 0 {
@@ -623,14 +761,7 @@ pub extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8 {
 
     // Check to make sure we keep space between the heap and the
     // stack so they don't accidentally step on each other.
-    let mut stack_pointer: usize;
-    #[cfg(target_os = "zkvm")]
-    unsafe {
-        asm!("add {stack_pointer}, sp, zero", stack_pointer = out(reg) stack_pointer)
-    };
-    #[cfg(not(target_os = "zkvm"))]
-    let stack_pointer: usize = crate::memory::STACK_TOP as usize;
-    if stack_pointer - (RESERVED_STACK as usize) < heap_pos {
+    if heap_limit() < heap_pos {
         const MSG: &[u8] = "Out of memory!".as_bytes();
         unsafe { sys_panic(MSG.as_ptr(), MSG.len()) };
     }
@@ -638,3 +769,11 @@ pub extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8 {
     unsafe { HEAP_POS = heap_pos };
     ptr
 }
+
+/// Returns the number of bytes that can still be allocated with
+/// [sys_alloc_aligned] before the heap reaches the memory reserved for the
+/// stack.
+#[no_mangle]
+pub extern "C" fn sys_heap_free() -> usize {
+    heap_limit().saturating_sub(heap_pos())
+}
@@ -29,6 +29,8 @@ fn main() {
             r##"
 pub const FIB_ID: [u32; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
 pub const FIB_RECEIPT: &[u8] = &[];
+pub const FIB_RECEIPT_BAD_SEAL: &[u8] = &[];
+pub const FIB_RECEIPT_BAD_JOURNAL: &[u8] = &[];
                 "##,
         )
         .unwrap();
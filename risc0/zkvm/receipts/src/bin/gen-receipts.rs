@@ -0,0 +1,54 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+use risc0_zkvm::prove::default_prover;
+use risc0_zkvm_receipts::generate::Generator;
+
+/// Generates receipt fixtures for guest ELF binaries.
+#[derive(Parser)]
+#[clap(about, version, author)]
+struct Args {
+    /// A fixture to generate, in the form NAME=ELF or NAME=ELF:INPUT, where
+    /// INPUT is a file holding the raw input passed to the guest.
+    #[clap(long, required = true, action = clap::ArgAction::Append)]
+    fixture: Vec<String>,
+
+    /// The Rust source file to write the fixtures to.
+    #[clap(long, default_value = "src/receipts.rs")]
+    out: PathBuf,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let mut generator = Generator::new();
+    for fixture in args.fixture.iter() {
+        let (name, paths) = fixture
+            .split_once('=')
+            .expect("Fixtures should be of the form NAME=ELF or NAME=ELF:INPUT");
+        let (elf, input) = match paths.split_once(':') {
+            Some((elf, input)) => (elf, fs::read(input).expect("Unable to read input")),
+            None => (paths, Vec::new()),
+        };
+        let elf = fs::read(elf).expect("Unable to read ELF");
+        generator.add(name, &elf, &input);
+    }
+
+    generator
+        .write(default_prover().as_ref(), &args.out)
+        .unwrap();
+}
@@ -0,0 +1,182 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of receipt fixtures for arbitrary guests.
+//!
+//! A [Generator] proves each guest added to it, and renders the image ID and
+//! receipt of each as Rust constants, along with deliberately corrupted
+//! copies of the receipt that must fail to verify:
+//!
+//! ```ignore
+//! pub const FIB_ID: [u32; 8] = [...];
+//! pub const FIB_RECEIPT: &[u8] = &[...];
+//! pub const FIB_RECEIPT_BAD_SEAL: &[u8] = &[...];
+//! pub const FIB_RECEIPT_BAD_JOURNAL: &[u8] = &[...];
+//! ```
+//!
+//! Receipts are encoded with bincode. Writing the result to `src/receipts.rs`
+//! with [Generator::write] embeds the fixtures in this crate.
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use anyhow::{bail, Result};
+use bytemuck::Pod;
+use risc0_zkvm::{prove::Prover, receipt::InnerReceipt, ExecutorEnv, ExecutorPool, Receipt};
+
+/// A deliberate corruption of a receipt, which must fail to verify.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Corruption {
+    /// Flip a bit in the middle of the seal of the final segment.
+    Seal,
+
+    /// Flip a bit of the journal, or add a byte to an empty journal.
+    Journal,
+}
+
+impl Corruption {
+    /// Every kind of corruption.
+    pub const ALL: [Corruption; 2] = [Corruption::Seal, Corruption::Journal];
+
+    /// The suffix of the name of the constant holding a receipt with this
+    /// corruption.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Corruption::Seal => "BAD_SEAL",
+            Corruption::Journal => "BAD_JOURNAL",
+        }
+    }
+
+    /// Apply this corruption to the given receipt.
+    pub fn apply(&self, receipt: &mut Receipt) -> Result<()> {
+        match self {
+            Corruption::Seal => {
+                let seal = match &mut receipt.inner {
+                    InnerReceipt::Flat(segments) => match segments.0.last_mut() {
+                        Some(segment) => &mut segment.seal,
+                        None => bail!("receipt has no segments"),
+                    },
+                    InnerReceipt::Succinct(inner) => &mut inner.seal,
+                    InnerReceipt::Fake => bail!("fake receipts have no seal"),
+                };
+                let idx = seal.len() / 2;
+                seal[idx] ^= 1;
+            }
+            Corruption::Journal => match receipt.journal.first_mut() {
+                Some(byte) => *byte ^= 1,
+                None => receipt.journal.push(0),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// A guest to generate fixtures for.
+struct Fixture {
+    name: String,
+    elf: Vec<u8>,
+    input: Vec<u8>,
+    corruptions: Vec<Corruption>,
+}
+
+/// A generator of receipt fixtures, see the [module documentation](self).
+#[derive(Default)]
+pub struct Generator {
+    fixtures: Vec<Fixture>,
+}
+
+impl Generator {
+    /// Construct a generator with no fixtures.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fixture of the given guest and input, with every kind of
+    /// [Corruption].
+    ///
+    /// The name prefixes the constants of the fixture, and must be an upper
+    /// case identifier such as `FIB`.
+    pub fn add<T: Pod>(&mut self, name: &str, elf: &[u8], input: &[T]) -> &mut Self {
+        self.add_with_corruptions(name, elf, input, &Corruption::ALL)
+    }
+
+    /// Add a fixture of the given guest and input, with the given kinds of
+    /// [Corruption].
+    pub fn add_with_corruptions<T: Pod>(
+        &mut self,
+        name: &str,
+        elf: &[u8],
+        input: &[T],
+        corruptions: &[Corruption],
+    ) -> &mut Self {
+        self.fixtures.push(Fixture {
+            name: name.to_string(),
+            elf: elf.to_vec(),
+            input: bytemuck::cast_slice(input).to_vec(),
+            corruptions: corruptions.to_vec(),
+        });
+        self
+    }
+
+    /// Prove every fixture with the given prover, and return the Rust source
+    /// declaring their constants.
+    pub fn generate(&self, prover: &dyn Prover) -> Result<String> {
+        let mut code = String::new();
+        for fixture in self.fixtures.iter() {
+            let name = &fixture.name;
+            let is_ident = name.starts_with(|x: char| x.is_ascii_uppercase())
+                && name
+                    .chars()
+                    .all(|x| x.is_ascii_uppercase() || x.is_ascii_digit() || x == '_');
+            if !is_ident {
+                bail!("invalid fixture name: {name}");
+            }
+
+            let image_id = ExecutorPool::from_elf(&fixture.elf)?.image_id();
+            let env = ExecutorEnv::builder().add_input(&fixture.input).build()?;
+            let receipt = prover.prove_elf(env, &fixture.elf)?;
+            receipt.verify(image_id)?;
+
+            let receipt_bytes = bincode::serialize(&receipt)?;
+            writeln!(
+                code,
+                "pub const {name}_ID: [u32; 8] = {:?};",
+                image_id.as_words()
+            )?;
+            writeln!(
+                code,
+                "pub const {name}_RECEIPT: &[u8] = &{receipt_bytes:?};"
+            )?;
+            for corruption in fixture.corruptions.iter() {
+                let mut corrupted = receipt.clone();
+                corruption.apply(&mut corrupted)?;
+                if corrupted.verify(image_id).is_ok() {
+                    bail!("{name}: receipt with {corruption:?} corruption still verifies");
+                }
+                let receipt_bytes = bincode::serialize(&corrupted)?;
+                writeln!(
+                    code,
+                    "pub const {name}_RECEIPT_{}: &[u8] = &{receipt_bytes:?};",
+                    corruption.suffix()
+                )?;
+            }
+        }
+        Ok(code)
+    }
+
+    /// Prove every fixture with the given prover, and write the Rust source
+    /// declaring their constants to the given file.
+    pub fn write(&self, prover: &dyn Prover, path: impl AsRef<Path>) -> Result<()> {
+        Ok(fs::write(path, self.generate(prover)?)?)
+    }
+}
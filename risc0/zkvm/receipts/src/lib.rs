@@ -13,3 +13,6 @@
 // limitations under the License.
 
 include!(concat!(env!("OUT_DIR"), "/lib.rs"));
+
+#[cfg(feature = "generate")]
+pub mod generate;
@@ -0,0 +1,171 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts bound to a TEE attestation quote.
+//!
+//! An [AttestedReceipt] pairs a [Receipt] with a quote from a trusted
+//! execution environment (e.g. an SGX enclave) whose report data commits to
+//! the receipt's claim, as computed by [report_data]. The claim is the
+//! digest of the [ReceiptMetadata](crate::ReceiptMetadata) of the receipt,
+//! which covers the image ID, the input, the exit code, and the output
+//! committing to the journal. The host running inside
+//! the enclave requests a quote over [report_data] after proving, and
+//! [AttestedReceipt::verify] checks the proof, the quote, and that the two
+//! refer to the same claim.
+//!
+//! Parsing and validating quotes is platform specific and is delegated to a
+//! [QuoteVerifier].
+
+use alloc::{string::String, vec::Vec};
+
+use risc0_zkp::verify::VerificationError;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    receipt::{Receipt, VerifierContext},
+    sha::{Digest, DIGEST_BYTES},
+};
+
+/// The size of the report data carried in a quote, as in SGX.
+pub const REPORT_DATA_BYTES: usize = 64;
+
+/// Validates TEE quotes and extracts the report data they attest to.
+pub trait QuoteVerifier {
+    /// Verify that `quote` was produced by a trusted enclave, and return the
+    /// report data it carries.
+    fn verify_quote(&self, quote: &[u8]) -> Result<[u8; REPORT_DATA_BYTES], AttestationError>;
+}
+
+/// An error produced while checking a quote.
+#[derive(Debug, PartialEq)]
+pub enum AttestationError {
+    /// The quote is not well formed or was not produced by a trusted enclave.
+    InvalidQuote(String),
+
+    /// The report data in the quote does not commit to the receipt.
+    ReportDataMismatch,
+}
+
+impl core::fmt::Display for AttestationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AttestationError::InvalidQuote(msg) => write!(f, "invalid quote: {msg}"),
+            AttestationError::ReportDataMismatch => {
+                write!(f, "quote report data does not match receipt")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AttestationError {}
+
+/// The error returned by [AttestedReceipt::verify].
+#[derive(Debug, PartialEq)]
+pub enum AttestedReceiptError {
+    /// The inner [Receipt] failed to verify.
+    Receipt(VerificationError),
+
+    /// The quote failed to verify or is not bound to the receipt.
+    Attestation(AttestationError),
+}
+
+impl core::fmt::Display for AttestedReceiptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AttestedReceiptError::Receipt(err) => write!(f, "receipt failed to verify: {err}"),
+            AttestedReceiptError::Attestation(err) => {
+                write!(f, "attestation failed to verify: {err}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AttestedReceiptError {}
+
+/// A [Receipt] together with a TEE quote over its claim.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AttestedReceipt {
+    /// The attested receipt.
+    pub receipt: Receipt,
+
+    /// The quote, whose report data is [report_data] of the receipt.
+    pub quote: Vec<u8>,
+}
+
+/// Compute the digest of the claim made by a [Receipt]: the
+/// [ReceiptMetadata::digest](crate::ReceiptMetadata::digest) of its
+/// [InnerReceipt::get_metadata](crate::receipt::InnerReceipt::get_metadata).
+pub fn claim_digest(receipt: &Receipt) -> Result<Digest, VerificationError> {
+    receipt.inner.get_metadata()?.digest()
+}
+
+/// Compute the report data an enclave should include in its quote to bind
+/// the given [Receipt]: the [claim_digest] followed by zeros.
+pub fn report_data(receipt: &Receipt) -> Result<[u8; REPORT_DATA_BYTES], VerificationError> {
+    let mut data = [0u8; REPORT_DATA_BYTES];
+    let digest = claim_digest(receipt)?;
+    data[..DIGEST_BYTES].copy_from_slice(digest.as_bytes());
+    Ok(data)
+}
+
+impl AttestedReceipt {
+    /// Pair a [Receipt] with a quote over its [report_data].
+    pub fn new(receipt: Receipt, quote: Vec<u8>) -> Self {
+        Self { receipt, quote }
+    }
+
+    /// Verify the proof of the inner [Receipt] against the given `image_id`,
+    /// and the quote with the given [QuoteVerifier].
+    pub fn verify(
+        &self,
+        image_id: impl Into<Digest>,
+        verifier: &impl QuoteVerifier,
+    ) -> Result<(), AttestedReceiptError> {
+        self.verify_with_context(&VerifierContext::default(), image_id, verifier)
+    }
+
+    /// Verify the proof of the inner [Receipt] with the given
+    /// [VerifierContext], and the quote with the given [QuoteVerifier].
+    pub fn verify_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        verifier: &impl QuoteVerifier,
+    ) -> Result<(), AttestedReceiptError> {
+        self.verify_attestation(verifier)?;
+        self.receipt
+            .verify_with_context(ctx, image_id)
+            .map_err(AttestedReceiptError::Receipt)
+    }
+
+    /// Verify only the quote and its binding to the claim of the receipt,
+    /// without checking the proof.
+    pub fn verify_attestation(
+        &self,
+        verifier: &impl QuoteVerifier,
+    ) -> Result<(), AttestedReceiptError> {
+        let data = verifier
+            .verify_quote(&self.quote)
+            .map_err(AttestedReceiptError::Attestation)?;
+        let expected = report_data(&self.receipt).map_err(AttestedReceiptError::Receipt)?;
+        if data != expected {
+            return Err(AttestedReceiptError::Attestation(
+                AttestationError::ReportDataMismatch,
+            ));
+        }
+        Ok(())
+    }
+}
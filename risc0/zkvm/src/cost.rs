@@ -0,0 +1,303 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Estimates of the time and memory needed to prove a computation.
+//!
+//! A [CostModel] maps the segments of a session to the expected proving time
+//! and peak memory of a given backend, so that a service can schedule and
+//! price a job from its cycle count before proving it. The proving time of a
+//! segment is modelled as a fixed overhead plus a cost per padded cycle, and
+//! the memory as a fixed base plus a cost per padded cycle.
+//!
+//! [CostModel::builtin] provides rough reference figures for each backend.
+//! For accurate estimates, benchmark the machine that will do the proving
+//! with `r0vm --calibrate <PROFILE>` and load the saved profile with
+//! [CostModel::load].
+
+use alloc::string::{String, ToString};
+use core::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Error, Result};
+use risc0_zkp::{MAX_CYCLES_PO2, MIN_CYCLES_PO2};
+use serde::{Deserialize, Serialize};
+
+/// A model of the cost of proving segments on a given backend.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CostModel {
+    /// The backend this model describes, e.g. `cpu`, `cuda` or `metal`.
+    pub backend: String,
+
+    /// The fixed time to prove a segment, in seconds.
+    pub secs_per_segment: f64,
+
+    /// The time to prove each padded cycle of a segment, in seconds.
+    pub secs_per_cycle: f64,
+
+    /// The memory used by the prover regardless of segment size, in bytes.
+    pub base_memory_bytes: f64,
+
+    /// The memory used for each padded cycle of a segment, in bytes.
+    pub memory_bytes_per_cycle: f64,
+}
+
+/// The estimated cost of proving a session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostEstimate {
+    /// The number of segments.
+    pub segments: usize,
+
+    /// The expected proving time, in seconds.
+    pub secs: f64,
+
+    /// The expected peak memory usage of the prover, in bytes.
+    pub peak_memory_bytes: u64,
+}
+
+impl CostEstimate {
+    /// The price of this estimate, given the hourly cost of the machine.
+    pub fn price(&self, rate_per_hour: f64) -> f64 {
+        self.secs / 3600.0 * rate_per_hour
+    }
+}
+
+impl CostModel {
+    /// Return reference figures for the given backend, or `None` if the
+    /// backend is unknown.
+    ///
+    /// These are rough figures for typical hardware; use a calibrated
+    /// profile where accuracy matters.
+    pub fn builtin(backend: &str) -> Option<Self> {
+        let (secs_per_segment, secs_per_cycle) = match backend {
+            "cpu" => (0.5, 50e-6),
+            "metal" => (0.2, 10e-6),
+            "cuda" => (0.1, 4e-6),
+            _ => return None,
+        };
+        Some(Self {
+            backend: backend.to_string(),
+            secs_per_segment,
+            secs_per_cycle,
+            base_memory_bytes: 256.0 * 1024.0 * 1024.0,
+            memory_bytes_per_cycle: 8192.0,
+        })
+    }
+
+    /// The expected time to prove a segment of `2^po2` cycles, in seconds.
+    pub fn segment_secs(&self, po2: usize) -> f64 {
+        self.secs_per_segment + self.secs_per_cycle * (1u64 << po2) as f64
+    }
+
+    /// The expected peak memory to prove a segment of `2^po2` cycles, in
+    /// bytes.
+    pub fn segment_memory_bytes(&self, po2: usize) -> u64 {
+        (self.base_memory_bytes + self.memory_bytes_per_cycle * (1u64 << po2) as f64) as u64
+    }
+
+    /// Estimate the cost of proving segments of the given sizes, as powers of
+    /// 2 cycles, e.g. the `po2` of each segment of an executed session.
+    pub fn estimate_segments(&self, po2s: impl IntoIterator<Item = usize>) -> CostEstimate {
+        let mut estimate = CostEstimate {
+            segments: 0,
+            secs: 0.0,
+            peak_memory_bytes: 0,
+        };
+        for po2 in po2s {
+            estimate.segments += 1;
+            estimate.secs += self.segment_secs(po2);
+            estimate.peak_memory_bytes = estimate
+                .peak_memory_bytes
+                .max(self.segment_memory_bytes(po2));
+        }
+        estimate
+    }
+
+    /// Estimate the cost of proving a session of the given number of cycles,
+    /// split into segments of at most `2^segment_limit_po2` cycles.
+    ///
+    /// This assumes every segment but the last is full, so it slightly
+    /// underestimates sessions with many system splits.
+    pub fn estimate(&self, cycles: u64, segment_limit_po2: usize) -> CostEstimate {
+        let segment_limit_po2 = segment_limit_po2.clamp(MIN_CYCLES_PO2, MAX_CYCLES_PO2);
+        let full = cycles >> segment_limit_po2;
+        let rest = cycles & ((1 << segment_limit_po2) - 1);
+        let last = (rest > 0).then(|| {
+            let po2 = (u64::BITS - (rest - 1).leading_zeros()) as usize;
+            po2.max(MIN_CYCLES_PO2)
+        });
+        let po2s = core::iter::repeat(segment_limit_po2).take(full as usize);
+        self.estimate_segments(po2s.chain(last))
+    }
+}
+
+impl fmt::Display for CostModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "backend = \"{}\"", self.backend)?;
+        writeln!(f, "secs_per_segment = {}", self.secs_per_segment)?;
+        writeln!(f, "secs_per_cycle = {}", self.secs_per_cycle)?;
+        writeln!(f, "base_memory_bytes = {}", self.base_memory_bytes)?;
+        writeln!(
+            f,
+            "memory_bytes_per_cycle = {}",
+            self.memory_bytes_per_cycle
+        )
+    }
+}
+
+impl FromStr for CostModel {
+    type Err = Error;
+
+    /// Parse a profile written by the [Display](fmt::Display) implementation.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut backend = None;
+        let mut values = [None; 4];
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid cost profile line: {line}"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let idx = match key {
+                "backend" => {
+                    backend = Some(value.trim_matches('"').to_string());
+                    continue;
+                }
+                "secs_per_segment" => 0,
+                "secs_per_cycle" => 1,
+                "base_memory_bytes" => 2,
+                "memory_bytes_per_cycle" => 3,
+                _ => bail!("unknown cost profile key: {key}"),
+            };
+            let value = value
+                .parse::<f64>()
+                .map_err(|_| anyhow!("invalid value for {key}: {value}"))?;
+            values[idx] = Some(value);
+        }
+        let missing = |name: &str| anyhow!("missing cost profile key: {name}");
+        Ok(Self {
+            backend: backend.ok_or_else(|| missing("backend"))?,
+            secs_per_segment: values[0].ok_or_else(|| missing("secs_per_segment"))?,
+            secs_per_cycle: values[1].ok_or_else(|| missing("secs_per_cycle"))?,
+            base_memory_bytes: values[2].ok_or_else(|| missing("base_memory_bytes"))?,
+            memory_bytes_per_cycle: values[3].ok_or_else(|| missing("memory_bytes_per_cycle"))?,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl CostModel {
+    /// Load a profile saved with [CostModel::save].
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        std::fs::read_to_string(path.as_ref())?.parse()
+    }
+
+    /// Save this model as a profile.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        Ok(std::fs::write(path.as_ref(), self.to_string())?)
+    }
+}
+
+#[cfg(feature = "prove")]
+mod calibrate {
+    use std::{collections::BTreeMap, ops::RangeInclusive, time::Instant};
+
+    use anyhow::{ensure, Result};
+    use risc0_binfmt::{MemoryImage, Program};
+    use risc0_zkvm_platform::{PAGE_SIZE, WORD_SIZE};
+
+    use super::CostModel;
+    use crate::{prove::Prover, Executor, ExecutorEnv, Segment, VerifierContext};
+
+    impl CostModel {
+        /// Calibrate a model for the given [Prover] on this machine, by
+        /// proving a synthetic segment of each of the given sizes, as powers
+        /// of 2 cycles.
+        pub fn calibrate(prover: &dyn Prover, po2s: RangeInclusive<usize>) -> Result<Self> {
+            ensure!(
+                po2s.clone().count() >= 2,
+                "calibration needs at least two segment sizes"
+            );
+            let ctx = VerifierContext::default();
+            let mut samples = Vec::new();
+            for po2 in po2s {
+                let segment = calibration_segment(po2)?;
+                let start = Instant::now();
+                prover.prove_segment(&ctx, &segment)?;
+                let secs = start.elapsed().as_secs_f64();
+                // Segments are proven in increasing size, so the peak is that
+                // of the current segment.
+                let memory = prover.get_peak_memory_usage() as f64;
+                samples.push(((1u64 << po2) as f64, secs, memory));
+            }
+
+            let (secs_per_segment, secs_per_cycle) = fit(samples.iter().map(|x| (x.0, x.1)));
+            let (base_memory_bytes, memory_bytes_per_cycle) =
+                fit(samples.iter().map(|x| (x.0, x.2)));
+            Ok(Self {
+                backend: prover.capabilities().backend,
+                secs_per_segment,
+                secs_per_cycle,
+                base_memory_bytes,
+                memory_bytes_per_cycle,
+            })
+        }
+    }
+
+    // Produce a full segment of `2^po2` cycles, by running a loop longer than
+    // one segment.
+    fn calibration_segment(po2: usize) -> Result<Segment> {
+        let iterations = 1u32 << po2;
+        let upper = (iterations + 0x800) >> 12;
+        let lower = iterations.wrapping_sub(upper << 12) & 0xfff;
+        let code = [
+            (upper << 12) | 0x637,   // lui a2, upper
+            (lower << 20) | 0x60613, // addi a2, a2, lower
+            0xfff60613,              // addi a2, a2, -1
+            0xfe061ee3,              // bnez a2, -4
+            0x000055b7,              // lui a1, 0x00005000
+            0xc0058593,              // addi a1, a1, -0x400
+            0x00000073,              // ecall(halt)
+        ];
+        let entry = 0x4000;
+        let image: BTreeMap<u32, u32> = code
+            .iter()
+            .enumerate()
+            .map(|(idx, insn)| (entry + (idx * WORD_SIZE) as u32, *insn))
+            .collect();
+        let program = Program { entry, image };
+        let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
+        let env = ExecutorEnv::builder().segment_limit_po2(po2).build()?;
+        let session = Executor::new(env, image).run()?;
+        let segment = session.segments[0].resolve()?;
+        ensure!(segment.po2 == po2, "calibration segment is not full");
+        Ok(segment)
+    }
+
+    // Least squares fit of `y = a + b * x`, clamped to non-negative
+    // coefficients.
+    fn fit(samples: impl Iterator<Item = (f64, f64)> + Clone) -> (f64, f64) {
+        let n = samples.clone().count() as f64;
+        let mean_x = samples.clone().map(|x| x.0).sum::<f64>() / n;
+        let mean_y = samples.clone().map(|x| x.1).sum::<f64>() / n;
+        let cov: f64 = samples
+            .clone()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let var: f64 = samples.map(|(x, _)| (x - mean_x) * (x - mean_x)).sum();
+        let b = (cov / var).max(0.0);
+        let a = (mean_y - b * mean_x).max(0.0);
+        (a, b)
+    }
+}
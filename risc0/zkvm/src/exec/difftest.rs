@@ -0,0 +1,376 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Differential testing of the executor against a reference emulator.
+//!
+//! With [ExecutorEnvBuilder::diff_test](crate::ExecutorEnvBuilder::diff_test),
+//! every sampled instruction is also executed by a [ReferenceEmulator],
+//! starting from the same architectural state. The executor fails with an
+//! error describing the first instruction whose resulting PC, registers, or
+//! memory writes differ.
+//!
+//! The reference reads memory as the executor saw it before the instruction,
+//! so each instruction is checked in isolation and a divergence is reported
+//! at the instruction that caused it. Syscalls (`ecall`) are not checked,
+//! since their effects are defined by the host.
+//!
+//! [Rv32imInterpreter] is a small interpreter written independently of the
+//! executor's instruction decoder, and can be replaced by a binding to an
+//! external emulator by implementing [ReferenceEmulator].
+
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::{bail, Result};
+use risc0_zkvm_platform::syscall::reg_abi::REG_MAX;
+
+use super::monitor::MemoryMonitor;
+
+/// The architectural state of a RISC-V hart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchState {
+    /// The program counter.
+    pub pc: u32,
+
+    /// The general purpose registers, `x0` through `x31`.
+    pub regs: [u32; REG_MAX],
+}
+
+/// Memory accessed by a [ReferenceEmulator].
+pub trait ReferenceMemory {
+    /// Load the byte at `addr`.
+    fn load_u8(&mut self, addr: u32) -> Result<u8>;
+
+    /// Store a byte at `addr`.
+    fn store_u8(&mut self, addr: u32, value: u8) -> Result<()>;
+}
+
+/// An RV32IM emulator to compare the executor against.
+pub trait ReferenceEmulator {
+    /// Execute the single instruction at `state.pc`, updating `state` and
+    /// `mem`.
+    fn step(&mut self, state: &mut ArchState, mem: &mut dyn ReferenceMemory) -> Result<()>;
+}
+
+/// A straightforward RV32IM interpreter.
+#[derive(Default)]
+pub struct Rv32imInterpreter;
+
+fn load(mem: &mut dyn ReferenceMemory, addr: u32, len: u32) -> Result<u32> {
+    let mut value = 0;
+    for i in (0..len).rev() {
+        value = (value << 8) | mem.load_u8(addr.wrapping_add(i))? as u32;
+    }
+    Ok(value)
+}
+
+fn store(mem: &mut dyn ReferenceMemory, addr: u32, len: u32, value: u32) -> Result<()> {
+    for i in 0..len {
+        mem.store_u8(addr.wrapping_add(i), (value >> (8 * i)) as u8)?;
+    }
+    Ok(())
+}
+
+/// Sign extend the low `bits` bits of `value`.
+fn sext(value: u32, bits: u32) -> u32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32 >> shift) as u32
+}
+
+impl ReferenceEmulator for Rv32imInterpreter {
+    fn step(&mut self, state: &mut ArchState, mem: &mut dyn ReferenceMemory) -> Result<()> {
+        let pc = state.pc;
+        let insn = load(mem, pc, 4)?;
+        let opcode = insn & 0x7f;
+        let rd = ((insn >> 7) & 0x1f) as usize;
+        let funct3 = (insn >> 12) & 0x7;
+        let rs1 = state.regs[((insn >> 15) & 0x1f) as usize];
+        let rs2 = state.regs[((insn >> 20) & 0x1f) as usize];
+        let funct7 = insn >> 25;
+
+        let imm_i = sext(insn >> 20, 12);
+        let imm_s = sext(((insn >> 25) << 5) | ((insn >> 7) & 0x1f), 12);
+        let imm_b = sext(
+            (insn >> 31) << 12
+                | ((insn >> 7) & 0x1) << 11
+                | ((insn >> 25) & 0x3f) << 5
+                | ((insn >> 8) & 0xf) << 1,
+            13,
+        );
+        let imm_u = insn & 0xffff_f000;
+        let imm_j = sext(
+            (insn >> 31) << 20
+                | ((insn >> 12) & 0xff) << 12
+                | ((insn >> 20) & 0x1) << 11
+                | ((insn >> 21) & 0x3ff) << 1,
+            21,
+        );
+
+        let mut next_pc = pc.wrapping_add(4);
+        let result = match opcode {
+            0b0110111 => Some(imm_u),
+            0b0010111 => Some(pc.wrapping_add(imm_u)),
+            0b1101111 => {
+                next_pc = pc.wrapping_add(imm_j);
+                Some(pc.wrapping_add(4))
+            }
+            0b1100111 => {
+                next_pc = rs1.wrapping_add(imm_i) & !1;
+                Some(pc.wrapping_add(4))
+            }
+            0b1100011 => {
+                let taken = match funct3 {
+                    0 => rs1 == rs2,
+                    1 => rs1 != rs2,
+                    4 => (rs1 as i32) < (rs2 as i32),
+                    5 => (rs1 as i32) >= (rs2 as i32),
+                    6 => rs1 < rs2,
+                    7 => rs1 >= rs2,
+                    _ => bail!("illegal branch: 0x{insn:08x}"),
+                };
+                if taken {
+                    next_pc = pc.wrapping_add(imm_b);
+                }
+                None
+            }
+            0b0000011 => {
+                let addr = rs1.wrapping_add(imm_i);
+                Some(match funct3 {
+                    0 => sext(load(mem, addr, 1)?, 8),
+                    1 => sext(load(mem, addr, 2)?, 16),
+                    2 => load(mem, addr, 4)?,
+                    4 => load(mem, addr, 1)?,
+                    5 => load(mem, addr, 2)?,
+                    _ => bail!("illegal load: 0x{insn:08x}"),
+                })
+            }
+            0b0100011 => {
+                let addr = rs1.wrapping_add(imm_s);
+                match funct3 {
+                    0 => store(mem, addr, 1, rs2)?,
+                    1 => store(mem, addr, 2, rs2)?,
+                    2 => store(mem, addr, 4, rs2)?,
+                    _ => bail!("illegal store: 0x{insn:08x}"),
+                }
+                None
+            }
+            0b0010011 => {
+                let shamt = imm_i & 0x1f;
+                Some(match funct3 {
+                    0 => rs1.wrapping_add(imm_i),
+                    1 => rs1 << shamt,
+                    2 => ((rs1 as i32) < (imm_i as i32)) as u32,
+                    3 => (rs1 < imm_i) as u32,
+                    4 => rs1 ^ imm_i,
+                    5 if funct7 == 0x20 => ((rs1 as i32) >> shamt) as u32,
+                    5 => rs1 >> shamt,
+                    6 => rs1 | imm_i,
+                    _ => rs1 & imm_i,
+                })
+            }
+            0b0110011 if funct7 == 0x01 => {
+                let (a, b) = (rs1 as i32, rs2 as i32);
+                Some(match funct3 {
+                    0 => rs1.wrapping_mul(rs2),
+                    1 => ((a as i64 * b as i64) >> 32) as u32,
+                    2 => ((a as i64 * rs2 as i64) >> 32) as u32,
+                    3 => ((rs1 as u64 * rs2 as u64) >> 32) as u32,
+                    4 if b == 0 => u32::MAX,
+                    4 => a.wrapping_div(b) as u32,
+                    5 if rs2 == 0 => u32::MAX,
+                    5 => rs1 / rs2,
+                    6 if b == 0 => rs1,
+                    6 => a.wrapping_rem(b) as u32,
+                    _ if rs2 == 0 => rs1,
+                    _ => rs1 % rs2,
+                })
+            }
+            0b0110011 => {
+                let shamt = rs2 & 0x1f;
+                Some(match (funct3, funct7) {
+                    (0, 0x00) => rs1.wrapping_add(rs2),
+                    (0, 0x20) => rs1.wrapping_sub(rs2),
+                    (1, 0x00) => rs1 << shamt,
+                    (2, 0x00) => ((rs1 as i32) < (rs2 as i32)) as u32,
+                    (3, 0x00) => (rs1 < rs2) as u32,
+                    (4, 0x00) => rs1 ^ rs2,
+                    (5, 0x00) => rs1 >> shamt,
+                    (5, 0x20) => ((rs1 as i32) >> shamt) as u32,
+                    (6, 0x00) => rs1 | rs2,
+                    (7, 0x00) => rs1 & rs2,
+                    _ => bail!("illegal instruction: 0x{insn:08x}"),
+                })
+            }
+            _ => bail!("unsupported instruction: 0x{insn:08x}"),
+        };
+
+        if let Some(value) = result.filter(|_| rd != 0) {
+            state.regs[rd] = value;
+        }
+        state.pc = next_pc;
+        Ok(())
+    }
+}
+
+/// The configuration of differential testing in an
+/// [ExecutorEnv](crate::ExecutorEnv).
+#[derive(Clone)]
+pub(crate) struct DiffTest<'a> {
+    pub emulator: Rc<RefCell<dyn ReferenceEmulator + 'a>>,
+    pub interval: u32,
+}
+
+/// Memory as seen by the executor, with stores buffered instead of applied.
+struct MonitorMemory<'m> {
+    monitor: &'m mut MemoryMonitor,
+    stores: Vec<(u32, u8)>,
+}
+
+impl ReferenceMemory for MonitorMemory<'_> {
+    fn load_u8(&mut self, addr: u32) -> Result<u8> {
+        match self.stores.iter().rev().find(|(x, _)| *x == addr) {
+            Some((_, value)) => Ok(*value),
+            None => self.monitor.load_u8(addr),
+        }
+    }
+
+    fn store_u8(&mut self, addr: u32, value: u8) -> Result<()> {
+        self.stores.push((addr, value));
+        Ok(())
+    }
+}
+
+/// The state the reference expects after executing an instruction.
+pub(crate) struct Expected {
+    pc: u32,
+    state: ArchState,
+    stores: Vec<(u32, u8)>,
+}
+
+impl DiffTest<'_> {
+    /// Run the reference on the instruction at `pc`, before the executor
+    /// executes it.
+    pub fn expect(
+        &self,
+        pc: u32,
+        regs: [u32; REG_MAX],
+        monitor: &mut MemoryMonitor,
+    ) -> Result<Expected> {
+        let mut state = ArchState { pc, regs };
+        let mut mem = MonitorMemory {
+            monitor,
+            stores: Vec::new(),
+        };
+        self.emulator.borrow_mut().step(&mut state, &mut mem)?;
+        Ok(Expected {
+            pc,
+            state,
+            stores: mem.stores,
+        })
+    }
+}
+
+impl Expected {
+    /// Compare the state after the executor executed the instruction with the
+    /// state expected by the reference.
+    pub fn check(&self, pc: u32, regs: &[u32; REG_MAX], monitor: &mut MemoryMonitor) -> Result<()> {
+        let at = self.pc;
+        if pc != self.state.pc {
+            bail!(
+                "difftest divergence at 0x{at:08x}: next pc is 0x{pc:08x}, reference expects 0x{:08x}",
+                self.state.pc
+            );
+        }
+        for (idx, (actual, expected)) in regs.iter().zip(self.state.regs.iter()).enumerate() {
+            if actual != expected {
+                bail!(
+                    "difftest divergence at 0x{at:08x}: x{idx} is 0x{actual:08x}, reference expects 0x{expected:08x}"
+                );
+            }
+        }
+        for (addr, expected) in self.stores.iter() {
+            let actual = monitor.load_u8(*addr)?;
+            if actual != *expected {
+                bail!(
+                    "difftest divergence at 0x{at:08x}: byte 0x{addr:08x} is 0x{actual:02x}, reference expects 0x{expected:02x}"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestMemory(BTreeMap<u32, u8>);
+
+    impl ReferenceMemory for TestMemory {
+        fn load_u8(&mut self, addr: u32) -> Result<u8> {
+            Ok(self.0.get(&addr).copied().unwrap_or_default())
+        }
+
+        fn store_u8(&mut self, addr: u32, value: u8) -> Result<()> {
+            self.0.insert(addr, value);
+            Ok(())
+        }
+    }
+
+    fn run(insns: &[u32], regs: &[(usize, u32)]) -> (ArchState, TestMemory) {
+        let mut mem = TestMemory::default();
+        for (idx, insn) in insns.iter().enumerate() {
+            store(&mut mem, 0x1000 + 4 * idx as u32, 4, *insn).unwrap();
+        }
+        let mut state = ArchState {
+            pc: 0x1000,
+            regs: [0; REG_MAX],
+        };
+        for (reg, value) in regs {
+            state.regs[*reg] = *value;
+        }
+        for _ in insns {
+            Rv32imInterpreter.step(&mut state, &mut mem).unwrap();
+        }
+        (state, mem)
+    }
+
+    #[test]
+    fn arith() {
+        // addi a0, zero, -3; mul a1, a0, a0; div a2, a0, zero; srai a3, a0, 1
+        let (state, _) = run(&[0xffd00513, 0x02a505b3, 0x02054633, 0x40155693], &[]);
+        assert_eq!(state.regs[10], -3i32 as u32);
+        assert_eq!(state.regs[11], 9);
+        assert_eq!(state.regs[12], u32::MAX);
+        assert_eq!(state.regs[13], -2i32 as u32);
+        assert_eq!(state.pc, 0x1010);
+    }
+
+    #[test]
+    fn load_store() {
+        // sw a1, 4(a0); lb a2, 7(a0)
+        let (state, mut mem) = run(&[0x00b52223, 0x00750603], &[(10, 0x2000), (11, 0x80000000)]);
+        assert_eq!(load(&mut mem, 0x2004, 4).unwrap(), 0x80000000);
+        assert_eq!(state.regs[12], 0xffffff80);
+    }
+
+    #[test]
+    fn branch() {
+        // bne a0, zero, -4
+        let (state, _) = run(&[0xfe051ee3], &[(10, 1)]);
+        assert_eq!(state.pc, 0x0ffc);
+    }
+}
@@ -15,26 +15,43 @@
 //! This module defines the [ExecutorEnv] and [ExecutorEnvBuilder].
 
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     io::{BufRead, BufReader, Cursor, Read, Write},
+    ops::Range,
     rc::Rc,
 };
 
 use anyhow::Result;
 use bytemuck::Pod;
+use risc0_binfmt::{MemoryImage, Program};
 use risc0_zkvm_platform::{
     fileno,
+    memory::MEM_SIZE,
     syscall::{
-        nr::{SYS_GETENV, SYS_READ, SYS_READ_AVAIL, SYS_WRITE},
+        nr::{
+            SYS_GETENV, SYS_LAYOUT, SYS_READ, SYS_READ_AVAIL, SYS_READ_FRAME, SYS_WRITE,
+            SYS_YIELD_OUTPUT,
+        },
         SyscallName,
     },
+    PAGE_SIZE, WORD_SIZE,
 };
+use serde::Serialize;
 use thiserror::Error;
 
 use super::{
     io::{slice_io_from_fn, syscalls, PosixIo, SliceIo, Syscall, SyscallTable},
-    TraceEvent,
+    readonly::{map_regions, ReadonlyRegion},
+    FaultInfo, SegmentInfo, SyscallInfo, TraceEvent,
+};
+use crate::{
+    align_up,
+    oracle::{KvStore, MerkleProof, OracleRequest, SYS_KV_ORACLE},
+    ports::{PortKind, PortManifest},
+    serde::{from_slice, to_vec},
+    sha::Digest,
+    storage::STREAM_CHUNK_BYTES,
 };
 
 /// The default segment limit specified in powers of 2 cycles. Choose this value
@@ -47,9 +64,44 @@ pub struct ExecutorEnvBuilder<'a> {
     inner: ExecutorEnv<'a>,
 }
 
+// The input of an [ExecutorEnv] as read by the guest, shared with the template
+// of the environment rather than copied.
+struct SharedInput(Rc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedInput {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 /// A callback used to collect [TraceEvent]s.
 pub type TraceCallback<'a> = dyn FnMut(TraceEvent) -> Result<()> + 'a;
 
+/// A callback invoked at each segment boundary with a [SegmentInfo].
+pub type SegmentCallback<'a> = dyn FnMut(&SegmentInfo) -> Result<()> + 'a;
+
+/// A callback invoked whenever the guest calls into a host syscall.
+pub type SyscallCallback<'a> = dyn FnMut(&SyscallInfo) -> Result<()> + 'a;
+
+/// A callback invoked with each output yielded by the guest.
+pub type OutputCallback<'a> = dyn FnMut(&[u8]) -> Result<()> + 'a;
+
+/// A callback invoked when execution of the guest faults.
+pub type FaultCallback<'a> = dyn FnMut(&FaultInfo) + 'a;
+
+/// A function used to check the input of the guest before execution.
+pub type InputValidator<'a> = dyn Fn(&[u8]) -> Result<()> + 'a;
+
+// A port bound on the host, checked against the port manifest by
+// ExecutorEnvBuilder::build.
+#[derive(Clone)]
+struct PortBinding {
+    name: String,
+    kind: &'static str,
+    schema: String,
+    error: Option<crate::serde::Error>,
+}
+
 /// The [super::Executor] is configured from this object.
 ///
 /// The executor environment holds configuration details that inform how the
@@ -61,8 +113,29 @@ pub struct ExecutorEnv<'a> {
     session_limit: Option<usize>,
     syscalls: SyscallTable<'a>,
     pub(crate) io: Rc<RefCell<PosixIo<'a>>>,
-    pub(crate) input: Vec<u8>,
+    pub(crate) input: Rc<Vec<u8>>,
+    frames: Rc<Vec<Vec<u8>>>,
     pub(crate) trace_callback: Option<Rc<RefCell<TraceCallback<'a>>>>,
+    pub(crate) segment_callbacks: Vec<Rc<RefCell<SegmentCallback<'a>>>>,
+    pub(crate) syscall_callbacks: Vec<Rc<RefCell<SyscallCallback<'a>>>>,
+    pub(crate) fault_callbacks: Vec<Rc<RefCell<FaultCallback<'a>>>>,
+    output_callbacks: Vec<Rc<RefCell<OutputCallback<'a>>>>,
+    pub(crate) preload_pages: Vec<Range<u32>>,
+    pub(crate) readonly_regions: Vec<ReadonlyRegion<'a>>,
+    pub(crate) fault_proofs: bool,
+    pub(crate) retain_memory: bool,
+    pub(crate) execution_stats: Option<usize>,
+    #[cfg(feature = "difftest")]
+    pub(crate) diff_test: Option<super::difftest::DiffTest<'a>>,
+    pub(crate) stack_limit: Rc<Cell<Option<u32>>>,
+    max_input_bytes: Option<usize>,
+    input_validators: Vec<Rc<InputValidator<'a>>>,
+    input_slots: Vec<(usize, String)>,
+    slot_values: HashMap<String, Vec<u8>>,
+    template: Option<Rc<ExecutorEnvBuilder<'a>>>,
+    port_manifest: Option<PortManifest>,
+    port_bindings: Vec<PortBinding>,
+    current_thread_syscalls: Vec<String>,
 }
 
 impl<'a> ExecutorEnv<'a> {
@@ -83,6 +156,57 @@ impl<'a> ExecutorEnv<'a> {
         ExecutorEnvBuilder::default()
     }
 
+    /// Construct a [ExecutorEnvBuilder] with the configuration this
+    /// [ExecutorEnv] was built from.
+    ///
+    /// The returned builder inherits the limits, environment variables,
+    /// syscalls, file descriptors, callbacks, and input of this environment,
+    /// and can be changed without affecting it. Handlers, callbacks, readers
+    /// and writers are shared rather than copied, so a reader that was
+    /// consumed by a previous run is not rewound.
+    ///
+    /// Combined with [ExecutorEnvBuilder::input_slot], this makes it cheap to
+    /// construct many environments that only differ in part of their input.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .segment_limit_po2(16)
+    ///     .input_slot("value")
+    ///     .fill_slot("value", &[1u32])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let other = env.to_builder().fill_slot("value", &[2u32]).build().unwrap();
+    /// ```
+    pub fn to_builder(&self) -> ExecutorEnvBuilder<'a> {
+        let mut builder = match &self.template {
+            Some(template) => template.as_ref().clone(),
+            None => ExecutorEnvBuilder {
+                inner: self.clone(),
+            },
+        };
+        builder.inner.io = Rc::new(RefCell::new(builder.inner.io.borrow().clone()));
+        builder
+    }
+
+    /// Return the image ID of the given ELF binary with the read-only regions
+    /// of this environment mapped, as mapped by
+    /// [ExecutorEnvBuilder::map_readonly].
+    ///
+    /// This is the image ID that receipts of the guest run with this
+    /// environment are verified against. Without read-only regions, it is the
+    /// image ID of the ELF binary.
+    pub fn image_id(&self, elf: &[u8]) -> Result<Digest> {
+        let program = Program::load_elf(elf, MEM_SIZE as u32)?;
+        let mut image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
+        map_regions(&mut image, &self.readonly_regions)?;
+        Ok(image.compute_id())
+    }
+
     pub(crate) fn get_segment_limit(&self) -> usize {
         1 << self.segment_limit_po2
     }
@@ -112,7 +236,28 @@ impl<'a> Default for ExecutorEnvBuilder<'a> {
                 syscalls: Default::default(),
                 io: Default::default(),
                 input: Default::default(),
+                frames: Default::default(),
                 trace_callback: Default::default(),
+                segment_callbacks: Default::default(),
+                syscall_callbacks: Default::default(),
+                fault_callbacks: Default::default(),
+                output_callbacks: Default::default(),
+                preload_pages: Default::default(),
+                readonly_regions: Default::default(),
+                fault_proofs: false,
+                retain_memory: false,
+                execution_stats: None,
+                #[cfg(feature = "difftest")]
+                diff_test: None,
+                stack_limit: Default::default(),
+                max_input_bytes: None,
+                input_validators: Default::default(),
+                input_slots: Default::default(),
+                slot_values: Default::default(),
+                template: None,
+                port_manifest: None,
+                port_bindings: Default::default(),
+                current_thread_syscalls: Default::default(),
             },
         }
     }
@@ -124,6 +269,55 @@ pub enum ExecutorEnvBuilderErr {
     /// Segment limit PO2 falls outside supported range.
     #[error("Invalid segment_limit_po2: {po2}")]
     SegmentLimitPo2OutOfBounds { po2: usize },
+
+    /// The input is larger than the configured maximum.
+    #[error("Input of {size} bytes exceeds max_input_bytes: {max}")]
+    InputTooLarge { size: usize, max: usize },
+
+    /// The input was rejected by an input validator.
+    #[error("Invalid input: {0}")]
+    InvalidInput(anyhow::Error),
+
+    /// An input slot was declared but never filled.
+    #[error("Input slot was not filled: {name}")]
+    UnfilledInputSlot { name: String },
+
+    /// A port declared by the guest was not bound.
+    #[error("Port declared by the guest was not bound: {name}")]
+    UnboundPort { name: String },
+
+    /// A port was bound that the guest does not declare, or it was bound
+    /// before the port manifest was set.
+    #[error("Port is not declared in the port manifest: {name}")]
+    UnknownPort { name: String },
+
+    /// A port was bound as a different kind of port than the guest declares.
+    #[error("Port {name} is declared as an {expected} port, but was bound as an {actual} port")]
+    PortKindMismatch {
+        name: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    /// A port was bound with a different schema than the guest declares.
+    #[error("Port {name} is declared with schema {expected}, but was bound with schema {actual}")]
+    PortSchemaMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// An async syscall handler was given a handle of a current-thread
+    /// runtime, see [ExecutorEnvBuilder::io_callback_async].
+    #[error("Async handler for syscall {syscall} requires a multi-thread runtime")]
+    CurrentThreadRuntime { syscall: String },
+
+    /// The value bound to an input port could not be serialized.
+    #[error("Unable to serialize the value of port {name}: {error}")]
+    PortSerialization {
+        name: String,
+        error: crate::serde::Error,
+    },
 }
 
 impl<'a> ExecutorEnvBuilder<'a> {
@@ -148,26 +342,148 @@ impl<'a> ExecutorEnvBuilder<'a> {
             });
         }
 
+        // Splice the contents of the input slots into the input. Without
+        // slots, the input is shared with the template rather than copied.
+        let input = match self.inner.input_slots.is_empty() {
+            true => self.inner.input.clone(),
+            false => {
+                let mut input = Vec::new();
+                let mut offset = 0;
+                for (pos, name) in self.inner.input_slots.iter() {
+                    let value = self.inner.slot_values.get(name).ok_or_else(|| {
+                        ExecutorEnvBuilderErr::UnfilledInputSlot { name: name.clone() }
+                    })?;
+                    input.extend_from_slice(&self.inner.input[offset..*pos]);
+                    input.extend_from_slice(value);
+                    offset = *pos;
+                }
+                input.extend_from_slice(&self.inner.input[offset..]);
+                Rc::new(input)
+            }
+        };
+
+        // Check the input before anything is executed
+        if let Some(max) = self.inner.max_input_bytes {
+            if input.len() > max {
+                return Err(ExecutorEnvBuilderErr::InputTooLarge {
+                    size: input.len(),
+                    max,
+                });
+            }
+        }
+        for validator in self.inner.input_validators.iter() {
+            validator(&input).map_err(ExecutorEnvBuilderErr::InvalidInput)?;
+        }
+        self.check_ports()?;
+        if let Some(syscall) = self.inner.current_thread_syscalls.first() {
+            return Err(ExecutorEnvBuilderErr::CurrentThreadRuntime {
+                syscall: syscall.clone(),
+            });
+        }
+
+        // Remember this configuration for ExecutorEnv::to_builder
+        let mut template = self.clone();
+        template.inner.template = None;
+        template.inner.io = Rc::new(RefCell::new(self.inner.io.borrow().clone()));
+
         // Construct the executor environment
         let mut result = self.clone();
         let getenv = syscalls::Getenv(self.inner.env_vars.clone());
-        if !self.inner.input.is_empty() {
-            let reader = Cursor::new(self.inner.input.clone());
-            result
-                .inner
-                .io
-                .borrow_mut()
-                .with_read_fd(fileno::STDIN, reader);
+        let yield_output = syscalls::YieldOutput(self.inner.output_callbacks.clone());
+        let stack_limit = Rc::new(Cell::new(None));
+        let layout = syscalls::Layout(stack_limit.clone());
+        let mut io = self.inner.io.borrow().clone();
+        if !input.is_empty() {
+            io.with_read_fd(fileno::STDIN, Cursor::new(SharedInput(input.clone())));
         }
+        result.inner.io = Rc::new(RefCell::new(io));
+        result.inner.input = input;
+        result.inner.stack_limit = stack_limit;
+        result.inner.input_slots.clear();
+        result.inner.slot_values.clear();
+        result.inner.template = Some(Rc::new(template));
         let io = result.inner.io.clone();
+        let frames = self.inner.frames.clone();
+        let next_frame = Cell::new(0);
         result
+            .slice_io(
+                SYS_READ_FRAME,
+                slice_io_from_fn(move |_: &[u32]| -> Vec<u32> {
+                    // The length of the frame in bytes, followed by the
+                    // frame padded to a whole number of words.
+                    let Some(frame) = frames.get(next_frame.get()) else {
+                        return vec![u32::MAX];
+                    };
+                    next_frame.set(next_frame.get() + 1);
+                    let mut words = vec![0u32; 1 + align_up(frame.len(), WORD_SIZE) / WORD_SIZE];
+                    words[0] = frame.len() as u32;
+                    bytemuck::cast_slice_mut(&mut words[1..])[..frame.len()].copy_from_slice(frame);
+                    words
+                }),
+            )
             .syscall(SYS_GETENV, getenv)
+            .syscall(SYS_LAYOUT, layout)
             .syscall(SYS_READ, io.clone())
             .syscall(SYS_READ_AVAIL, io.clone())
-            .syscall(SYS_WRITE, io);
+            .syscall(SYS_WRITE, io)
+            .syscall(SYS_YIELD_OUTPUT, yield_output);
         Ok(result.inner.clone())
     }
 
+    // Check the bound ports against the port manifest, if one was set.
+    fn check_ports(&self) -> Result<(), ExecutorEnvBuilderErr> {
+        let bindings = &self.inner.port_bindings;
+        let Some(manifest) = &self.inner.port_manifest else {
+            return match bindings.first() {
+                Some(binding) => Err(ExecutorEnvBuilderErr::UnknownPort {
+                    name: binding.name.clone(),
+                }),
+                None => Ok(()),
+            };
+        };
+        for binding in bindings.iter() {
+            if let Some(error) = &binding.error {
+                return Err(ExecutorEnvBuilderErr::PortSerialization {
+                    name: binding.name.clone(),
+                    error: error.clone(),
+                });
+            }
+            let port =
+                manifest
+                    .get(&binding.name)
+                    .ok_or_else(|| ExecutorEnvBuilderErr::UnknownPort {
+                        name: binding.name.clone(),
+                    })?;
+            let expected = match port.kind {
+                PortKind::Input { .. } => "input",
+                PortKind::Output { .. } => "output",
+                PortKind::Oracle => "oracle",
+            };
+            if expected != binding.kind {
+                return Err(ExecutorEnvBuilderErr::PortKindMismatch {
+                    name: binding.name.clone(),
+                    expected,
+                    actual: binding.kind,
+                });
+            }
+            if port.schema != binding.schema {
+                return Err(ExecutorEnvBuilderErr::PortSchemaMismatch {
+                    name: binding.name.clone(),
+                    expected: port.schema.clone(),
+                    actual: binding.schema.clone(),
+                });
+            }
+        }
+        for port in manifest.ports.iter() {
+            if !bindings.iter().any(|x| x.name == port.name) {
+                return Err(ExecutorEnvBuilderErr::UnboundPort {
+                    name: port.name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Set a segment limit, specified in powers of 2 cycles.
     ///
     /// Given value must be between [risc0_zkp::MIN_CYCLES_PO2] and
@@ -266,9 +582,55 @@ impl<'a> ExecutorEnvBuilder<'a> {
     ///     .unwrap();
     /// ```
     pub fn add_input<T: Pod>(&mut self, slice: &[T]) -> &mut Self {
+        Rc::make_mut(&mut self.inner.input).extend_from_slice(bytemuck::cast_slice(slice));
+        self
+    }
+
+    /// Write an input frame that the guest reads with
+    /// [env::read_frame](crate::guest::env::read_frame).
+    ///
+    /// Frames are independent of the input added with [Self::add_input] and
+    /// of each other: the guest reads each frame as a whole, in the order they
+    /// are written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .write_frame(b"header")
+    ///     .write_frame(b"body")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn write_frame(&mut self, bytes: &[u8]) -> &mut Self {
+        Rc::make_mut(&mut self.inner.frames).push(bytes.to_vec());
+        self
+    }
+
+    /// Declare a named slot in the input, at the current end of the input.
+    ///
+    /// The contents of the slot are provided with [Self::fill_slot], and
+    /// spliced into the input by [Self::build]; input added with
+    /// [Self::add_input] after the slot follows its contents. [Self::build]
+    /// returns [ExecutorEnvBuilderErr::UnfilledInputSlot] if a slot has not
+    /// been filled.
+    ///
+    /// Slots allow the input of an environment created with
+    /// [ExecutorEnv::to_builder] to be partially replaced.
+    pub fn input_slot(&mut self, name: &str) -> &mut Self {
+        let pos = self.inner.input.len();
+        self.inner.input_slots.push((pos, name.to_string()));
+        self
+    }
+
+    /// Set the contents of the input slot with the given `name`, replacing any
+    /// previous contents.
+    pub fn fill_slot<T: Pod>(&mut self, name: &str, slice: &[T]) -> &mut Self {
         self.inner
-            .input
-            .extend_from_slice(bytemuck::cast_slice(slice));
+            .slot_values
+            .insert(name.to_string(), bytemuck::cast_slice(slice).to_vec());
         self
     }
 
@@ -278,6 +640,17 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self
     }
 
+    /// Register a handler for a custom syscall, invoked from the guest with
+    /// [ext_syscall!](crate::ext_syscall).
+    ///
+    /// Custom syscalls live in their own namespace, so `name` cannot replace
+    /// or collide with a built-in syscall. This allows prototyping new host
+    /// services without adding them to `risc0-zkvm-platform`.
+    pub fn register_syscall(&mut self, name: &str, handler: impl Syscall + 'a) -> &mut Self {
+        self.inner.syscalls.with_ext_syscall(name, handler);
+        self
+    }
+
     /// Add a posix-style standard input.
     pub fn stdin(&mut self, reader: impl Read + 'a) -> &mut Self {
         self.read_fd(fileno::STDIN, BufReader::new(reader))
@@ -299,12 +672,110 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self
     }
 
+    /// Stream the contents of a file to the guest on the given file
+    /// descriptor, to be hashed with
+    /// [guest::hash_stream](crate::guest::hash_stream).
+    ///
+    /// The file is read lazily, in chunks of [STREAM_CHUNK_BYTES], as the
+    /// guest consumes it.
+    pub fn stream_file(&mut self, fd: u32, file: impl Read + 'a) -> &mut Self {
+        self.read_fd(fd, BufReader::with_capacity(STREAM_CHUNK_BYTES, file))
+    }
+
     /// Add a posix-style file descriptor for writing.
     pub fn write_fd(&mut self, fd: u32, writer: impl Write + 'a) -> &mut Self {
         self.inner.io.borrow_mut().with_write_fd(fd, writer);
         self
     }
 
+    /// Set the manifest of the ports declared by the guest, loaded with
+    /// [PortManifest::from_elf].
+    ///
+    /// Ports are then bound by name with [Self::input_port],
+    /// [Self::output_port] and [Self::oracle_port], which must be called after
+    /// this method. [Self::build] fails if any port of the guest is not bound,
+    /// or is bound as a different kind of port or with a different schema
+    /// than the guest declares.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let env = ExecutorEnv::builder()
+    ///     .port_manifest(PortManifest::from_elf(GUEST_ELF)?)
+    ///     .input_port("prices", "prices.v1", &prices)
+    ///     .output_port("report", "my_core.Report.v2", &mut report)
+    ///     .oracle_port("price_feed", "price_feed.v1", PriceFeed)
+    ///     .build()?;
+    /// ```
+    pub fn port_manifest(&mut self, manifest: PortManifest) -> &mut Self {
+        self.inner.port_manifest = Some(manifest);
+        self
+    }
+
+    /// Bind the input port with the given name and schema to a value, which
+    /// the guest reads with [env::read_port](crate::guest::env::read_port).
+    ///
+    /// If the value cannot be serialized with [crate::serde], [Self::build]
+    /// fails with [ExecutorEnvBuilderErr::PortSerialization].
+    pub fn input_port<T: Serialize>(&mut self, name: &str, schema: &str, value: &T) -> &mut Self {
+        let words = match to_vec(value) {
+            Ok(words) => words,
+            Err(error) => {
+                self.bind_port(name, "input", schema, Some(error));
+                return self;
+            }
+        };
+        self.bind_port(name, "input", schema, None);
+        if let Some(PortKind::Input { fd }) = self.port_kind(name) {
+            self.read_fd(fd, Cursor::new(bytemuck::cast_slice(&words).to_vec()));
+        }
+        self
+    }
+
+    /// Bind the output port with the given name and schema to a writer,
+    /// which receives the values written by the guest with
+    /// [env::write_port](crate::guest::env::write_port), serialized with
+    /// [crate::serde].
+    pub fn output_port(&mut self, name: &str, schema: &str, writer: impl Write + 'a) -> &mut Self {
+        self.bind_port(name, "output", schema, None);
+        if let Some(PortKind::Output { fd }) = self.port_kind(name) {
+            self.write_fd(fd, writer);
+        }
+        self
+    }
+
+    /// Bind the oracle port with the given name and schema to a handler for
+    /// the custom syscall of the same name.
+    pub fn oracle_port(
+        &mut self,
+        name: &str,
+        schema: &str,
+        handler: impl Syscall + 'a,
+    ) -> &mut Self {
+        self.bind_port(name, "oracle", schema, None);
+        self.register_syscall(name, handler)
+    }
+
+    fn bind_port(
+        &mut self,
+        name: &str,
+        kind: &'static str,
+        schema: &str,
+        error: Option<crate::serde::Error>,
+    ) {
+        self.inner.port_bindings.push(PortBinding {
+            name: name.to_string(),
+            kind,
+            schema: schema.to_string(),
+            error,
+        });
+    }
+
+    fn port_kind(&self, name: &str) -> Option<PortKind> {
+        let manifest = self.inner.port_manifest.as_ref()?;
+        Some(manifest.get(name)?.kind.clone())
+    }
+
     /// Add a handler for a syscall which inputs and outputs a slice
     /// of plain old data.
     pub fn slice_io(&mut self, syscall: SyscallName, handler: impl SliceIo + 'a) -> &mut Self {
@@ -323,6 +794,59 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self
     }
 
+    /// Add an async handler for a syscall which inputs and outputs a slice
+    /// of plain old data.
+    ///
+    /// The future returned by the handler is driven to completion on the given
+    /// runtime `handle`, so handlers can perform async I/O (e.g. database or
+    /// network requests) without a dedicated runtime per session. The executor
+    /// itself is synchronous and blocks while the future runs. It may run on a
+    /// worker thread of a multi-thread runtime, e.g. in a spawned task: the
+    /// worker is then handed off with [tokio::task::block_in_place] so that
+    /// the other tasks of the runtime keep running. Within a current-thread
+    /// runtime, which cannot hand off its thread, the future is driven from a
+    /// dedicated thread instead, and the other tasks of that runtime wait for
+    /// it.
+    ///
+    /// `handle` must be a handle of a multi-thread runtime, since a
+    /// current-thread runtime cannot drive the future while the executor
+    /// blocks it. Otherwise, [ExecutorEnvBuilder::build] returns
+    /// [ExecutorEnvBuilderErr::CurrentThreadRuntime].
+    #[cfg(feature = "async")]
+    pub fn io_callback_async<F, Fut>(
+        &mut self,
+        syscall: SyscallName,
+        handle: tokio::runtime::Handle,
+        f: F,
+    ) -> &mut Self
+    where
+        F: Fn(Vec<u8>) -> Fut + 'a,
+        Fut: std::future::Future<Output = Vec<u8>> + Send,
+    {
+        use tokio::runtime::{Handle, RuntimeFlavor};
+
+        if handle.runtime_flavor() != RuntimeFlavor::MultiThread {
+            self.inner
+                .current_thread_syscalls
+                .push(syscall.as_str().to_string());
+        }
+        self.io_callback(syscall, move |buf: &[u8]| {
+            let fut = f(buf.to_vec());
+            match Handle::try_current().map(|current| current.runtime_flavor()) {
+                Ok(RuntimeFlavor::MultiThread) => {
+                    tokio::task::block_in_place(|| handle.block_on(fut))
+                }
+                Ok(_) => std::thread::scope(|scope| {
+                    scope
+                        .spawn(|| handle.block_on(fut))
+                        .join()
+                        .unwrap_or_else(|err| std::panic::resume_unwind(err))
+                }),
+                Err(_) => handle.block_on(fut),
+            }
+        })
+    }
+
     /// Add a callback handler for raw trace messages.
     pub fn trace_callback(
         &mut self,
@@ -331,4 +855,273 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self.inner.trace_callback = Some(Rc::new(RefCell::new(callback)));
         self
     }
+
+    /// Subscribe to segment boundaries.
+    ///
+    /// The callback is invoked each time the executor finishes a [Segment],
+    /// before the segment is handed off to be stored. Returning an error aborts
+    /// the execution, which can be used to implement early-abort heuristics.
+    ///
+    /// Multiple callbacks may be registered; they are invoked in the order in
+    /// which they were added.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .on_segment(|info| {
+    ///         println!("segment {}: {} cycles", info.index, info.insn_cycles);
+    ///         Ok(())
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [Segment]: crate::Segment
+    pub fn on_segment(
+        &mut self,
+        callback: impl FnMut(&SegmentInfo) -> Result<()> + 'a,
+    ) -> &mut Self {
+        self.inner
+            .segment_callbacks
+            .push(Rc::new(RefCell::new(callback)));
+        self
+    }
+
+    /// Subscribe to syscalls made by the guest.
+    ///
+    /// The callback is invoked after the host handler for a syscall has run.
+    /// Returning an error aborts the execution.
+    pub fn on_syscall(
+        &mut self,
+        callback: impl FnMut(&SyscallInfo) -> Result<()> + 'a,
+    ) -> &mut Self {
+        self.inner
+            .syscall_callbacks
+            .push(Rc::new(RefCell::new(callback)));
+        self
+    }
+
+    /// Subscribe to guest faults.
+    ///
+    /// The callback is invoked when the guest executes an instruction that
+    /// fails, such as an illegal instruction, an invalid memory access or a
+    /// panic. Errors that are not caused by the guest, such as exceeding the
+    /// session limit or a callback returning an error, are only returned from
+    /// [Executor::run](crate::Executor::run).
+    ///
+    /// With [fault_proofs](Self::fault_proofs) enabled, the callback is also
+    /// invoked for faults that end the session with a fault proof.
+    pub fn on_fault(&mut self, callback: impl FnMut(&FaultInfo) + 'a) -> &mut Self {
+        self.inner
+            .fault_callbacks
+            .push(Rc::new(RefCell::new(callback)));
+        self
+    }
+
+    /// Subscribe to outputs yielded by the guest.
+    ///
+    /// The callback is invoked with the bytes passed to
+    /// [env::yield_output](crate::guest::env::yield_output) each time the
+    /// guest calls it, while execution continues. Returning an error aborts
+    /// the execution.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .on_output(|bytes| {
+    ///         println!("guest yielded {} bytes", bytes.len());
+    ///         Ok(())
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn on_output(&mut self, callback: impl FnMut(&[u8]) -> Result<()> + 'a) -> &mut Self {
+        self.inner
+            .output_callbacks
+            .push(Rc::new(RefCell::new(callback)));
+        self
+    }
+
+    /// Provide an authenticated key-value oracle to the guest.
+    ///
+    /// The guest commits `root` to the journal with
+    /// [guest::oracle::init](crate::guest::oracle::init) and looks up values
+    /// with [guest::oracle::get](crate::guest::oracle::get), which verifies
+    /// each value against `root`. See [crate::oracle] for details.
+    pub fn kv_oracle(&mut self, root: Digest, store: impl KvStore + 'a) -> &mut Self {
+        self.slice_io(
+            SYS_KV_ORACLE,
+            slice_io_from_fn(move |from_guest: &[u32]| -> Vec<u32> {
+                match from_slice(from_guest) {
+                    Ok(OracleRequest::Root) => to_vec(&root),
+                    Ok(OracleRequest::Get(key)) => to_vec(&store.get(&key)),
+                    Err(_) => to_vec(&None::<(Vec<u8>, MerkleProof)>),
+                }
+                .unwrap()
+            }),
+        );
+        self
+    }
+
+    /// Limit the size of the input added with [Self::add_input].
+    ///
+    /// [Self::build] returns [ExecutorEnvBuilderErr::InputTooLarge] if the
+    /// input is larger than `max` bytes, before any guest code is executed.
+    pub fn max_input_bytes(&mut self, max: usize) -> &mut Self {
+        self.inner.max_input_bytes = Some(max);
+        self
+    }
+
+    /// Add a validator for the input added with [Self::add_input].
+    ///
+    /// Validators are run by [Self::build], in the order they are added, after
+    /// the [Self::max_input_bytes] check. An error from a validator is returned
+    /// as [ExecutorEnvBuilderErr::InvalidInput]. This allows rejecting
+    /// malformed input before spending any time executing the guest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use anyhow::ensure;
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let result = ExecutorEnv::builder()
+    ///     .add_input(&[1u32, 2, 3])
+    ///     .input_validator(|input| {
+    ///         ensure!(input.len() % 8 == 0, "expected pairs of words");
+    ///         Ok(())
+    ///     })
+    ///     .build();
+    /// assert!(result.is_err());
+    /// ```
+    pub fn input_validator(&mut self, validator: impl Fn(&[u8]) -> Result<()> + 'a) -> &mut Self {
+        self.inner.input_validators.push(Rc::new(validator));
+        self
+    }
+
+    /// End execution with a provable fault instead of an error when the guest
+    /// executes an illegal instruction or accesses memory outside of the
+    /// guest address space.
+    ///
+    /// The resulting [Session](crate::Session) ends just before the faulting
+    /// instruction, and can be proven with
+    /// [Session::prove_fault](crate::Session::prove_fault). See
+    /// [crate::fault] for details.
+    pub fn fault_proofs(&mut self, enable: bool) -> &mut Self {
+        self.inner.fault_proofs = enable;
+        self
+    }
+
+    /// Keep the final memory of the guest in the resulting
+    /// [Session](crate::Session), so that it can be inspected with
+    /// [Session::read_memory](crate::Session::read_memory).
+    ///
+    /// This is off by default, since the final memory image holds every page
+    /// the guest touched.
+    pub fn retain_memory(&mut self, enable: bool) -> &mut Self {
+        self.inner.retain_memory = enable;
+        self
+    }
+
+    /// Collect a histogram of the executed instructions and the `hot_pcs`
+    /// PCs at which the most cycles were spent, available from
+    /// [Session::stats](crate::Session::stats).
+    pub fn execution_stats(&mut self, hot_pcs: usize) -> &mut Self {
+        self.inner.execution_stats = Some(hot_pcs);
+        self
+    }
+
+    /// Check every `interval`-th instruction of each segment against the given
+    /// reference emulator, failing at the first divergence.
+    ///
+    /// This is a testing aid for the executor and is slow; see
+    /// [crate::difftest] for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::{difftest::Rv32imInterpreter, ExecutorEnv};
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .diff_test(Rv32imInterpreter, 1)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "difftest")]
+    pub fn diff_test(
+        &mut self,
+        emulator: impl super::difftest::ReferenceEmulator + 'a,
+        interval: u32,
+    ) -> &mut Self {
+        self.inner.diff_test = Some(super::difftest::DiffTest {
+            emulator: Rc::new(RefCell::new(emulator)),
+            interval: interval.max(1),
+        });
+        self
+    }
+
+    /// Load the pages covering the given address ranges at the start of every
+    /// segment.
+    ///
+    /// Pages are normally paged in the first time the guest touches them,
+    /// which makes the cost of paging hard to predict. Preloading hot data
+    /// makes it resident from cycle zero of each segment. Preloaded pages
+    /// count towards the segment limit whether or not they are used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .preload_pages(&[0x0001_0000..0x0001_4000])
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn preload_pages(&mut self, ranges: &[Range<u32>]) -> &mut Self {
+        self.inner.preload_pages.extend_from_slice(ranges);
+        self
+    }
+
+    /// Map a host buffer into the guest address space as a read-only region,
+    /// which the guest finds with `env::readonly_region`, in the order the
+    /// regions were mapped.
+    ///
+    /// Unlike input, the region is not copied into guest memory through a
+    /// syscall: it is part of the initial memory image, and the guest only
+    /// pays for the pages it reads. This makes it practical for guests to
+    /// process inputs of hundreds of megabytes, up to the free address space
+    /// between the program and the stack. Since the region is part of the
+    /// image, the receipt is verified against the image ID returned by
+    /// [ExecutorEnv::image_id] rather than that of the ELF alone.
+    ///
+    /// The region is placed at `addr_hint`, rounded up to a page boundary, if
+    /// it fits there, and otherwise below the memory reserved for the stack;
+    /// an `addr_hint` of zero always places it below the stack. The heap of
+    /// the guest stops below the region, a guest that configures a stack
+    /// overlapping it is rejected, and writing to it faults the guest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let data = vec![0u8; 1 << 20];
+    /// let env = ExecutorEnv::builder()
+    ///     .map_readonly(0, &data)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn map_readonly(&mut self, addr_hint: u32, bytes: &'a [u8]) -> &mut Self {
+        self.inner
+            .readonly_regions
+            .push(ReadonlyRegion { addr_hint, bytes });
+        self
+    }
 }
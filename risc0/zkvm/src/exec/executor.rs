@@ -14,7 +14,14 @@
 
 //! This module implements the Executor.
 
-use std::{cell::RefCell, fmt::Debug, io::Write, mem::take, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    io::Write,
+    mem::take,
+    rc::Rc,
+};
 
 use addr2line::{
     fallible_iterator::FallibleIterator,
@@ -26,7 +33,7 @@ use crypto_bigint::{CheckedMul, Encoding, NonZero, U256, U512};
 use risc0_binfmt::{MemoryImage, Program};
 use risc0_zkp::{
     core::{
-        digest::{DIGEST_BYTES, DIGEST_WORDS},
+        digest::{Digest, DIGEST_BYTES, DIGEST_WORDS},
         hash::sha::{BLOCK_BYTES, BLOCK_WORDS},
         log2_ceil,
     },
@@ -34,22 +41,24 @@ use risc0_zkp::{
 };
 use risc0_zkvm_platform::{
     fileno,
-    memory::MEM_SIZE,
+    memory::{MEM_SIZE, READONLY_TABLE},
     syscall::{
         bigint, ecall, halt,
-        reg_abi::{REG_A0, REG_A1, REG_A2, REG_A3, REG_A4, REG_T0},
+        reg_abi::{REG_A0, REG_A1, REG_A2, REG_A3, REG_A4, REG_SP, REG_T0},
     },
     PAGE_SIZE, WORD_SIZE,
 };
 use rrs_lib::{instruction_executor::InstructionExecutor, HartState};
 use serde::{Deserialize, Serialize};
 
-use super::TraceEvent;
+use super::{readonly::map_regions, FaultInfo, HostError, SegmentInfo, SyscallInfo, TraceEvent};
 use crate::{
     align_up,
     exec::monitor::MemoryMonitor,
+    fault::{self, FaultProof},
     opcode::{MajorType, OpCode},
     receipt::ExitCode,
+    session::{ExecutionStats, InsnStats, PagingStats},
     ExecutorEnv, Loader, Segment, SegmentRef, Session, SimpleSegmentRef,
 };
 
@@ -92,6 +101,59 @@ impl OpCodeResult {
     }
 }
 
+// Collects the instruction histogram and hot PCs of an [ExecutionStats].
+#[derive(Default)]
+struct StatsCollector {
+    opcodes: BTreeMap<&'static str, InsnStats>,
+    pcs: HashMap<u32, InsnStats>,
+    hot_pcs: usize,
+}
+
+impl StatsCollector {
+    fn new(hot_pcs: usize) -> Self {
+        Self {
+            hot_pcs,
+            ..Default::default()
+        }
+    }
+
+    fn record(&mut self, mnemonic: &'static str, pc: u32, cycles: usize) {
+        for stats in [
+            self.opcodes.entry(mnemonic).or_default(),
+            self.pcs.entry(pc).or_default(),
+        ] {
+            stats.count += 1;
+            stats.cycles += cycles;
+        }
+    }
+
+    fn finish(self) -> ExecutionStats {
+        let mut hot_pcs: Vec<_> = self.pcs.into_iter().collect();
+        hot_pcs.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(&b.0)));
+        hot_pcs.truncate(self.hot_pcs);
+        ExecutionStats {
+            opcodes: self
+                .opcodes
+                .into_iter()
+                .map(|(name, stats)| (name.to_string(), stats))
+                .collect(),
+            hot_pcs,
+        }
+    }
+}
+
+// The key of an ecall in [ExecutionStats::opcodes].
+fn ecall_mnemonic(kind: u32) -> &'static str {
+    match kind {
+        ecall::HALT => "ecall:halt",
+        ecall::INPUT => "ecall:input",
+        ecall::SOFTWARE => "ecall:software",
+        ecall::SHA => "ecall:sha",
+        ecall::BIGINT => "ecall:bigint",
+        _ => "ecall",
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SyscallRecord {
     pub to_guest: Vec<u32>,
@@ -117,6 +179,12 @@ pub struct Executor<'a> {
     syscalls: Vec<SyscallRecord>,
     exit_code: Option<ExitCode>,
     obj_ctx: Option<ObjectContext>,
+    paging: PagingStats,
+    faulted: bool,
+    fault: Option<FaultProof>,
+    memory: Option<MemoryImage>,
+    stats: Option<StatsCollector>,
+    regions_mapped: bool,
 }
 
 impl<'a> Executor<'a> {
@@ -159,6 +227,12 @@ impl<'a> Executor<'a> {
             syscalls: Vec::new(),
             exit_code: None,
             obj_ctx,
+            paging: PagingStats::default(),
+            faulted: false,
+            fault: None,
+            memory: None,
+            stats: None,
+            regions_mapped: false,
         }
     }
 
@@ -205,17 +279,44 @@ impl<'a> Executor<'a> {
             bail!("cannot resume an execution which exited with ExitCode::Halted");
         }
 
+        if !self.regions_mapped {
+            self.map_readonly_regions()?;
+        }
         self.monitor.clear_session()?;
+        self.preload_pages()?;
+        self.paging = PagingStats::default();
+        self.stats = self.env.execution_stats.map(StatsCollector::new);
 
         let journal = Journal::default();
         self.env
             .io
             .borrow_mut()
             .with_write_fd(fileno::JOURNAL, journal.clone());
+        let metadata_digest = Journal::default();
+        self.env
+            .io
+            .borrow_mut()
+            .with_write_fd(fileno::METADATA_DIGEST, metadata_digest.clone());
 
         let mut run_loop = || -> Result<ExitCode> {
             loop {
-                if let Some(exit_code) = self.step()? {
+                let step = match self.step() {
+                    Ok(step) => step,
+                    // Errors of host callbacks are returned as they are, since
+                    // the guest did not fault.
+                    Err(err) if err.is::<HostError>() => {
+                        return Err(err.downcast::<HostError>().unwrap().0)
+                    }
+                    Err(err) => {
+                        let provable = self.check_fault()?;
+                        self.notify_fault(&err, provable);
+                        match provable {
+                            true => Some(ExitCode::SystemSplit),
+                            false => return Err(err),
+                        }
+                    }
+                };
+                if let Some(exit_code) = step {
                     let total_cycles = self.total_cycles();
                     log::debug!("exit_code: {exit_code:?}, total_cycles: {total_cycles}");
                     assert!(total_cycles <= (1 << self.env.segment_limit_po2));
@@ -223,6 +324,10 @@ impl<'a> Executor<'a> {
                     let post_image = self.monitor.build_image(self.pc);
                     let post_image_id = post_image.compute_id();
                     let syscalls = take(&mut self.syscalls);
+                    self.paging.pages_loaded += self.monitor.faults.reads.len();
+                    self.paging.pages_stored += self.monitor.faults.writes.len();
+                    self.paging.page_read_cycles += self.monitor.page_read_cycles;
+                    self.paging.page_write_cycles += self.monitor.page_write_cycles;
                     let faults = take(&mut self.monitor.faults);
                     let segment = Segment::new(
                         pre_image,
@@ -238,18 +343,36 @@ impl<'a> Executor<'a> {
                             .context("Too many segments to fit in u32")?,
                         self.body_cycles,
                     );
+                    crate::telemetry::segment_executed(segment.po2, segment.insn_cycles);
+                    let info = SegmentInfo {
+                        index: segment.index,
+                        po2: segment.po2,
+                        insn_cycles: segment.insn_cycles,
+                        exit_code,
+                    };
+                    for segment_callback in self.env.segment_callbacks.iter() {
+                        segment_callback.borrow_mut()(&info)?;
+                    }
                     let segment_ref = callback(segment)?;
                     self.segments.push(segment_ref);
                     match exit_code {
+                        ExitCode::SystemSplit if self.faulted => {
+                            log::debug!("Fault: 0x{:08x}", self.pc);
+                            self.fault = Some(FaultProof::new(&post_image));
+                            self.retain_memory(&post_image);
+                            return Ok(exit_code);
+                        }
                         ExitCode::SystemSplit => self.split(post_image)?,
                         ExitCode::SessionLimit => bail!("Session limit exceeded"),
                         ExitCode::Paused(inner) => {
                             log::debug!("Paused({inner}): {}", self.segment_cycle);
+                            self.retain_memory(&post_image);
                             self.split(post_image)?;
                             return Ok(exit_code);
                         }
                         ExitCode::Halted(inner) => {
                             log::debug!("Halted({inner}): {}", self.segment_cycle);
+                            self.retain_memory(&post_image);
                             return Ok(exit_code);
                         }
                     };
@@ -259,11 +382,71 @@ impl<'a> Executor<'a> {
 
         let exit_code = run_loop()?;
         self.exit_code = Some(exit_code);
-        Ok(Session::new(
-            take(&mut self.segments),
-            journal.buf.take(),
-            exit_code,
-        ))
+        let mut session = Session::new(take(&mut self.segments), journal.buf.take(), exit_code);
+        session.paging = take(&mut self.paging);
+        session.fault = take(&mut self.fault);
+        session.memory = take(&mut self.memory);
+        session.stats = take(&mut self.stats).map(StatsCollector::finish);
+        session.metadata_digest = match metadata_digest.buf.take().as_slice() {
+            [] => None,
+            bytes => Some(Digest::try_from(bytes).context("Invalid metadata digest")?),
+        };
+        Ok(session)
+    }
+
+    // Map the read-only regions of the environment into the initial memory
+    // image, and protect them and their table from writes.
+    fn map_readonly_regions(&mut self) -> Result<()> {
+        self.regions_mapped = true;
+        if self.env.readonly_regions.is_empty() {
+            return Ok(());
+        }
+        let mut readonly = map_regions(&mut self.pre_image, &self.env.readonly_regions)?;
+        readonly.push(READONLY_TABLE..READONLY_TABLE + PAGE_SIZE as u32);
+        self.monitor =
+            MemoryMonitor::new(self.pre_image.clone(), self.env.trace_callback.is_some());
+        self.monitor.readonly = readonly;
+        Ok(())
+    }
+
+    // Invoke the fault callbacks for an instruction that failed to execute.
+    fn notify_fault(&self, err: &anyhow::Error, provable: bool) {
+        let info = FaultInfo {
+            pc: self.pc,
+            cycle: self.session_cycle(),
+            message: err.to_string(),
+            provable,
+        };
+        for fault_callback in self.env.fault_callbacks.iter() {
+            fault_callback.borrow_mut()(&info);
+        }
+    }
+
+    // Keep the final memory image of the session, if requested.
+    fn retain_memory(&mut self, image: &MemoryImage) {
+        if self.env.retain_memory {
+            self.memory = Some(image.clone());
+        }
+    }
+
+    /// Check whether the instruction at the current PC faults, in which case
+    /// the segment ends just before it.
+    ///
+    /// Only faults that can be proven with a [FaultProof] are checked, and
+    /// only if fault proofs are enabled.
+    fn check_fault(&mut self) -> Result<bool> {
+        if !self.env.fault_proofs {
+            return Ok(false);
+        }
+        self.monitor.undo()?;
+        self.pending_syscall = None;
+        let insn = self.monitor.load_u32(self.pc)?;
+        if fault::classify(insn, &self.monitor.load_registers()).is_none() {
+            return Ok(false);
+        }
+        self.split_insn = Some(self.insn_counter);
+        self.faulted = true;
+        Ok(true)
     }
 
     fn split(&mut self, pre_image: MemoryImage) -> Result<()> {
@@ -272,7 +455,25 @@ impl<'a> Executor<'a> {
         self.split_insn = None;
         self.insn_counter = 0;
         self.segment_cycle = self.init_cycles;
-        self.monitor.clear_segment()
+        self.monitor.clear_segment()?;
+        self.preload_pages()
+    }
+
+    fn preload_pages(&mut self) -> Result<()> {
+        for range in self.env.preload_pages.iter() {
+            let start = range.start - range.start % PAGE_SIZE as u32;
+            for addr in (start..range.end).step_by(PAGE_SIZE) {
+                self.monitor.preload_page(addr)?;
+            }
+        }
+        if self.total_cycles() > self.env.get_segment_limit() {
+            bail!(
+                "Preloaded pages require {} cycles, which exceeds the segment limit",
+                self.monitor.page_read_cycles
+            );
+        }
+        self.segment_cycle = self.init_cycles + self.monitor.page_read_cycles;
+        Ok(())
     }
 
     /// Execute a single instruction.
@@ -285,6 +486,13 @@ impl<'a> Executor<'a> {
             }
         }
 
+        if let Some(stack_limit) = self.env.stack_limit.get() {
+            let sp = self.monitor.load_register(REG_SP);
+            if sp < stack_limit {
+                bail!("Stack overflow: sp 0x{sp:08x} is below the stack limit 0x{stack_limit:08x}");
+            }
+        }
+
         let insn = self.monitor.load_u32(self.pc)?;
         let opcode = OpCode::decode(insn, self.pc)?;
 
@@ -325,10 +533,24 @@ impl<'a> Executor<'a> {
             );
         }
 
+        let mnemonic = match opcode.major {
+            MajorType::ECall if self.stats.is_some() => {
+                ecall_mnemonic(self.monitor.load_register(REG_T0))
+            }
+            _ => opcode.mnemonic,
+        };
+
         let op_result = if opcode.major == MajorType::ECall {
             self.ecall()?
         } else {
             let registers = self.monitor.load_registers();
+            #[cfg(feature = "difftest")]
+            let expected = match &self.env.diff_test {
+                Some(diff) if self.insn_counter % diff.interval == 0 => {
+                    Some(diff.expect(self.pc, registers, &mut self.monitor)?)
+                }
+                _ => None,
+            };
             let mut hart = HartState {
                 registers,
                 pc: self.pc,
@@ -346,6 +568,11 @@ impl<'a> Executor<'a> {
                 self.monitor.store_register(idx, hart.registers[idx]);
             }
 
+            #[cfg(feature = "difftest")]
+            if let Some(expected) = expected {
+                expected.check(hart.pc, &hart.registers, &mut self.monitor)?;
+            }
+
             OpCodeResult::new(hart.pc, None, 0)
         };
 
@@ -370,6 +597,9 @@ impl<'a> Executor<'a> {
             self.monitor.undo()?;
             Some(ExitCode::SystemSplit)
         } else {
+            if let Some(stats) = self.stats.as_mut() {
+                stats.record(mnemonic, self.pc, opcode.cycles + op_result.extra_cycles);
+            }
             self.advance(opcode, op_result)
         };
         Ok(exit_code)
@@ -579,6 +809,14 @@ impl<'a> Executor<'a> {
                 handler
                     .borrow_mut()
                     .syscall(&syscall_name, &mut self.monitor, &mut to_guest)?;
+            let info = SyscallInfo {
+                name: syscall_name.clone(),
+                cycle: self.session_cycle(),
+                pc: self.pc,
+            };
+            for syscall_callback in self.env.syscall_callbacks.iter() {
+                syscall_callback.borrow_mut()(&info).map_err(HostError)?;
+            }
             let syscall = SyscallRecord {
                 to_guest,
                 regs: (a0, a1),
@@ -30,10 +30,11 @@ use risc0_zkvm_platform::{
     fileno,
     syscall::{
         nr::{
-            SYS_CYCLE_COUNT, SYS_LOG, SYS_PANIC, SYS_RANDOM, SYS_READ, SYS_READ_AVAIL, SYS_WRITE,
+            SYS_BIGINT_DIVREM, SYS_CYCLE_COUNT, SYS_LOG, SYS_PANIC, SYS_RANDOM, SYS_READ,
+            SYS_READ_AVAIL, SYS_WRITE,
         },
         reg_abi::{REG_A3, REG_A4, REG_A5},
-        SyscallName,
+        SyscallName, EXT_SYSCALL_PREFIX,
     },
     WORD_SIZE,
 };
@@ -364,7 +365,8 @@ impl<'a> Default for SyscallTable<'a> {
         let mut new = Self {
             inner: Default::default(),
         };
-        new.with_syscall(SYS_CYCLE_COUNT, syscalls::CycleCount)
+        new.with_syscall(SYS_BIGINT_DIVREM, syscalls::BigIntDivRem)
+            .with_syscall(SYS_CYCLE_COUNT, syscalls::CycleCount)
             .with_syscall(SYS_LOG, syscalls::Log)
             .with_syscall(SYS_PANIC, syscalls::Panic)
             .with_syscall(SYS_RANDOM, syscalls::Random);
@@ -378,18 +380,77 @@ impl<'a> SyscallTable<'a> {
             .insert(syscall.as_str().to_string(), Rc::new(RefCell::new(handler)));
         self
     }
+
+    pub fn with_ext_syscall(&mut self, name: &str, handler: impl Syscall + 'a) -> &mut Self {
+        self.inner.insert(
+            format!("{EXT_SYSCALL_PREFIX}{name}"),
+            Rc::new(RefCell::new(handler)),
+        );
+        self
+    }
 }
 
 pub(crate) mod syscalls {
-    use std::{cmp::min, collections::HashMap, str::from_utf8};
+    use std::{
+        cell::{Cell, RefCell},
+        cmp::min,
+        collections::HashMap,
+        rc::Rc,
+        str::from_utf8,
+    };
 
-    use anyhow::{bail, Result};
+    use anyhow::{anyhow, bail, Result};
+    use crypto_bigint::{Encoding, NonZero, U1024};
     use risc0_zkvm_platform::{
+        memory::{STACK_TOP, TEXT_START},
         syscall::reg_abi::{REG_A3, REG_A4},
         WORD_SIZE,
     };
 
     use super::{Syscall, SyscallContext};
+    use crate::exec::{env::OutputCallback, readonly::mapped_regions, HostError};
+
+    /// Divides a product by a modulus for the modular multiplication of the
+    /// `risc0-bigint` crate, which checks the quotient and remainder.
+    pub(crate) struct BigIntDivRem;
+    impl Syscall for BigIntDivRem {
+        fn syscall(
+            &mut self,
+            _syscall: &str,
+            ctx: &mut dyn SyscallContext,
+            to_guest: &mut [u32],
+        ) -> Result<(u32, u32)> {
+            let buf_ptr = ctx.load_register(REG_A3);
+            let buf_len = ctx.load_register(REG_A4);
+            let from_guest = ctx.load_region(buf_ptr, buf_len)?;
+            // The request is a product of twice the width of the modulus,
+            // followed by the modulus.
+            let width = from_guest.len() / 3;
+            if from_guest.len() % 3 != 0
+                || width % WORD_SIZE != 0
+                || 2 * width > U1024::BYTES
+                || to_guest.len() * WORD_SIZE != 2 * width
+            {
+                bail!(
+                    "Invalid bigint divrem request of {} bytes",
+                    from_guest.len()
+                );
+            }
+            let to_uint = |bytes: &[u8]| {
+                let mut buf = [0u8; U1024::BYTES];
+                buf[..bytes.len()].copy_from_slice(bytes);
+                U1024::from_le_slice(&buf)
+            };
+            let (product, modulus) = from_guest.split_at(2 * width);
+            let modulus = Option::<NonZero<U1024>>::from(NonZero::new(to_uint(modulus)))
+                .ok_or_else(|| anyhow!("Bigint divrem by zero"))?;
+            let (quotient, remainder) = to_uint(product).div_rem(&modulus);
+            let out: &mut [u8] = bytemuck::cast_slice_mut(to_guest);
+            out[..width].copy_from_slice(&quotient.to_le_bytes()[..width]);
+            out[width..].copy_from_slice(&remainder.to_le_bytes()[..width]);
+            Ok((0, 0))
+        }
+    }
 
     pub(crate) struct CycleCount;
     impl Syscall for CycleCount {
@@ -445,6 +506,64 @@ pub(crate) mod syscalls {
         }
     }
 
+    pub(crate) struct YieldOutput<'a>(pub Vec<Rc<RefCell<OutputCallback<'a>>>>);
+    impl<'a> Syscall for YieldOutput<'a> {
+        fn syscall(
+            &mut self,
+            _syscall: &str,
+            ctx: &mut dyn SyscallContext,
+            _to_guest: &mut [u32],
+        ) -> Result<(u32, u32)> {
+            let buf_ptr = ctx.load_register(REG_A3);
+            let buf_len = ctx.load_register(REG_A4);
+            let from_guest = ctx.load_region(buf_ptr, buf_len)?;
+            for callback in self.0.iter() {
+                callback.borrow_mut()(&from_guest).map_err(HostError)?;
+            }
+            Ok((0, 0))
+        }
+    }
+
+    /// Validates the layout configured by the guest, and records the lowest
+    /// address the stack may grow to.
+    pub(crate) struct Layout(pub Rc<Cell<Option<u32>>>);
+    impl Syscall for Layout {
+        fn syscall(
+            &mut self,
+            _syscall: &str,
+            ctx: &mut dyn SyscallContext,
+            _to_guest: &mut [u32],
+        ) -> Result<(u32, u32)> {
+            let heap_start = ctx.load_register(REG_A3);
+            let stack_size = ctx.load_register(REG_A4);
+            if stack_size as usize % WORD_SIZE != 0 {
+                bail!("Stack size must be word aligned: {stack_size}");
+            }
+            let stack_limit = STACK_TOP
+                .checked_sub(stack_size)
+                .filter(|limit| *limit > TEXT_START)
+                .ok_or_else(|| anyhow!("Stack size is too large: {stack_size}"))?;
+            if heap_start != 0 && heap_start >= stack_limit {
+                bail!("Heap start 0x{heap_start:08x} overlaps the stack");
+            }
+            for region in mapped_regions(ctx)? {
+                if region.contains(&heap_start) {
+                    bail!("Heap start 0x{heap_start:08x} overlaps a read-only region");
+                }
+                if stack_size != 0 && region.end > stack_limit {
+                    bail!(
+                        "Stack of {stack_size} bytes overlaps the read-only region at 0x{:08x}",
+                        region.start
+                    );
+                }
+            }
+            if stack_size != 0 {
+                self.0.set(Some(stack_limit));
+            }
+            Ok((0, 0))
+        }
+    }
+
     pub(crate) struct Panic;
     impl Syscall for Panic {
         fn syscall(
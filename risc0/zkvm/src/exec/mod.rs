@@ -18,12 +18,16 @@
 //! [crate::Session] contains one or more [crate::Segment]s, each of which
 //! contains an execution trace of the specified program.
 
+#[cfg(feature = "difftest")]
+pub mod difftest;
 mod env;
 mod executor;
 pub(crate) mod io;
 mod monitor;
+mod pool;
 #[cfg(feature = "profiler")]
 pub(crate) mod profiler;
+mod readonly;
 #[cfg(test)]
 mod tests;
 
@@ -31,7 +35,11 @@ use std::fmt::Debug;
 
 pub use executor::{Executor, SyscallRecord};
 
-pub use self::env::{ExecutorEnv, ExecutorEnvBuilder};
+pub use self::{
+    env::{ExecutorEnv, ExecutorEnvBuilder, ExecutorEnvBuilderErr},
+    pool::ExecutorPool,
+};
+use crate::receipt::ExitCode;
 
 /// An event traced from the running VM.
 #[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
@@ -61,6 +69,66 @@ pub enum TraceEvent {
     },
 }
 
+/// Information about a [crate::Segment] reported at a segment boundary.
+#[derive(Clone, Debug)]
+pub struct SegmentInfo {
+    /// The index of the segment within the session.
+    pub index: u32,
+
+    /// The number of cycles in powers of 2.
+    pub po2: usize,
+
+    /// The number of cycles used to execute instructions.
+    pub insn_cycles: usize,
+
+    /// The [ExitCode] with which the segment terminated.
+    pub exit_code: ExitCode,
+}
+
+/// Information about a syscall made by the guest.
+#[derive(Clone, Debug)]
+pub struct SyscallInfo {
+    /// The name of the syscall.
+    pub name: String,
+
+    /// The session cycle at which the syscall was made.
+    pub cycle: usize,
+
+    /// The program counter of the `ecall` instruction.
+    pub pc: u32,
+}
+
+/// Information about a fault that terminated execution.
+#[derive(Clone, Debug)]
+pub struct FaultInfo {
+    /// The program counter at the time of the fault.
+    pub pc: u32,
+
+    /// The session cycle at which the fault occurred.
+    pub cycle: usize,
+
+    /// A description of the fault.
+    pub message: String,
+
+    /// Whether the fault is recorded in the session to be proven with
+    /// [Session::prove_fault](crate::Session::prove_fault), in which case
+    /// execution ends successfully instead of returning an error.
+    pub provable: bool,
+}
+
+/// An error returned by a host callback while handling a syscall, rather than
+/// caused by the guest, so that it is not reported as a guest fault.
+#[derive(Debug)]
+pub(crate) struct HostError(pub anyhow::Error);
+
+impl std::fmt::Display for HostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for HostError {}
+
 impl Debug for TraceEvent {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
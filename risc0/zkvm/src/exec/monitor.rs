@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{array, collections::BTreeSet, mem::take};
+use std::{array, collections::BTreeSet, mem::take, ops::Range};
 
 use anyhow::{bail, Result};
 use risc0_binfmt::MemoryImage;
@@ -79,6 +79,8 @@ pub struct MemoryMonitor {
     enable_trace: bool,
     pages: Vec<Option<Page>>,
     registers: [u32; REG_MAX],
+    /// Ranges of memory the guest may not write to.
+    pub readonly: Vec<Range<u32>>,
 }
 
 impl MemoryMonitor {
@@ -101,6 +103,7 @@ impl MemoryMonitor {
             enable_trace,
             pages,
             registers: [0; REG_MAX],
+            readonly: Vec::new(),
         }
     }
 
@@ -164,6 +167,14 @@ impl MemoryMonitor {
         Ok(())
     }
 
+    /// Load the page containing `addr` so that it is resident from the start
+    /// of the current segment.
+    pub fn preload_page(&mut self, addr: u32) -> Result<()> {
+        self.load_page(addr)?;
+        self.pending_actions.clear();
+        Ok(())
+    }
+
     fn mark_page(&mut self, addr: u32) {
         let info = &self.image.info;
         let page_idx = info.get_page_index(addr);
@@ -243,8 +254,16 @@ impl MemoryMonitor {
         String::from_utf8(s).map_err(anyhow::Error::msg)
     }
 
+    fn check_writable(&self, addr: u32) -> Result<()> {
+        if self.readonly.iter().any(|range| range.contains(&addr)) {
+            bail!("Write to read-only memory at 0x{addr:08x}");
+        }
+        Ok(())
+    }
+
     fn raw_store_u8(&mut self, addr: u32, data: u8) -> Result<()> {
         // log::trace!("raw_store_u8: 0x{addr:08x}");
+        self.check_writable(addr)?;
         let old = self.load_u8(addr)?;
         self.pending_actions.push(Action::StoreU8(addr, old));
         self.store_bytes(addr, &[data])?;
@@ -267,6 +286,7 @@ impl MemoryMonitor {
     pub fn store_u16(&mut self, addr: u32, data: u16) -> Result<()> {
         // log::trace!("store_u16: 0x{addr:08x}");
         assert_eq!(addr % 2, 0, "unaligned store");
+        self.check_writable(addr)?;
         let old = self.load_u16(addr)?;
         self.pending_actions.push(Action::StoreU16(addr, old));
         self.store_bytes(addr, &data.to_le_bytes())?;
@@ -283,6 +303,7 @@ impl MemoryMonitor {
     pub fn store_u32(&mut self, addr: u32, data: u32) -> Result<()> {
         // log::trace!("store_u32: 0x{addr:08x}");
         assert_eq!(addr % WORD_SIZE as u32, 0, "unaligned store");
+        self.check_writable(addr)?;
         let old = self.load_u32(addr)?;
         self.pending_actions.push(Action::StoreU32(addr, old));
         self.store_bytes(addr, &data.to_le_bytes())?;
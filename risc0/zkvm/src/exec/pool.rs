@@ -0,0 +1,71 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements the ExecutorPool.
+
+use anyhow::Result;
+use risc0_binfmt::{MemoryImage, Program};
+use risc0_zkp::core::digest::Digest;
+use risc0_zkvm_platform::{memory::MEM_SIZE, PAGE_SIZE};
+
+use super::{Executor, ExecutorEnv};
+use crate::Session;
+
+/// Runs many executions of the same guest image.
+///
+/// Loading an ELF and constructing its [MemoryImage], including the page
+/// table Merkle tree, is done once when the pool is created, rather than once
+/// per execution. Each execution starts from its own copy of the initial
+/// image with its own [ExecutorEnv], so executions are isolated from each
+/// other.
+///
+/// An [ExecutorPool] is `Send` and `Sync`, so it can be shared between
+/// threads that each run their own executions.
+#[derive(Clone)]
+pub struct ExecutorPool {
+    image: MemoryImage,
+    image_id: Digest,
+}
+
+impl ExecutorPool {
+    /// Construct an [ExecutorPool] for the given initial [MemoryImage].
+    pub fn new(image: MemoryImage) -> Self {
+        let image_id = image.compute_id();
+        Self { image, image_id }
+    }
+
+    /// Construct an [ExecutorPool] for the given ELF binary.
+    pub fn from_elf(elf: &[u8]) -> Result<Self> {
+        let program = Program::load_elf(elf, MEM_SIZE as u32)?;
+        let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
+        Ok(Self::new(image))
+    }
+
+    /// The image ID of the guest run by this pool.
+    pub fn image_id(&self) -> Digest {
+        self.image_id
+    }
+
+    /// Construct an [Executor] that runs the guest with the given
+    /// [ExecutorEnv].
+    pub fn executor<'a>(&self, env: ExecutorEnv<'a>) -> Executor<'a> {
+        Executor::new(env, self.image.clone())
+    }
+
+    /// Run the guest with the given [ExecutorEnv] to completion, producing a
+    /// [Session].
+    pub fn execute(&self, env: ExecutorEnv<'_>) -> Result<Session> {
+        self.executor(env).run()
+    }
+}
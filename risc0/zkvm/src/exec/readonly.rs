@@ -0,0 +1,174 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only regions of guest memory mapped from host buffers.
+//!
+//! A region is written into the initial [MemoryImage] of the guest, so it is
+//! committed to by the image ID like the program itself, and the guest reads
+//! it in place instead of copying it in through a syscall. Only the pages it
+//! is read from are paged in, each at the cost of hashing a single page.
+//!
+//! Regions start on a page boundary, so mapping them only changes their own
+//! pages and the page table entries above them, which are all that is
+//! rehashed. Their addresses and lengths are written to the table at
+//! [READONLY_TABLE], which is also part of the image, after the
+//! [READONLY_TABLE_MAGIC] word marking it as mapped, for the guest to find
+//! them with `env::readonly_region`.
+
+use std::{collections::BTreeSet, ops::Range};
+
+use anyhow::{bail, ensure, Result};
+use risc0_binfmt::MemoryImage;
+use risc0_zkvm_platform::{
+    memory::{
+        READONLY_MAX_REGIONS, READONLY_TABLE, READONLY_TABLE_MAGIC, RESERVED_STACK, STACK_TOP,
+        TEXT_START,
+    },
+    PAGE_SIZE, WORD_SIZE,
+};
+
+use super::io::SyscallContext;
+use crate::align_up;
+
+/// The end of the memory that regions may be placed in, below the memory
+/// reserved for the stack by default.
+const REGIONS_END: u32 = STACK_TOP - RESERVED_STACK;
+
+/// A region of guest memory mapped from a host buffer, see
+/// [ExecutorEnvBuilder::map_readonly](super::ExecutorEnvBuilder::map_readonly).
+#[derive(Clone)]
+pub(crate) struct ReadonlyRegion<'a> {
+    pub addr_hint: u32,
+    pub bytes: &'a [u8],
+}
+
+// Return whether `range` can hold a region: it must lie between the start of
+// the program and the memory reserved for the stack, and not overlap the
+// program or another region.
+fn is_free(image: &MemoryImage, mapped: &[Range<u32>], range: &Range<u32>) -> bool {
+    range.start >= TEXT_START
+        && range.end <= REGIONS_END
+        && mapped
+            .iter()
+            .all(|other| range.end <= other.start || other.end <= range.start)
+        && range
+            .clone()
+            .step_by(PAGE_SIZE)
+            .all(|addr| !image.has_page(image.info.get_page_index(addr)))
+}
+
+/// Map the regions into the image and rehash the pages they change, returning
+/// the page-aligned range of memory each region occupies.
+///
+/// A region is placed at its address hint, rounded up to a page boundary, if
+/// it fits there. Otherwise, and for regions without a hint, it is placed
+/// below the memory reserved for the stack and all regions placed before it.
+/// Placement only depends on the image and the regions, so a verifier can
+/// reproduce the image ID.
+pub(crate) fn map_regions(
+    image: &mut MemoryImage,
+    regions: &[ReadonlyRegion],
+) -> Result<Vec<Range<u32>>> {
+    if regions.is_empty() {
+        return Ok(Vec::new());
+    }
+    ensure!(
+        regions.len() <= READONLY_MAX_REGIONS,
+        "Too many read-only regions: {}, the maximum is {READONLY_MAX_REGIONS}",
+        regions.len()
+    );
+    let table_idx = image.info.get_page_index(READONLY_TABLE);
+    ensure!(
+        !image.has_page(table_idx),
+        "The program overlaps the table of read-only regions at 0x{READONLY_TABLE:08x}"
+    );
+
+    let mut mapped: Vec<Range<u32>> = Vec::new();
+    let mut lowest = REGIONS_END;
+    for region in regions.iter() {
+        let len = u32::try_from(align_up(region.bytes.len(), PAGE_SIZE))
+            .ok()
+            .filter(|len| *len <= REGIONS_END);
+        let Some(len) = len else {
+            bail!("Read-only region of {} bytes does not fit in memory", region.bytes.len());
+        };
+        let hinted = match region.addr_hint {
+            0 => None,
+            hint => (align_up(hint as usize, PAGE_SIZE) as u32)
+                .checked_add(len)
+                .map(|end| end - len..end)
+                .filter(|range| is_free(image, &mapped, range)),
+        };
+        if region.addr_hint != 0 && hinted.is_none() {
+            log::warn!(
+                "Read-only region cannot be mapped at 0x{:08x}, placing it below the stack",
+                region.addr_hint
+            );
+        }
+        let range = match hinted {
+            Some(range) => range,
+            None => match lowest.checked_sub(len).map(|start| start..lowest) {
+                Some(range) if is_free(image, &mapped, &range) => range,
+                _ => bail!(
+                    "Not enough free memory to map a read-only region of {} bytes",
+                    region.bytes.len()
+                ),
+            },
+        };
+        lowest = lowest.min(range.start);
+        mapped.push(range);
+    }
+
+    let mut table = vec![READONLY_TABLE_MAGIC, regions.len() as u32];
+    let mut pages = BTreeSet::new();
+    for (region, range) in regions.iter().zip(mapped.iter()) {
+        table.extend([range.start, region.bytes.len() as u32]);
+        for (idx, chunk) in region.bytes.chunks(PAGE_SIZE).enumerate() {
+            let addr = range.start + (idx * PAGE_SIZE) as u32;
+            image.store_region_in_page(addr, chunk);
+            pages.insert(image.info.get_page_index(addr));
+        }
+    }
+    image.store_region_in_page(READONLY_TABLE, bytemuck::cast_slice(&table));
+    pages.insert(table_idx);
+
+    // Page table pages are above the pages they hold the entries of, so
+    // hashing in ascending order updates each entry before its page is hashed.
+    let mut changed = BTreeSet::new();
+    for mut page_idx in pages {
+        while page_idx < image.info.root_idx && changed.insert(page_idx) {
+            let entry_addr = image.info.get_page_entry_addr(page_idx);
+            page_idx = image.info.get_page_index(entry_addr);
+        }
+    }
+    image.hash_pages_iter(changed.into_iter());
+    Ok(mapped)
+}
+
+/// Return the page-aligned range of memory each read-only region occupies, as
+/// listed in the table of the running guest, which it cannot write to.
+pub(crate) fn mapped_regions(ctx: &mut dyn SyscallContext) -> Result<Vec<Range<u32>>> {
+    if ctx.load_u32(READONLY_TABLE)? != READONLY_TABLE_MAGIC {
+        return Ok(Vec::new());
+    }
+    let count = ctx.load_u32(READONLY_TABLE + WORD_SIZE as u32)?;
+    (0..count)
+        .map(|idx| {
+            let entry = READONLY_TABLE + (2 + 2 * idx) * WORD_SIZE as u32;
+            let start = ctx.load_u32(entry)?;
+            let len = ctx.load_u32(entry + WORD_SIZE as u32)?;
+            Ok(start..start + align_up(len as usize, PAGE_SIZE) as u32)
+        })
+        .collect()
+}
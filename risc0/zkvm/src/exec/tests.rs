@@ -15,19 +15,30 @@
 use std::{collections::BTreeMap, io::Cursor, str::from_utf8, sync::Mutex};
 
 use anyhow::Result;
+use rand_core::RngCore;
 use risc0_zkp::core::digest::Digest;
 use risc0_zkvm_methods::{
-    multi_test::{MultiTestSpec, SYS_MULTI_TEST},
-    HELLO_COMMIT_ELF, MULTI_TEST_ELF, SLICE_IO_ELF, STANDARD_LIB_ELF,
+    multi_test::{MultiTestSpec, TypedCommit, SYS_MULTI_TEST},
+    HELLO_COMMIT_ELF, MULTI_TEST_ELF, MULTI_TEST_ID, SLICE_IO_ELF, STACK_LAYOUT_ELF,
+    STANDARD_LIB_ELF,
 };
-use risc0_zkvm_platform::{fileno, PAGE_SIZE, WORD_SIZE};
+use risc0_zkvm_platform::{
+    fileno,
+    memory::{READONLY_TABLE, READONLY_TABLE_MAGIC, RESERVED_STACK, STACK_TOP, TEXT_START},
+    syscall::reg_abi::{REG_A3, REG_A4},
+    PAGE_SIZE, WORD_SIZE,
+};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest as _, Sha256};
 use test_log::test;
 
-use super::{Executor, ExecutorEnv, TraceEvent};
+use super::{Executor, ExecutorEnv, ExecutorEnvBuilderErr, ExecutorPool, TraceEvent};
 use crate::{
-    serde::{from_slice, to_vec},
-    testutils, ExitCode, MemoryImage, Program, Session,
+    guest::GuestRng,
+    ports::PortManifest,
+    serde::{from_slice, to_vec, Journal, JournalCommit, JournalError},
+    storage::{StreamDigest, STREAM_CHUNK_BYTES},
+    testutils, ExitCode, MemoryImage, Program, Session, Syscall, SyscallContext,
 };
 
 fn run_test(spec: MultiTestSpec) {
@@ -106,11 +117,6 @@ fn system_split() {
     assert_eq!(segments[1].index, 1);
 }
 
-#[test]
-fn libm_build() {
-    run_test(MultiTestSpec::LibM);
-}
-
 #[test]
 fn host_syscall() {
     let expected: Vec<Vec<u8>> = vec![
@@ -142,6 +148,420 @@ fn host_syscall() {
     assert_eq!(*actual.lock().unwrap(), expected[..expected.len() - 1]);
 }
 
+#[test]
+fn ext_syscall() {
+    struct Reverse;
+
+    impl Syscall for Reverse {
+        fn syscall(
+            &mut self,
+            _syscall: &str,
+            ctx: &mut dyn SyscallContext,
+            to_guest: &mut [u32],
+        ) -> Result<(u32, u32)> {
+            let buf_ptr = ctx.load_register(REG_A3);
+            let buf_len = ctx.load_register(REG_A4);
+            let mut bytes = ctx.load_region(buf_ptr, buf_len)?;
+            bytes.reverse();
+            bytemuck::cast_slice_mut::<u32, u8>(to_guest)[..bytes.len()].copy_from_slice(&bytes);
+            Ok((bytes.len() as u32, 0))
+        }
+    }
+
+    let spec = to_vec(&MultiTestSpec::ExtSyscall {
+        input: b"hello".to_vec(),
+    })
+    .unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .register_syscall("multi_test.reverse", Reverse)
+        .build()
+        .unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(session.journal, b"olleh");
+
+    // Without a registered handler, the syscall is unknown.
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .register_syscall("multi_test.other", Reverse)
+        .build()
+        .unwrap();
+    assert!(Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .is_err());
+}
+
+#[test]
+fn guest_rng() {
+    let spec = to_vec(&MultiTestSpec::Rng { len: 100 }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&spec).build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+
+    // The guest commits the seed first, with commit_rng_seed, followed by the
+    // bytes of each stream.
+    let (seed, bytes) = session.journal.split_at(32);
+    let seed = Digest::try_from(seed).unwrap();
+    for (stream, bytes) in bytes.chunks(100).enumerate() {
+        let mut expected = [0u8; 100];
+        GuestRng::new(&seed, stream as u32).fill_bytes(&mut expected);
+        assert_eq!(bytes, expected);
+    }
+    assert_ne!(bytes[..100], bytes[100..]);
+}
+
+#[test]
+fn io_ports() {
+    let manifest = PortManifest::from_elf(MULTI_TEST_ELF).unwrap();
+    assert_eq!(manifest.ports.len(), 2);
+    let spec = to_vec(&MultiTestSpec::Ports).unwrap();
+
+    let err = ExecutorEnv::builder()
+        .port_manifest(manifest.clone())
+        .input_port(
+            "multi_test.values",
+            "multi_test.values.v1",
+            &vec![1u32, 2, 3],
+        )
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ExecutorEnvBuilderErr::UnboundPort { name } if name == "multi_test.sum"));
+
+    let err = ExecutorEnv::builder()
+        .port_manifest(manifest.clone())
+        .input_port(
+            "multi_test.values",
+            "multi_test.values.v2",
+            &vec![1u64, 2, 3],
+        )
+        .output_port("multi_test.sum", "multi_test.sum.v1", Vec::new())
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ExecutorEnvBuilderErr::PortSchemaMismatch { expected, .. } if expected == "multi_test.values.v1"
+    ));
+
+    let err = ExecutorEnv::builder()
+        .port_manifest(manifest.clone())
+        .input_port(
+            "multi_test.values",
+            "multi_test.values.v1",
+            &vec![1u32, 2, 3],
+        )
+        .output_port("multi_test.sum", "multi_test.sum.v1", Vec::new())
+        .input_port("multi_test.other", "u32", &0u32)
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(err, ExecutorEnvBuilderErr::UnknownPort { name } if name == "multi_test.other")
+    );
+
+    // A value that fails to serialize is reported by build.
+    struct Unserializable;
+    impl serde::Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("unserializable"))
+        }
+    }
+    let err = ExecutorEnv::builder()
+        .port_manifest(manifest.clone())
+        .input_port("multi_test.values", "multi_test.values.v1", &Unserializable)
+        .output_port("multi_test.sum", "multi_test.sum.v1", Vec::new())
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ExecutorEnvBuilderErr::PortSerialization { name, .. } if name == "multi_test.values"
+    ));
+
+    let mut sum = Vec::new();
+    {
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .port_manifest(manifest)
+            .input_port(
+                "multi_test.values",
+                "multi_test.values.v1",
+                &vec![1u32, 2, 3],
+            )
+            .output_port("multi_test.sum", "multi_test.sum.v1", &mut sum)
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+    }
+    assert_eq!(sum, 6u32.to_le_bytes());
+}
+
+#[test]
+fn read_memory() {
+    // Straddle a page boundary in the middle of the memory space, which is
+    // unlikely to be touched by either the stack or heap.
+    const POS: u32 = crate::align_up((TEXT_START + STACK_TOP) as usize / 2, PAGE_SIZE) as u32 - 4;
+    let spec = to_vec(&MultiTestSpec::ReadWriteMem {
+        values: vec![(POS, 0x11223344), (POS + 4, 0x55667788)],
+    })
+    .unwrap();
+
+    let env = ExecutorEnv::builder().add_input(&spec).build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    session.read_memory(POS, 8).unwrap_err();
+
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .retain_memory(true)
+        .build()
+        .unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(
+        session.read_memory(POS, 12).unwrap(),
+        [0x44, 0x33, 0x22, 0x11, 0x88, 0x77, 0x66, 0x55, 0, 0, 0, 0]
+    );
+    session.read_memory(u32::MAX - 3, 8).unwrap_err();
+}
+
+#[test]
+fn readonly_regions() {
+    let large: Vec<u8> = (0..3 * PAGE_SIZE + 100).map(|x| x as u8).collect();
+    let small = b"risc0".to_vec();
+    let run = |spec: MultiTestSpec| -> Result<(Digest, Session)> {
+        let env = ExecutorEnv::builder()
+            .add_input(&to_vec(&spec).unwrap())
+            .map_readonly((TEXT_START + STACK_TOP) / 2, &large)
+            .map_readonly(0, &small)
+            .build()
+            .unwrap();
+        let image_id = env.image_id(MULTI_TEST_ELF)?;
+        let session = Executor::from_elf(env, MULTI_TEST_ELF)?.run()?;
+        Ok((image_id, session))
+    };
+
+    for (idx, expected) in [(0, Some(&large)), (1, Some(&small)), (2, None)] {
+        let (image_id, session) = run(MultiTestSpec::ReadonlyRegion { idx }).unwrap();
+        let actual: Option<Vec<u8>> = from_slice(&session.journal).unwrap();
+        assert_eq!(actual.as_ref(), expected);
+        let segment = session.segments[0].resolve().unwrap();
+        assert_eq!(segment.pre_image.compute_id(), image_id);
+        assert_ne!(image_id, Digest::from(MULTI_TEST_ID));
+    }
+
+    let err = run(MultiTestSpec::WriteReadonlyRegion).err().unwrap();
+    assert!(format!("{err:#}").contains("read-only"), "{err:#}");
+
+    // The table is marked as mapped, and left as zeros without regions.
+    let (_, session) = run(MultiTestSpec::ReadonlyRegion { idx: 0 }).unwrap();
+    let mut table = [0u8; 8];
+    let segment = session.segments[0].resolve().unwrap();
+    segment
+        .pre_image
+        .load_region_in_page(READONLY_TABLE, &mut table);
+    assert_eq!(table[..4], READONLY_TABLE_MAGIC.to_le_bytes());
+    assert_eq!(table[4..], 2u32.to_le_bytes());
+
+    let env = ExecutorEnv::builder()
+        .add_input(&to_vec(&MultiTestSpec::ReadonlyRegion { idx: 0 }).unwrap())
+        .build()
+        .unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let actual: Option<Vec<u8>> = from_slice(&session.journal).unwrap();
+    assert_eq!(actual, None);
+}
+
+#[test]
+fn readonly_region_heap() {
+    // A region above the heap, in the middle of the memory space.
+    const ADDR: u32 = crate::align_up((TEXT_START + STACK_TOP) as usize / 2, PAGE_SIZE) as u32;
+    let data = vec![1u8; PAGE_SIZE];
+    let run = |extra: u32| {
+        let env = ExecutorEnv::builder()
+            .add_input(&to_vec(&MultiTestSpec::AllocBelowReadonly { extra }).unwrap())
+            .map_readonly(ADDR, &data)
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF).unwrap().run()
+    };
+
+    // The heap ends where the region starts.
+    let session = run(0).unwrap();
+    let (region, heap_end): (u32, u32) = from_slice(&session.journal).unwrap();
+    assert_eq!(region, ADDR);
+    assert_eq!(heap_end, ADDR);
+
+    // Allocating past the region runs out of memory, rather than handing out
+    // memory of the region.
+    let err = run(1).err().unwrap();
+    assert!(err.to_string().contains("Out of memory!"), "{err:?}");
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn host_syscall_async() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let input = to_vec(&MultiTestSpec::Syscall { count: 3 }).unwrap();
+    let actual: Mutex<Vec<Vec<u8>>> = Vec::new().into();
+    let actual_ref = &actual;
+    let env = ExecutorEnv::builder()
+        .add_input(&input)
+        .io_callback_async(
+            SYS_MULTI_TEST,
+            runtime.handle().clone(),
+            move |buf| async move {
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                actual_ref.lock().unwrap().push(buf);
+                b"pong".to_vec()
+            },
+        )
+        .build()
+        .unwrap();
+    Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let expected: Vec<Vec<u8>> = vec!["".into(), "pong".into(), "pong".into()];
+    assert_eq!(*actual.lock().unwrap(), expected);
+}
+
+// The executor may run in a task of the runtime that drives the handler.
+#[cfg(feature = "async")]
+#[test]
+fn host_syscall_async_in_task() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let handle = runtime.handle().clone();
+    let actual: std::sync::Arc<Mutex<Vec<Vec<u8>>>> = Default::default();
+    let actual_ref = actual.clone();
+    let task = runtime.spawn(async move {
+        let input = to_vec(&MultiTestSpec::Syscall { count: 3 }).unwrap();
+        let env = ExecutorEnv::builder()
+            .add_input(&input)
+            .io_callback_async(SYS_MULTI_TEST, handle, move |buf| {
+                let actual = actual_ref.clone();
+                async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    actual.lock().unwrap().push(buf);
+                    b"pong".to_vec()
+                }
+            })
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+    });
+    runtime.block_on(task).unwrap();
+    let expected: Vec<Vec<u8>> = vec!["".into(), "pong".into(), "pong".into()];
+    assert_eq!(*actual.lock().unwrap(), expected);
+}
+
+// The executor may run in a task of a current-thread runtime, which cannot
+// hand off its thread.
+#[cfg(feature = "async")]
+#[test]
+fn host_syscall_async_in_current_thread() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let handle = runtime.handle().clone();
+    let current = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let actual: Mutex<Vec<Vec<u8>>> = Vec::new().into();
+    let actual_ref = &actual;
+    current.block_on(async move {
+        let input = to_vec(&MultiTestSpec::Syscall { count: 3 }).unwrap();
+        let env = ExecutorEnv::builder()
+            .add_input(&input)
+            .io_callback_async(SYS_MULTI_TEST, handle, move |buf| async move {
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                actual_ref.lock().unwrap().push(buf);
+                b"pong".to_vec()
+            })
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+    });
+    let expected: Vec<Vec<u8>> = vec!["".into(), "pong".into(), "pong".into()];
+    assert_eq!(*actual.lock().unwrap(), expected);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn host_syscall_async_current_thread() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    let result = ExecutorEnv::builder()
+        .io_callback_async(SYS_MULTI_TEST, runtime.handle().clone(), |buf| async move {
+            buf
+        })
+        .build();
+    assert!(matches!(
+        result,
+        Err(ExecutorEnvBuilderErr::CurrentThreadRuntime { syscall })
+            if syscall == SYS_MULTI_TEST.as_str()
+    ));
+}
+
+#[cfg(feature = "difftest")]
+#[test]
+fn diff_test() {
+    use crate::difftest::{ArchState, ReferenceEmulator, ReferenceMemory, Rv32imInterpreter};
+
+    let input = to_vec(&MultiTestSpec::ShaConforms).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&input)
+        .diff_test(Rv32imInterpreter, 1)
+        .build()
+        .unwrap();
+    Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+
+    // A reference that disagrees about every instruction.
+    struct Broken;
+
+    impl ReferenceEmulator for Broken {
+        fn step(&mut self, state: &mut ArchState, mem: &mut dyn ReferenceMemory) -> Result<()> {
+            Rv32imInterpreter.step(state, mem)?;
+            state.pc += 4;
+            Ok(())
+        }
+    }
+
+    let env = ExecutorEnv::builder()
+        .add_input(&input)
+        .diff_test(Broken, 1)
+        .build()
+        .unwrap();
+    let err = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .err()
+        .unwrap();
+    assert!(err.to_string().starts_with("difftest divergence at"));
+}
+
 // Make sure panics in the callback get propagated correctly.
 #[test]
 #[should_panic(expected = "I am panicking from here!")]
@@ -165,16 +585,6 @@ fn sha_accel() {
     run_test(MultiTestSpec::ShaConforms);
 }
 
-#[test]
-fn sha_cycle_count() {
-    run_test(MultiTestSpec::ShaCycleCount);
-}
-
-#[test]
-fn rsa_compat() {
-    run_test(MultiTestSpec::RsaCompat);
-}
-
 #[test]
 fn bigint_accel() {
     let cases = testutils::generate_bigint_test_cases(&mut rand::thread_rng(), 10);
@@ -197,6 +607,51 @@ fn bigint_accel() {
     }
 }
 
+#[test]
+fn bigint_wide() {
+    use crypto_bigint::{Encoding, NonZero, U1024};
+
+    let to_uint = |words: &[u32]| {
+        let mut bytes = [0u8; U1024::BYTES];
+        for (chunk, word) in bytes.chunks_mut(WORD_SIZE).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        U1024::from_le_slice(&bytes)
+    };
+    let run = |x: &[u32], y: &[u32], modulus: &[u32]| -> Result<Vec<u32>> {
+        let input = to_vec(&MultiTestSpec::BigIntWide {
+            x: x.to_vec(),
+            y: y.to_vec(),
+            modulus: modulus.to_vec(),
+        })?;
+        let env = ExecutorEnv::builder().add_input(&input).build()?;
+        let session = Executor::from_elf(env, MULTI_TEST_ELF)?.run()?;
+        Ok(bytemuck::pod_collect_to_vec(&session.journal))
+    };
+
+    let mut rng = rand::thread_rng();
+    for width in [12, 16] {
+        for _ in 0..4 {
+            let mut modulus: Vec<u32> = (0..width).map(|_| rng.next_u32()).collect();
+            modulus[width - 1] |= 1 << 31;
+            let m = NonZero::new(to_uint(&modulus)).unwrap();
+            let mut reduced = || -> Vec<u32> {
+                let words: Vec<u32> = (0..width).map(|_| rng.next_u32()).collect();
+                let bytes = to_uint(&words).rem(&m).to_le_bytes();
+                bytemuck::pod_collect_to_vec(&bytes[..width * WORD_SIZE])
+            };
+            let (x, y) = (reduced(), reduced());
+            let expected = to_uint(&x).wrapping_mul(&to_uint(&y)).rem(&m);
+            let actual = run(&x, &y, &modulus).unwrap();
+            assert_eq!(to_uint(&actual), expected);
+        }
+    }
+
+    // Operands must be reduced.
+    let modulus = [7u32; 12];
+    assert!(run(&modulus, &[1; 12], &modulus).is_err());
+}
+
 #[test]
 fn env_stdio() {
     const MSG: &str = "Hello world!  This is a test of standard input and output.";
@@ -359,6 +814,59 @@ fn large_sha() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn sha_batch() {
+    // Hashing the leaves of a Merkle tree in a batch takes fewer cycles than
+    // hashing each one on its own.
+    let msgs: Vec<Vec<u8>> = (0..100).map(|i| vec![i as u8; 32]).collect();
+    let expected: Vec<String> = msgs
+        .iter()
+        .map(|msg| hex::encode(Sha256::digest(msg)))
+        .collect();
+    let input = to_vec(&MultiTestSpec::ShaBatch { msgs }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = Executor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let (digests, batch_cycles, single_cycles): (Vec<Digest>, usize, usize) =
+        from_slice(&session.journal).unwrap();
+    let actual: Vec<String> = digests.iter().map(hex::encode).collect();
+    assert_eq!(expected, actual);
+    assert!(
+        batch_cycles < single_cycles,
+        "batch: {batch_cycles}, single: {single_cycles}"
+    );
+}
+
+#[test]
+fn free_memory() {
+    let input = to_vec(&MultiTestSpec::FreeMemory).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let (before, after): (u32, u32) = from_slice(&session.journal).unwrap();
+    assert!(before - after >= 1 << 20);
+}
+
+#[test]
+fn stack_layout() {
+    let run = |depth: u32| {
+        let env = ExecutorEnv::builder().add_input(&[depth]).build().unwrap();
+        Executor::from_elf(env, STACK_LAYOUT_ELF).unwrap().run()
+    };
+
+    let session = run(10).unwrap();
+    let (sum, free): (u32, u32) = from_slice(&session.journal).unwrap();
+    assert_eq!(sum, (1..=10).sum());
+    // The heap may grow into the space that is reserved for the stack by
+    // default.
+    assert!(free > STACK_TOP - RESERVED_STACK);
+
+    let err = run(1000).err().unwrap();
+    assert!(err.to_string().contains("Stack overflow"), "{err}");
+}
+
 #[test]
 fn std_stdio() {
     const STDIN: &str = "Hello world from stdin!\n";
@@ -457,6 +965,490 @@ fn fail() {
     assert!(err.to_string().contains("MultiTestSpec::Fail invoked"));
 }
 
+#[test]
+fn input_limits() {
+    let input = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let size = input.len() * WORD_SIZE;
+
+    let err = ExecutorEnv::builder()
+        .add_input(&input)
+        .max_input_bytes(size - 1)
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        ExecutorEnvBuilderErr::InputTooLarge { size: s, max } if s == size && max == size - 1
+    ));
+
+    let err = ExecutorEnv::builder()
+        .add_input(&input)
+        .input_validator(|_| anyhow::bail!("rejected"))
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, ExecutorEnvBuilderErr::InvalidInput(_)));
+    assert_eq!(err.to_string(), "Invalid input: rejected");
+
+    let env = ExecutorEnv::builder()
+        .add_input(&input)
+        .max_input_bytes(size)
+        .input_validator(|input| {
+            anyhow::ensure!(from_slice::<MultiTestSpec, u8>(input).is_ok());
+            Ok(())
+        })
+        .build()
+        .unwrap();
+    Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+}
+
+#[test]
+fn env_to_builder() {
+    let segments = std::cell::Cell::new(0);
+    let data = |data: &str| to_vec(&MultiTestSpec::ShaDigest { data: data.into() }).unwrap();
+    let run = |env: ExecutorEnv| {
+        let session = Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+        hex::encode(Digest::try_from(session.journal).unwrap())
+    };
+
+    let err = ExecutorEnv::builder()
+        .input_slot("spec")
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(err, ExecutorEnvBuilderErr::UnfilledInputSlot { name } if name == "spec"));
+
+    let env = ExecutorEnv::builder()
+        .on_segment(|_| {
+            segments.set(segments.get() + 1);
+            Ok(())
+        })
+        .input_slot("spec")
+        .fill_slot("spec", &data("a"))
+        .build()
+        .unwrap();
+    let other = env
+        .to_builder()
+        .fill_slot("spec", &data(""))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        run(env),
+        "ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb"
+    );
+    assert_eq!(
+        run(other),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(segments.get(), 2);
+    // Without slots, the input is shared with the template rather than copied.
+    let env = ExecutorEnv::builder()
+        .add_input(&data("a"))
+        .build()
+        .unwrap();
+    let other = env.to_builder().build().unwrap();
+    assert!(std::rc::Rc::ptr_eq(&env.input, &other.input));
+}
+
+#[test]
+fn read_frames() {
+    let frames = vec![b"abc".to_vec(), Vec::new(), b"hello world".to_vec()];
+    let spec = to_vec(&MultiTestSpec::ReadFrames).unwrap();
+    let mut builder = ExecutorEnv::builder();
+    builder.add_input(&spec);
+    for frame in frames.iter() {
+        builder.write_frame(frame);
+    }
+    let env = builder.build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let actual: Vec<Vec<u8>> = from_slice(&session.journal).unwrap();
+    assert_eq!(actual, frames);
+}
+
+#[test]
+fn hash_stream() {
+    const STREAM_FD: u32 = 10;
+
+    // Cover an empty stream, a tail that needs a second padding block, and a
+    // stream spanning several chunks.
+    for len in [0, 60, 3 * STREAM_CHUNK_BYTES + 100] {
+        let data: Vec<u8> = (0..len).map(|x| x as u8).collect();
+        let spec = to_vec(&MultiTestSpec::HashStream { fd: STREAM_FD }).unwrap();
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .stream_file(STREAM_FD, data.as_slice())
+            .build()
+            .unwrap();
+        let session = Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+        let actual: StreamDigest = from_slice(&session.journal).unwrap();
+        assert_eq!(actual, StreamDigest::from_reader(data.as_slice()).unwrap());
+        assert_eq!(actual.len, len as u64);
+        assert_eq!(actual.digest.as_bytes(), Sha256::digest(&data).as_slice());
+    }
+}
+
+#[test]
+fn commit_typed() {
+    let commit = TypedCommit {
+        value: 42,
+        data: b"hello".to_vec(),
+    };
+    let spec = to_vec(&MultiTestSpec::CommitTyped {
+        commit: commit.clone(),
+    })
+    .unwrap();
+    let env = ExecutorEnv::builder().add_input(&spec).build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(
+        Journal::<TypedCommit>::decode(&session.journal).unwrap(),
+        commit
+    );
+    // The schema ID derived for `TypedCommit { value: u32, data: Vec<u8> }`.
+    assert_eq!(TypedCommit::SCHEMA_ID, 0xf67d3c14);
+
+    // A host built against a newer version of the type must not decode it.
+    #[derive(Debug, Deserialize, Serialize)]
+    struct TypedCommitV3 {
+        value: u32,
+        data: Vec<u8>,
+    }
+    impl JournalCommit for TypedCommitV3 {
+        const NAME: &'static str = "TypedCommit";
+        const VERSION: u32 = 3;
+        const SCHEMA_ID: u32 = TypedCommit::SCHEMA_ID;
+    }
+    assert_eq!(
+        Journal::<TypedCommitV3>::decode(&session.journal).unwrap_err(),
+        JournalError::VersionMismatch {
+            name: "TypedCommit",
+            expected: 3,
+            actual: 2,
+        }
+    );
+
+    // Nor a type changed without bumping the version.
+    #[derive(Debug, Deserialize, Serialize)]
+    struct TypedCommitChanged {
+        value: u64,
+    }
+    impl JournalCommit for TypedCommitChanged {
+        const NAME: &'static str = "TypedCommit";
+        const VERSION: u32 = 2;
+        const SCHEMA_ID: u32 = TypedCommit::SCHEMA_ID ^ 1;
+    }
+    assert_eq!(
+        Journal::<TypedCommitChanged>::decode(&session.journal).unwrap_err(),
+        JournalError::SchemaMismatch {
+            name: "TypedCommit"
+        }
+    );
+}
+
+#[test]
+fn yield_output() {
+    let outputs = vec![b"first".to_vec(), b"second".to_vec()];
+    let spec = to_vec(&MultiTestSpec::YieldOutputs {
+        outputs: outputs.clone(),
+    })
+    .unwrap();
+
+    let received = std::cell::RefCell::new(Vec::new());
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .on_output(|bytes| {
+            received.borrow_mut().push(bytes.to_vec());
+            Ok(())
+        })
+        .build()
+        .unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(received.into_inner(), outputs);
+    assert!(session.journal.is_empty());
+
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .on_output(|_| anyhow::bail!("stop"))
+        .build()
+        .unwrap();
+    let err = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("stop"));
+}
+
+#[test]
+fn kv_oracle() {
+    use crate::oracle::{MerkleKvStore, OracleError};
+
+    let entries = BTreeMap::from([
+        (b"alice".to_vec(), b"10".to_vec()),
+        (b"bob".to_vec(), b"20".to_vec()),
+        (b"carol".to_vec(), b"30".to_vec()),
+    ]);
+    let run = |root: Digest, key: &[u8]| {
+        let spec = to_vec(&MultiTestSpec::KvOracle { key: key.to_vec() }).unwrap();
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .kv_oracle(root, MerkleKvStore::new(entries.clone()))
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF).unwrap().run()
+    };
+
+    let root = MerkleKvStore::new(entries.clone()).root();
+    let journal = |key: &[u8]| -> (Digest, Result<Vec<u8>, String>) {
+        from_slice(&run(root, key).unwrap().journal).unwrap()
+    };
+    assert_eq!(journal(b"bob"), (root, Ok(b"20".to_vec())));
+
+    // Unknown keys and mismatched roots are reported to the guest.
+    assert_eq!(
+        journal(b"dave"),
+        (root, Err(OracleError::NotFound.to_string()))
+    );
+    let session = run(Digest::default(), b"bob").unwrap();
+    let (committed_root, value): (Digest, Result<Vec<u8>, String>) =
+        from_slice(&session.journal).unwrap();
+    assert_eq!(committed_root, Digest::default());
+    assert_eq!(value, Err(OracleError::InvalidProof.to_string()));
+}
+
+#[test]
+fn logical_time() {
+    let spec = to_vec(&MultiTestSpec::LogicalTime).unwrap();
+    let env = ExecutorEnv::builder().add_input(&spec).build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let (during, at_exit): (u64, u64) = from_slice(&session.journal).unwrap();
+    assert!(during > 0);
+    assert!(at_exit > during);
+}
+
+#[test]
+fn paging_stats() {
+    let run = |preload: &[std::ops::Range<u32>]| {
+        let spec = to_vec(&MultiTestSpec::DoNothing).unwrap();
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .preload_pages(preload)
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap()
+    };
+
+    let session = run(&[]);
+    assert!(session.paging.pages_loaded > 0);
+    assert!(session.paging.page_read_cycles > 0);
+
+    // Preload 4 pages that the guest never touches.
+    let start = 0x0800_0000;
+    let preloaded = run(&[start + 1..start + 4 * PAGE_SIZE as u32]);
+    assert!(preloaded.paging.pages_loaded >= session.paging.pages_loaded + 4);
+    assert!(preloaded.paging.page_read_cycles > session.paging.page_read_cycles);
+    assert_eq!(preloaded.exit_code, ExitCode::Halted(0));
+}
+
+#[test]
+fn execution_stats() {
+    let spec = to_vec(&MultiTestSpec::ShaDigest {
+        data: vec![0u8; 1024],
+    })
+    .unwrap();
+    let run = |hot_pcs: Option<usize>| {
+        let mut builder = ExecutorEnv::builder();
+        builder.add_input(&spec);
+        if let Some(hot_pcs) = hot_pcs {
+            builder.execution_stats(hot_pcs);
+        }
+        Executor::from_elf(builder.build().unwrap(), MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap()
+    };
+
+    assert!(run(None).stats().is_none());
+
+    let session = run(Some(5));
+    let stats = session.stats().unwrap();
+    assert_eq!(stats.opcodes["ecall:halt"].count, 1);
+    assert!(stats.opcodes["ecall:sha"].cycles > stats.opcodes["ecall:sha"].count);
+    assert!(stats.opcodes.contains_key("lw"));
+    assert_eq!(stats.hot_pcs.len(), 5);
+    assert!(stats
+        .hot_pcs
+        .windows(2)
+        .all(|x| x[0].1.cycles >= x[1].1.cycles));
+    assert!(stats.to_string().contains("ecall:sha"));
+}
+
+#[test]
+fn session_events() {
+    let spec = to_vec(&MultiTestSpec::Syscall { count: 3 }).unwrap();
+    let mut segments = Vec::new();
+    let mut syscalls = Vec::new();
+    {
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .io_callback(SYS_MULTI_TEST, |_buf: &[u8]| -> Vec<u8> { Vec::new() })
+            .on_segment(|info| {
+                segments.push(info.clone());
+                Ok(())
+            })
+            .on_syscall(|info| {
+                syscalls.push(info.name.clone());
+                Ok(())
+            })
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+    }
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].index, 0);
+    assert_eq!(segments[0].exit_code, ExitCode::Halted(0));
+    let multi_test_calls = syscalls
+        .iter()
+        .filter(|name| name.as_str() == SYS_MULTI_TEST.as_str())
+        .count();
+    // Each `send_recv_slice` makes two calls to the syscall.
+    assert_eq!(multi_test_calls, 6);
+}
+
+#[test]
+fn session_events_abort() {
+    let spec = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let mut faults = 0;
+    let err = {
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .on_segment(|_| anyhow::bail!("aborted by on_segment"))
+            .on_fault(|_| faults += 1)
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .err()
+            .unwrap()
+    };
+    assert!(err.to_string().contains("aborted by on_segment"));
+    // Aborting is not a fault of the guest.
+    assert_eq!(faults, 0);
+}
+
+#[test]
+fn session_events_host_errors() {
+    #[derive(Debug, thiserror::Error)]
+    #[error("aborted by host")]
+    struct Aborted;
+
+    // Errors of callbacks run while handling a syscall are returned as they
+    // are, and not reported as guest faults.
+    let syscall_spec = to_vec(&MultiTestSpec::Syscall { count: 1 }).unwrap();
+    let output_spec = to_vec(&MultiTestSpec::YieldOutputs {
+        outputs: vec![b"output".to_vec()],
+    })
+    .unwrap();
+    for on_output in [false, true] {
+        let mut faults = 0;
+        let err = {
+            let mut builder = ExecutorEnv::builder();
+            builder
+                .io_callback(SYS_MULTI_TEST, |_buf: &[u8]| -> Vec<u8> { Vec::new() })
+                .on_fault(|_| faults += 1);
+            match on_output {
+                true => builder
+                    .add_input(&output_spec)
+                    .on_output(|_| Err(Aborted.into())),
+                false => builder
+                    .add_input(&syscall_spec)
+                    .on_syscall(|_| Err(Aborted.into())),
+            };
+            let env = builder.build().unwrap();
+            Executor::from_elf(env, MULTI_TEST_ELF)
+                .unwrap()
+                .run()
+                .err()
+                .unwrap()
+        };
+        assert!(err.is::<Aborted>(), "{err:#}");
+        assert_eq!(faults, 0);
+    }
+}
+
+#[test]
+fn session_events_fault() {
+    let spec = to_vec(&MultiTestSpec::Fail).unwrap();
+    let mut faults = Vec::new();
+    {
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .on_fault(|info| faults.push(info.clone()))
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .err()
+            .unwrap();
+    }
+    assert_eq!(faults.len(), 1);
+    assert!(faults[0].message.contains("MultiTestSpec::Fail invoked"));
+    assert!(!faults[0].provable);
+}
+
+#[test]
+fn session_events_provable_fault() {
+    let spec = to_vec(&MultiTestSpec::OutOfBounds).unwrap();
+    let addr = to_vec(&0x0C00_0000u32).unwrap();
+    let mut faults = Vec::new();
+    let session = {
+        let env = ExecutorEnv::builder()
+            .add_input(&spec)
+            .add_input(&addr)
+            .fault_proofs(true)
+            .on_fault(|info| faults.push(info.clone()))
+            .build()
+            .unwrap();
+        Executor::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap()
+    };
+    assert_eq!(session.exit_code, ExitCode::SystemSplit);
+    assert!(session.fault.is_some());
+    assert_eq!(faults.len(), 1);
+    assert!(faults[0].provable);
+}
+
 #[cfg(feature = "profiler")]
 #[test]
 fn profiler() {
@@ -670,3 +1662,24 @@ fn memory_access() {
     access_memory(0x0C00_0000).err().unwrap();
     access_memory(0x0B00_0000).unwrap();
 }
+
+#[test]
+fn executor_pool() {
+    let pool = ExecutorPool::from_elf(MULTI_TEST_ELF).unwrap();
+    assert_eq!(pool.image_id(), Digest::from(MULTI_TEST_ID));
+
+    std::thread::scope(|scope| {
+        for i in 0..4u8 {
+            let pool = &pool;
+            scope.spawn(move || {
+                let data = vec![i; 16];
+                let spec = to_vec(&MultiTestSpec::ShaDigest { data: data.clone() }).unwrap();
+                let env = ExecutorEnv::builder().add_input(&spec).build().unwrap();
+                let session = pool.execute(env).unwrap();
+                assert_eq!(session.exit_code, ExitCode::Halted(0));
+                let digest: Digest = from_slice(&session.journal).unwrap();
+                assert_eq!(digest.as_bytes(), Sha256::digest(&data).as_slice());
+            });
+        }
+    });
+}
@@ -0,0 +1,200 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts proving that a guest faulted.
+//!
+//! Normally, a guest that executes an illegal instruction or accesses memory
+//! outside of the guest address space causes [Executor::run] to return an
+//! error, and nothing can be proven. With
+//! [ExecutorEnvBuilder::fault_proofs] enabled, execution instead stops just
+//! before the faulting instruction, and the [Session] records a [FaultProof].
+//! [Session::prove_fault] then produces a [FaultReceipt].
+//!
+//! A [FaultReceipt] consists of a [Receipt] proving execution up to the
+//! faulting instruction, and the pages of the final memory image that hold the
+//! instruction and the registers. [FaultReceipt::verify] checks the receipt,
+//! checks the pages against the final memory image, and re-derives the
+//! [GuestFault] from the instruction and registers. A [FaultReceipt] is never
+//! accepted by [Receipt::verify], since it does not end in a halt.
+//!
+//! [Executor::run]: crate::Executor::run
+//! [ExecutorEnvBuilder::fault_proofs]: crate::ExecutorEnvBuilder::fault_proofs
+//! [Session]: crate::Session
+//! [Session::prove_fault]: crate::Session::prove_fault
+
+#[cfg(feature = "prove")]
+use risc0_binfmt::MemoryImage;
+use risc0_binfmt::{PageProof, PageTableInfo};
+use risc0_zkp::verify::VerificationError;
+use risc0_zkvm_platform::{
+    memory::{PAGE_TABLE, SYSTEM, TEXT_START},
+    syscall::reg_abi::REG_MAX,
+    PAGE_SIZE, WORD_SIZE,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    opcode::OpCode,
+    receipt::{InnerReceipt, Receipt, VerifierContext},
+    sha::Digest,
+    ExitCode,
+};
+
+/// The kind of fault raised by a guest.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum FaultKind {
+    /// The instruction could not be decoded.
+    IllegalInstruction {
+        /// The encoded instruction.
+        insn: u32,
+    },
+
+    /// A load or store accessed memory outside of the guest address space.
+    InvalidMemoryAccess {
+        /// The address that was accessed.
+        addr: u32,
+    },
+}
+
+/// A fault raised by a guest, as attested by a [FaultReceipt].
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct GuestFault {
+    /// The address of the faulting instruction.
+    pub pc: u32,
+
+    /// The kind of fault.
+    pub kind: FaultKind,
+}
+
+/// The pages of the final memory image needed to re-derive a [GuestFault].
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FaultProof {
+    /// The page holding the faulting instruction.
+    pub insn_page: PageProof,
+
+    /// The page holding the registers.
+    pub system_page: PageProof,
+}
+
+impl FaultProof {
+    #[cfg(feature = "prove")]
+    pub(crate) fn new(image: &MemoryImage) -> Self {
+        Self {
+            insn_page: image.page_proof(image.pc),
+            system_page: image.page_proof(SYSTEM.start() as u32),
+        }
+    }
+}
+
+/// A receipt attesting that a guest faulted.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FaultReceipt {
+    /// A receipt proving execution up to the faulting instruction.
+    ///
+    /// The journal of this receipt is not attested.
+    pub receipt: Receipt,
+
+    /// The pages needed to re-derive the fault.
+    pub proof: FaultProof,
+}
+
+impl FaultReceipt {
+    /// Verify that the guest with the given `image_id` faulted, returning the
+    /// [GuestFault].
+    pub fn verify(&self, image_id: impl Into<Digest>) -> Result<GuestFault, VerificationError> {
+        self.verify_with_context(&VerifierContext::default(), image_id)
+    }
+
+    /// Verify that the guest with the given `image_id` faulted, returning the
+    /// [GuestFault].
+    pub fn verify_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<GuestFault, VerificationError> {
+        let InnerReceipt::Flat(segments) = &self.receipt.inner else {
+            return Err(VerificationError::ReceiptFormatError);
+        };
+
+        // Every segment, including the last, must end with a split.
+        let mut prev_image_id = image_id.into();
+        let mut post = None;
+        for receipt in segments.0.iter() {
+            receipt.verify_with_context(ctx)?;
+            let metadata = receipt.get_metadata()?;
+            if prev_image_id != metadata.pre.digest() {
+                return Err(VerificationError::ImageVerificationError);
+            }
+            if metadata.exit_code != ExitCode::SystemSplit {
+                return Err(VerificationError::UnexpectedExitCode);
+            }
+            prev_image_id = metadata.post.digest();
+            post = Some(metadata.post);
+        }
+        let post = post.ok_or(VerificationError::ReceiptFormatError)?;
+
+        // Read the instruction and registers from the final memory image.
+        let info = PageTableInfo::new(PAGE_TABLE.start() as u32, PAGE_SIZE as u32);
+        let read_words = |proof: &PageProof, addr: u32, words: &mut [u32]| {
+            if proof.page_idx != info.get_page_index(addr) {
+                return Err(VerificationError::ReceiptFormatError);
+            }
+            let page = proof
+                .verify(&info, &post.merkle_root)
+                .or(Err(VerificationError::ReceiptFormatError))?;
+            let offset = (addr - info.get_page_addr(proof.page_idx)) as usize;
+            let bytes = page
+                .get(offset..offset + words.len() * WORD_SIZE)
+                .ok_or(VerificationError::ReceiptFormatError)?;
+            for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(WORD_SIZE)) {
+                *word = u32::from_le_bytes(bytes.try_into().unwrap());
+            }
+            Ok(())
+        };
+        let mut insn = [0u32];
+        read_words(&self.proof.insn_page, post.pc, &mut insn)?;
+        let mut registers = [0u32; REG_MAX];
+        read_words(
+            &self.proof.system_page,
+            SYSTEM.start() as u32,
+            &mut registers,
+        )?;
+
+        // The instruction must actually fault.
+        let kind = classify(insn[0], &registers).ok_or(VerificationError::InvalidProof)?;
+        Ok(GuestFault { pc: post.pc, kind })
+    }
+}
+
+/// Determine the fault raised by executing `insn` with the given registers,
+/// if any.
+pub(crate) fn classify(insn: u32, registers: &[u32; REG_MAX]) -> Option<FaultKind> {
+    if OpCode::decode(insn, 0).is_err() {
+        return Some(FaultKind::IllegalInstruction { insn });
+    }
+    let imm = match insn & 0x7f {
+        // Loads use an I-type immediate.
+        0b0000011 => (insn as i32) >> 20,
+        // Stores use an S-type immediate.
+        0b0100011 => ((insn as i32) >> 25 << 5) | ((insn >> 7) & 0x1f) as i32,
+        _ => return None,
+    };
+    let rs1 = ((insn >> 15) & 0x1f) as usize;
+    let addr = registers[rs1].wrapping_add(imm as u32);
+    if addr < TEXT_START || addr as usize >= SYSTEM.start() {
+        Some(FaultKind::InvalidMemoryAccess { addr })
+    } else {
+        None
+    }
+}
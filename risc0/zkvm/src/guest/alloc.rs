@@ -12,23 +12,110 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! The guest allocator.
+//!
+//! By default, the guest uses a bump allocator, which is the fastest option
+//! but never reuses freed memory. With the `heap-dlmalloc` feature, the guest
+//! uses dlmalloc instead, which reuses freed memory at the cost of more cycles
+//! per allocation. With the `heap-custom` feature, no allocator is installed,
+//! and the guest must provide its own `#[global_allocator]`, such as an arena.
+
 #![cfg(target_os = "zkvm")]
 
-use core::alloc::{GlobalAlloc, Layout};
+#[cfg(all(feature = "heap-dlmalloc", feature = "heap-custom"))]
+compile_error!("features `heap-dlmalloc` and `heap-custom` are mutually exclusive");
 
-use risc0_zkvm_platform::syscall;
+#[cfg(not(any(feature = "heap-dlmalloc", feature = "heap-custom")))]
+mod bump {
+    use core::alloc::{GlobalAlloc, Layout};
 
-struct BumpPointerAlloc;
+    use risc0_zkvm_platform::syscall;
 
-unsafe impl GlobalAlloc for BumpPointerAlloc {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        syscall::sys_alloc_aligned(layout.size(), layout.align())
-    }
+    struct BumpPointerAlloc;
+
+    unsafe impl GlobalAlloc for BumpPointerAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            syscall::sys_alloc_aligned(layout.size(), layout.align())
+        }
 
-    unsafe fn dealloc(&self, _: *mut u8, _: Layout) {
-        // this allocator never deallocates memory
+        unsafe fn dealloc(&self, _: *mut u8, _: Layout) {
+            // this allocator never deallocates memory
+        }
     }
+
+    #[global_allocator]
+    static HEAP: BumpPointerAlloc = BumpPointerAlloc;
 }
 
-#[global_allocator]
-static HEAP: BumpPointerAlloc = BumpPointerAlloc;
+#[cfg(feature = "heap-dlmalloc")]
+mod dlmalloc_heap {
+    use core::{
+        alloc::{GlobalAlloc, Layout},
+        cell::UnsafeCell,
+        ptr::null_mut,
+    };
+
+    use risc0_zkvm_platform::{syscall, PAGE_SIZE};
+
+    // Provides dlmalloc with memory from the bump allocator, which is never
+    // returned.
+    struct System;
+
+    unsafe impl dlmalloc::Allocator for System {
+        fn alloc(&self, size: usize) -> (*mut u8, usize, u32) {
+            (syscall::sys_alloc_aligned(size, PAGE_SIZE), size, 0)
+        }
+
+        fn remap(&self, _: *mut u8, _: usize, _: usize, _: bool) -> *mut u8 {
+            null_mut()
+        }
+
+        fn free_part(&self, _: *mut u8, _: usize, _: usize) -> bool {
+            false
+        }
+
+        fn free(&self, _: *mut u8, _: usize) -> bool {
+            false
+        }
+
+        fn can_release_part(&self, _: u32) -> bool {
+            false
+        }
+
+        fn allocates_zeros(&self) -> bool {
+            true
+        }
+
+        fn page_size(&self) -> usize {
+            PAGE_SIZE
+        }
+    }
+
+    struct DlmallocAlloc(UnsafeCell<dlmalloc::Dlmalloc<System>>);
+
+    // SAFETY: The guest is single threaded.
+    unsafe impl Sync for DlmallocAlloc {}
+
+    unsafe impl GlobalAlloc for DlmallocAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            (*self.0.get()).malloc(layout.size(), layout.align())
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            (*self.0.get()).calloc(layout.size(), layout.align())
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            (*self.0.get()).free(ptr, layout.size(), layout.align())
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            (*self.0.get()).realloc(ptr, layout.size(), layout.align(), new_size)
+        }
+    }
+
+    #[global_allocator]
+    static HEAP: DlmallocAlloc = DlmallocAlloc(UnsafeCell::new(
+        dlmalloc::Dlmalloc::new_with_allocator(System),
+    ));
+}
@@ -14,12 +14,20 @@
 
 //! Functions for interacting with the host environment.
 
+use alloc::vec::Vec;
+
 use bytemuck::Pod;
 use risc0_zkvm_platform::{
-    fileno, syscall,
+    fileno,
+    memory::{
+        READONLY_MAX_REGIONS, READONLY_TABLE, READONLY_TABLE_MAGIC, RESERVED_STACK, STACK_TOP,
+        TEXT_START,
+    },
+    syscall,
     syscall::{
-        sys_alloc_words, sys_cycle_count, sys_halt, sys_log, sys_pause, sys_read, sys_read_words,
-        sys_write, syscall_2, SyscallName,
+        nr::{SYS_READ_FRAME, SYS_YIELD_OUTPUT},
+        sys_alloc_words, sys_cycle_count, sys_halt, sys_heap_free, sys_log, sys_pause, sys_read,
+        sys_read_words, sys_write, syscall_2, SyscallName,
     },
     WORD_SIZE,
 };
@@ -27,21 +35,51 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     align_up,
-    serde::{Deserializer, Result as SerdeResult, Serializer, WordRead, WordWrite},
-    sha::rust_crypto::{Digest as _, Sha256},
+    output::{extend_journal_chain, journal_chain_init, Output},
+    serde::{
+        to_vec_typed, Deserializer, JournalCommit, Result as SerdeResult, Serializer, WordRead,
+        WordWrite,
+    },
+    sha::{
+        rust_crypto::{Digest as _, Sha256},
+        Digest,
+    },
 };
 
 static mut HASHER: Option<Sha256> = None;
 
+static mut LOGICAL_TIME: u64 = 0;
+
+static mut COMMIT_LOGICAL_TIME: bool = false;
+
+static mut METADATA_DIGEST: Option<Digest> = None;
+
+static mut JOURNAL_CHAIN: Option<Digest> = None;
+
+static mut JOURNAL_STARTED: bool = false;
+
 pub(crate) fn init() {
     unsafe { HASHER = Some(Sha256::new()) };
 }
 
 pub(crate) fn finalize(halt: bool, user_exit: u8) {
+    if halt && unsafe { COMMIT_LOGICAL_TIME } {
+        commit(&logical_time());
+    }
     unsafe {
         let hasher = core::mem::take(&mut HASHER);
-        let output = hasher.unwrap_unchecked().finalize();
-        let words: &[u32; 8] = bytemuck::cast_slice(output.as_slice()).try_into().unwrap();
+        let journal_chain = JOURNAL_CHAIN.take();
+        let journal = match journal_chain {
+            Some(head) => head,
+            None => Digest::try_from(hasher.unwrap_unchecked().finalize().as_slice()).unwrap(),
+        };
+        let output = Output {
+            journal,
+            journal_chain: journal_chain.is_some(),
+            metadata_digest: METADATA_DIGEST.take(),
+        }
+        .digest();
+        let words: &[u32; 8] = output.as_words().try_into().unwrap();
 
         if halt {
             sys_halt(user_exit, words)
@@ -64,6 +102,23 @@ pub fn syscall(syscall: SyscallName, to_host: &[u8], from_host: &mut [u32]) -> s
     }
 }
 
+/// Invoke a custom syscall registered by the host with
+/// `ExecutorEnvBuilder::register_syscall`.
+///
+/// The arguments are as for [syscall], with the registered name in place of
+/// the [SyscallName].
+///
+/// ```ignore
+/// let mut from_host = [0u32; 4];
+/// risc0_zkvm::ext_syscall!("my_service", b"request", &mut from_host);
+/// ```
+#[macro_export]
+macro_rules! ext_syscall {
+    ($name:literal, $to_host:expr, $from_host:expr) => {
+        $crate::guest::env::syscall($crate::ext_syscall_name!($name), $to_host, $from_host)
+    };
+}
+
 /// Exhanges slices of plain old data with the host.
 ///
 /// This makes two calls to the given syscall; the first gets the length of the
@@ -89,6 +144,55 @@ pub fn read_slice<T: Pod>(slice: &mut [T]) {
     stdin().read_slice(slice)
 }
 
+/// Read a value from the input port on the given file descriptor, declared
+/// with [io_port!](crate::io_port).
+pub fn read_port<T: DeserializeOwned>(fd: u32) -> T {
+    FdReader::new(fd).read()
+}
+
+/// Write a value to the output port on the given file descriptor, declared
+/// with [io_port!](crate::io_port).
+pub fn write_port<T: Serialize>(fd: u32, value: &T) {
+    FdWriter::new(fd, |_| {}).write(value)
+}
+
+/// Read the next input frame written by the host.
+///
+/// Frames are written on the host with `ExecutorEnvBuilder::write_frame`,
+/// and are read in the order they were written. Unlike data read from
+/// [stdin], each frame is delivered as a whole, so a guest that misreads one
+/// frame does not misread the ones that follow.
+///
+/// # Panics
+///
+/// Panics if all frames have been read, or if the host sent a malformed
+/// frame.
+pub fn read_frame() -> Vec<u8> {
+    try_read_frame()
+        .expect("malformed input frame")
+        .expect("no more input frames")
+}
+
+/// Read the next input frame written by the host, or `None` if all frames
+/// have been read.
+///
+/// Returns an error if the host sent a frame whose length is larger than the
+/// data it sent. See [read_frame].
+pub fn try_read_frame() -> SerdeResult<Option<Vec<u8>>> {
+    let words: &[u32] = send_recv_slice::<u32, u32>(SYS_READ_FRAME, &[]);
+    let (len, data) = words
+        .split_first()
+        .ok_or(crate::serde::Error::DeserializeUnexpectedEnd)?;
+    if *len == u32::MAX {
+        return Ok(None);
+    }
+    let bytes: &[u8] = bytemuck::cast_slice(data);
+    let frame = bytes
+        .get(..*len as usize)
+        .ok_or(crate::serde::Error::DeserializeUnexpectedEnd)?;
+    Ok(Some(frame.to_vec()))
+}
+
 /// Serialize the given data and write it to the STDOUT of the zkVM.
 ///
 /// This is available to the host as the private output on the prover.
@@ -107,11 +211,26 @@ pub fn write_slice<T: Pod>(slice: &[T]) {
     stdout().write_slice(slice);
 }
 
+/// Send the given bytes to the host immediately, while the guest keeps
+/// running.
+///
+/// The host receives each output through the callbacks registered with
+/// `ExecutorEnvBuilder::on_output`, in the order they were yielded, which
+/// allows downstream work to start before execution completes. Outputs are
+/// not part of the receipt; [commit] any data the verifier needs.
+pub fn yield_output(bytes: &[u8]) {
+    syscall(SYS_YIELD_OUTPUT, bytes, &mut []);
+}
+
 /// Serialize the given data and commit it to the journal.
 ///
 /// Data in the journal is included in the receipt and is available to the
 /// verifier. It is considered "public" data.
 pub fn commit<T: Serialize>(data: &T) {
+    if unsafe { JOURNAL_CHAIN.is_some() } {
+        commit_frame(bytemuck::cast_slice(&crate::serde::to_vec(data).unwrap()));
+        return;
+    }
     journal().write(data)
 }
 
@@ -120,15 +239,162 @@ pub fn commit<T: Serialize>(data: &T) {
 /// Data in the journal is included in the receipt and is available to the
 /// verifier. It is considered "public" data.
 pub fn commit_slice<T: Pod>(slice: &[T]) {
+    if unsafe { JOURNAL_CHAIN.is_some() } {
+        commit_frame(bytemuck::cast_slice(slice));
+        return;
+    }
     journal().write_slice(slice);
 }
 
+/// Switch the journal to chain mode, in which each commit extends a hash chain
+/// whose head is committed to the receipt in place of the journal digest.
+///
+/// Each commit is written to the journal as a length-prefixed frame, so the
+/// host can verify a large journal commit by commit as it arrives, with
+/// `JournalStreamVerifier`, without holding it in memory. Receipts of guests in
+/// chain mode also verify as usual with `Receipt::verify`.
+///
+/// In chain mode, write to the journal only with [commit], [commit_slice] and
+/// [commit_typed], rather than with [journal].
+///
+/// # Panics
+///
+/// Panics if anything has already been written to the journal.
+pub fn enable_journal_chain() {
+    unsafe {
+        if JOURNAL_STARTED || JOURNAL_CHAIN.is_some() {
+            panic!("journal chain must be enabled before the first commit");
+        }
+        JOURNAL_CHAIN = Some(journal_chain_init());
+    }
+}
+
+// Write a commit to the journal as a frame and extend the chain with it.
+fn commit_frame(bytes: &[u8]) {
+    let mut journal = journal();
+    journal.write_bytes(&(bytes.len() as u32).to_le_bytes());
+    journal.write_bytes(bytes);
+    unsafe {
+        let head = JOURNAL_CHAIN.as_ref().unwrap_unchecked();
+        JOURNAL_CHAIN = Some(extend_journal_chain(head, bytes));
+    }
+}
+
+/// Commit the given value to the journal with a versioned, length-prefixed
+/// encoding, to be decoded on the host with
+/// [Journal::decode](crate::serde::Journal::decode).
+///
+/// See [JournalCommit].
+pub fn commit_typed<T: JournalCommit>(data: &T) {
+    commit_slice(&to_vec_typed(data).unwrap());
+}
+
+/// Commit the given digest to the receipt claim alongside the journal.
+///
+/// This binds auxiliary data, such as a block hash or configuration hash, into
+/// the proof without adding it to the journal. The digest is not carried by
+/// the receipt: the verifier supplies the digest it expects with
+/// `Receipt::verify_with_metadata_digest`. The metadata digest may only be
+/// committed once.
+pub fn commit_metadata_digest(digest: &Digest) {
+    unsafe {
+        if METADATA_DIGEST.is_some() {
+            panic!("metadata digest already committed");
+        }
+        METADATA_DIGEST = Some(*digest);
+        sys_write(
+            fileno::METADATA_DIGEST,
+            digest.as_bytes().as_ptr(),
+            digest.as_bytes().len(),
+        );
+    }
+}
+
 /// Return the number of processor cycles that have occured since the guest
 /// began.
 pub fn get_cycle_count() -> usize {
     sys_cycle_count()
 }
 
+/// Return the number of bytes of guest memory that have not yet been handed
+/// out to the allocator.
+///
+/// The allocator obtains memory from a heap that grows towards the stack, so
+/// this is the memory left before the guest runs out. With the default bump
+/// allocator freed memory is never reused, so this only decreases. With the
+/// `heap-dlmalloc` feature, memory freed back to dlmalloc is reused before the
+/// heap grows, and is not included in this count.
+pub fn free_memory() -> usize {
+    sys_heap_free()
+}
+
+/// Return the read-only region with the given index, in the order the host
+/// mapped them with `ExecutorEnvBuilder::map_readonly`, or `None` if the host
+/// mapped fewer regions.
+///
+/// The regions and the table locating them are part of the initial memory
+/// image, so their contents are committed to by the image ID rather than
+/// provided by the host at runtime. Reading a region only pages in the pages
+/// that are read.
+///
+/// # Panics
+///
+/// Panics if the table holds more regions than fit in it, or a region that
+/// does not lie between the program and the memory reserved for the stack.
+pub fn readonly_region(idx: usize) -> Option<&'static [u8]> {
+    let table = READONLY_TABLE as usize as *const u32;
+    // SAFETY: the table is in the page above the stack, which neither the heap
+    // nor the stack use, and has room for READONLY_MAX_REGIONS regions.
+    let word = |offset: usize| unsafe { table.add(offset).read_volatile() };
+
+    // Unless the host mapped regions, the table holds zeros.
+    if word(0) != READONLY_TABLE_MAGIC {
+        return None;
+    }
+    let count = word(1) as usize;
+    assert!(
+        count <= READONLY_MAX_REGIONS,
+        "Too many read-only regions: {count}"
+    );
+    if idx >= count {
+        return None;
+    }
+    let addr = word(2 + 2 * idx);
+    let len = word(3 + 2 * idx);
+    let end = addr.checked_add(len);
+    assert!(
+        addr >= TEXT_START && end.map_or(false, |end| end <= STACK_TOP - RESERVED_STACK),
+        "Invalid read-only region at 0x{addr:08x} of {len} bytes"
+    );
+    // SAFETY: the region lies in guest memory, between the program and the
+    // stack, the heap stops below it, and the host faults the guest on any
+    // write to it, so it is never mutated.
+    Some(unsafe { core::slice::from_raw_parts(addr as usize as *const u8, len as usize) })
+}
+
+/// Return the current logical time of the guest.
+///
+/// The logical time is derived from the cycle count, and is guaranteed by the
+/// guest to strictly increase with each call, including across
+/// [pause]s. This gives guests a deterministic notion of time for timeouts
+/// and ordering. Note that the cycle count is reported by the host, so a
+/// malicious host may make logical time advance faster than cycles, but never
+/// go backwards.
+pub fn logical_time() -> u64 {
+    unsafe {
+        LOGICAL_TIME = (sys_cycle_count() as u64).max(LOGICAL_TIME + 1);
+        LOGICAL_TIME
+    }
+}
+
+/// Commit the final [logical_time] to the journal when the guest halts.
+///
+/// The value is committed as a `u64` after all other data written to the
+/// journal, so that it is included in the receipt.
+pub fn commit_logical_time_at_exit() {
+    unsafe { COMMIT_LOGICAL_TIME = true };
+}
+
 /// Print a message to the debug console.
 pub fn log(msg: &str) {
     let msg = msg.as_bytes();
@@ -150,7 +416,10 @@ pub fn stderr() -> FdWriter<impl for<'a> Fn(&'a [u8])> {
 /// Return a writer for the JOURNAL.
 pub fn journal() -> FdWriter<impl for<'a> Fn(&'a [u8])> {
     FdWriter::new(fileno::JOURNAL, |bytes| {
-        unsafe { HASHER.as_mut().unwrap_unchecked().update(bytes) };
+        unsafe {
+            JOURNAL_STARTED = true;
+            HASHER.as_mut().unwrap_unchecked().update(bytes)
+        };
     })
 }
 
@@ -159,6 +428,26 @@ pub fn stdin() -> FdReader {
     FdReader::new(fileno::STDIN)
 }
 
+/// Commit `journal` and halt the zkvm with an application-defined `status`.
+///
+/// The status is part of the claim proven by the receipt, as the
+/// [ExitCode::Halted](crate::ExitCode::Halted) code, so a verifier can tell
+/// which outcome was proven with `Receipt::verify_exit_code` without parsing
+/// the journal.
+///
+/// Returning from `main` halts with a status of 0, and the two are proven as
+/// the same claim: a receipt of `exit(0, ..)` is indistinguishable from one
+/// of a guest that returned normally after committing the same journal. Use
+/// a status of 0 only for the outcome a normal return stands for, usually
+/// success, and nonzero statuses for every outcome a verifier must tell apart
+/// from it. A distinct [ExitCode](crate::ExitCode) for `exit` is not possible,
+/// since the exit code in the claim is limited to what the circuit proves.
+pub fn exit<T: Serialize>(status: u8, journal: &T) -> ! {
+    commit(journal);
+    finalize(true, status);
+    unreachable!();
+}
+
 /// Pause the execution of the zkvm.
 ///
 /// Execution may be continued at a later time.
@@ -203,7 +492,7 @@ impl FdReader {
 
     // Like read_bytes, but fills the buffer completely or until EOF occurs.
     #[must_use = "read_bytes_all can potentially return EOF; this case should be handled."]
-    fn read_bytes_all(&mut self, mut buf: &mut [u8]) -> usize {
+    pub(crate) fn read_bytes_all(&mut self, mut buf: &mut [u8]) -> usize {
         let mut tot_read = 0;
         while !buf.is_empty() {
             let nread = self.read_bytes(buf);
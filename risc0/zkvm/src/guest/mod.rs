@@ -58,6 +58,8 @@
 
 mod alloc;
 pub mod env;
+pub mod oracle;
+mod rng;
 pub mod sha;
 
 #[cfg(target_os = "zkvm")]
@@ -65,11 +67,16 @@ use core::arch::asm;
 
 #[cfg(target_os = "zkvm")]
 use getrandom::{register_custom_getrandom, Error};
-use risc0_zkvm_platform::syscall::sys_panic;
+use risc0_zkvm_platform::syscall::{sys_panic, sys_set_layout};
 #[cfg(target_os = "zkvm")]
 use risc0_zkvm_platform::{syscall::sys_rand, WORD_SIZE};
 
+pub use self::rng::{commit_rng_seed, rng, GuestRng};
 pub use crate::entry;
+use crate::{
+    sha::{Block, BLOCK_BYTES},
+    storage::{StreamDigest, STREAM_CHUNK_BYTES},
+};
 
 /// This is a getrandom handler for the zkvm. It's intended to hook into a
 /// getrandom crate or a depdent of the getrandom crate used by the guest code.
@@ -108,6 +115,31 @@ pub fn abort(msg: &str) -> ! {
     }
 }
 
+/// Hash the stream provided by the host on the given file descriptor, and
+/// commit its [StreamDigest] to the journal.
+///
+/// The stream is read in chunks of [STREAM_CHUNK_BYTES] and hashed with the
+/// SHA accelerator as it arrives, so it need not fit in guest memory. See
+/// [storage](crate::storage).
+pub fn hash_stream(fd: u32) -> StreamDigest {
+    let mut buf =
+        ::alloc::vec![<Block as bytemuck::Zeroable>::zeroed(); STREAM_CHUNK_BYTES / BLOCK_BYTES];
+    let mut reader = env::FdReader::new(fd);
+    let mut hasher = sha::StreamHasher::new();
+    loop {
+        let nread = reader.read_bytes_all(bytemuck::cast_slice_mut(&mut buf));
+        let nblocks = nread / BLOCK_BYTES;
+        hasher.update_blocks(&buf[..nblocks]);
+        if nread < STREAM_CHUNK_BYTES {
+            let bytes: &[u8] = bytemuck::cast_slice(&buf);
+            let (digest, len) = hasher.finalize(&bytes[nblocks * BLOCK_BYTES..nread]);
+            let stream = StreamDigest { digest, len };
+            env::commit(&stream);
+            return stream;
+        }
+    }
+}
+
 #[cfg(all(not(feature = "std"), target_os = "zkvm"))]
 mod handlers {
     use core::panic::PanicInfo;
@@ -119,8 +151,44 @@ mod handlers {
     }
 }
 
+/// The memory layout of a guest, as configured with [entry!].
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    /// The size of the stack in bytes.
+    ///
+    /// The heap may grow until the bottom of the stack, and the host faults
+    /// the guest if the stack grows beyond it. If zero, the heap may grow until
+    /// a fixed reserve below the stack pointer, and stack overflows are not
+    /// detected. Either way, the heap stops below any read-only region mapped
+    /// by the host, and the host rejects a stack that overlaps one.
+    pub stack_size: usize,
+
+    /// The address at which the heap starts.
+    ///
+    /// If zero, the heap starts right after the program.
+    pub heap_start: usize,
+}
+
+impl Layout {
+    /// The default layout.
+    pub const DEFAULT: Self = Self {
+        stack_size: 0,
+        heap_start: 0,
+    };
+
+    #[doc(hidden)]
+    pub fn apply(&self) {
+        // SAFETY: This is called by the generated main before the guest
+        // allocates.
+        unsafe { sys_set_layout(self.heap_start, self.stack_size) }
+    }
+}
+
 /// Used for defining a main entrypoint.
 ///
+/// The memory [Layout] of the guest can be configured by passing its fields
+/// after the entrypoint.
+///
 /// # Example
 ///
 /// ```ignore
@@ -128,6 +196,12 @@ mod handlers {
 ///
 /// fn main() { }
 /// ```
+///
+/// ```ignore
+/// risc0_zkvm::entry!(main, stack_size = 64 * 1024);
+///
+/// fn main() { }
+/// ```
 #[macro_export]
 macro_rules! entry {
     ($path:path) => {
@@ -143,6 +217,25 @@ macro_rules! entry {
             }
         }
     };
+    ($path:path, $($field:ident = $value:expr),+ $(,)?) => {
+        // Type check the given path
+        const ZKVM_ENTRY: fn() = $path;
+        #[allow(clippy::needless_update)]
+        const ZKVM_LAYOUT: $crate::guest::Layout = $crate::guest::Layout {
+            $($field: $value,)+
+            ..$crate::guest::Layout::DEFAULT
+        };
+
+        // Include generated main in a module so we don't conflict
+        // with any other definitions of "main" in this file.
+        mod zkvm_generated_main {
+            #[no_mangle]
+            fn main() {
+                super::ZKVM_LAYOUT.apply();
+                super::ZKVM_ENTRY()
+            }
+        }
+    };
 }
 
 #[cfg(target_os = "zkvm")]
@@ -0,0 +1,73 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest access to the authenticated key-value oracle.
+//!
+//! See [crate::oracle] for how the host provides the store.
+
+use alloc::vec::Vec;
+
+use crate::{
+    guest::env,
+    oracle::{MerkleProof, OracleError, OracleRequest, SYS_KV_ORACLE},
+    serde::{from_slice, to_vec},
+    sha::Digest,
+};
+
+static mut ROOT: Option<Digest> = None;
+
+fn request(req: &OracleRequest) -> &'static [u32] {
+    env::send_recv_slice(SYS_KV_ORACLE, &to_vec(req).unwrap())
+}
+
+/// Fetch the Merkle root of the key-value oracle from the host and commit it
+/// to the journal, so that verifiers can check which store the guest used.
+///
+/// Call this once, before [get], at the point in the journal where the root
+/// should appear. Later calls return the same root without committing it
+/// again.
+pub fn init() -> Digest {
+    unsafe {
+        if let Some(root) = ROOT {
+            return root;
+        }
+        let root: Digest = from_slice(request(&OracleRequest::Root)).unwrap();
+        env::commit(&root);
+        ROOT = Some(root);
+        root
+    }
+}
+
+/// Return the Merkle root committed by [init], if any.
+pub fn root() -> Option<Digest> {
+    unsafe { ROOT }
+}
+
+/// Look up the value of `key` in the key-value oracle.
+///
+/// The value is verified against the [root] of the oracle before it is
+/// returned. Returns [OracleError::Uninitialized] if [init] has not been
+/// called, [OracleError::NotFound] if the host does not provide a value for
+/// `key`, and [OracleError::InvalidProof] if the provided value does not match
+/// the root. Absence of a key cannot be proven.
+pub fn get(key: &[u8]) -> Result<Vec<u8>, OracleError> {
+    let root = root().ok_or(OracleError::Uninitialized)?;
+    let response: Option<(Vec<u8>, MerkleProof)> =
+        from_slice(request(&OracleRequest::Get(key.to_vec()))).unwrap();
+    let (value, proof) = response.ok_or(OracleError::NotFound)?;
+    if !proof.verify(&root, key, &value) {
+        return Err(OracleError::InvalidProof);
+    }
+    Ok(value)
+}
@@ -0,0 +1,140 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A cryptographic RNG seeded from committed host entropy.
+
+use rand_core::{impls, CryptoRng, Error, RngCore};
+
+use crate::{
+    guest::env,
+    sha::{Digest, Impl, Sha256},
+};
+
+static mut SEED: Option<Digest> = None;
+
+static mut NEXT_STREAM: u32 = 0;
+
+/// Read a 32-byte seed for [rng] from the host and commit it to the journal,
+/// returning the seed.
+///
+/// **The seed is written to the journal at the point of this call**, as 8
+/// words with [env::commit_slice], so the guest chooses where it appears in
+/// the journal, and a verifier reads it from there. Anyone holding the receipt
+/// can then audit every value the guest drew, by reproducing them with
+/// [GuestRng::new]. Since the seed is public, values drawn from the RNG must
+/// not be used as secrets outside of the guest.
+///
+/// # Panics
+///
+/// Panics if the seed was already committed.
+pub fn commit_rng_seed() -> Digest {
+    unsafe {
+        if SEED.is_some() {
+            panic!("the RNG seed was already committed");
+        }
+        let mut seed = Digest::default();
+        getrandom::getrandom(seed.as_mut_bytes()).unwrap();
+        env::commit_slice(seed.as_words());
+        SEED = Some(seed);
+        seed
+    }
+}
+
+/// Return a cryptographic RNG seeded from host entropy, for use with crates
+/// that take an `Rng` parameter, such as for key generation or sampling.
+///
+/// The seed must first be committed to the journal with [commit_rng_seed],
+/// which makes the values drawn auditable. Each call returns an independent
+/// stream derived from the seed, numbered from 0 in the order of the calls.
+///
+/// # Panics
+///
+/// Panics if [commit_rng_seed] was not called first.
+pub fn rng() -> GuestRng {
+    unsafe {
+        let Some(seed) = SEED.as_ref() else {
+            panic!("commit_rng_seed must be called before rng");
+        };
+        let stream = NEXT_STREAM;
+        NEXT_STREAM += 1;
+        GuestRng::new(seed, stream)
+    }
+}
+
+/// A deterministic RNG producing the SHA-256 digests of a stream key and
+/// an incrementing counter, returned by [rng].
+pub struct GuestRng {
+    key: Digest,
+    counter: u32,
+    block: Digest,
+    pos: usize,
+}
+
+impl GuestRng {
+    /// Construct the RNG of the given stream of a seed.
+    ///
+    /// This reproduces the values drawn in the guest from the seed committed
+    /// by [commit_rng_seed].
+    pub fn new(seed: &Digest, stream: u32) -> Self {
+        let mut data = [0u8; 36];
+        data[..32].copy_from_slice(seed.as_bytes());
+        data[32..].copy_from_slice(&stream.to_le_bytes());
+        Self {
+            key: *Impl::hash_bytes(&data),
+            counter: 0,
+            block: Digest::default(),
+            pos: core::mem::size_of::<Digest>(),
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut data = [0u8; 36];
+        data[..32].copy_from_slice(self.key.as_bytes());
+        data[32..].copy_from_slice(&self.counter.to_le_bytes());
+        self.block = *Impl::hash_bytes(&data);
+        self.counter = self.counter.checked_add(1).expect("GuestRng exhausted");
+        self.pos = 0;
+    }
+}
+
+impl RngCore for GuestRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.pos == self.block.as_bytes().len() {
+                self.next_block();
+            }
+            let bytes = &self.block.as_bytes()[self.pos..];
+            let len = bytes.len().min(dest.len() - filled);
+            dest[filled..filled + len].copy_from_slice(&bytes[..len]);
+            filled += len;
+            self.pos += len;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for GuestRng {}
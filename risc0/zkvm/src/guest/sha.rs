@@ -14,11 +14,11 @@
 
 //! Functions for computing SHA-256 hashes.
 
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 
 use risc0_zkp::core::{
     digest::Digest,
-    hash::sha::{Block, BLOCK_WORDS, SHA256_INIT},
+    hash::sha::{Block, BLOCK_BYTES, BLOCK_WORDS, SHA256_INIT},
 };
 use risc0_zkvm_platform::{
     syscall::{sys_sha_buffer, sys_sha_compress},
@@ -97,20 +97,17 @@ const fn compute_u32s_needed(len_bytes: usize, trailer: Trailer) -> usize {
     }
 }
 
-// Slow path for when we need padding and/or alignment.
-fn copy_and_update(
-    out_state: *mut Digest,
-    mut in_state: *const Digest,
-    bytes: &[u8],
-    trailer: Trailer,
-) {
+// Append the given bytes to `padbuf` as whole blocks, padded according to
+// `trailer`.
+fn pad_into(padbuf: &mut Vec<u32>, bytes: &[u8], trailer: Trailer) {
+    let start = padbuf.len();
     let padlen = compute_u32s_needed(bytes.len(), trailer);
-    let mut padbuf: Vec<u32> = Vec::with_capacity(padlen);
+    padbuf.reserve(padlen);
     assert!(bytes.len() <= padlen * WORD_SIZE);
     // SAFETY: u32 is more stringently aligned than bytes, and we've
-    // already checked to make sure the buffer is big enough.
+    // already reserved enough space after the existing contents.
     unsafe {
-        let padbuf_u8: *mut u8 = padbuf.as_mut_ptr().cast();
+        let padbuf_u8: *mut u8 = padbuf.as_mut_ptr().add(start).cast();
         padbuf_u8.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
 
         match trailer {
@@ -126,13 +123,25 @@ fn copy_and_update(
                     .write_bytes(0, padlen * WORD_SIZE - bytes.len());
             }
         }
-        padbuf.set_len(padlen);
+        padbuf.set_len(start + padlen);
     }
 
     if let WithTrailer { total_bits } = trailer {
-        assert_eq!(padbuf[padlen - 1], 0);
-        padbuf[padlen - 1] = total_bits.to_be();
+        let last = start + padlen - 1;
+        assert_eq!(padbuf[last], 0);
+        padbuf[last] = total_bits.to_be();
     }
+}
+
+// Slow path for when we need padding and/or alignment.
+fn copy_and_update(
+    out_state: *mut Digest,
+    mut in_state: *const Digest,
+    bytes: &[u8],
+    trailer: Trailer,
+) {
+    let mut padbuf = Vec::new();
+    pad_into(&mut padbuf, bytes, trailer);
 
     match bytemuck::pod_align_to::<u32, Block>(padbuf.as_slice()) {
         (&[], blocks @ &[..], &[]) => {
@@ -220,6 +229,88 @@ fn update_u8(out_state: *mut Digest, mut in_state: *const Digest, bytes: &[u8],
     }
 }
 
+/// An incremental SHA-256 hasher over whole blocks, for messages too large to
+/// hold in guest memory at once.
+pub(crate) struct StreamHasher {
+    state: Digest,
+    len: u64,
+}
+
+impl StreamHasher {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: SHA256_INIT,
+            len: 0,
+        }
+    }
+
+    /// Absorb the given blocks.
+    pub(crate) fn update_blocks(&mut self, blocks: &[Block]) {
+        if blocks.is_empty() {
+            return;
+        }
+        let state: *mut Digest = &mut self.state;
+        compress_slice(state, state, blocks);
+        self.len += (blocks.len() * BLOCK_BYTES) as u64;
+    }
+
+    /// Absorb the final partial block and return the digest and total length
+    /// of the message.
+    ///
+    /// Unlike [Trailer], the length is encoded in 64 bits, so messages may
+    /// exceed 4 GiB.
+    pub(crate) fn finalize(mut self, tail: &[u8]) -> (Digest, u64) {
+        assert!(tail.len() < BLOCK_BYTES);
+        let len = self.len + tail.len() as u64;
+        let mut blocks = [<Block as bytemuck::Zeroable>::zeroed(); 2];
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut blocks);
+        bytes[..tail.len()].copy_from_slice(tail);
+        bytes[tail.len()] = END_MARKER;
+        // The end marker and the 8 byte length must fit after the tail.
+        let end = if tail.len() + 9 <= BLOCK_BYTES {
+            BLOCK_BYTES
+        } else {
+            2 * BLOCK_BYTES
+        };
+        bytes[end - 8..end].copy_from_slice(&(len * 8).to_be_bytes());
+        let state: *mut Digest = &mut self.state;
+        compress_slice(state, state, &blocks[..end / BLOCK_BYTES]);
+        (self.state, len)
+    }
+}
+
+/// Compute the SHA-256 digests of many independent messages.
+///
+/// All messages are padded into a single buffer up front. This saves the
+/// allocations and bookkeeping of hashing each message on its own, which
+/// dominate the cost of hashing many small messages, such as the leaves of a
+/// Merkle tree. The SHA accelerator compresses one chain of blocks per ecall,
+/// so each message is still compressed with an ecall of its own.
+pub fn hash_batch(msgs: &[&[u8]]) -> Vec<Digest> {
+    let mut padbuf = Vec::new();
+    let mut counts = Vec::with_capacity(msgs.len());
+    for msg in msgs {
+        let start = padbuf.len();
+        let total_bits = msg.len() as u32 * 8;
+        pad_into(&mut padbuf, msg, WithTrailer { total_bits });
+        counts.push((start, (padbuf.len() - start) / BLOCK_WORDS));
+    }
+    let mut digests = vec![Digest::default(); msgs.len()];
+    for (&(start, count), digest) in counts.iter().zip(digests.iter_mut()) {
+        // SAFETY: The job points at whole padded blocks of `padbuf` and a
+        // distinct output digest.
+        unsafe {
+            sys_sha_buffer(
+                (digest as *mut Digest).cast(),
+                (&SHA256_INIT as *const Digest).cast(),
+                padbuf[start..].as_ptr().cast(),
+                count as u32,
+            )
+        };
+    }
+    digests
+}
+
 /// A guest-side [Sha256] implementation.
 ///
 /// [Sha256]: risc0_zkp::core::hash::sha::Sha256
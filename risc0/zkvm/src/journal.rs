@@ -0,0 +1,340 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proofs about the contents of a journal.
+//!
+//! A receipt commits to its journal by the SHA-256 digest of the journal. A
+//! [JournalSliceProof], created with [Receipt::prove_journal_slice], shows
+//! that a slice of bytes appears at a given offset of the journal with a given
+//! digest, without including the bytes of the journal before the slice. It is
+//! verified against the [Output] returned by [Receipt::verify_and_get_output].
+//!
+//! The proof records the SHA-256 state after hashing the whole blocks that
+//! precede the slice, so the verifier only hashes from the start of the block
+//! containing the slice to the end of the journal. SHA-256 cannot skip the
+//! bytes after the slice, so the proof carries all of them: a proof is as
+//! large as the journal from the block containing the slice onwards, and
+//! hides nothing after the slice. Proofs are therefore smallest for slices
+//! near the end of the journal.
+//!
+//! A guest that enables chain mode with
+//! [env::enable_journal_chain](crate::guest::env::enable_journal_chain)
+//! instead commits to the head of a hash chain over its commits. The host can
+//! then check the seal first and verify a large journal commit by commit as it
+//! arrives, with a [JournalStreamVerifier], without holding the whole journal
+//! in memory. Slice proofs do not apply to such journals.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use anyhow::{bail, Result};
+use risc0_zkp::{
+    core::hash::sha::{Block, BLOCK_BYTES, SHA256_INIT},
+    verify::VerificationError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    output::{extend_journal_chain, journal_chain_init, Output, JOURNAL_FRAME_HEADER_BYTES},
+    receipt::{InnerReceipt, Receipt, VerifierContext},
+    sha::{Digest, Impl, Sha256},
+};
+
+/// A proof that a slice of bytes appears at a given offset of a journal.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct JournalSliceProof {
+    /// The offset of the slice in the journal.
+    pub offset: u32,
+
+    /// The SHA-256 state after hashing the whole blocks of the journal that
+    /// precede the slice.
+    pub midstate: Digest,
+
+    /// The bytes of the journal between the last block hashed into the
+    /// midstate and the slice.
+    pub head: Vec<u8>,
+
+    /// The proven slice of the journal.
+    pub slice: Vec<u8>,
+
+    /// The bytes of the journal after the slice, up to the end of the
+    /// journal.
+    ///
+    /// These are needed to finish the SHA-256 digest of the journal, and are
+    /// not hidden by the proof.
+    pub tail: Vec<u8>,
+}
+
+impl Receipt {
+    /// Produce a [JournalSliceProof] that `journal[range]` appears in the
+    /// journal of this receipt.
+    pub fn prove_journal_slice(&self, range: Range<usize>) -> Result<JournalSliceProof> {
+        if range.start > range.end || range.end > self.journal.len() {
+            bail!(
+                "Range {range:?} is out of bounds for a journal of {} bytes",
+                self.journal.len()
+            );
+        }
+        let prefix_len = range.start - range.start % BLOCK_BYTES;
+        Ok(JournalSliceProof {
+            offset: range.start as u32,
+            midstate: compress_bytes(&SHA256_INIT, &self.journal[..prefix_len]),
+            head: self.journal[prefix_len..range.start].to_vec(),
+            slice: self.journal[range.clone()].to_vec(),
+            tail: self.journal[range.end..].to_vec(),
+        })
+    }
+}
+
+impl JournalSliceProof {
+    /// Compute the digest of the journal this proof was created from.
+    ///
+    /// Returns an error if the head of the proof is longer than its offset.
+    pub fn journal_digest(&self) -> Result<Digest, VerificationError> {
+        let prefix_len = (self.offset as usize)
+            .checked_sub(self.head.len())
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        let mut data = [&self.head[..], &self.slice, &self.tail].concat();
+        let total_bits = (prefix_len + data.len()) as u64 * 8;
+        data.push(0x80);
+        while data.len() % BLOCK_BYTES != BLOCK_BYTES - 8 {
+            data.push(0);
+        }
+        data.extend_from_slice(&total_bits.to_be_bytes());
+        Ok(compress_bytes(&self.midstate, &data))
+    }
+
+    /// Verify that the slice appears at `offset` of the journal committed to
+    /// by the given [Output], returning the slice.
+    ///
+    /// The output is taken from a verified receipt, see
+    /// [Receipt::verify_and_get_output]. Journals written in chain mode are
+    /// committed to by the head of their journal chain rather than their
+    /// SHA-256 digest, and are rejected.
+    pub fn verify(&self, output: &Output) -> Result<&[u8], VerificationError> {
+        if output.journal_chain {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        let offset = self.offset as usize;
+        if self.head.len() >= BLOCK_BYTES
+            || offset < self.head.len()
+            || (offset - self.head.len()) % BLOCK_BYTES != 0
+        {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        if self.journal_digest()? != output.journal {
+            return Err(VerificationError::JournalDigestMismatch);
+        }
+        Ok(&self.slice)
+    }
+}
+
+/// Compute the head of the journal chain of a journal written in chain mode,
+/// or `None` if the journal is not a sequence of complete frames.
+pub fn journal_chain_head(journal: &[u8]) -> Option<Digest> {
+    let mut head = journal_chain_init();
+    let mut rest = journal;
+    while !rest.is_empty() {
+        let (commit, next) = split_frame(rest)?;
+        head = extend_journal_chain(&head, commit);
+        rest = next;
+    }
+    Some(head)
+}
+
+/// Verifies a journal written in chain mode one commit at a time, as it
+/// arrives.
+///
+/// The seal is verified up front by [JournalStreamVerifier::new]. Each call to
+/// [JournalStreamVerifier::update] returns the commits completed by the given
+/// bytes, which are trustworthy only once [JournalStreamVerifier::finish]
+/// succeeds. A caller that acts on commits before then must be prepared to
+/// roll back.
+pub struct JournalStreamVerifier {
+    output: Digest,
+    metadata_digest: Option<Digest>,
+    head: Digest,
+    pending: Vec<u8>,
+}
+
+impl JournalStreamVerifier {
+    /// Verify the seal of the given receipt and start verifying its journal.
+    pub fn new(
+        ctx: &VerifierContext,
+        inner: &InnerReceipt,
+        image_id: impl Into<Digest>,
+        metadata_digest: Option<Digest>,
+    ) -> Result<Self, VerificationError> {
+        let output = inner.verify_output(ctx, image_id)?;
+        Ok(Self::from_output(output, metadata_digest))
+    }
+
+    /// Start verifying a journal against an [Output] digest taken from an
+    /// already verified receipt.
+    pub fn from_output(output: Digest, metadata_digest: Option<Digest>) -> Self {
+        Self {
+            output,
+            metadata_digest,
+            head: journal_chain_init(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Add the next bytes of the journal, returning the commits they complete.
+    pub fn update(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        self.pending.extend_from_slice(bytes);
+        let mut commits = Vec::new();
+        let mut consumed = 0;
+        while let Some((commit, _)) = split_frame(&self.pending[consumed..]) {
+            self.head = extend_journal_chain(&self.head, commit);
+            consumed += JOURNAL_FRAME_HEADER_BYTES + commit.len();
+            commits.push(commit.to_vec());
+        }
+        self.pending.drain(..consumed);
+        commits
+    }
+
+    /// Check that the commits received so far make up the whole journal
+    /// committed by the guest.
+    pub fn finish(self) -> Result<(), VerificationError> {
+        let output = Output {
+            journal: self.head,
+            journal_chain: true,
+            metadata_digest: self.metadata_digest,
+        }
+        .digest();
+        if !self.pending.is_empty() || output != self.output {
+            return Err(VerificationError::JournalDigestMismatch);
+        }
+        Ok(())
+    }
+}
+
+// Split the first complete frame off the given bytes, returning the commit
+// and the remaining bytes.
+fn split_frame(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len_bytes = bytes.get(..JOURNAL_FRAME_HEADER_BYTES)?;
+    let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    let end = JOURNAL_FRAME_HEADER_BYTES.checked_add(len)?;
+    let commit = bytes.get(JOURNAL_FRAME_HEADER_BYTES..end)?;
+    Some((commit, &bytes[end..]))
+}
+
+/// Compress the given bytes, which must be a whole number of blocks, into the
+/// given SHA-256 state.
+fn compress_bytes(state: &Digest, bytes: &[u8]) -> Digest {
+    let blocks: Vec<Block> = bytes
+        .chunks_exact(BLOCK_BYTES)
+        .map(|block| Block::try_from(block).unwrap())
+        .collect();
+    *Impl::compress_slice(state, &blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_slice_proofs() {
+        let journal: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let output = Output {
+            journal: *Impl::hash_bytes(&journal),
+            journal_chain: false,
+            metadata_digest: None,
+        };
+        let receipt = Receipt::new(InnerReceipt::Fake, journal.clone());
+        for range in [0..0, 0..10, 60..70, 64..128, 150..200, 200..200] {
+            let proof = receipt.prove_journal_slice(range.clone()).unwrap();
+            assert!(proof.head.len() < BLOCK_BYTES);
+            assert_eq!(proof.verify(&output).unwrap(), &journal[range]);
+
+            let mut forged = proof.clone();
+            forged.slice[..].reverse();
+            forged.slice.push(0);
+            assert_eq!(
+                forged.verify(&output).unwrap_err(),
+                VerificationError::JournalDigestMismatch
+            );
+        }
+        receipt.prove_journal_slice(100..201).unwrap_err();
+
+        // A head longer than the offset is malformed.
+        let mut malformed = receipt.prove_journal_slice(10..20).unwrap();
+        malformed.offset = 5;
+        assert_eq!(
+            malformed.journal_digest().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(
+            malformed.verify(&output).unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+
+        // Journals written in chain mode are rejected, even if the chain head
+        // is the digest the proof computes.
+        let proof = receipt.prove_journal_slice(10..20).unwrap();
+        let chained = Output {
+            journal_chain: true,
+            ..output
+        };
+        assert_eq!(
+            proof.verify(&chained).unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+
+    #[test]
+    fn journal_stream_verifier() {
+        let commits: Vec<Vec<u8>> = vec![b"a".to_vec(), Vec::new(), (0..100).collect()];
+        let mut journal = Vec::new();
+        let mut head = journal_chain_init();
+        for commit in commits.iter() {
+            journal.extend_from_slice(&(commit.len() as u32).to_le_bytes());
+            journal.extend_from_slice(commit);
+            head = extend_journal_chain(&head, commit);
+        }
+        assert_eq!(journal_chain_head(&journal), Some(head));
+        let output = Output {
+            journal: head,
+            journal_chain: true,
+            metadata_digest: None,
+        }
+        .digest();
+
+        let mut verifier = JournalStreamVerifier::from_output(output, None);
+        let received: Vec<Vec<u8>> = journal
+            .chunks(7)
+            .flat_map(|chunk| verifier.update(chunk))
+            .collect();
+        assert_eq!(received, commits);
+        verifier.finish().unwrap();
+
+        // A journal missing its last commit is rejected.
+        let mut verifier = JournalStreamVerifier::from_output(output, None);
+        verifier.update(&journal[..journal.len() - 104]);
+        assert_eq!(
+            verifier.finish().unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+
+        // A truncated frame is rejected.
+        let mut verifier = JournalStreamVerifier::from_output(output, None);
+        verifier.update(&journal[..journal.len() - 1]);
+        assert_eq!(
+            verifier.finish().unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+        assert_eq!(journal_chain_head(&journal[..journal.len() - 1]), None);
+    }
+}
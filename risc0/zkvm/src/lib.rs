@@ -19,12 +19,25 @@
 
 extern crate alloc;
 #[cfg(not(target_os = "zkvm"))]
+pub mod attest;
+#[cfg(not(target_os = "zkvm"))]
 mod control_id;
+#[cfg(not(target_os = "zkvm"))]
+pub mod cost;
 #[cfg(feature = "prove")]
 mod exec;
+#[cfg(not(target_os = "zkvm"))]
+pub mod fault;
 pub mod guest;
-#[cfg(feature = "prove")]
+#[cfg(not(target_os = "zkvm"))]
+pub mod journal;
+#[cfg(not(target_os = "zkvm"))]
 mod opcode;
+pub mod oracle;
+pub mod output;
+pub mod ports;
+#[cfg(all(feature = "protobuf", not(target_os = "zkvm")))]
+pub mod protobuf;
 #[cfg(feature = "prove")]
 pub mod prove;
 #[cfg(not(target_os = "zkvm"))]
@@ -35,6 +48,14 @@ pub mod serde;
 #[cfg(feature = "prove")]
 mod session;
 pub mod sha;
+#[cfg(not(target_os = "zkvm"))]
+pub mod signed;
+pub mod storage;
+#[cfg(not(target_os = "zkvm"))]
+mod telemetry;
+pub mod testing;
+#[cfg(all(feature = "timestamp", not(target_os = "zkvm")))]
+pub mod timestamp;
 
 #[cfg(test)]
 mod testutils;
@@ -42,20 +63,34 @@ mod testutils;
 pub use anyhow::Result;
 #[cfg(not(target_os = "zkvm"))]
 pub use risc0_binfmt::{MemoryImage, Program, SystemState};
-pub use risc0_zkvm_platform::{declare_syscall, memory::MEM_SIZE, PAGE_SIZE};
+pub use risc0_zkvm_platform::{declare_syscall, ext_syscall_name, memory::MEM_SIZE, PAGE_SIZE};
 
 #[cfg(not(target_os = "zkvm"))]
-pub use self::control_id::POSEIDON_CONTROL_ID;
+pub use self::control_id::{BLAKE2B_CONTROL_ID, POSEIDON_CONTROL_ID, SHA256_CONTROL_ID};
+#[cfg(feature = "difftest")]
+pub use self::exec::difftest;
 #[cfg(feature = "profiler")]
 pub use self::exec::profiler::Profiler;
 #[cfg(not(target_os = "zkvm"))]
-pub use self::receipt::{ExitCode, Receipt, ReceiptMetadata, SegmentReceipt, VerifierContext};
+pub use self::fault::{FaultReceipt, GuestFault};
+pub use self::output::Output;
+#[cfg(not(target_os = "zkvm"))]
+pub use self::receipt::{
+    verify_batch, ExitCode, Receipt, ReceiptMetadata, SegmentReceipt, VerifierContext,
+    VerifierParams,
+};
 #[cfg(feature = "prove")]
 pub use self::{
     exec::io::{Syscall, SyscallContext},
-    exec::{Executor, ExecutorEnv, ExecutorEnvBuilder},
-    prove::{default_prover, loader::Loader},
-    session::{FileSegmentRef, Segment, SegmentRef, Session, SessionEvents, SimpleSegmentRef},
+    exec::{
+        Executor, ExecutorEnv, ExecutorEnvBuilder, ExecutorEnvBuilderErr, ExecutorPool, FaultInfo,
+        SegmentInfo, SyscallInfo,
+    },
+    prove::{default_prover, loader::Loader, ProverCapabilities, ProverLimitError, ProverOpts},
+    session::{
+        ExecutionStats, FileSegmentRef, InsnStats, PagingStats, Segment, SegmentRef, Session,
+        SessionEvents, SimpleSegmentRef,
+    },
 };
 
 #[cfg(not(target_os = "zkvm"))]
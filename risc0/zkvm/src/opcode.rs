@@ -12,8 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::format;
+#[cfg(feature = "prove")]
+use alloc::string::String;
+
 use anyhow::{bail, Result};
 use num_traits::FromPrimitive;
+#[cfg(feature = "prove")]
 use rrs_lib::{instruction_string_outputter::InstructionStringOutputter, process_instruction};
 
 #[allow(dead_code)]
@@ -176,6 +181,7 @@ impl OpCode {
     }
 
     #[allow(dead_code)]
+    #[cfg(feature = "prove")]
     pub fn debug(&self, cycle: usize, insn_pc: u32) -> String {
         let mut outputter = InstructionStringOutputter { insn_pc };
         let desc = process_instruction(&mut outputter, self.insn);
@@ -189,6 +195,7 @@ impl OpCode {
     }
 }
 
+#[cfg(feature = "prove")]
 impl core::fmt::Debug for OpCode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut outputter = InstructionStringOutputter {
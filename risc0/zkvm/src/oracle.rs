@@ -0,0 +1,288 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authenticated key-value witness oracle.
+//!
+//! The host provides a key-value store whose contents are committed to by a
+//! Merkle root. The guest commits the root to the journal with
+//! [guest::oracle::init](crate::guest::oracle::init) and then looks up values
+//! with [guest::oracle::get](crate::guest::oracle::get), which verifies every
+//! value against the root. A verifier that checks the root in the journal
+//! knows that every value the guest used was part of the committed store.
+//!
+//! On the host, provide the store with
+//! [ExecutorEnvBuilder::kv_oracle](crate::ExecutorEnvBuilder::kv_oracle):
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//!
+//! use risc0_zkvm::{oracle::MerkleKvStore, ExecutorEnv};
+//!
+//! let store = MerkleKvStore::new(BTreeMap::from([
+//!     (b"alice".to_vec(), b"10".to_vec()),
+//!     (b"bob".to_vec(), b"20".to_vec()),
+//! ]));
+//! let env = ExecutorEnv::builder()
+//!     .kv_oracle(store.root(), store)
+//!     .build()
+//!     .unwrap();
+//! ```
+//!
+//! Leaves, inner nodes and the root are hashed with distinct domain tags, and
+//! the root commits to the depth of the tree, so a proof can only verify at
+//! the depth the tree was built with.
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    declare_syscall,
+    sha::{Digest, Impl, Sha256},
+};
+
+declare_syscall!(
+    /// Syscall used by the guest to query the key-value oracle.
+    pub SYS_KV_ORACLE
+);
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+const ROOT_TAG: u8 = 2;
+
+/// The maximum depth of a key-value oracle tree.
+pub const MAX_DEPTH: usize = 32;
+
+/// A request sent from the guest to the key-value oracle.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum OracleRequest {
+    /// Request the Merkle root of the store.
+    Root,
+
+    /// Request the value of the given key, along with its [MerkleProof].
+    Get(Vec<u8>),
+}
+
+/// An error produced while looking up a key in the key-value oracle.
+#[derive(Debug, PartialEq)]
+pub enum OracleError {
+    /// The root has not been committed with
+    /// [guest::oracle::init](crate::guest::oracle::init).
+    Uninitialized,
+
+    /// The host did not provide a value for the key. This is not a proof that
+    /// the key is absent from the store.
+    NotFound,
+
+    /// The value provided by the host does not match the root.
+    InvalidProof,
+}
+
+impl core::fmt::Display for OracleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OracleError::Uninitialized => write!(f, "kv oracle root has not been committed"),
+            OracleError::NotFound => write!(f, "kv oracle has no value for key"),
+            OracleError::InvalidProof => write!(f, "kv oracle value does not match root"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OracleError {}
+
+/// A proof that a leaf is included in a Merkle tree.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// The index of the leaf in the tree.
+    pub index: u32,
+
+    /// The sibling digests from the leaf up to the root.
+    pub siblings: Vec<Digest>,
+}
+
+impl MerkleProof {
+    /// Compute the root of the tree from the given leaf digest.
+    ///
+    /// Returns [None] if the proof is deeper than [MAX_DEPTH] or the index
+    /// does not fit in a tree of its depth.
+    pub fn root(&self, leaf: &Digest) -> Option<Digest> {
+        let depth = self.siblings.len();
+        if depth > MAX_DEPTH || (depth < 32 && self.index >> depth != 0) {
+            return None;
+        }
+        let mut index = self.index;
+        let mut digest = *leaf;
+        for sibling in self.siblings.iter() {
+            digest = if index & 1 == 0 {
+                node_digest(&digest, sibling)
+            } else {
+                node_digest(sibling, &digest)
+            };
+            index >>= 1;
+        }
+        Some(root_digest(depth, &digest))
+    }
+
+    /// Verify that the `key` maps to `value` in the tree with the given root.
+    pub fn verify(&self, root: &Digest, key: &[u8], value: &[u8]) -> bool {
+        self.root(&leaf_digest(key, value)) == Some(*root)
+    }
+}
+
+/// Compute the digest of a key-value pair, as stored in the Merkle tree.
+///
+/// The length of the key is included so that a (key, value) pair cannot be
+/// confused with a different split of the same bytes.
+pub fn leaf_digest(key: &[u8], value: &[u8]) -> Digest {
+    let mut data = Vec::with_capacity(5 + key.len() + value.len());
+    data.push(LEAF_TAG);
+    data.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    data.extend_from_slice(key);
+    data.extend_from_slice(value);
+    *Impl::hash_bytes(&data)
+}
+
+fn node_digest(left: &Digest, right: &Digest) -> Digest {
+    let mut data = Vec::with_capacity(1 + 2 * core::mem::size_of::<Digest>());
+    data.push(NODE_TAG);
+    data.extend_from_slice(left.as_bytes());
+    data.extend_from_slice(right.as_bytes());
+    *Impl::hash_bytes(&data)
+}
+
+fn root_digest(depth: usize, node: &Digest) -> Digest {
+    let mut data = Vec::with_capacity(5 + core::mem::size_of::<Digest>());
+    data.push(ROOT_TAG);
+    data.extend_from_slice(&(depth as u32).to_le_bytes());
+    data.extend_from_slice(node.as_bytes());
+    *Impl::hash_bytes(&data)
+}
+
+/// A source of values and their [MerkleProof]s for the key-value oracle.
+pub trait KvStore {
+    /// Return the value of `key` and a proof of its inclusion, if present.
+    fn get(&self, key: &[u8]) -> Option<(Vec<u8>, MerkleProof)>;
+}
+
+/// An in-memory [KvStore] that builds a Merkle tree over its entries.
+///
+/// Leaves are ordered by key, and the tree is padded to a power of two with
+/// zero digests.
+pub struct MerkleKvStore {
+    entries: BTreeMap<Vec<u8>, (u32, Vec<u8>)>,
+    levels: Vec<Vec<Digest>>,
+}
+
+impl MerkleKvStore {
+    /// Construct a [MerkleKvStore] from the given entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `2^MAX_DEPTH` entries.
+    pub fn new(entries: BTreeMap<Vec<u8>, Vec<u8>>) -> Self {
+        let size = entries.len().next_power_of_two();
+        assert!(
+            size.trailing_zeros() as usize <= MAX_DEPTH,
+            "too many kv oracle entries"
+        );
+        let mut leaves = vec![Digest::default(); size];
+        for (leaf, (key, value)) in leaves.iter_mut().zip(entries.iter()) {
+            *leaf = leaf_digest(key, value);
+        }
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks_exact(2)
+                .map(|pair| node_digest(&pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+        }
+        let entries = entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, (key, value))| (key, (index as u32, value)))
+            .collect();
+        Self { entries, levels }
+    }
+
+    /// The Merkle root of this store.
+    pub fn root(&self) -> Digest {
+        root_digest(self.levels.len() - 1, &self.levels.last().unwrap()[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merkle_proofs() {
+        let entries: BTreeMap<Vec<u8>, Vec<u8>> = (0..5u8)
+            .map(|i| (vec![b'k', i], vec![b'v'; i as usize]))
+            .collect();
+        let store = MerkleKvStore::new(entries.clone());
+        let root = store.root();
+        for (key, value) in entries.iter() {
+            let (got, proof) = store.get(key).unwrap();
+            assert_eq!(&got, value);
+            assert_eq!(proof.siblings.len(), 3);
+            assert!(proof.verify(&root, key, value));
+            assert!(!proof.verify(&root, key, b"forged"));
+        }
+        assert!(store.get(b"missing").is_none());
+    }
+
+    #[test]
+    fn proof_depth() {
+        let entries: BTreeMap<Vec<u8>, Vec<u8>> = (0..4u8).map(|i| (vec![i], vec![i])).collect();
+        let store = MerkleKvStore::new(entries);
+        let root = store.root();
+        let (value, proof) = store.get(&[1]).unwrap();
+        assert!(proof.verify(&root, &[1], &value));
+
+        // An inner node presented as a leaf with a shorter proof is rejected.
+        let short = MerkleProof {
+            index: 0,
+            siblings: proof.siblings[1..].to_vec(),
+        };
+        let node = node_digest(&leaf_digest(&[0], &[0]), &leaf_digest(&[1], &[1]));
+        assert_ne!(short.root(&node), Some(root));
+
+        // Extra siblings or an index outside the tree are rejected.
+        let mut long = proof.clone();
+        long.siblings.push(Digest::default());
+        assert!(!long.verify(&root, &[1], &value));
+        let out_of_range = MerkleProof {
+            index: proof.index + 4,
+            ..proof.clone()
+        };
+        assert_eq!(out_of_range.root(&leaf_digest(&[1], &value)), None);
+        let too_deep = MerkleProof {
+            index: 0,
+            siblings: vec![Digest::default(); MAX_DEPTH + 1],
+        };
+        assert_eq!(too_deep.root(&Digest::default()), None);
+    }
+
+    #[test]
+    fn single_entry() {
+        let store = MerkleKvStore::new(BTreeMap::from([(b"a".to_vec(), b"b".to_vec())]));
+        let (value, proof) = store.get(b"a").unwrap();
+        assert!(proof.siblings.is_empty());
+        assert!(proof.verify(&store.root(), b"a", &value));
+    }
+}
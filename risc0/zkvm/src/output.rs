@@ -0,0 +1,127 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The public output of a guest, as committed to the receipt claim.
+//!
+//! The output always includes the digest of the journal. A guest may also bind
+//! an auxiliary digest with
+//! [env::commit_metadata_digest](crate::guest::env::commit_metadata_digest),
+//! such as a block hash or configuration hash, without adding the underlying
+//! data to the journal.
+//!
+//! A guest that calls
+//! [env::enable_journal_chain](crate::guest::env::enable_journal_chain)
+//! instead commits to the head of a hash chain over its commits, as computed
+//! by [extend_journal_chain], so that the journal can be verified commit by
+//! commit as it is received. The output digest records which of the two the
+//! journal digest is, so a journal is only checked against the chain head
+//! when the guest committed one.
+
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sha::{Digest, Impl, Sha256};
+
+/// Tag from which the [Output::digest] is derived when a metadata digest is
+/// present.
+const OUTPUT_TAG: &[u8] = b"risc0.Output";
+
+/// Tag from which the commitment to a journal chain head is derived, see
+/// [Output::digest].
+const OUTPUT_CHAIN_TAG: &[u8] = b"risc0.Output.JournalChain";
+
+/// Tag hashed into the head of an empty journal chain.
+const JOURNAL_CHAIN_TAG: &[u8] = b"risc0.JournalChain";
+
+/// The number of bytes of the length prefix of each commit in a journal
+/// written in chain mode.
+pub const JOURNAL_FRAME_HEADER_BYTES: usize = 4;
+
+/// The public output of a guest.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Output {
+    /// The SHA-256 digest of the journal, or the head of the journal chain if
+    /// `journal_chain` is set.
+    pub journal: Digest,
+
+    /// Whether `journal` is the head of a journal chain, as computed by
+    /// [extend_journal_chain], rather than the digest of the journal.
+    pub journal_chain: bool,
+
+    /// The metadata digest committed by the guest, if any.
+    pub metadata_digest: Option<Digest>,
+}
+
+impl Output {
+    /// Compute the digest of this output, as recorded in
+    /// `ReceiptMetadata::output`.
+    ///
+    /// Without a metadata digest or journal chain, this is the journal digest,
+    /// so receipts of guests that use neither are unchanged.
+    ///
+    /// The head of a journal chain is a SHA-256 digest like any other, so it
+    /// is first compressed with [tagged_compress] under a tag of its own.
+    /// Otherwise, a journal holding the last link of the chain would have the
+    /// head as its plain digest, and would pass for the journal of the guest.
+    /// A metadata digest is then compressed together with the journal digest,
+    /// so the output digest cannot be mistaken for the digest of some other
+    /// journal either.
+    pub fn digest(&self) -> Digest {
+        let journal = match self.journal_chain {
+            true => tagged_compress(OUTPUT_CHAIN_TAG, &self.journal, &Digest::default()),
+            false => self.journal,
+        };
+        match &self.metadata_digest {
+            Some(metadata_digest) => tagged_compress(OUTPUT_TAG, &journal, metadata_digest),
+            None => journal,
+        }
+    }
+}
+
+/// Compress the given pair of digests with SHA-256, from an initial state
+/// derived from `tag`.
+///
+/// The initial state is the SHA-256 digest of `tag` with every bit flipped,
+/// which is not the state of SHA-256 after hashing any known message. So
+/// unlike a tagged SHA-256 hash, the result is not the SHA-256 digest of any
+/// known message, and a digest computed this way cannot be passed off as the
+/// digest of a journal.
+pub(crate) fn tagged_compress(tag: &[u8], a: &Digest, b: &Digest) -> Digest {
+    let mut state = *Impl::hash_bytes(tag);
+    for word in state.as_mut_words() {
+        *word = !*word;
+    }
+    *Impl::compress(&state, a, b)
+}
+
+/// The head of a journal chain with no commits.
+pub fn journal_chain_init() -> Digest {
+    *Impl::hash_bytes(JOURNAL_CHAIN_TAG)
+}
+
+/// Extend a journal chain with the given commit.
+///
+/// In chain mode, each commit is written to the journal as a frame: its length
+/// as a little-endian `u32`, followed by its bytes. The new head is the
+/// SHA-256 digest of the previous head followed by the frame.
+pub fn extend_journal_chain(head: &Digest, commit: &[u8]) -> Digest {
+    let mut data = Vec::with_capacity(
+        core::mem::size_of::<Digest>() + JOURNAL_FRAME_HEADER_BYTES + commit.len(),
+    );
+    data.extend_from_slice(head.as_bytes());
+    data.extend_from_slice(&(commit.len() as u32).to_le_bytes());
+    data.extend_from_slice(commit);
+    *Impl::hash_bytes(&data)
+}
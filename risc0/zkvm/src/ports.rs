@@ -0,0 +1,250 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Named I/O ports declared by a guest.
+//!
+//! A guest declares the inputs, outputs and oracles it uses with
+//! [io_port!](crate::io_port), which records each port in a manifest embedded
+//! in the `.risc0.ports` section of the ELF:
+//!
+//! ```ignore
+//! risc0_zkvm::io_port!(input "prices" = 10, schema "prices.v1");
+//! risc0_zkvm::io_port!(output "report" = 11, schema "my_core.Report.v2");
+//! risc0_zkvm::io_port!(oracle "price_feed", schema "price_feed.v1");
+//! ```
+//!
+//! The schema of a port is an identifier chosen by the guest author for the
+//! format of the values passed through it, such as a type name with a
+//! version. The host binds each port with the schema it expects, and the two
+//! are compared exactly. Unlike a type name derived by the compiler, the
+//! schema does not change when a type is moved or renamed, and can be changed
+//! deliberately when the format changes. Names and schemas may not be empty or
+//! contain whitespace.
+//!
+//! On the host, the manifest is loaded with [PortManifest::from_elf] and
+//! passed to `ExecutorEnvBuilder::port_manifest`, and each port is bound by
+//! name. `ExecutorEnvBuilder::build` then checks that every port of the guest
+//! is bound, with the same schema as the guest declared, so that a missing or
+//! mismatched input is reported before the guest runs rather than as a failed
+//! read in the middle of execution.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use anyhow::{anyhow, bail, Result};
+
+/// The name of the ELF section holding the port manifest.
+pub const PORTS_SECTION: &str = ".risc0.ports";
+
+/// The kind of an I/O port, and how it is connected to the host.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortKind {
+    /// A value read by the guest from a file descriptor.
+    Input {
+        /// The file descriptor the guest reads from.
+        fd: u32,
+    },
+
+    /// Values written by the guest to a file descriptor.
+    Output {
+        /// The file descriptor the guest writes to.
+        fd: u32,
+    },
+
+    /// A custom syscall of the same name, invoked with
+    /// [ext_syscall!](crate::ext_syscall).
+    Oracle,
+}
+
+/// A port declared by a guest with [io_port!](crate::io_port).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Port {
+    /// The name of the port.
+    pub name: String,
+
+    /// The kind of the port.
+    pub kind: PortKind,
+
+    /// The schema of the values passed through the port, as declared by the
+    /// guest.
+    pub schema: String,
+}
+
+/// The ports declared by a guest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PortManifest {
+    /// The declared ports, in no particular order.
+    pub ports: Vec<Port>,
+}
+
+impl PortManifest {
+    /// Parse the contents of the [PORTS_SECTION] of a guest.
+    ///
+    /// Each port is recorded as a line of the form `<kind> <name> <target>
+    /// <schema>`, where the target is the file descriptor of an input or
+    /// output, and `-` for an oracle. Padding between records is ignored.
+    pub fn parse(section: &[u8]) -> Result<Self> {
+        let section =
+            core::str::from_utf8(section).map_err(|_| anyhow!("port manifest is not UTF-8"))?;
+        let mut ports = Vec::new();
+        for line in section.lines() {
+            let line = line.trim_matches(|x: char| x == '\0' || x.is_whitespace());
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [kind, name, target, schema] = fields[..] else {
+                bail!("invalid port record: {line}");
+            };
+            let fd = || -> Result<u32> {
+                target
+                    .parse()
+                    .map_err(|_| anyhow!("invalid file descriptor for port {name}: {target}"))
+            };
+            let kind = match kind {
+                "input" => PortKind::Input { fd: fd()? },
+                "output" => PortKind::Output { fd: fd()? },
+                "oracle" => PortKind::Oracle,
+                _ => bail!("unknown kind of port {name}: {kind}"),
+            };
+            if ports.iter().any(|x: &Port| x.name == name) {
+                bail!("port declared more than once: {name}");
+            }
+            ports.push(Port {
+                name: name.to_string(),
+                kind,
+                schema: schema.to_string(),
+            });
+        }
+        Ok(Self { ports })
+    }
+
+    /// Load the manifest embedded in the given guest ELF.
+    ///
+    /// A guest that declares no ports has an empty manifest.
+    #[cfg(not(target_os = "zkvm"))]
+    pub fn from_elf(elf: &[u8]) -> Result<Self> {
+        match risc0_binfmt::read_elf_section(elf, PORTS_SECTION)? {
+            Some(section) => Self::parse(&section),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Return the port with the given name.
+    pub fn get(&self, name: &str) -> Option<&Port> {
+        self.ports.iter().find(|x| x.name == name)
+    }
+}
+
+/// Check that a port name or schema is not empty and contains no whitespace,
+/// which separates the fields of a port record.
+pub const fn is_valid_field(field: &str) -> bool {
+    let bytes = field.as_bytes();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx].is_ascii_whitespace() {
+            return false;
+        }
+        idx += 1;
+    }
+    !bytes.is_empty()
+}
+
+#[doc(hidden)]
+pub const fn port_record<const N: usize>(record: &str) -> [u8; N] {
+    let bytes = record.as_bytes();
+    let mut out = [0u8; N];
+    let mut idx = 0;
+    while idx < N {
+        out[idx] = bytes[idx];
+        idx += 1;
+    }
+    out
+}
+
+/// Declare a named I/O port of the guest in its port manifest.
+///
+/// Inputs and outputs are connected to a file descriptor, and read and
+/// written with `env::read_port` and `env::write_port`. Oracles are custom
+/// syscalls of the same name, invoked with [ext_syscall!](crate::ext_syscall).
+///
+/// ```ignore
+/// risc0_zkvm::io_port!(input "prices" = 10, schema "prices.v1");
+/// risc0_zkvm::io_port!(output "report" = 11, schema "my_core.Report.v2");
+/// risc0_zkvm::io_port!(oracle "price_feed", schema "price_feed.v1");
+/// ```
+///
+/// The schema identifies the format of the values passed through the port,
+/// and is checked against the schema the host binds the port with. Names and
+/// schemas that are empty or contain whitespace fail to compile. See the
+/// [ports](crate::ports) module.
+#[macro_export]
+macro_rules! io_port {
+    (input $name:literal = $fd:literal, schema $schema:literal) => {
+        $crate::io_port!(@record $name, $schema, concat!("input ", $name, " ", $fd, " ", $schema, "\n"));
+    };
+    (output $name:literal = $fd:literal, schema $schema:literal) => {
+        $crate::io_port!(@record $name, $schema, concat!("output ", $name, " ", $fd, " ", $schema, "\n"));
+    };
+    (oracle $name:literal, schema $schema:literal) => {
+        $crate::io_port!(@record $name, $schema, concat!("oracle ", $name, " - ", $schema, "\n"));
+    };
+    (@record $name:literal, $schema:literal, $record:expr) => {
+        const _: () = {
+            assert!(
+                $crate::ports::is_valid_field($name),
+                "port names may not be empty or contain whitespace"
+            );
+            assert!(
+                $crate::ports::is_valid_field($schema),
+                "port schemas may not be empty or contain whitespace"
+            );
+            const RECORD: &str = $record;
+            #[used]
+            #[link_section = ".risc0.ports"]
+            static PORT: [u8; RECORD.len()] = $crate::ports::port_record(RECORD);
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let section = b"input prices 10 prices.v1\n\0\0oracle price_feed - price_feed.v1\n";
+        let manifest = PortManifest::parse(section).unwrap();
+        let prices = manifest.get("prices").unwrap();
+        assert_eq!(prices.kind, PortKind::Input { fd: 10 });
+        assert_eq!(prices.schema, "prices.v1");
+        assert_eq!(manifest.get("price_feed").unwrap().kind, PortKind::Oracle);
+
+        assert!(PortManifest::parse(b"input prices ten u32\n").is_err());
+        assert!(PortManifest::parse(b"input a 1 u32\ninput a 2 u32\n").is_err());
+        // A schema with whitespace does not make a valid record.
+        assert!(PortManifest::parse(b"input a 1 Vec < u32 >\n").is_err());
+        assert!(PortManifest::parse(b"input a 1\n").is_err());
+    }
+
+    #[test]
+    fn valid_fields() {
+        assert!(is_valid_field("multi_test.values"));
+        assert!(!is_valid_field(""));
+        assert!(!is_valid_field("my prices"));
+        assert!(!is_valid_field("Vec<\tu32>"));
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protobuf encoding of receipts.
+//!
+//! The schema is defined in `receipt.proto` under the `risc0.receipt.v1`
+//! package, so that services written in other languages can generate matching
+//! types. Native types convert into the generated types with [From], and back
+//! with [TryFrom], which fails if a required field is missing or a digest has
+//! the wrong length.
+//!
+//! ```ignore
+//! use prost::Message;
+//! use risc0_zkvm::protobuf;
+//!
+//! let bytes = protobuf::Receipt::from(receipt.clone()).encode_to_vec();
+//! let decoded: Receipt = protobuf::Receipt::decode(bytes.as_slice())?.try_into()?;
+//! ```
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::{
+    receipt::{self as native, InnerReceipt},
+    recursion,
+    sha::Digest,
+};
+
+mod proto {
+    #![allow(missing_docs)]
+
+    // Generated proto interface.
+    include!(concat!(env!("OUT_DIR"), "/risc0.receipt.v1.rs"));
+}
+
+pub use proto::*;
+
+fn digest_from_bytes(bytes: &[u8]) -> Result<Digest> {
+    Ok(Digest::try_from(bytes)?)
+}
+
+fn required<T>(field: Option<T>, name: &str) -> Result<T> {
+    field.ok_or_else(|| anyhow!("missing field: {name}"))
+}
+
+impl From<native::Receipt> for Receipt {
+    fn from(value: native::Receipt) -> Self {
+        let inner = match value.inner {
+            InnerReceipt::Flat(x) => receipt::Inner::Flat(x.into()),
+            InnerReceipt::Succinct(x) => receipt::Inner::Succinct(x.into()),
+            InnerReceipt::Fake => receipt::Inner::Fake(Empty {}),
+        };
+        Self {
+            inner: Some(inner),
+            journal: value.journal,
+        }
+    }
+}
+
+impl TryFrom<Receipt> for native::Receipt {
+    type Error = Error;
+
+    fn try_from(value: Receipt) -> Result<Self> {
+        let inner = match required(value.inner, "Receipt.inner")? {
+            receipt::Inner::Flat(x) => InnerReceipt::Flat(x.into()),
+            receipt::Inner::Succinct(x) => InnerReceipt::Succinct(x.try_into()?),
+            receipt::Inner::Fake(_) => InnerReceipt::Fake,
+        };
+        Ok(Self {
+            inner,
+            journal: value.journal,
+        })
+    }
+}
+
+impl From<native::SegmentReceipts> for SegmentReceipts {
+    fn from(value: native::SegmentReceipts) -> Self {
+        Self {
+            receipts: value.0.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<SegmentReceipts> for native::SegmentReceipts {
+    fn from(value: SegmentReceipts) -> Self {
+        Self(value.receipts.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<native::SegmentReceipt> for SegmentReceipt {
+    fn from(value: native::SegmentReceipt) -> Self {
+        Self {
+            seal: value.seal,
+            index: value.index,
+            hashfn: value.hashfn,
+        }
+    }
+}
+
+impl From<SegmentReceipt> for native::SegmentReceipt {
+    fn from(value: SegmentReceipt) -> Self {
+        Self {
+            seal: value.seal,
+            index: value.index,
+            hashfn: value.hashfn,
+        }
+    }
+}
+
+impl From<recursion::SuccinctReceipt> for SuccinctReceipt {
+    fn from(value: recursion::SuccinctReceipt) -> Self {
+        Self {
+            seal: value.seal,
+            control_id: value.control_id.as_bytes().to_vec(),
+            meta: Some(value.meta.into()),
+        }
+    }
+}
+
+impl TryFrom<SuccinctReceipt> for recursion::SuccinctReceipt {
+    type Error = Error;
+
+    fn try_from(value: SuccinctReceipt) -> Result<Self> {
+        Ok(Self {
+            seal: value.seal,
+            control_id: digest_from_bytes(&value.control_id)?,
+            meta: required(value.meta, "SuccinctReceipt.meta")?.try_into()?,
+        })
+    }
+}
+
+impl From<native::ReceiptMetadata> for ReceiptMetadata {
+    fn from(value: native::ReceiptMetadata) -> Self {
+        Self {
+            pre: Some(value.pre.into()),
+            post: Some(value.post.into()),
+            exit_code: Some(value.exit_code.into()),
+            input: value.input.as_bytes().to_vec(),
+            output: value.output.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<ReceiptMetadata> for native::ReceiptMetadata {
+    type Error = Error;
+
+    fn try_from(value: ReceiptMetadata) -> Result<Self> {
+        Ok(Self {
+            pre: required(value.pre, "ReceiptMetadata.pre")?.try_into()?,
+            post: required(value.post, "ReceiptMetadata.post")?.try_into()?,
+            exit_code: required(value.exit_code, "ReceiptMetadata.exit_code")?.try_into()?,
+            input: digest_from_bytes(&value.input)?,
+            output: digest_from_bytes(&value.output)?,
+        })
+    }
+}
+
+impl From<risc0_binfmt::SystemState> for SystemState {
+    fn from(value: risc0_binfmt::SystemState) -> Self {
+        Self {
+            pc: value.pc,
+            merkle_root: value.merkle_root.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<SystemState> for risc0_binfmt::SystemState {
+    type Error = Error;
+
+    fn try_from(value: SystemState) -> Result<Self> {
+        Ok(Self {
+            pc: value.pc,
+            merkle_root: digest_from_bytes(&value.merkle_root)?,
+        })
+    }
+}
+
+impl From<native::ExitCode> for ExitCode {
+    fn from(value: native::ExitCode) -> Self {
+        let kind = match value {
+            native::ExitCode::SystemSplit => exit_code::Kind::SystemSplit(Empty {}),
+            native::ExitCode::SessionLimit => exit_code::Kind::SessionLimit(Empty {}),
+            native::ExitCode::Paused(code) => exit_code::Kind::Paused(code),
+            native::ExitCode::Halted(code) => exit_code::Kind::Halted(code),
+        };
+        Self { kind: Some(kind) }
+    }
+}
+
+impl TryFrom<ExitCode> for native::ExitCode {
+    type Error = Error;
+
+    fn try_from(value: ExitCode) -> Result<Self> {
+        Ok(match required(value.kind, "ExitCode.kind")? {
+            exit_code::Kind::SystemSplit(_) => Self::SystemSplit,
+            exit_code::Kind::SessionLimit(_) => Self::SessionLimit,
+            exit_code::Kind::Paused(code) => Self::Paused(code),
+            exit_code::Kind::Halted(code) => Self::Halted(code),
+        })
+    }
+}
@@ -12,7 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::Result;
+use std::{
+    cell::Cell,
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
 use risc0_binfmt::MemoryImage;
 use risc0_circuit_rv32im::{
     layout::{OutBuffer, LAYOUT},
@@ -21,17 +28,20 @@ use risc0_circuit_rv32im::{
 use risc0_core::field::baby_bear::{BabyBear, Elem, ExtElem};
 use risc0_zkp::{
     adapter::TapsProvider,
+    core::digest::Digest,
     hal::{EvalCheck, Hal},
     layout::Buffer,
     prove::adapter::ProveAdapter,
 };
 
-use super::{HalEval, Prover};
+use super::{HalEval, Prover, ProverCapabilities, ProverLimitError, ProverOpts};
 use crate::{
+    fault::FaultReceipt,
     prove::exec::MachineContext,
     receipt::{InnerReceipt, SegmentReceipts},
-    Executor, ExecutorEnv, Loader, Receipt, Segment, SegmentReceipt, Session, VerifierContext,
-    CIRCUIT,
+    sha::{Impl, Sha256},
+    Executor, ExecutorEnv, Loader, Receipt, Segment, SegmentReceipt, Session, SimpleSegmentRef,
+    VerifierContext, CIRCUIT,
 };
 
 /// An implementation of a [Prover] that runs locally.
@@ -42,6 +52,10 @@ where
 {
     name: String,
     hal_eval: HalEval<H, E>,
+    opts: ProverOpts,
+
+    // Total cycles proven and time spent proving them.
+    throughput: Cell<(u64, Duration)>,
 }
 
 impl<H, E> LocalProver<H, E>
@@ -54,7 +68,35 @@ where
         Self {
             name: name.to_string(),
             hal_eval,
+            opts: ProverOpts::default(),
+            throughput: Cell::new((0, Duration::ZERO)),
+        }
+    }
+
+    /// Return this [LocalProver] configured with the given [ProverOpts].
+    pub fn with_opts(mut self, opts: ProverOpts) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    /// Start enforcing the limits of the [ProverOpts].
+    fn watchdog(&self) -> Watchdog<'_, H> {
+        Watchdog {
+            hal: self.hal_eval.hal.as_ref(),
+            start: Instant::now(),
+            max_duration: self.opts.max_prove_seconds.map(Duration::from_secs),
+            max_memory_bytes: self.opts.max_memory_bytes,
+        }
+    }
+
+    /// Returns the given [VerifierContext], with the domain separator of the
+    /// [ProverOpts] if one is set.
+    fn verifier_context(&self, ctx: &VerifierContext) -> VerifierContext {
+        let mut ctx = ctx.clone();
+        if self.opts.domain.is_some() {
+            ctx.domain = self.opts.domain.clone();
         }
+        ctx
     }
 }
 
@@ -71,15 +113,83 @@ where
         self.hal_eval.hal.get_memory_usage()
     }
 
+    fn capabilities(&self) -> ProverCapabilities {
+        let (cycles, elapsed) = self.throughput.get();
+        let secs_per_mcycle =
+            (cycles > 0).then(|| elapsed.as_secs_f64() * 1_000_000.0 / cycles as f64);
+        ProverCapabilities {
+            hashfns: vec![self.hal_eval.hal.get_hash_suite().name.clone()],
+            max_segment_po2: risc0_zkp::MAX_CYCLES_PO2,
+            backend: self.name.split(':').next().unwrap().to_string(),
+            secs_per_mcycle,
+        }
+    }
+
     fn prove_session(&self, ctx: &VerifierContext, session: &Session) -> Result<Receipt> {
+        self.prove_session_with(ctx, session, &self.watchdog())
+    }
+
+    fn prove_segment(&self, ctx: &VerifierContext, segment: &Segment) -> Result<SegmentReceipt> {
+        self.prove_segment_with(ctx, segment, &self.watchdog())
+    }
+
+    fn prove(
+        &self,
+        env: ExecutorEnv<'_>,
+        ctx: &VerifierContext,
+        image: MemoryImage,
+    ) -> Result<Receipt> {
+        // The time limit covers execution too, checked as each segment is
+        // split off.
+        let watchdog = self.watchdog();
+        let mut exec = Executor::new(env, image);
+        let session = exec.run_with_callback(|segment| {
+            watchdog.check()?;
+            Ok(Box::new(SimpleSegmentRef::new(segment)))
+        })?;
+        self.prove_session_with(ctx, &session, &watchdog)
+    }
+}
+
+impl<H, E> LocalProver<H, E>
+where
+    H: Hal<Field = BabyBear, Elem = Elem, ExtElem = ExtElem>,
+    E: EvalCheck<H>,
+{
+    fn prove_session_with(
+        &self,
+        ctx: &VerifierContext,
+        session: &Session,
+        watchdog: &Watchdog<H>,
+    ) -> Result<Receipt> {
         log::info!("prove_session: {}", self.name);
+        let ctx = &self.verifier_context(ctx);
         let mut segments = Vec::new();
         for segment_ref in session.segments.iter() {
+            watchdog.check()?;
             let segment = segment_ref.resolve()?;
+            let checkpoint = match &self.opts.checkpoint_dir {
+                Some(dir) => {
+                    let key = checkpoint_key(ctx, &segment)?;
+                    Some(dir.join(format!("{}-{key}.receipt", segment.index)))
+                }
+                None => None,
+            };
+            if let Some(path) = &checkpoint {
+                if let Some(receipt) = load_checkpoint(ctx, &segment, path) {
+                    log::info!("prove_session: resuming segment {}", segment.index);
+                    segments.push(receipt);
+                    continue;
+                }
+            }
             for hook in &session.hooks {
                 hook.on_pre_prove_segment(&segment);
             }
-            segments.push(self.prove_segment(ctx, &segment)?);
+            let receipt = self.prove_segment_with(ctx, &segment, watchdog)?;
+            if let Some(path) = &checkpoint {
+                save_checkpoint(&receipt, path)?;
+            }
+            segments.push(receipt);
             for hook in &session.hooks {
                 hook.on_post_prove_segment(&segment);
             }
@@ -87,13 +197,48 @@ where
         let inner = InnerReceipt::Flat(SegmentReceipts(segments));
         let receipt = Receipt::new(inner, session.journal.clone());
         let image_id = session.segments[0].resolve()?.pre_image.compute_id();
-        receipt.verify_with_context(ctx, image_id)?;
+        match &session.fault {
+            Some(proof) => {
+                let receipt = FaultReceipt {
+                    receipt: receipt.clone(),
+                    proof: proof.clone(),
+                };
+                receipt.verify_with_context(ctx, image_id)?;
+            }
+            None => receipt.inner.verify_with_output(
+                ctx,
+                image_id,
+                &receipt.journal,
+                session.metadata_digest.as_ref(),
+            )?,
+        }
         Ok(receipt)
     }
 
-    fn prove_segment(&self, ctx: &VerifierContext, segment: &Segment) -> Result<SegmentReceipt> {
+    fn prove_segment_with(
+        &self,
+        ctx: &VerifierContext,
+        segment: &Segment,
+        watchdog: &Watchdog<H>,
+    ) -> Result<SegmentReceipt> {
+        match &self.opts.numa_policy {
+            Some(policy) => policy.install(|| self.prove_segment_placed(ctx, segment, watchdog))?,
+            None => self.prove_segment_placed(ctx, segment, watchdog),
+        }
+    }
+
+    // Prove a segment on the calling thread, which has been placed according
+    // to the NUMA policy of the prover, if any.
+    fn prove_segment_placed(
+        &self,
+        ctx: &VerifierContext,
+        segment: &Segment,
+        watchdog: &Watchdog<H>,
+    ) -> Result<SegmentReceipt> {
         use risc0_zkp::prove::executor::Executor;
 
+        let ctx = &self.verifier_context(ctx);
+        let start = Instant::now();
         log::info!(
             "prove_segment[{}]: po2: {}, insn_cycles: {}",
             segment.index,
@@ -103,18 +248,20 @@ where
         let (hal, eval) = (self.hal_eval.hal.as_ref(), &self.hal_eval.eval);
         let hashfn = &hal.get_hash_suite().name;
 
-        let io = segment.prepare_globals();
+        let io = segment.prepare_globals(&ctx.input_digest());
         let machine = MachineContext::new(segment);
         let mut executor = Executor::new(&CIRCUIT, machine, segment.po2, segment.po2, &io);
 
         let loader = Loader::new();
         loader.load(|chunk, fini| executor.step(chunk, fini))?;
         executor.finalize();
+        watchdog.check()?;
 
         let mut adapter = ProveAdapter::new(&mut executor);
         let mut prover = risc0_zkp::prove::Prover::new(hal, CIRCUIT.get_taps());
 
         adapter.execute(prover.iop());
+        watchdog.check()?;
 
         prover.set_po2(adapter.po2() as usize);
 
@@ -126,6 +273,7 @@ where
             REGISTER_GROUP_DATA,
             hal.copy_from_elem("data", &adapter.get_data().as_slice()),
         );
+        watchdog.check()?;
         adapter.accumulate(prover.iop());
         prover.commit_group(
             REGISTER_GROUP_ACCUM,
@@ -137,6 +285,7 @@ where
 
         log::debug!("Globals: {:?}", OutBuffer(out_slice).tree(&LAYOUT));
         let out = hal.copy_from_elem("out", &adapter.get_io().as_slice());
+        watchdog.check()?;
 
         let seal = prover.finalize(&[&mix, &out], eval.as_ref());
 
@@ -147,17 +296,95 @@ where
         };
         receipt.verify_with_context(ctx)?;
 
+        let (cycles, elapsed) = self.throughput.get();
+        self.throughput
+            .set((cycles + (1 << segment.po2), elapsed + start.elapsed()));
+
+        #[cfg(feature = "metrics")]
+        crate::telemetry::segment_proved(start, receipt.get_seal_bytes().len());
+
         Ok(receipt)
     }
+}
 
-    fn prove(
-        &self,
-        env: ExecutorEnv<'_>,
-        ctx: &VerifierContext,
-        image: MemoryImage,
-    ) -> Result<Receipt> {
-        let mut exec = Executor::new(env, image);
-        let session = exec.run()?;
-        self.prove_session(ctx, &session)
+/// Enforces the limits of the [ProverOpts] of a [LocalProver].
+///
+/// Proving cannot be interrupted mid-phase, so the limits are checked between
+/// phases, before each segment, and as each segment is executed, and a
+/// prover that exceeds one stops at the next check.
+///
+/// The memory in use is tracked by the [Hal] for the whole process, not per
+/// prover, see [ProverOpts::max_memory_bytes].
+struct Watchdog<'a, H: Hal> {
+    hal: &'a H,
+    start: Instant,
+    max_duration: Option<Duration>,
+    max_memory_bytes: Option<usize>,
+}
+
+impl<'a, H: Hal> Watchdog<'a, H> {
+    fn check(&self) -> Result<(), ProverLimitError> {
+        if let Some(limit) = self.max_duration {
+            let elapsed = self.start.elapsed();
+            if elapsed > limit {
+                return Err(ProverLimitError::Timeout { limit, elapsed });
+            }
+        }
+        if let Some(limit) = self.max_memory_bytes {
+            let used = self.hal.get_memory_in_use();
+            if used > limit {
+                return Err(ProverLimitError::MemoryLimit { limit, used });
+            }
+        }
+        Ok(())
     }
 }
+
+/// The key of the checkpoint of `segment`, which identifies the claim that
+/// its [SegmentReceipt] proves: the pre and post state of the segment, its
+/// exit code, and the input digest of `ctx`.
+fn checkpoint_key(ctx: &VerifierContext, segment: &Segment) -> Result<Digest> {
+    let claim = (
+        segment.pre_image.compute_id(),
+        segment.post_image_id,
+        segment.exit_code,
+        ctx.input_digest(),
+    );
+    Ok(*Impl::hash_bytes(&bincode::serialize(&claim)?))
+}
+
+/// Load the [SegmentReceipt] checkpointed at `path`, if it exists, verifies,
+/// and proves the claim of `segment`.
+fn load_checkpoint(
+    ctx: &VerifierContext,
+    segment: &Segment,
+    path: &Path,
+) -> Option<SegmentReceipt> {
+    let contents = fs::read(path).ok()?;
+    let receipt: SegmentReceipt = bincode::deserialize(&contents).ok()?;
+    let metadata = receipt.get_metadata().ok()?;
+    let is_valid = metadata.pre.digest() == segment.pre_image.compute_id()
+        && metadata.post.digest() == segment.post_image_id
+        && metadata.exit_code == segment.exit_code
+        && metadata.input == ctx.input_digest()
+        && receipt.verify_with_context(ctx).is_ok();
+    if !is_valid {
+        log::warn!("ignoring invalid checkpoint: {}", path.display());
+        return None;
+    }
+    Some(receipt)
+}
+
+/// Persist the given [SegmentReceipt] at `path`.
+///
+/// The receipt is written to a temporary file first, so that an interrupted
+/// write never leaves a partial checkpoint behind.
+fn save_checkpoint(receipt: &SegmentReceipt, path: &Path) -> Result<()> {
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create checkpoint dir: {}", dir.display()))?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bincode::serialize(receipt)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
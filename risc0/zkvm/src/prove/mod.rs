@@ -33,14 +33,15 @@
 mod exec;
 pub(crate) mod loader;
 mod local;
+mod numa;
 mod plonk;
 mod remote;
 #[cfg(test)]
 mod tests;
 
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::HashMap, path::PathBuf, rc::Rc, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use risc0_binfmt::{MemoryImage, Program};
 use risc0_circuit_rv32im::CircuitImpl;
 use risc0_core::field::{
@@ -49,13 +50,16 @@ use risc0_core::field::{
 };
 use risc0_zkp::{
     adapter::CircuitInfo,
-    core::digest::DIGEST_WORDS,
+    core::digest::{Digest, DIGEST_WORDS},
     hal::{EvalCheck, Hal},
 };
 use risc0_zkvm_platform::{memory::MEM_SIZE, PAGE_SIZE, WORD_SIZE};
+use thiserror::Error;
 
+pub use self::numa::NumaPolicy;
 use self::{local::LocalProver, remote::RemoteProver};
 use crate::{
+    fault::FaultReceipt,
     receipt::{Receipt, VerifierContext},
     ExecutorEnv, Segment, SegmentReceipt, Session,
 };
@@ -171,6 +175,135 @@ where
     pub eval: Rc<E>,
 }
 
+/// Options to configure a [LocalProver].
+#[derive(Clone, Debug, Default)]
+pub struct ProverOpts {
+    /// A domain separator committed to the input digest of the
+    /// [ReceiptMetadata](crate::ReceiptMetadata) of every segment.
+    ///
+    /// Receipts proven with a domain only verify with a [VerifierContext] that
+    /// has the same domain, see [VerifierContext::with_domain]. If unset, the
+    /// domain of the [VerifierContext] passed to the prover is used.
+    pub domain: Option<String>,
+
+    /// A directory in which to persist each [SegmentReceipt] as it is proven.
+    ///
+    /// If proving is interrupted, proving the same [Session] again with the
+    /// same directory resumes from the completed segments rather than starting
+    /// over. Checkpoints are keyed by the segment index and the claim of the
+    /// segment: its pre and post state, exit code, and input digest. A
+    /// checkpoint is only reused if it verifies and proves that claim, so a
+    /// directory may be shared between sessions.
+    pub checkpoint_dir: Option<PathBuf>,
+
+    /// The longest a call to `prove`, `prove_session` or `prove_segment` may
+    /// run, in seconds, before it fails with [ProverLimitError::Timeout].
+    ///
+    /// For `prove`, this includes executing the guest.
+    pub max_prove_seconds: Option<u64>,
+
+    /// The most memory that may be allocated for proving, in bytes, before
+    /// proving fails with [ProverLimitError::MemoryLimit].
+    ///
+    /// This limit is process-wide: allocations are tracked by the [Hal]
+    /// across the whole process, not per prover or per job. When several jobs
+    /// are proven concurrently in one process, a job fails if the memory of
+    /// all jobs together exceeds the limit, so the limit should be the memory
+    /// budget of the whole process.
+    pub max_memory_bytes: Option<usize>,
+
+    /// How to place the threads and memory of the prover on the NUMA nodes
+    /// of the machine, see [NumaPolicy].
+    ///
+    /// When set, each segment is proven on a thread pool of the policy,
+    /// rather than on the calling thread and the global rayon pool. This is
+    /// meant for the CPU prover. If unset, placement is left to the operating
+    /// system.
+    pub numa_policy: Option<NumaPolicy>,
+}
+
+impl ProverOpts {
+    /// Return these [ProverOpts] with the given domain separator.
+    pub fn with_domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Return these [ProverOpts] with the given checkpoint directory.
+    pub fn with_checkpoint_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.checkpoint_dir = Some(dir.into());
+        self
+    }
+
+    /// Return these [ProverOpts] with the given time limit, in seconds.
+    pub fn with_max_prove_seconds(mut self, secs: u64) -> Self {
+        self.max_prove_seconds = Some(secs);
+        self
+    }
+
+    /// Return these [ProverOpts] with the given memory limit, in bytes.
+    pub fn with_max_memory_bytes(mut self, bytes: usize) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Return these [ProverOpts] with the given [NumaPolicy].
+    pub fn with_numa_policy(mut self, policy: NumaPolicy) -> Self {
+        self.numa_policy = Some(policy);
+        self
+    }
+}
+
+/// The error returned when proving exceeds a limit set in [ProverOpts].
+///
+/// Limits are checked between the phases of proving each segment, so a
+/// prover stops shortly after a limit is exceeded, releasing its memory,
+/// rather than being interrupted mid-phase.
+#[derive(Debug, Error, PartialEq)]
+pub enum ProverLimitError {
+    /// Proving took longer than [ProverOpts::max_prove_seconds].
+    #[error("Proving exceeded max_prove_seconds: {elapsed:?} > {limit:?}")]
+    Timeout {
+        /// The configured limit.
+        limit: Duration,
+        /// The time spent proving when the limit was detected.
+        elapsed: Duration,
+    },
+
+    /// The memory in use exceeded [ProverOpts::max_memory_bytes].
+    ///
+    /// The memory in use is that of the whole process, which includes the
+    /// memory of any other job proving concurrently.
+    #[error("Proving exceeded max_memory_bytes: {used} > {limit}")]
+    MemoryLimit {
+        /// The configured limit.
+        limit: usize,
+        /// The memory in use by the process when the limit was detected.
+        used: usize,
+    },
+}
+
+/// The capabilities of a [Prover], used to choose between several provers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProverCapabilities {
+    /// The names of the hash functions this prover produces receipts with.
+    ///
+    /// This is empty if the hash function is chosen by a remote service.
+    pub hashfns: Vec<String>,
+
+    /// The largest supported segment size, as a power of 2 cycles.
+    pub max_segment_po2: usize,
+
+    /// The hardware backend, e.g. `cpu`, `cuda`, `metal` or `remote`.
+    pub backend: String,
+
+    /// The estimated proving time in seconds per million cycles, if known.
+    ///
+    /// For a [LocalProver], this is measured from the segments it has proven
+    /// so far.
+    pub secs_per_mcycle: Option<f64>,
+}
+
 /// A Prover can execute a given [MemoryImage] and produce a [Receipt] that can
 /// be used to verify correct computation.
 pub trait Prover {
@@ -210,16 +343,33 @@ pub trait Prover {
 
     /// Return a name for this [Prover].
     fn get_name(&self) -> String;
+
+    /// Return the [ProverCapabilities] of this [Prover].
+    ///
+    /// The default is conservative, for provers that do not describe
+    /// themselves: no known hash functions, the smallest segment size, an
+    /// `unknown` backend, and no throughput estimate.
+    fn capabilities(&self) -> ProverCapabilities {
+        ProverCapabilities {
+            hashfns: Vec::new(),
+            max_segment_po2: risc0_zkp::MIN_CYCLES_PO2,
+            backend: "unknown".to_string(),
+            secs_per_mcycle: None,
+        }
+    }
 }
 
-fn provers() -> HashMap<String, Rc<dyn Prover>> {
+fn provers(opts: &ProverOpts) -> HashMap<String, Rc<dyn Prover>> {
     let mut table: HashMap<String, Rc<dyn Prover>> = HashMap::new();
     {
-        let prover = Rc::new(LocalProver::new("cpu", cpu::sha256_hal_eval()));
+        let prover =
+            Rc::new(LocalProver::new("cpu", cpu::sha256_hal_eval()).with_opts(opts.clone()));
         table.insert("cpu".to_string(), prover.clone());
         table.insert("$default".to_string(), prover);
 
-        let prover = Rc::new(LocalProver::new("cpu:poseidon", cpu::poseidon_hal_eval()));
+        let prover = Rc::new(
+            LocalProver::new("cpu:poseidon", cpu::poseidon_hal_eval()).with_opts(opts.clone()),
+        );
         table.insert("cpu:poseidon".to_string(), prover.clone());
         table.insert("$poseidon".to_string(), prover);
 
@@ -228,12 +378,15 @@ fn provers() -> HashMap<String, Rc<dyn Prover>> {
     }
     #[cfg(feature = "cuda")]
     {
-        let prover = Rc::new(LocalProver::new("cuda", cuda::sha256_hal_eval()));
+        let prover =
+            Rc::new(LocalProver::new("cuda", cuda::sha256_hal_eval()).with_opts(opts.clone()));
         table.insert("cuda".to_string(), prover.clone());
         table.insert("$gpu".to_string(), prover.clone());
         table.insert("$default".to_string(), prover);
 
-        let prover = Rc::new(LocalProver::new("cuda:poseidon", cuda::poseidon_hal_eval()));
+        let prover = Rc::new(
+            LocalProver::new("cuda:poseidon", cuda::poseidon_hal_eval()).with_opts(opts.clone()),
+        );
         table.insert("cuda:poseidon".to_string(), prover.clone());
         table.insert("$poseidon".to_string(), prover);
         #[cfg(feature = "dual")]
@@ -246,7 +399,7 @@ fn provers() -> HashMap<String, Rc<dyn Prover>> {
                 hal: Rc::new(DualHal::new(lhs.hal, rhs.hal)),
                 eval: Rc::new(DualEvalCheck::new(lhs.eval, rhs.eval)),
             };
-            let prover = Rc::new(LocalProver::new("dual", dual));
+            let prover = Rc::new(LocalProver::new("dual", dual).with_opts(opts.clone()));
             table.insert("$default".to_string(), prover);
 
             let lhs = cpu::poseidon_hal_eval();
@@ -255,21 +408,21 @@ fn provers() -> HashMap<String, Rc<dyn Prover>> {
                 hal: Rc::new(DualHal::new(lhs.hal, rhs.hal)),
                 eval: Rc::new(DualEvalCheck::new(lhs.eval, rhs.eval)),
             };
-            let prover = Rc::new(LocalProver::new("dual", dual));
+            let prover = Rc::new(LocalProver::new("dual", dual).with_opts(opts.clone()));
             table.insert("$poseidon".to_string(), prover);
         }
     }
     #[cfg(feature = "metal")]
     {
-        let prover = Rc::new(LocalProver::new("metal", metal::sha256_hal_eval()));
+        let prover =
+            Rc::new(LocalProver::new("metal", metal::sha256_hal_eval()).with_opts(opts.clone()));
         table.insert("metal".to_string(), prover.clone());
         table.insert("$gpu".to_string(), prover.clone());
         table.insert("$default".to_string(), prover);
 
-        let prover = Rc::new(LocalProver::new(
-            "metal:poseidon",
-            metal::poseidon_hal_eval(),
-        ));
+        let prover = Rc::new(
+            LocalProver::new("metal:poseidon", metal::poseidon_hal_eval()).with_opts(opts.clone()),
+        );
         table.insert("metal:poseidon".to_string(), prover.clone());
         table.insert("$poseidon".to_string(), prover);
         #[cfg(feature = "dual")]
@@ -282,7 +435,7 @@ fn provers() -> HashMap<String, Rc<dyn Prover>> {
                 hal: Rc::new(DualHal::new(lhs.hal, rhs.hal)),
                 eval: Rc::new(DualEvalCheck::new(lhs.eval, rhs.eval)),
             };
-            let prover = Rc::new(LocalProver::new("dual", dual));
+            let prover = Rc::new(LocalProver::new("dual", dual).with_opts(opts.clone()));
             table.insert("$default".to_string(), prover);
 
             let lhs = cpu::poseidon_hal_eval();
@@ -291,7 +444,7 @@ fn provers() -> HashMap<String, Rc<dyn Prover>> {
                 hal: Rc::new(DualHal::new(lhs.hal, rhs.hal)),
                 eval: Rc::new(DualEvalCheck::new(lhs.eval, rhs.eval)),
             };
-            let prover = Rc::new(LocalProver::new("dual", dual));
+            let prover = Rc::new(LocalProver::new("dual", dual).with_opts(opts.clone()));
             table.insert("$poseidon".to_string(), prover);
         }
     }
@@ -301,7 +454,14 @@ fn provers() -> HashMap<String, Rc<dyn Prover>> {
 /// Return a default [Prover] based on environment variables, falling back to a
 /// default CPU-based prover.
 pub fn default_prover() -> Rc<dyn Prover> {
-    let provers = provers();
+    default_prover_with_opts(&ProverOpts::default())
+}
+
+/// Return a default [Prover] configured with the given [ProverOpts].
+///
+/// See [default_prover] for how the prover is selected.
+pub fn default_prover_with_opts(opts: &ProverOpts) -> Rc<dyn Prover> {
+    let provers = provers(opts);
 
     if let Ok(requested) = std::env::var("RISC0_PROVER") {
         if let Some(prover) = provers.get(&requested) {
@@ -321,7 +481,13 @@ pub fn default_prover() -> Rc<dyn Prover> {
 
 /// Return a [Prover] registered by with specified `name`.
 pub fn get_prover(name: &str) -> Rc<dyn Prover> {
-    provers().get(name).unwrap().clone()
+    get_prover_with_opts(name, &ProverOpts::default())
+}
+
+/// Return a [Prover] registered by with specified `name`, configured with the
+/// given [ProverOpts].
+pub fn get_prover_with_opts(name: &str, opts: &ProverOpts) -> Rc<dyn Prover> {
+    provers(opts).get(name).unwrap().clone()
 }
 
 impl Session {
@@ -329,6 +495,15 @@ impl Session {
     pub fn prove(&self) -> Result<Receipt> {
         default_prover().prove_session(&VerifierContext::default(), self)
     }
+
+    /// Prove that the guest faulted, see [crate::fault].
+    ///
+    /// Returns an error if the session did not end with a fault.
+    pub fn prove_fault(&self) -> Result<FaultReceipt> {
+        let proof = self.fault.clone().context("Session did not fault")?;
+        let receipt = self.prove()?;
+        Ok(FaultReceipt { receipt, proof })
+    }
 }
 
 impl Segment {
@@ -337,14 +512,15 @@ impl Segment {
         default_prover().prove_segment(ctx, self)
     }
 
-    fn prepare_globals(&self) -> Vec<Elem> {
+    fn prepare_globals(&self, input: &Digest) -> Vec<Elem> {
         let mut io = vec![Elem::INVALID; CircuitImpl::OUTPUT_SIZE];
         log::debug!("run> pc: 0x{:08x}", self.pre_image.pc);
 
         // initialize Input
         let mut offset = 0;
+        let input_bytes = input.as_bytes();
         for i in 0..DIGEST_WORDS * WORD_SIZE {
-            io[offset + i] = Elem::ZERO;
+            io[offset + i] = (input_bytes[i] as u32).into();
         }
         offset += DIGEST_WORDS * WORD_SIZE;
 
@@ -0,0 +1,268 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! NUMA-aware placement of the threads and memory of the prover.
+//!
+//! On machines with several NUMA nodes, the NTT and hashing access memory
+//! across the whole working set of a segment, and lose much of their
+//! throughput when it spans nodes. A [NumaPolicy] keeps the working set
+//! node-local. Linux allocates memory on the node of the thread that first
+//! touches it, so each policy has a rayon thread pool of its own, whose
+//! worker threads are placed according to the policy, and each segment is
+//! proven on that pool with [NumaPolicy::install]. The thread calling the
+//! prover, and the global rayon pool, are left where they are.
+//!
+//! Placement is only supported on Linux, and is skipped with a warning on
+//! other platforms.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use rayon::ThreadPool;
+
+/// A policy for placing the threads and memory of the prover on NUMA nodes,
+/// see [ProverOpts::numa_policy](super::ProverOpts::numa_policy).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NumaPolicy {
+    /// Run the prover on the CPUs of the given node, so that its memory is
+    /// allocated on that node.
+    Bind(usize),
+
+    /// Interleave the memory of the prover across all nodes, page by page.
+    ///
+    /// This balances memory bandwidth across nodes when a single node does
+    /// not have enough CPUs or memory for the prover.
+    Interleave,
+}
+
+// The thread pools of the policies used so far. Like the global rayon pool,
+// they are kept for the life of the process.
+static POOLS: Mutex<Vec<(NumaPolicy, Arc<ThreadPool>)>> = Mutex::new(Vec::new());
+
+// Asserts that a value may be sent to another thread, see
+// [NumaPolicy::install].
+struct AssertSend<T>(T);
+
+// SAFETY: only used to pass a closure and its result through
+// `ThreadPool::install`, which blocks the calling thread until the closure
+// has returned on a worker, so the values are never used by two threads at
+// once.
+unsafe impl<T> Send for AssertSend<T> {}
+
+impl<T> AssertSend<T> {
+    fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl NumaPolicy {
+    /// Run `op` on a worker of the thread pool of this policy, so that `op`,
+    /// and any parallel work it starts with rayon, runs on threads placed
+    /// according to this policy.
+    ///
+    /// The calling thread blocks until `op` returns. `op` need not be
+    /// [Send], but must not depend on running on the calling thread.
+    pub(crate) fn install<R>(&self, op: impl FnOnce() -> R) -> Result<R> {
+        let op = AssertSend(op);
+        let result = self
+            .pool()?
+            .install(move || AssertSend((op.into_inner())()));
+        Ok(result.into_inner())
+    }
+
+    // Return the thread pool of this policy, building it on first use.
+    fn pool(&self) -> Result<Arc<ThreadPool>> {
+        let mut pools = POOLS.lock().unwrap();
+        if let Some((_, pool)) = pools.iter().find(|(policy, _)| policy == self) {
+            return Ok(pool.clone());
+        }
+        let pool = Arc::new(self.build_pool()?);
+        pools.push((self.clone(), pool.clone()));
+        Ok(pool)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn build_pool(&self) -> Result<ThreadPool> {
+        let mut builder = rayon::ThreadPoolBuilder::new();
+        if let NumaPolicy::Bind(node) = self {
+            builder = builder.num_threads(sys::node_cpus(*node)?.len());
+        }
+        let policy = self.clone();
+        let pool = builder
+            .thread_name(|idx| format!("risc0-numa-{idx}"))
+            .start_handler(move |_| {
+                if let Err(err) = policy.apply() {
+                    log::warn!("Unable to apply {policy:?} to a worker thread: {err:#}");
+                }
+            })
+            .build()?;
+        Ok(pool)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn build_pool(&self) -> Result<ThreadPool> {
+        log::warn!("NUMA placement is only supported on Linux, ignoring {self:?}");
+        Ok(rayon::ThreadPoolBuilder::new().build()?)
+    }
+
+    // Place the calling thread according to this policy.
+    #[cfg(target_os = "linux")]
+    fn apply(&self) -> Result<()> {
+        match self {
+            NumaPolicy::Bind(node) => sys::set_affinity(&sys::cpu_set(&sys::node_cpus(*node)?)),
+            NumaPolicy::Interleave => {
+                sys::set_mempolicy(sys::MPOL_INTERLEAVE, &sys::online_nodes()?)
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::{fs, io, mem, ptr};
+
+    use anyhow::{anyhow, bail, Context, Result};
+
+    pub const MPOL_INTERLEAVE: libc::c_int = 3;
+
+    // The number of nodes in a node mask passed to `set_mempolicy`.
+    const MAX_NODES: usize = 1024;
+
+    // Parse a list of ranges such as `0-3,8-11`, as found in sysfs.
+    fn parse_list(list: &str) -> Result<Vec<usize>> {
+        let mut items = Vec::new();
+        for range in list.trim().split(',').filter(|x| !x.is_empty()) {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let parse = |x: &str| x.parse().map_err(|_| anyhow!("invalid range: {range}"));
+            items.extend(parse(start)?..=parse(end)?);
+        }
+        Ok(items)
+    }
+
+    fn read_list(path: &str) -> Result<Vec<usize>> {
+        parse_list(&fs::read_to_string(path).with_context(|| format!("Unable to read {path}"))?)
+    }
+
+    pub fn node_cpus(node: usize) -> Result<Vec<usize>> {
+        read_list(&format!("/sys/devices/system/node/node{node}/cpulist"))
+    }
+
+    pub fn online_nodes() -> Result<Vec<usize>> {
+        read_list("/sys/devices/system/node/online")
+    }
+
+    pub fn cpu_set(cpus: &[usize]) -> libc::cpu_set_t {
+        unsafe {
+            let mut set: libc::cpu_set_t = mem::zeroed();
+            for cpu in cpus {
+                libc::CPU_SET(*cpu, &mut set);
+            }
+            set
+        }
+    }
+
+    pub fn set_affinity(set: &libc::cpu_set_t) -> Result<()> {
+        if unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), set) } != 0 {
+            bail!(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn set_mempolicy(mode: libc::c_int, nodes: &[usize]) -> Result<()> {
+        let mut mask = [0 as libc::c_ulong; MAX_NODES / libc::c_ulong::BITS as usize];
+        let bits = libc::c_ulong::BITS as usize;
+        for node in nodes.iter().filter(|node| **node < MAX_NODES) {
+            mask[node / bits] |= 1 << (node % bits);
+        }
+        let (mask, max_node) = match nodes.is_empty() {
+            true => (ptr::null(), 0),
+            false => (mask.as_ptr(), MAX_NODES + 1),
+        };
+        let ret = unsafe { libc::syscall(libc::SYS_set_mempolicy, mode, mask, max_node) };
+        if ret != 0 {
+            bail!(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Return the mode of the memory policy of the calling thread.
+    #[cfg(test)]
+    pub fn get_mempolicy() -> Result<libc::c_int> {
+        let mut mode: libc::c_int = 0;
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_get_mempolicy,
+                &mut mode as *mut libc::c_int,
+                ptr::null_mut::<libc::c_ulong>(),
+                0,
+                ptr::null_mut::<libc::c_void>(),
+                0,
+            )
+        };
+        if ret != 0 {
+            bail!(io::Error::last_os_error());
+        }
+        Ok(mode)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn parse_list() {
+            assert_eq!(
+                super::parse_list("0-3,8,10-11\n").unwrap(),
+                [0, 1, 2, 3, 8, 10, 11]
+            );
+            assert!(super::parse_list("0-x").is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::NumaPolicy;
+
+    #[test]
+    fn pool_per_policy() {
+        let pool = NumaPolicy::Interleave.pool().unwrap();
+        assert!(Arc::ptr_eq(&pool, &NumaPolicy::Interleave.pool().unwrap()));
+
+        // The work runs on a worker of the pool of the policy, rather than on
+        // the calling thread.
+        let index = NumaPolicy::Interleave
+            .install(|| pool.current_thread_index())
+            .unwrap();
+        assert!(index.is_some());
+        assert_eq!(pool.current_thread_index(), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn interleave_workers() {
+        use super::sys::{get_mempolicy, MPOL_INTERLEAVE};
+
+        // Memory policies need a kernel with NUMA support, and are not
+        // available to unprivileged containers.
+        let Ok(caller_mode) = get_mempolicy() else {
+            return;
+        };
+        let mode = NumaPolicy::Interleave
+            .install(|| get_mempolicy().unwrap())
+            .unwrap();
+        assert_eq!(mode, MPOL_INTERLEAVE);
+        assert_eq!(get_mempolicy().unwrap(), caller_mode);
+    }
+}
@@ -19,7 +19,7 @@ use anyhow::{bail, Result};
 use bonsai_sdk::alpha as bonsai_sdk;
 use risc0_binfmt::MemoryImage;
 
-use super::Prover;
+use super::{Prover, ProverCapabilities};
 use crate::{ExecutorEnv, Receipt, Segment, SegmentReceipt, Session, VerifierContext};
 
 /// An implementation of a [Prover] that runs proof workloads remotely.
@@ -46,6 +46,15 @@ impl Prover for RemoteProver {
         0
     }
 
+    fn capabilities(&self) -> ProverCapabilities {
+        ProverCapabilities {
+            hashfns: Vec::new(),
+            max_segment_po2: risc0_zkp::MAX_CYCLES_PO2,
+            backend: "remote".to_string(),
+            secs_per_mcycle: None,
+        }
+    }
+
     fn prove_session(&self, _ctx: &VerifierContext, _session: &Session) -> Result<Receipt> {
         bail!("this is unimplemented for prover [{}]", self.get_name())
     }
@@ -75,7 +84,7 @@ impl Prover for RemoteProver {
         }
 
         // upload input data
-        let input_id = client.upload_input(env.input)?;
+        let input_id = client.upload_input(env.input.to_vec())?;
 
         // While this is the executor, we want to start a session on the bonsai prover.
         // By doing so, we can return a session ID so that the prover can use it to
@@ -26,12 +26,16 @@ use risc0_zkvm_platform::{memory, WORD_SIZE};
 use serial_test::serial;
 use test_log::test;
 
-use super::{get_prover, LocalProver, Prover};
+use super::{get_prover, get_prover_with_opts, LocalProver, Prover, ProverLimitError, ProverOpts};
 use crate::{
+    cost::CostModel,
+    journal::{journal_chain_head, JournalStreamVerifier},
+    output::{extend_journal_chain, journal_chain_init, Output},
     prove::HalEval,
-    receipt::Receipt,
+    receipt::{InnerReceipt, Receipt, VerifierContext, VerifierParams},
     serde::{from_slice, to_vec},
-    testutils, Executor, ExecutorEnv, ExitCode, CIRCUIT,
+    sha::{Impl, Sha256},
+    testutils, Executor, ExecutorEnv, ExitCode, Session, CIRCUIT,
 };
 
 fn prove_nothing(name: &str) -> Result<Receipt> {
@@ -40,6 +44,234 @@ fn prove_nothing(name: &str) -> Result<Receipt> {
     get_prover(name).prove_elf(env, MULTI_TEST_ELF)
 }
 
+#[test]
+fn capabilities() {
+    let prover = get_prover("cpu:poseidon");
+    let caps = prover.capabilities();
+    assert_eq!(caps.hashfns, vec!["poseidon".to_string()]);
+    assert_eq!(caps.backend, "cpu");
+    assert_eq!(caps.max_segment_po2, risc0_zkp::MAX_CYCLES_PO2);
+    assert_eq!(caps.secs_per_mcycle, None);
+
+    let input = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    prover.prove_elf(env, MULTI_TEST_ELF).unwrap();
+    assert!(prover.capabilities().secs_per_mcycle.unwrap() > 0.0);
+}
+
+#[test]
+fn default_capabilities() {
+    // A prover that does not describe itself.
+    struct MinimalProver;
+
+    impl Prover for MinimalProver {
+        fn prove_session(&self, _ctx: &VerifierContext, _session: &Session) -> Result<Receipt> {
+            unimplemented!()
+        }
+
+        fn prove_segment(
+            &self,
+            _ctx: &VerifierContext,
+            _segment: &crate::Segment,
+        ) -> Result<crate::SegmentReceipt> {
+            unimplemented!()
+        }
+
+        fn get_peak_memory_usage(&self) -> usize {
+            0
+        }
+
+        fn get_name(&self) -> String {
+            "minimal".to_string()
+        }
+    }
+
+    let caps = MinimalProver.capabilities();
+    assert!(caps.hashfns.is_empty());
+    assert_eq!(caps.max_segment_po2, risc0_zkp::MIN_CYCLES_PO2);
+    assert_eq!(caps.backend, "unknown");
+    assert_eq!(caps.secs_per_mcycle, None);
+}
+
+#[test]
+fn prover_limits() {
+    let opts = ProverOpts::default().with_max_memory_bytes(1);
+    let input = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let err = get_prover_with_opts("cpu", &opts)
+        .prove_elf(env, MULTI_TEST_ELF)
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ProverLimitError>(),
+        Some(ProverLimitError::MemoryLimit { limit: 1, .. })
+    ));
+}
+
+#[test]
+fn prover_timeout() {
+    let opts = ProverOpts::default().with_max_prove_seconds(0);
+    let prover = get_prover_with_opts("cpu", &opts);
+    let is_timeout = |err: anyhow::Error| {
+        matches!(
+            err.downcast_ref::<ProverLimitError>(),
+            Some(ProverLimitError::Timeout { .. })
+        )
+    };
+    let env = || {
+        let spec = MultiTestSpec::BusyLoop { cycles: 1 << 16 };
+        ExecutorEnv::builder()
+            .add_input(&to_vec(&spec).unwrap())
+            .segment_limit_po2(16)
+            .build()
+            .unwrap()
+    };
+
+    // The deadline is checked as the guest is executed, before any segment
+    // is proven.
+    let err = prover.prove_elf(env(), MULTI_TEST_ELF).unwrap_err();
+    assert!(is_timeout(err));
+
+    // And before each segment of a session is proven.
+    let session = Executor::from_elf(env(), MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let err = prover
+        .prove_session(&VerifierContext::default(), &session)
+        .unwrap_err();
+    assert!(is_timeout(err));
+}
+
+#[test]
+fn journal_chain() {
+    let commits: Vec<Vec<u8>> = vec![b"hello".to_vec(), Vec::new(), vec![7; 1000]];
+    let spec = MultiTestSpec::JournalChain {
+        commits: commits.clone(),
+    };
+    let env = ExecutorEnv::builder()
+        .add_input(&to_vec(&spec).unwrap())
+        .build()
+        .unwrap();
+    let receipt = get_prover("cpu").prove_elf(env, MULTI_TEST_ELF).unwrap();
+    receipt.verify(MULTI_TEST_ID).unwrap();
+
+    let ctx = VerifierContext::default();
+    let mut verifier =
+        JournalStreamVerifier::new(&ctx, &receipt.inner, MULTI_TEST_ID, None).unwrap();
+    let received: Vec<Vec<u8>> = receipt
+        .journal
+        .chunks(64)
+        .flat_map(|chunk| verifier.update(chunk))
+        .collect();
+    assert_eq!(received, commits);
+    verifier.finish().unwrap();
+
+    let mut tampered = receipt.journal.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let mut verifier =
+        JournalStreamVerifier::new(&ctx, &receipt.inner, MULTI_TEST_ID, None).unwrap();
+    verifier.update(&tampered);
+    assert_eq!(
+        verifier.finish().unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+
+    // The head of the chain is the SHA-256 digest of the previous head and
+    // the last frame, which must not pass for a plain journal.
+    let (last, rest) = commits.split_last().unwrap();
+    let prev_head = rest.iter().fold(journal_chain_init(), |head, commit| {
+        extend_journal_chain(&head, commit)
+    });
+    let mut forged = prev_head.as_bytes().to_vec();
+    forged.extend_from_slice(&(last.len() as u32).to_le_bytes());
+    forged.extend_from_slice(last);
+    assert_eq!(
+        receipt.inner.verify(MULTI_TEST_ID, &forged).unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+
+    // Slice proofs do not apply to journals written in chain mode.
+    let output = receipt.verify_and_get_output(MULTI_TEST_ID, None).unwrap();
+    assert_eq!(
+        output,
+        Output {
+            journal: journal_chain_head(&receipt.journal).unwrap(),
+            journal_chain: true,
+            metadata_digest: None,
+        }
+    );
+    let proof = receipt.prove_journal_slice(0..4).unwrap();
+    assert_eq!(
+        proof.verify(&output).unwrap_err(),
+        VerificationError::ReceiptFormatError
+    );
+}
+
+#[test]
+fn verifier_params() {
+    let receipt = prove_nothing("cpu").unwrap();
+    let params = VerifierContext::default().export_minimal(MULTI_TEST_ID);
+    assert_eq!(params.hashfns, ["blake2b", "poseidon", "sha-256"]);
+
+    let params: VerifierParams =
+        bincode::deserialize(&bincode::serialize(&params).unwrap()).unwrap();
+    receipt.verify_with_params(&params).unwrap();
+
+    let other_image = VerifierParams {
+        image_id: Digest::default(),
+        ..params.clone()
+    };
+    assert_eq!(
+        receipt.verify_with_params(&other_image).unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+
+    let other_hashfn = VerifierParams {
+        hashfns: vec!["poseidon".to_string()],
+        ..params.clone()
+    };
+    assert_eq!(
+        receipt.verify_with_params(&other_hashfn).unwrap_err(),
+        VerificationError::InvalidHashSuite
+    );
+}
+
+#[test]
+fn exit_code() {
+    let spec = to_vec(&MultiTestSpec::Exit { status: 3 }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&spec).build().unwrap();
+    let receipt = get_prover("cpu").prove_elf(env, MULTI_TEST_ELF).unwrap();
+    assert_eq!(receipt.get_exit_code().unwrap(), ExitCode::Halted(3));
+    assert_eq!(from_slice::<u8, _>(&receipt.journal).unwrap(), 3);
+    receipt
+        .verify_exit_code(MULTI_TEST_ID, ExitCode::Halted(3))
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_exit_code(MULTI_TEST_ID, ExitCode::Halted(0))
+            .unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
+}
+
+#[test]
+fn cost_model() {
+    let prover = get_prover("cpu");
+    let model = CostModel::calibrate(prover.as_ref(), 13..=14).unwrap();
+    assert_eq!(model.backend, "cpu");
+    assert!(model.segment_secs(14) > 0.0);
+    assert_eq!(model.to_string().parse::<CostModel>().unwrap(), model);
+
+    assert_eq!(
+        model.estimate(3 << 14, 14),
+        model.estimate_segments([14, 14, 14])
+    );
+    assert_eq!(
+        model.estimate((2 << 14) + 1, 14),
+        model.estimate_segments([14, 14, 13])
+    );
+}
+
 #[test]
 #[cfg_attr(feature = "cuda", serial)]
 fn hashfn_poseidon() {
@@ -68,6 +300,43 @@ fn receipt_serde() {
     decoded.verify(MULTI_TEST_ID).unwrap();
 }
 
+#[cfg(feature = "compress")]
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn receipt_compress() {
+    let receipt = prove_nothing("$default").unwrap();
+    let compressed = receipt.compress().unwrap();
+    let encoded: Vec<u32> = to_vec(&receipt).unwrap();
+    assert_eq!(receipt.compressed_size().unwrap(), compressed.len());
+    assert!(compressed.len() < encoded.len() * WORD_SIZE);
+
+    let decoded = Receipt::decompress(&compressed).unwrap();
+    assert_eq!(decoded, receipt);
+    decoded.verify(MULTI_TEST_ID).unwrap();
+
+    // Decompression stops at the limit rather than expanding the whole input.
+    let err = Receipt::decompress_with_limit(&compressed, 1024).unwrap_err();
+    assert!(err.to_string().contains("exceeds the limit"));
+
+    // Uncompressed receipts are decoded transparently.
+    let plain = Receipt::decompress(bytemuck::cast_slice(&encoded)).unwrap();
+    assert_eq!(plain, receipt);
+}
+
+#[cfg(feature = "compress")]
+#[test]
+fn receipt_decompress_truncated() {
+    // Inputs that are not a whole number of words are rejected rather than
+    // panicking in the decoder, whether or not they are compressed.
+    let err = Receipt::decompress(&[0; 7]).unwrap_err();
+    assert!(err.to_string().contains("not a multiple of 4"));
+
+    let mut compressed = b"R0ZS".to_vec();
+    compressed.extend(zstd::stream::encode_all(&[0u8; 7][..], 0).unwrap());
+    let err = Receipt::decompress(&compressed).unwrap_err();
+    assert!(err.to_string().contains("not a multiple of 4"));
+}
+
 #[test]
 #[cfg_attr(feature = "cuda", serial)]
 fn check_image_id() {
@@ -82,6 +351,341 @@ fn check_image_id() {
     );
 }
 
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_batch() {
+    let receipt = prove_nothing("$default").unwrap();
+    let mut tampered = receipt.clone();
+    tampered.journal.push(0);
+    let fake = Receipt::new(InnerReceipt::Fake, vec![]);
+    let receipts = [receipt.clone(), receipt, tampered, fake];
+
+    crate::verify_batch(MULTI_TEST_ID, &receipts[..2]).unwrap();
+    let err = crate::verify_batch(MULTI_TEST_ID, &receipts[..3]).unwrap_err();
+    assert_eq!(err.index, 2);
+    assert_eq!(err.error, VerificationError::JournalDigestMismatch);
+
+    // Receipts that cannot verify regardless of their seal are rejected
+    // before any seal is verified.
+    let err = crate::verify_batch(MULTI_TEST_ID, &receipts).unwrap_err();
+    assert_eq!(err.index, 3);
+    assert_eq!(err.error, VerificationError::InvalidProof);
+    let err = crate::verify_batch(Digest::default(), &receipts[..2]).unwrap_err();
+    assert_eq!(err.index, 0);
+    assert_eq!(err.error, VerificationError::ImageVerificationError);
+}
+
+#[test]
+fn domain_separator() {
+    let input = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let opts = ProverOpts::default().with_domain("app-a");
+    let receipt = get_prover_with_opts("$default", &opts)
+        .prove_elf(env, MULTI_TEST_ELF)
+        .unwrap();
+    let metadata = receipt.inner.flat()[0].get_metadata().unwrap();
+    assert_ne!(metadata.input, Digest::default());
+
+    let ctx = VerifierContext::default().with_domain("app-a");
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    let ctx = VerifierContext::default().with_domain("app-b");
+    assert_eq!(
+        receipt
+            .verify_with_context(&ctx, MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::InputDigestMismatch
+    );
+    assert_eq!(
+        receipt.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::InputDigestMismatch
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn checkpoint_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let opts = ProverOpts::default().with_checkpoint_dir(dir.path());
+    let prove_with = |opts: &ProverOpts| {
+        let spec = MultiTestSpec::BusyLoop { cycles: 1 << 16 };
+        let input = to_vec(&spec).unwrap();
+        let env = ExecutorEnv::builder()
+            .add_input(&input)
+            .segment_limit_po2(16)
+            .build()
+            .unwrap();
+        get_prover_with_opts("$default", opts)
+            .prove_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+    };
+    let prove = || prove_with(&opts);
+    let receipt = prove();
+    let checkpoints = std::fs::read_dir(dir.path()).unwrap().count();
+    assert_eq!(checkpoints, receipt.inner.flat().len());
+    assert!(checkpoints > 1);
+
+    // Seals are randomized, so identical receipts must come from the
+    // checkpoints.
+    assert_eq!(prove(), receipt);
+
+    // The same segments proven for another domain have a different claim, so
+    // they are proven again and checkpointed separately.
+    let other = prove_with(&opts.clone().with_domain("other"));
+    assert_ne!(other, receipt);
+    let count = std::fs::read_dir(dir.path()).unwrap().count();
+    assert_eq!(count, 2 * checkpoints);
+
+    // Invalid checkpoints are proven again.
+    for entry in std::fs::read_dir(dir.path()).unwrap() {
+        std::fs::write(entry.unwrap().path(), b"corrupt").unwrap();
+    }
+    let reproven = prove();
+    assert_ne!(reproven, receipt);
+    reproven.verify(MULTI_TEST_ID).unwrap();
+}
+
+#[test]
+fn metadata_digest() {
+    let digest = Digest::from([1, 2, 3, 4, 5, 6, 7, 8]);
+    let input = to_vec(&MultiTestSpec::MetadataDigest { digest }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let receipt = get_prover("$default")
+        .prove_elf(env, MULTI_TEST_ELF)
+        .unwrap();
+    receipt
+        .verify_with_metadata_digest(MULTI_TEST_ID, &digest)
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_metadata_digest(MULTI_TEST_ID, &Digest::default())
+            .unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+    assert_eq!(
+        receipt.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+
+    let output = receipt
+        .verify_and_get_output(MULTI_TEST_ID, Some(&digest))
+        .unwrap();
+    assert_eq!(
+        output,
+        Output {
+            journal: *Impl::hash_bytes(&receipt.journal),
+            journal_chain: false,
+            metadata_digest: Some(digest),
+        }
+    );
+    let proof = receipt.prove_journal_slice(0..4).unwrap();
+    assert_eq!(proof.verify(&output).unwrap(), &receipt.journal[..4]);
+
+    // The output digest is not the digest of any known journal, so the receipt
+    // cannot be passed off as one without a metadata digest.
+    let mut forged = receipt.clone();
+    forged.journal = [
+        b"risc0.Output".as_slice(),
+        Impl::hash_bytes(&receipt.journal).as_bytes(),
+        digest.as_bytes(),
+    ]
+    .concat();
+    assert_eq!(
+        forged.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+}
+
+#[test]
+fn signed_receipt() {
+    use crate::signed::{
+        ReceiptSigner, SignatureError, SignatureVerifier, SignedReceipt, SignedReceiptError,
+    };
+
+    // A keyed hash stands in for a real signature scheme.
+    struct TestSigner(u8);
+
+    impl ReceiptSigner for TestSigner {
+        fn signer(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+
+        fn sign(&self, msg: &Digest) -> Result<Vec<u8>, SignatureError> {
+            let data = [&[self.0][..], msg.as_bytes()].concat();
+            Ok(Impl::hash_bytes(&data).as_bytes().to_vec())
+        }
+    }
+
+    struct TestVerifier(u8);
+
+    impl SignatureVerifier for TestVerifier {
+        fn verify(&self, signer: &[u8], msg: &Digest, sig: &[u8]) -> Result<(), SignatureError> {
+            if signer != [self.0] {
+                return Err(SignatureError::UntrustedSigner);
+            }
+            match TestSigner(self.0).sign(msg)? == sig {
+                true => Ok(()),
+                false => Err(SignatureError::InvalidSignature),
+            }
+        }
+    }
+
+    let receipt = prove_nothing("$default").unwrap();
+    let signed = SignedReceipt::sign(receipt, &TestSigner(1)).unwrap();
+    signed.verify(MULTI_TEST_ID, &TestVerifier(1)).unwrap();
+    assert_eq!(
+        signed.verify(MULTI_TEST_ID, &TestVerifier(2)).unwrap_err(),
+        SignedReceiptError::Signature(SignatureError::UntrustedSigner)
+    );
+    assert_eq!(
+        signed
+            .verify(Digest::default(), &TestVerifier(1))
+            .unwrap_err(),
+        SignedReceiptError::Receipt(VerificationError::ImageVerificationError)
+    );
+
+    let mut tampered = signed.clone();
+    tampered.receipt.journal.push(0);
+    assert_eq!(
+        tampered
+            .verify(MULTI_TEST_ID, &TestVerifier(1))
+            .unwrap_err(),
+        SignedReceiptError::Signature(SignatureError::InvalidSignature)
+    );
+}
+
+#[cfg(feature = "protobuf")]
+#[test]
+fn protobuf_round_trip() {
+    use prost::Message;
+
+    use crate::protobuf;
+
+    let receipt = prove_nothing("$default").unwrap();
+    let bytes = protobuf::Receipt::from(receipt.clone()).encode_to_vec();
+    let decoded: Receipt = protobuf::Receipt::decode(bytes.as_slice())
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(decoded, receipt);
+    decoded.verify(MULTI_TEST_ID).unwrap();
+}
+
+#[test]
+fn session_encode_decode() {
+    let input = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let bytes = session.encode().unwrap();
+
+    let decoded = Session::decode(&bytes).unwrap();
+    assert_eq!(decoded.segments.len(), session.segments.len());
+    assert_eq!(decoded.journal, session.journal);
+    let receipt = get_prover("$default")
+        .prove_session(&VerifierContext::default(), &decoded)
+        .unwrap();
+    receipt.verify(MULTI_TEST_ID).unwrap();
+
+    assert!(Session::decode(&bytes[1..]).is_err());
+}
+
+#[test]
+fn attested_receipt() {
+    use crate::attest::{
+        claim_digest, report_data, AttestationError, AttestedReceipt, AttestedReceiptError,
+        QuoteVerifier, REPORT_DATA_BYTES,
+    };
+
+    // The quote is just the report data, behind a one byte "signature".
+    struct TestVerifier;
+
+    impl QuoteVerifier for TestVerifier {
+        fn verify_quote(&self, quote: &[u8]) -> Result<[u8; REPORT_DATA_BYTES], AttestationError> {
+            match quote.split_first() {
+                Some((0xAA, data)) => data
+                    .try_into()
+                    .map_err(|_| AttestationError::InvalidQuote("bad length".into())),
+                _ => Err(AttestationError::InvalidQuote("untrusted".into())),
+            }
+        }
+    }
+
+    let receipt = prove_nothing("$default").unwrap();
+    let image_id = Digest::from(MULTI_TEST_ID);
+    // The claim is the same digest that a succinct receipt attests to.
+    let metadata = receipt.inner.get_metadata().unwrap();
+    assert_eq!(metadata.pre.digest(), image_id);
+    assert_eq!(claim_digest(&receipt).unwrap(), metadata.digest().unwrap());
+    let quote = [&[0xAA][..], &report_data(&receipt).unwrap()].concat();
+    let attested = AttestedReceipt::new(receipt, quote);
+    attested.verify(image_id, &TestVerifier).unwrap();
+
+    let mut untrusted = attested.clone();
+    untrusted.quote[0] = 0;
+    assert!(matches!(
+        untrusted.verify(image_id, &TestVerifier).unwrap_err(),
+        AttestedReceiptError::Attestation(AttestationError::InvalidQuote(_))
+    ));
+
+    // A quote over another claim is rejected.
+    let mut other_claim = attested.clone();
+    other_claim.quote[1] ^= 1;
+    assert_eq!(
+        other_claim.verify(image_id, &TestVerifier).unwrap_err(),
+        AttestedReceiptError::Attestation(AttestationError::ReportDataMismatch)
+    );
+
+    // The journal is bound to the claim through the output of the receipt.
+    let mut tampered = attested.clone();
+    tampered.receipt.journal.push(0);
+    assert_eq!(
+        tampered.verify(image_id, &TestVerifier).unwrap_err(),
+        AttestedReceiptError::Receipt(VerificationError::JournalDigestMismatch)
+    );
+}
+
+#[test]
+fn fault_proof() {
+    use crate::fault::{FaultKind, GuestFault};
+
+    let spec = to_vec(&MultiTestSpec::OutOfBounds).unwrap();
+    let addr = to_vec(&0x0C00_0000u32).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .add_input(&addr)
+        .fault_proofs(true)
+        .build()
+        .unwrap();
+    let session = Executor::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(session.exit_code, ExitCode::SystemSplit);
+
+    let receipt = session.prove_fault().unwrap();
+    let fault: GuestFault = receipt.verify(MULTI_TEST_ID).unwrap();
+    assert_eq!(
+        fault.kind,
+        FaultKind::InvalidMemoryAccess { addr: 0x0C00_0000 }
+    );
+    assert_eq!(
+        receipt.receipt.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
+
+    let mut tampered = receipt.clone();
+    tampered.proof.insn_page.pages[0].fill(0);
+    tampered.verify(MULTI_TEST_ID).unwrap_err();
+
+    let err = crate::Session::new(Vec::new(), Vec::new(), ExitCode::Halted(0))
+        .prove_fault()
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "Session did not fault");
+}
+
 #[test]
 #[serial]
 fn sha_basics() {
@@ -94,6 +94,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     control_id::{BLAKE2B_CONTROL_ID, POSEIDON_CONTROL_ID, SHA256_CONTROL_ID},
+    journal::journal_chain_head,
+    output::Output,
     recursion::SuccinctReceipt,
     sha::rust_crypto::{Digest as _, Sha256},
 };
@@ -114,6 +116,11 @@ pub enum ExitCode {
 
     /// This indicates normal termination of a program with an interior exit
     /// code returned from the guest.
+    ///
+    /// The code is 0 when the guest returns from `main`, and otherwise the
+    /// application-defined status passed to `env::exit`. A guest that calls
+    /// `env::exit` with a status of 0 also halts with `Halted(0)`, which
+    /// cannot be told apart from a return from `main`.
     Halted(u32),
 }
 
@@ -133,7 +140,7 @@ pub struct ReceiptMetadata {
     /// A [Digest] of the input, from the viewpoint of the guest.
     pub input: Digest,
 
-    /// A [Digest] of the journal, from the viewpoint of the guest.
+    /// A [Digest] of the [Output], from the viewpoint of the guest.
     pub output: Digest,
 }
 
@@ -143,7 +150,7 @@ pub struct ReceiptMetadata {
 /// [crate::Session] based on a specified memory image. This image is _not_
 /// included in the receipt and must be provided by the verifier when calling
 /// [Receipt::verify].
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Receipt {
     /// The polymorphic [InnerReceipt].
     pub inner: InnerReceipt,
@@ -157,7 +164,7 @@ pub struct Receipt {
 
 /// An inner receipt can take the form of a collection of [SegmentReceipts] or a
 /// [SuccinctReceipt].
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub enum InnerReceipt {
     /// The [SegmentReceipts].
     Flat(SegmentReceipts),
@@ -170,7 +177,7 @@ pub enum InnerReceipt {
 }
 
 /// A wrapper around `Vec<SegmentReceipt>`.
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct SegmentReceipts(pub Vec<SegmentReceipt>);
 
 impl SegmentReceipts {
@@ -181,14 +188,51 @@ impl SegmentReceipts {
         image_id: Digest,
         journal: &[u8],
     ) -> Result<(), VerificationError> {
+        self.verify_with_output(ctx, image_id, journal, None)
+    }
+
+    /// Verify the integrity of this receipt, including the metadata digest
+    /// committed by the guest.
+    pub fn verify_with_output(
+        &self,
+        ctx: &VerifierContext,
+        image_id: Digest,
+        journal: &[u8],
+        metadata_digest: Option<&Digest>,
+    ) -> Result<(), VerificationError> {
+        let output = self.verify_output(ctx, image_id)?;
+        check_journal(journal, metadata_digest, &output).map(|_| ())
+    }
+
+    /// Verify the integrity of this receipt, except for its journal, and
+    /// return the [Output] digest committed by the guest.
+    pub fn verify_output(
+        &self,
+        ctx: &VerifierContext,
+        image_id: Digest,
+    ) -> Result<Digest, VerificationError> {
+        self.verify_output_with_progress(ctx, image_id, |_, _| {})
+    }
+
+    /// Like [SegmentReceipts::verify_output], calling `on_progress` with the
+    /// number of segments verified so far and the total number of segments
+    /// after each segment is verified.
+    pub fn verify_output_with_progress(
+        &self,
+        ctx: &VerifierContext,
+        image_id: Digest,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<Digest, VerificationError> {
+        let total = self.0.len();
         let (final_receipt, receipts) = self
             .0
             .as_slice()
             .split_last()
             .ok_or(VerificationError::ReceiptFormatError)?;
         let mut prev_image_id = image_id;
-        for receipt in receipts {
+        for (idx, receipt) in receipts.iter().enumerate() {
             receipt.verify_with_context(ctx)?;
+            on_progress(idx + 1, total);
             let metadata = receipt.get_metadata()?;
             log::debug!("metadata: {metadata:#?}");
             if prev_image_id != metadata.pre.digest() {
@@ -200,6 +244,7 @@ impl SegmentReceipts {
             prev_image_id = metadata.post.digest();
         }
         final_receipt.verify_with_context(ctx)?;
+        on_progress(total, total);
         let metadata = final_receipt.get_metadata()?;
         log::debug!("final: {metadata:#?}");
         if prev_image_id != metadata.pre.digest() {
@@ -210,26 +255,51 @@ impl SegmentReceipts {
             return Err(VerificationError::UnexpectedExitCode);
         }
 
-        let digest = Sha256::digest(journal);
-        let digest_words: &[u32] = bytemuck::cast_slice(digest.as_slice());
-        let output_words = metadata.output.as_words();
-        let is_journal_valid = || {
-            (journal.is_empty() && output_words.iter().all(|x| *x == 0))
-                || digest_words == output_words
-        };
-        if !is_journal_valid() {
-            log::debug!(
-                "journal: \"{}\", digest: 0x{}, output: 0x{}, {:?}",
-                hex::encode(journal),
-                hex::encode(bytemuck::cast_slice(digest_words)),
-                hex::encode(bytemuck::cast_slice(output_words)),
-                journal
-            );
-            return Err(VerificationError::JournalDigestMismatch);
-        }
+        Ok(metadata.output)
+    }
+}
 
-        Ok(())
+// Check that the journal and metadata digest match the output digest
+// committed by the guest, returning the [Output] they match.
+//
+// The output digest commits to whether the guest wrote a plain journal or a
+// journal chain, see [Output::digest], so the journal is checked against the
+// one interpretation that the output digest can match: the digests of the
+// two are domain separated, and a journal cannot satisfy one in place of the
+// other.
+fn check_journal(
+    journal: &[u8],
+    metadata_digest: Option<&Digest>,
+    output: &Digest,
+) -> Result<Output, VerificationError> {
+    let output_for = |journal, journal_chain| Output {
+        journal,
+        journal_chain,
+        metadata_digest: metadata_digest.copied(),
+    };
+    let digest = Sha256::digest(journal);
+    let expected = output_for(Digest::try_from(digest.as_slice()).unwrap(), false);
+    let expected_digest = expected.digest();
+    if journal.is_empty() && metadata_digest.is_none() && *output == Digest::default() {
+        // A guest that never committed has an all-zero output digest.
+        return Ok(output_for(Digest::default(), false));
+    }
+    if expected_digest == *output {
+        return Ok(expected);
     }
+    if let Some(chained) = journal_chain_head(journal).map(|head| output_for(head, true)) {
+        if chained.digest() == *output {
+            return Ok(chained);
+        }
+    }
+    log::debug!(
+        "journal: \"{}\", digest: 0x{}, output: 0x{}, {:?}",
+        hex::encode(journal),
+        hex::encode(expected_digest.as_bytes()),
+        hex::encode(output.as_bytes()),
+        journal
+    );
+    Err(VerificationError::JournalDigestMismatch)
 }
 
 impl InnerReceipt {
@@ -249,9 +319,89 @@ impl InnerReceipt {
         image_id: impl Into<Digest>,
         journal: &[u8],
     ) -> Result<(), VerificationError> {
+        self.verify_with_output(ctx, image_id, journal, None)
+    }
+
+    /// Verify the integrity of this receipt, including the metadata digest
+    /// committed by the guest.
+    pub fn verify_with_output(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        journal: &[u8],
+        metadata_digest: Option<&Digest>,
+    ) -> Result<(), VerificationError> {
+        let output = self.verify_output(ctx, image_id)?;
+        check_journal(journal, metadata_digest, &output).map(|_| ())
+    }
+
+    /// Verify the integrity of this receipt, except for its journal, and
+    /// return the [Output] digest committed by the guest.
+    ///
+    /// This is used to verify a journal separately from the seal, e.g. with a
+    /// [JournalStreamVerifier](crate::journal::JournalStreamVerifier).
+    pub fn verify_output(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<Digest, VerificationError> {
+        match self {
+            InnerReceipt::Flat(x) => x.verify_output(ctx, image_id.into()),
+            InnerReceipt::Succinct(x) => {
+                x.verify_with_context(ctx)?;
+                ctx.check_input(&x.meta)?;
+                if x.meta.pre.digest() != image_id.into() {
+                    return Err(VerificationError::ImageVerificationError);
+                }
+                if x.meta.exit_code == ExitCode::SystemSplit {
+                    return Err(VerificationError::UnexpectedExitCode);
+                }
+                Ok(x.meta.output)
+            }
+            // TODO: add support for dev-mode
+            InnerReceipt::Fake => Err(VerificationError::InvalidProof),
+        }
+    }
+
+    /// Return the [ExitCode] of the final segment of this receipt.
+    ///
+    /// The exit code is read from the seal, and is only authenticated once
+    /// the receipt is verified.
+    pub fn get_exit_code(&self) -> Result<ExitCode, VerificationError> {
+        match self {
+            InnerReceipt::Flat(x) => Ok(x
+                .0
+                .last()
+                .ok_or(VerificationError::ReceiptFormatError)?
+                .get_metadata()?
+                .exit_code),
+            InnerReceipt::Succinct(x) => Ok(x.meta.exit_code),
+            // TODO: add support for dev-mode
+            InnerReceipt::Fake => Err(VerificationError::InvalidProof),
+        }
+    }
+
+    /// Return the [ReceiptMetadata] of the session proven by this receipt: the
+    /// pre state of its first segment, and the post state, exit code, and
+    /// output of its last.
+    ///
+    /// This is the metadata a [SuccinctReceipt] of the same session carries,
+    /// so its [ReceiptMetadata::digest] identifies the claim of the receipt
+    /// in either form. The metadata is read from the seal, and is only
+    /// authenticated once the receipt is verified.
+    pub fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
         match self {
-            InnerReceipt::Flat(x) => x.verify_with_context(ctx, image_id.into(), journal),
-            InnerReceipt::Succinct(x) => x.verify_with_context(ctx),
+            InnerReceipt::Flat(x) => {
+                let (first, last) = match (x.0.first(), x.0.last()) {
+                    (Some(first), Some(last)) => (first, last),
+                    _ => return Err(VerificationError::ReceiptFormatError),
+                };
+                Ok(ReceiptMetadata {
+                    pre: first.get_metadata()?.pre,
+                    ..last.get_metadata()?
+                })
+            }
+            InnerReceipt::Succinct(x) => Ok(x.meta.clone()),
             // TODO: add support for dev-mode
             InnerReceipt::Fake => Err(VerificationError::InvalidProof),
         }
@@ -298,9 +448,118 @@ pub struct SegmentReceipt {
 }
 
 /// Context available to the verification process.
+#[derive(Clone)]
 pub struct VerifierContext {
     /// A registry of hash functions to be used by the verification process.
     pub suites: BTreeMap<String, HashSuite<BabyBear>>,
+
+    /// A domain separator committed to the input digest of the
+    /// [ReceiptMetadata].
+    ///
+    /// Receipts only verify against the domain they were proven with, which
+    /// prevents replaying receipts across applications or chains. Receipts
+    /// proven without a domain have an input digest of zero.
+    pub domain: Option<String>,
+}
+
+impl VerifierContext {
+    /// Return this [VerifierContext] with the given domain separator.
+    pub fn with_domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Export the parameters needed to verify receipts of the given image with
+    /// this context.
+    ///
+    /// The [VerifierParams] name the hash functions of this context rather
+    /// than holding them, so they are small enough to ship to constrained
+    /// verifiers, which rebuild only the hash suites they need. Suites that
+    /// are not built into this crate are left out, since they cannot be
+    /// rebuilt by name.
+    pub fn export_minimal(&self, image_id: impl Into<Digest>) -> VerifierParams {
+        VerifierParams {
+            image_id: image_id.into(),
+            hashfns: self
+                .suites
+                .keys()
+                .filter(|name| BUILTIN_HASHFNS.contains(&name.as_str()))
+                .cloned()
+                .collect(),
+            domain: self.domain.clone(),
+        }
+    }
+
+    /// Returns the input digest that the [ReceiptMetadata] of receipts must
+    /// have to verify with this context.
+    pub(crate) fn input_digest(&self) -> Digest {
+        self.domain
+            .as_deref()
+            .map_or_else(Digest::default, domain_digest)
+    }
+
+    // Check that the given [ReceiptMetadata] was proven for the domain of
+    // this context.
+    fn check_input(&self, metadata: &ReceiptMetadata) -> Result<(), VerificationError> {
+        if metadata.input != self.input_digest() {
+            return Err(VerificationError::InputDigestMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// The parameters needed to verify receipts of a single image, exported from a
+/// [VerifierContext] with [VerifierContext::export_minimal].
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct VerifierParams {
+    /// The image ID that receipts must have been produced by.
+    pub image_id: Digest,
+
+    /// The names of the hash functions that receipts may be proven with.
+    pub hashfns: Vec<String>,
+
+    /// The domain separator that receipts must have been proven with, if any.
+    pub domain: Option<String>,
+}
+
+impl VerifierParams {
+    /// Build a [VerifierContext] holding only the hash suites named by these
+    /// parameters.
+    pub fn context(&self) -> Result<VerifierContext, VerificationError> {
+        let suites = self
+            .hashfns
+            .iter()
+            .map(|name| {
+                Ok((
+                    name.clone(),
+                    builtin_suite(name).ok_or(VerificationError::InvalidHashSuite)?,
+                ))
+            })
+            .collect::<Result<_, VerificationError>>()?;
+        Ok(VerifierContext {
+            suites,
+            domain: self.domain.clone(),
+        })
+    }
+}
+
+/// The names of the hash suites built into this crate.
+const BUILTIN_HASHFNS: &[&str] = &["blake2b", "poseidon", "sha-256"];
+
+// Construct the hash suite built into this crate with the given name.
+fn builtin_suite(name: &str) -> Option<HashSuite<BabyBear>> {
+    match name {
+        "blake2b" => Some(Blake2bCpuHashSuite::new_suite()),
+        "poseidon" => Some(PoseidonHashSuite::new_suite()),
+        "sha-256" => Some(Sha256HashSuite::new_suite()),
+        _ => None,
+    }
+}
+
+/// Compute the digest of a domain separator, as committed to the input digest.
+fn domain_digest(domain: &str) -> Digest {
+    let digest = Sha256::digest(domain.as_bytes());
+    Digest::try_from(digest.as_slice()).unwrap()
 }
 
 impl Receipt {
@@ -332,10 +591,293 @@ impl Receipt {
     ) -> Result<(), VerificationError> {
         self.inner.verify_with_context(ctx, image_id, &self.journal)
     }
+
+    /// Verify the integrity of this receipt, and that the guest committed the
+    /// given metadata digest with
+    /// [env::commit_metadata_digest](crate::guest::env::commit_metadata_digest).
+    ///
+    /// The metadata digest is not part of the receipt: like the image ID, it
+    /// is supplied by the verifier, e.g. as the hash of a block it trusts.
+    /// Receipts of guests that commit a metadata digest do not verify with
+    /// [Receipt::verify].
+    pub fn verify_with_metadata_digest(
+        &self,
+        image_id: impl Into<Digest>,
+        metadata_digest: &Digest,
+    ) -> Result<(), VerificationError> {
+        self.inner.verify_with_output(
+            &VerifierContext::default(),
+            image_id,
+            &self.journal,
+            Some(metadata_digest),
+        )
+    }
+
+    /// Verify the integrity of this receipt, and return the [Output] of the
+    /// guest: the digest of its journal, or the head of its journal chain,
+    /// and its metadata digest.
+    ///
+    /// `metadata_digest` is the digest the guest is expected to have
+    /// committed with
+    /// [env::commit_metadata_digest](crate::guest::env::commit_metadata_digest),
+    /// as in [Receipt::verify_with_metadata_digest]. The returned [Output] is
+    /// what a [JournalSliceProof](crate::journal::JournalSliceProof) is
+    /// verified against.
+    pub fn verify_and_get_output(
+        &self,
+        image_id: impl Into<Digest>,
+        metadata_digest: Option<&Digest>,
+    ) -> Result<Output, VerificationError> {
+        let output = self
+            .inner
+            .verify_output(&VerifierContext::default(), image_id)?;
+        check_journal(&self.journal, metadata_digest, &output)
+    }
+
+    /// Verify the integrity of this receipt with the given [VerifierParams],
+    /// including that it was produced by their image.
+    pub fn verify_with_params(&self, params: &VerifierParams) -> Result<(), VerificationError> {
+        self.verify_with_context(&params.context()?, params.image_id)
+    }
+
+    /// Verify the integrity of this receipt, and that the guest terminated
+    /// with the given [ExitCode].
+    ///
+    /// This distinguishes the outcomes of a guest that halts with an
+    /// application-defined status, e.g. a proof of success from a proof of a
+    /// particular failure, without parsing the journal.
+    pub fn verify_exit_code(
+        &self,
+        image_id: impl Into<Digest>,
+        exit_code: ExitCode,
+    ) -> Result<(), VerificationError> {
+        self.verify(image_id)?;
+        if self.get_exit_code()? != exit_code {
+            return Err(VerificationError::UnexpectedExitCode);
+        }
+        Ok(())
+    }
+
+    /// Return the [ExitCode] of the guest, without verifying this receipt.
+    pub fn get_exit_code(&self) -> Result<ExitCode, VerificationError> {
+        self.inner.get_exit_code()
+    }
+
+    /// Verify the integrity of this receipt, calling `on_progress` with the
+    /// number of segments verified so far and the total number of segments
+    /// after each segment is verified.
+    ///
+    /// This allows showing the progress of verifying a receipt of many
+    /// segments, which can take several seconds. Receipts that are not made
+    /// of segments report a single step.
+    pub fn verify_with_progress(
+        &self,
+        image_id: impl Into<Digest>,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<(), VerificationError> {
+        let ctx = VerifierContext::default();
+        match &self.inner {
+            InnerReceipt::Flat(x) => {
+                let output = x.verify_output_with_progress(&ctx, image_id.into(), on_progress)?;
+                check_journal(&self.journal, None, &output).map(|_| ())
+            }
+            _ => {
+                self.verify_with_context(&ctx, image_id)?;
+                on_progress(1, 1);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Prefix identifying a [Receipt] compressed with [Receipt::compress].
+#[cfg(feature = "compress")]
+const COMPRESSED_MAGIC: &[u8; 4] = b"R0ZS";
+
+/// The zstd compression level used by [Receipt::compress].
+#[cfg(feature = "compress")]
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// The largest decoded size, in bytes, of a receipt accepted by
+/// [Receipt::decompress].
+///
+/// This bounds the memory used to decompress untrusted input, which could
+/// otherwise expand to an arbitrary size. It allows flat receipts of over a
+/// thousand segments; use [Receipt::decompress_with_limit] for larger ones.
+#[cfg(feature = "compress")]
+pub const MAX_DECOMPRESSED_SIZE: usize = 256 << 20;
+
+#[cfg(feature = "compress")]
+impl Receipt {
+    /// Encode this receipt and compress it with zstd.
+    ///
+    /// The bulk of a receipt is its seal, so this reduces the cost of sending
+    /// receipts over the network. Use [Receipt::decompress] to decode it.
+    pub fn compress(&self) -> Result<Vec<u8>> {
+        let words = crate::serde::to_vec(self)?;
+        let mut out = COMPRESSED_MAGIC.to_vec();
+        zstd::stream::copy_encode(
+            bytemuck::cast_slice::<u32, u8>(&words),
+            &mut out,
+            COMPRESSION_LEVEL,
+        )?;
+        Ok(out)
+    }
+
+    /// Decode a receipt produced by [Receipt::compress].
+    ///
+    /// Receipts that were encoded with [crate::serde::to_vec] without
+    /// compression are also accepted, so callers do not need to know which
+    /// format they were sent. Fails if the receipt decompresses to more than
+    /// [MAX_DECOMPRESSED_SIZE] bytes.
+    pub fn decompress(bytes: &[u8]) -> Result<Self> {
+        Self::decompress_with_limit(bytes, MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Decode a receipt produced by [Receipt::compress], failing if it
+    /// decompresses to more than `limit` bytes.
+    pub fn decompress_with_limit(bytes: &[u8], limit: usize) -> Result<Self> {
+        use std::io::Read;
+
+        match bytes.strip_prefix(COMPRESSED_MAGIC) {
+            Some(compressed) => {
+                let mut decoded = Vec::new();
+                zstd::stream::Decoder::new(compressed)?
+                    .take(limit as u64 + 1)
+                    .read_to_end(&mut decoded)?;
+                anyhow::ensure!(
+                    decoded.len() <= limit,
+                    "Decompressed receipt exceeds the limit of {limit} bytes"
+                );
+                Self::decode(&decoded)
+            }
+            None => Self::decode(bytes),
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        anyhow::ensure!(
+            bytes.len() % 4 == 0,
+            "Receipt length of {} bytes is not a multiple of 4",
+            bytes.len()
+        );
+        Ok(crate::serde::from_slice(bytes)?)
+    }
+
+    /// Returns the size in bytes of this receipt after [Receipt::compress].
+    pub fn compressed_size(&self) -> Result<usize> {
+        Ok(self.compress()?.len())
+    }
+}
+
+/// The error returned by [verify_batch].
+#[derive(Debug)]
+pub struct BatchVerificationError {
+    /// The index of the first receipt in the batch that failed to verify.
+    pub index: usize,
+
+    /// The reason the receipt failed to verify.
+    pub error: VerificationError,
+}
+
+impl core::fmt::Display for BatchVerificationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "receipt {} failed to verify: {}", self.index, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BatchVerificationError {}
+
+/// Verify many receipts of the same image.
+///
+/// This is equivalent to calling [Receipt::verify] on each receipt, but the
+/// checks that do not depend on the seals are done for the whole batch, with
+/// a single [VerifierContext], before any seal is verified: the hash function
+/// of each receipt must be supported, and its pre-state digest must match
+/// `image_id`. A batch with a receipt of another image fails without spending
+/// the cost of verifying the other seals.
+///
+/// The seals themselves are independent proofs and are verified one by one.
+/// With the `parallel` feature, which is enabled by the default `prove`
+/// feature, they are verified on the rayon thread pool, with one
+/// [VerifierContext] per thread. Builds without `prove`, such as
+/// verifier-only builds, verify seals sequentially unless `parallel` is
+/// enabled explicitly.
+///
+/// On failure, returns the index of a receipt that failed to verify: the
+/// lowest index that fails the checks done up front if any, and otherwise the
+/// lowest index whose seal fails to verify.
+pub fn verify_batch(
+    image_id: impl Into<Digest>,
+    receipts: &[Receipt],
+) -> Result<(), BatchVerificationError> {
+    let image_id = image_id.into();
+    check_batch(image_id, receipts)?;
+
+    #[cfg(feature = "parallel")]
+    let result = {
+        use rayon::prelude::*;
+        receipts
+            .par_iter()
+            .enumerate()
+            .map_init(VerifierContext::default, |ctx, (index, receipt)| {
+                receipt
+                    .verify_with_context(ctx, image_id)
+                    .map_err(|error| BatchVerificationError { index, error })
+            })
+            .find_first(|result| result.is_err())
+    };
+
+    #[cfg(not(feature = "parallel"))]
+    let result = {
+        let ctx = VerifierContext::default();
+        receipts
+            .iter()
+            .enumerate()
+            .map(|(index, receipt)| {
+                receipt
+                    .verify_with_context(&ctx, image_id)
+                    .map_err(|error| BatchVerificationError { index, error })
+            })
+            .find(|result| result.is_err())
+    };
+
+    result.unwrap_or(Ok(()))
+}
+
+// Check the parts of a batch that do not require verifying a seal: that every
+// hash function used by the batch is supported, and that every receipt starts
+// from `image_id`. These are read from the seals, and are authenticated when
+// the seals are verified.
+fn check_batch(image_id: Digest, receipts: &[Receipt]) -> Result<(), BatchVerificationError> {
+    let ctx = VerifierContext::default();
+    let check_suite = |hashfn: &str| match ctx.suites.contains_key(hashfn) {
+        true => Ok(()),
+        false => Err(VerificationError::InvalidHashSuite),
+    };
+    let check = |receipt: &Receipt| -> Result<(), VerificationError> {
+        match &receipt.inner {
+            InnerReceipt::Flat(x) => x.0.iter().try_for_each(|x| check_suite(&x.hashfn))?,
+            InnerReceipt::Succinct(x) => check_suite(x.get_hashfn())?,
+            InnerReceipt::Fake => return Err(VerificationError::InvalidProof),
+        }
+        if receipt.inner.get_metadata()?.pre.digest() != image_id {
+            return Err(VerificationError::ImageVerificationError);
+        }
+        Ok(())
+    };
+    receipts
+        .iter()
+        .enumerate()
+        .try_for_each(|(index, receipt)| {
+            check(receipt).map_err(|error| BatchVerificationError { index, error })
+        })
 }
 
 impl SegmentReceipt {
-    /// Verify the integrity of this receipt.
+    /// Verify the integrity of this receipt, including that it was proven for
+    /// the domain of the given [VerifierContext].
     pub fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
         use hex::FromHex;
         let check_code = |_, control_id: &Digest| -> Result<(), VerificationError> {
@@ -351,7 +893,33 @@ impl SegmentReceipt {
             .suites
             .get(&self.hashfn)
             .ok_or(VerificationError::InvalidHashSuite)?;
-        risc0_zkp::verify::verify(&crate::CIRCUIT, suite, &self.seal, check_code)
+        #[cfg(feature = "metrics")]
+        let start = crate::telemetry::Instant::now();
+        risc0_zkp::verify::verify(&crate::CIRCUIT, suite, &self.seal, check_code)?;
+        #[cfg(feature = "metrics")]
+        crate::telemetry::segment_verified(start);
+        ctx.check_input(&self.get_metadata()?)
+    }
+
+    /// Returns the control ID committed in the seal of this receipt, which
+    /// identifies the version of the circuit it was proven with.
+    ///
+    /// The seal is only read up to the control ID, and is not verified.
+    pub fn get_control_id(&self, ctx: &VerifierContext) -> Result<Digest, VerificationError> {
+        let suite = ctx
+            .suites
+            .get(&self.hashfn)
+            .ok_or(VerificationError::InvalidHashSuite)?;
+        let control_id = core::cell::Cell::new(None);
+        // Stop reading the seal once the control ID is known.
+        let check_code = |_, id: &Digest| -> Result<(), VerificationError> {
+            control_id.set(Some(*id));
+            Err(VerificationError::ControlVerificationError)
+        };
+        let _ = risc0_zkp::verify::verify(&crate::CIRCUIT, suite, &self.seal, check_code);
+        control_id
+            .into_inner()
+            .ok_or(VerificationError::ReceiptFormatError)
     }
 
     /// Returns the [ReceiptMetadata] for this receipt.
@@ -456,6 +1024,7 @@ impl Default for VerifierContext {
                 ("poseidon".into(), PoseidonHashSuite::new_suite()),
                 ("sha-256".into(), Sha256HashSuite::new_suite()),
             ]),
+            domain: None,
         }
     }
 }
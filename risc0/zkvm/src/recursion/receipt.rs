@@ -104,6 +104,13 @@ pub struct SuccinctReceipt {
 }
 
 impl SuccinctReceipt {
+    /// Returns the name of the hash function of the seal of this receipt.
+    ///
+    /// The recursion circuit is always proven with Poseidon.
+    pub fn get_hashfn(&self) -> &'static str {
+        "poseidon"
+    }
+
     /// Verify the integrity of this receipt.
     pub fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
         let valid_ids = valid_control_ids();
@@ -116,7 +123,7 @@ impl SuccinctReceipt {
         };
         let suite = ctx
             .suites
-            .get("poseidon")
+            .get(self.get_hashfn())
             .ok_or(VerificationError::InvalidHashSuite)?;
         // Verify the receipt itself is correct
         risc0_zkp::verify::verify(&CIRCUIT_CORE, suite, &self.seal, check_code)?;
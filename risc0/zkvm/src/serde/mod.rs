@@ -34,17 +34,26 @@
 //! [`env::commit`], so this crate rarely needs to be directly used in the
 //! guest.
 //!
+//! Values that cross the host/guest boundary through the journal can instead
+//! derive [JournalCommit], and be committed with [`env::commit_typed`] and
+//! decoded with [Journal::decode], which fails on a mismatched version or
+//! definition of the type rather than misinterpreting the journal.
+//!
 //! [`env`]: ../guest/env/index.html
+//! [`env::commit_typed`]: ../guest/env/fn.commit_typed.html
 //! [`env::commit`]: ../guest/env/fn.commit.html
 //! [`env::read`]: ../guest/env/fn.read.html
 
 mod deserializer;
 mod err;
 mod serializer;
+mod typed;
 
 pub use deserializer::{from_slice, Deserializer, WordRead};
 pub use err::{Error, Result};
+pub use risc0_zkvm_derive::JournalCommit;
 pub use serializer::{to_vec, to_vec_with_capacity, Serializer, WordWrite};
+pub use typed::{to_vec_typed, Journal, JournalCommit, JournalError};
 
 #[cfg(test)]
 mod tests {
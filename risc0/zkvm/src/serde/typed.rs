@@ -0,0 +1,159 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{from_slice, to_vec, Error};
+
+/// The number of words preceding the encoded value of a typed commit.
+const HEADER_WORDS: usize = 3;
+
+/// A type committed to the journal with a versioned, length-prefixed
+/// encoding, with `env::commit_typed` in the guest and decoded with
+/// [Journal::decode] on the host.
+///
+/// Implement this trait with `#[derive(JournalCommit)]`, and set the version
+/// with `#[journal(version = N)]` whenever the encoding of the type changes.
+pub trait JournalCommit: Serialize + DeserializeOwned {
+    /// The name of the type, used in error messages.
+    const NAME: &'static str;
+
+    /// The version of the schema of the type.
+    const VERSION: u32;
+
+    /// An identifier derived from the names and types of the fields.
+    const SCHEMA_ID: u32;
+}
+
+/// An error produced by [Journal::decode].
+#[derive(Debug, PartialEq)]
+pub enum JournalError {
+    /// The journal is too short to contain the header, or is not a whole
+    /// number of words.
+    Truncated,
+
+    /// The journal was committed with a different version of the type.
+    VersionMismatch {
+        /// The name of the decoded type.
+        name: &'static str,
+        /// The version the host expected.
+        expected: u32,
+        /// The version the guest committed.
+        actual: u32,
+    },
+
+    /// The journal was committed with a different definition of the type, at
+    /// the same version.
+    SchemaMismatch {
+        /// The name of the decoded type.
+        name: &'static str,
+    },
+
+    /// The length prefix does not match the length of the journal.
+    LengthMismatch {
+        /// The length of the value, in words, according to the header.
+        expected: usize,
+        /// The number of words following the header.
+        actual: usize,
+    },
+
+    /// The value could not be deserialized.
+    Deserialize(Error),
+}
+
+impl core::fmt::Display for JournalError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            JournalError::Truncated => write!(f, "journal is too short for a typed commit"),
+            JournalError::VersionMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "journal version mismatch for {name}: expected version {expected}, but the guest \
+                 committed version {actual}"
+            ),
+            JournalError::SchemaMismatch { name } => write!(
+                f,
+                "journal schema mismatch for {name}: the guest was built with a different \
+                 definition of the type"
+            ),
+            JournalError::LengthMismatch { expected, actual } => write!(
+                f,
+                "journal length mismatch: expected {expected} words, found {actual}"
+            ),
+            JournalError::Deserialize(err) => write!(f, "failed to deserialize journal: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JournalError {}
+
+/// Encode a value with its [JournalCommit] header, as committed by
+/// `env::commit_typed`.
+pub fn to_vec_typed<T: JournalCommit>(value: &T) -> Result<Vec<u32>, Error> {
+    let body = to_vec(value)?;
+    let mut words = Vec::with_capacity(HEADER_WORDS + body.len());
+    words.extend_from_slice(&[T::SCHEMA_ID, T::VERSION, body.len() as u32]);
+    words.extend_from_slice(&body);
+    Ok(words)
+}
+
+/// A journal holding a single value of type `T`, committed with
+/// `env::commit_typed`.
+///
+/// ```ignore
+/// let output = Journal::<Output>::decode(&receipt.journal)?;
+/// ```
+pub struct Journal<T>(PhantomData<T>);
+
+impl<T: JournalCommit> Journal<T> {
+    /// Decode the value committed to the given journal.
+    ///
+    /// Fails if the guest committed a different version or definition of
+    /// `T`, rather than silently misinterpreting the journal.
+    pub fn decode(journal: &[u8]) -> Result<T, JournalError> {
+        if journal.len() % 4 != 0 || journal.len() < HEADER_WORDS * 4 {
+            return Err(JournalError::Truncated);
+        }
+        // The journal is not necessarily word aligned, so copy it out.
+        let words: Vec<u32> = journal
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+            .collect();
+        let (header, body) = words.split_at(HEADER_WORDS);
+        if header[1] != T::VERSION {
+            return Err(JournalError::VersionMismatch {
+                name: T::NAME,
+                expected: T::VERSION,
+                actual: header[1],
+            });
+        }
+        if header[0] != T::SCHEMA_ID {
+            return Err(JournalError::SchemaMismatch { name: T::NAME });
+        }
+        if header[2] as usize != body.len() {
+            return Err(JournalError::LengthMismatch {
+                expected: header[2] as usize,
+                actual: body.len(),
+            });
+        }
+        from_slice(body).map_err(JournalError::Deserialize)
+    }
+}
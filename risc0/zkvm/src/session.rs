@@ -15,18 +15,21 @@
 //! This module defines [Session] and [Segment] which provides a way to share
 //! execution traces between the execution phase and the proving phase.
 
-use alloc::collections::BTreeSet;
+use alloc::collections::{BTreeMap, BTreeSet};
 use std::{
+    fmt,
     fs::File,
     io::{Read, Write},
     path::{Path, PathBuf},
 };
 
+use anyhow::{bail, ensure};
 use risc0_binfmt::MemoryImage;
 use risc0_zkp::core::digest::Digest;
+use risc0_zkvm_platform::memory::MEM_SIZE;
 use serde::{Deserialize, Serialize};
 
-use crate::{exec::SyscallRecord, receipt::ExitCode};
+use crate::{exec::SyscallRecord, fault::FaultProof, receipt::ExitCode};
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
 pub struct PageFaults {
@@ -34,6 +37,93 @@ pub struct PageFaults {
     pub(crate) writes: BTreeSet<u32>,
 }
 
+/// Statistics about the paging performed while executing a [Session].
+///
+/// Pages are loaded into the zkVM the first time they are read in each
+/// segment, and written back at the end of the segment if they were modified.
+/// Both cost cycles that are not spent executing guest instructions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PagingStats {
+    /// The number of pages loaded, summed over all segments.
+    pub pages_loaded: usize,
+
+    /// The number of pages written back, summed over all segments.
+    pub pages_stored: usize,
+
+    /// The number of cycles spent loading pages.
+    pub page_read_cycles: usize,
+
+    /// The number of cycles spent writing pages back.
+    pub page_write_cycles: usize,
+}
+
+/// The number of times an instruction was executed, and the cycles spent
+/// executing it, excluding paging.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InsnStats {
+    /// The number of times the instruction was executed.
+    pub count: usize,
+
+    /// The number of cycles spent executing the instruction.
+    pub cycles: usize,
+}
+
+/// Statistics about the instructions executed in a [Session], collected when
+/// enabled with `ExecutorEnvBuilder::execution_stats`.
+///
+/// These show at a glance whether the cycles of a guest go to hashing, memory
+/// operations or arithmetic, and where in the guest they are spent. The
+/// [Display](fmt::Display) implementation prints them as a report.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionStats {
+    /// The executed instructions by mnemonic, e.g. `add` or `lw`. Ecalls are
+    /// keyed by their kind, e.g. `ecall:sha`, and include the cycles of the
+    /// accelerator they invoke.
+    pub opcodes: BTreeMap<String, InsnStats>,
+
+    /// The PCs at which the most cycles were spent, hottest first.
+    pub hot_pcs: Vec<(u32, InsnStats)>,
+}
+
+impl fmt::Display for ExecutionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: usize = self.opcodes.values().map(|x| x.cycles).sum();
+        let percent = |cycles: usize| cycles as f64 * 100.0 / total.max(1) as f64;
+        let mut opcodes: Vec<_> = self.opcodes.iter().collect();
+        opcodes.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles));
+        writeln!(
+            f,
+            "{:<16} {:>12} {:>12} {:>7}",
+            "opcode", "count", "cycles", "%"
+        )?;
+        for (name, stats) in opcodes {
+            writeln!(
+                f,
+                "{name:<16} {:>12} {:>12} {:>6.2}%",
+                stats.count,
+                stats.cycles,
+                percent(stats.cycles)
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<16} {:>12} {:>12} {:>7}",
+            "pc", "count", "cycles", "%"
+        )?;
+        for (pc, stats) in self.hot_pcs.iter() {
+            writeln!(
+                f,
+                "0x{pc:08x}       {:>12} {:>12} {:>6.2}%",
+                stats.count,
+                stats.cycles,
+                percent(stats.cycles)
+            )?;
+        }
+        Ok(())
+    }
+}
+
 /// The execution trace of a program.
 ///
 /// The record of memory transactions of an execution that starts from an
@@ -51,14 +141,54 @@ pub struct Session {
     /// The data publicly committed by the guest program.
     pub journal: Vec<u8>,
 
+    /// The metadata digest committed by the guest program, if any.
+    #[serde(default)]
+    pub metadata_digest: Option<Digest>,
+
     /// The [ExitCode] of the session.
     pub exit_code: ExitCode,
 
+    /// The paging performed during execution.
+    #[serde(default)]
+    pub paging: PagingStats,
+
+    /// If the guest faulted with fault proofs enabled, the proof of the
+    /// fault. In this case, the final [Segment] ends with
+    /// [ExitCode::SystemSplit] just before the faulting instruction.
+    #[serde(default)]
+    pub fault: Option<FaultProof>,
+
+    /// The final memory of the guest, if retained with
+    /// `ExecutorEnvBuilder::retain_memory`.
+    #[serde(skip)]
+    pub(crate) memory: Option<MemoryImage>,
+
+    /// The statistics collected during execution, if enabled.
+    #[serde(default)]
+    pub(crate) stats: Option<ExecutionStats>,
+
     /// The hooks to be called during the proving phase.
     #[serde(skip)]
     pub hooks: Vec<Box<dyn SessionEvents>>,
 }
 
+/// Magic bytes at the start of an encoded [Session], see [Session::encode].
+const SESSION_MAGIC: &[u8; 8] = b"R0SESSN\0";
+
+/// The version of the encoding produced by [Session::encode].
+const SESSION_FORMAT_VERSION: u32 = 1;
+
+/// The body of an encoded [Session], with every segment resolved.
+#[derive(Serialize, Deserialize)]
+struct EncodedSession {
+    segments: Vec<Segment>,
+    journal: Vec<u8>,
+    metadata_digest: Option<Digest>,
+    exit_code: ExitCode,
+    paging: PagingStats,
+    fault: Option<FaultProof>,
+}
+
 /// A reference to a [Segment].
 ///
 /// This allows implementors to determine the best way to represent this in an
@@ -114,7 +244,12 @@ impl Session {
         Self {
             segments,
             journal,
+            metadata_digest: None,
             exit_code,
+            paging: PagingStats::default(),
+            fault: None,
+            memory: None,
+            stats: None,
             hooks: Vec::new(),
         }
     }
@@ -128,6 +263,94 @@ impl Session {
             .collect()
     }
 
+    /// Return the [ExecutionStats] collected while executing this session, if
+    /// enabled with `ExecutorEnvBuilder::execution_stats`.
+    pub fn stats(&self) -> Option<&ExecutionStats> {
+        self.stats.as_ref()
+    }
+
+    /// Read `len` bytes of the final memory of the guest, starting at `addr`.
+    ///
+    /// This is only available if the session was executed with
+    /// `ExecutorEnvBuilder::retain_memory` enabled, and is not preserved by
+    /// [Session::encode]. Memory that the guest never wrote reads as zero.
+    pub fn read_memory(&self, addr: u32, len: usize) -> anyhow::Result<Vec<u8>> {
+        let Some(image) = self.memory.as_ref() else {
+            bail!("guest memory was not retained, see ExecutorEnvBuilder::retain_memory");
+        };
+        let end = (addr as usize).checked_add(len);
+        ensure!(
+            end.map_or(false, |end| end <= MEM_SIZE),
+            "memory range 0x{addr:08x}+{len} is outside of guest memory"
+        );
+        let page_size = image.info.page_size as usize;
+        let mut bytes = vec![0u8; len];
+        let mut offset = 0;
+        while offset < len {
+            let cur = addr as usize + offset;
+            let chunk = (page_size - cur % page_size).min(len - offset);
+            image.load_region_in_page(cur as u32, &mut bytes[offset..offset + chunk]);
+            offset += chunk;
+        }
+        Ok(bytes)
+    }
+
+    /// Encode this [Session] so that it can be proven in another process.
+    ///
+    /// The encoding is self-contained: every [SegmentRef] is resolved and its
+    /// [Segment] is embedded, so the result does not depend on files or other
+    /// state local to this process. It consists of the 8 magic bytes
+    /// `R0SESSN\0`, a little-endian `u32` format version (currently 1), and
+    /// the bincode encoding of the segments, journal, metadata digest, exit
+    /// code, paging statistics, and fault proof, in that order. Hooks are not
+    /// encoded.
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let body = EncodedSession {
+            segments: self.resolve()?,
+            journal: self.journal.clone(),
+            metadata_digest: self.metadata_digest,
+            exit_code: self.exit_code,
+            paging: self.paging.clone(),
+            fault: self.fault.clone(),
+        };
+        let mut bytes = SESSION_MAGIC.to_vec();
+        bytes.extend_from_slice(&SESSION_FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, &body)?;
+        Ok(bytes)
+    }
+
+    /// Decode a [Session] produced by [Session::encode].
+    ///
+    /// The segments of the decoded session are held in memory as
+    /// [SimpleSegmentRef]s.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        let header_len = SESSION_MAGIC.len() + 4;
+        ensure!(
+            bytes.len() >= header_len && bytes.starts_with(SESSION_MAGIC),
+            "not an encoded session"
+        );
+        let version = u32::from_le_bytes(bytes[SESSION_MAGIC.len()..header_len].try_into()?);
+        if version != SESSION_FORMAT_VERSION {
+            bail!("unsupported session format version: {version}");
+        }
+        let body: EncodedSession = bincode::deserialize(&bytes[header_len..])?;
+        Ok(Self {
+            segments: body
+                .segments
+                .into_iter()
+                .map(|segment| Box::new(SimpleSegmentRef::new(segment)) as Box<dyn SegmentRef>)
+                .collect(),
+            journal: body.journal,
+            metadata_digest: body.metadata_digest,
+            exit_code: body.exit_code,
+            paging: body.paging,
+            fault: body.fault,
+            memory: None,
+            stats: None,
+            hooks: Vec::new(),
+        })
+    }
+
     /// Add a hook to be called during the proving phase.
     pub fn add_hook<E: SessionEvents + 'static>(&mut self, hook: E) {
         self.hooks.push(Box::new(hook));
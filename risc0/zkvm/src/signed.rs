@@ -0,0 +1,280 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts signed by the operator that proved them.
+//!
+//! A [SignedReceipt] wraps a [Receipt] with a signature from the proving
+//! operator, so that a receipt can be attributed to whoever produced it. The
+//! signature is over [signing_digest] of the receipt, and is produced by a
+//! [ReceiptSigner]. [SignedReceipt::verify] checks both the zero-knowledge
+//! proof and the signature with a [SignatureVerifier], which also decides
+//! which signers to trust.
+//!
+//! With the `ed25519` feature, `Ed25519Signer` and `Ed25519Verifier` provide
+//! an implementation of these traits using Ed25519 keys.
+
+use alloc::{string::String, vec::Vec};
+
+use risc0_zkp::verify::VerificationError;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    receipt::{Receipt, VerifierContext},
+    serde::to_vec,
+    sha::{Digest, Impl, Sha256},
+};
+
+/// Tag hashed into every [signing_digest], so that signatures over receipts
+/// cannot be confused with signatures over other messages.
+const SIGNING_TAG: &[u8] = b"risc0.SignedReceipt";
+
+/// Produces signatures over receipts on behalf of a proving operator.
+pub trait ReceiptSigner {
+    /// The encoded public key identifying this signer.
+    fn signer(&self) -> Vec<u8>;
+
+    /// Sign the given message, as computed by [signing_digest].
+    fn sign(&self, msg: &Digest) -> Result<Vec<u8>, SignatureError>;
+}
+
+/// Checks signatures produced by a [ReceiptSigner].
+pub trait SignatureVerifier {
+    /// Verify that `sig` is a signature over `msg` by a trusted `signer`.
+    fn verify(&self, signer: &[u8], msg: &Digest, sig: &[u8]) -> Result<(), SignatureError>;
+}
+
+/// An error produced while signing a receipt or checking its signature.
+#[derive(Debug, PartialEq)]
+pub enum SignatureError {
+    /// The signer is not trusted by the [SignatureVerifier].
+    UntrustedSigner,
+
+    /// The signer or signature is not well formed.
+    Malformed(String),
+
+    /// The signature does not match the receipt and signer.
+    InvalidSignature,
+}
+
+impl core::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SignatureError::UntrustedSigner => write!(f, "signer is not trusted"),
+            SignatureError::Malformed(msg) => write!(f, "malformed signature: {msg}"),
+            SignatureError::InvalidSignature => write!(f, "signature is invalid"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignatureError {}
+
+/// The error returned by [SignedReceipt::verify].
+#[derive(Debug, PartialEq)]
+pub enum SignedReceiptError {
+    /// The inner [Receipt] failed to verify.
+    Receipt(VerificationError),
+
+    /// The operator signature failed to verify.
+    Signature(SignatureError),
+}
+
+impl core::fmt::Display for SignedReceiptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SignedReceiptError::Receipt(err) => write!(f, "receipt failed to verify: {err}"),
+            SignedReceiptError::Signature(err) => write!(f, "signature failed to verify: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignedReceiptError {}
+
+/// A [Receipt] together with a signature from the operator that proved it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SignedReceipt {
+    /// The signed receipt.
+    pub receipt: Receipt,
+
+    /// The encoded public key of the signer, see [ReceiptSigner::signer].
+    pub signer: Vec<u8>,
+
+    /// The signature over [signing_digest] of the receipt.
+    pub sig: Vec<u8>,
+}
+
+/// Compute the message signed for the given [Receipt].
+pub fn signing_digest(receipt: &Receipt) -> Digest {
+    let words = to_vec(receipt).unwrap();
+    let mut data = Vec::with_capacity(SIGNING_TAG.len() + words.len() * 4);
+    data.extend_from_slice(SIGNING_TAG);
+    data.extend_from_slice(bytemuck::cast_slice(&words));
+    *Impl::hash_bytes(&data)
+}
+
+impl SignedReceipt {
+    /// Sign the given [Receipt] with the given [ReceiptSigner].
+    pub fn sign(receipt: Receipt, signer: &impl ReceiptSigner) -> Result<Self, SignatureError> {
+        let sig = signer.sign(&signing_digest(&receipt))?;
+        Ok(Self {
+            receipt,
+            signer: signer.signer(),
+            sig,
+        })
+    }
+
+    /// Verify the proof of the inner [Receipt] against the given `image_id`,
+    /// and the operator signature with the given [SignatureVerifier].
+    pub fn verify(
+        &self,
+        image_id: impl Into<Digest>,
+        verifier: &impl SignatureVerifier,
+    ) -> Result<(), SignedReceiptError> {
+        self.verify_with_context(&VerifierContext::default(), image_id, verifier)
+    }
+
+    /// Verify the proof of the inner [Receipt] with the given
+    /// [VerifierContext], and the operator signature with the given
+    /// [SignatureVerifier].
+    pub fn verify_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        verifier: &impl SignatureVerifier,
+    ) -> Result<(), SignedReceiptError> {
+        self.verify_signature(verifier)
+            .map_err(SignedReceiptError::Signature)?;
+        self.receipt
+            .verify_with_context(ctx, image_id)
+            .map_err(SignedReceiptError::Receipt)
+    }
+
+    /// Verify only the operator signature, without checking the proof.
+    pub fn verify_signature(
+        &self,
+        verifier: &impl SignatureVerifier,
+    ) -> Result<(), SignatureError> {
+        verifier.verify(&self.signer, &signing_digest(&self.receipt), &self.sig)
+    }
+}
+
+/// A [ReceiptSigner] using an Ed25519 signing key.
+#[cfg(feature = "ed25519")]
+pub struct Ed25519Signer {
+    key: ed25519_dalek::SigningKey,
+}
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Signer {
+    /// Construct an [Ed25519Signer] from the bytes of a secret key.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            key: ed25519_dalek::SigningKey::from_bytes(secret),
+        }
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl ReceiptSigner for Ed25519Signer {
+    fn signer(&self) -> Vec<u8> {
+        self.key.verifying_key().to_bytes().to_vec()
+    }
+
+    fn sign(&self, msg: &Digest) -> Result<Vec<u8>, SignatureError> {
+        use ed25519_dalek::Signer;
+        Ok(self.key.sign(msg.as_bytes()).to_bytes().to_vec())
+    }
+}
+
+/// A [SignatureVerifier] for signatures produced by an [Ed25519Signer].
+///
+/// Only signatures by one of a fixed set of trusted public keys are accepted;
+/// a verifier with no trusted keys rejects every signature.
+#[cfg(feature = "ed25519")]
+pub struct Ed25519Verifier {
+    trusted: Vec<[u8; 32]>,
+}
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Verifier {
+    /// Construct an [Ed25519Verifier] that only accepts signatures by the
+    /// given public keys.
+    pub fn new(trusted: &[[u8; 32]]) -> Self {
+        Self {
+            trusted: trusted.to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl SignatureVerifier for Ed25519Verifier {
+    fn verify(&self, signer: &[u8], msg: &Digest, sig: &[u8]) -> Result<(), SignatureError> {
+        let signer: [u8; 32] = signer
+            .try_into()
+            .map_err(|_| SignatureError::Malformed("signer must be 32 bytes".into()))?;
+        if !self.trusted.contains(&signer) {
+            return Err(SignatureError::UntrustedSigner);
+        }
+        let key = ed25519_dalek::VerifyingKey::from_bytes(&signer)
+            .map_err(|err| SignatureError::Malformed(err.to_string()))?;
+        let sig = ed25519_dalek::Signature::from_slice(sig)
+            .map_err(|err| SignatureError::Malformed(err.to_string()))?;
+        key.verify_strict(msg.as_bytes(), &sig)
+            .map_err(|_| SignatureError::InvalidSignature)
+    }
+}
+
+#[cfg(all(test, feature = "ed25519"))]
+mod tests {
+    use super::*;
+    use crate::receipt::InnerReceipt;
+
+    #[test]
+    fn ed25519() {
+        let signer = Ed25519Signer::from_bytes(&[1; 32]);
+        let other = Ed25519Signer::from_bytes(&[2; 32]);
+        let key: [u8; 32] = signer.signer().try_into().unwrap();
+        let receipt = Receipt::new(InnerReceipt::Fake, vec![1, 2, 3]);
+        let signed = SignedReceipt::sign(receipt, &signer).unwrap();
+
+        signed
+            .verify_signature(&Ed25519Verifier::new(&[key]))
+            .unwrap();
+        assert_eq!(
+            signed.verify_signature(&Ed25519Verifier::new(&[])),
+            Err(SignatureError::UntrustedSigner)
+        );
+        let other_key: [u8; 32] = other.signer().try_into().unwrap();
+        assert_eq!(
+            signed.verify_signature(&Ed25519Verifier::new(&[other_key])),
+            Err(SignatureError::UntrustedSigner)
+        );
+
+        let mut tampered = signed.clone();
+        tampered.receipt.journal.push(4);
+        assert_eq!(
+            tampered.verify_signature(&Ed25519Verifier::new(&[key])),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // A signature by another key, presented as if made by a trusted one.
+        let mut forged = SignedReceipt::sign(signed.receipt.clone(), &other).unwrap();
+        forged.signer = key.to_vec();
+        assert_eq!(
+            forged.verify_signature(&Ed25519Verifier::new(&[key])),
+            Err(SignatureError::InvalidSignature)
+        );
+    }
+}
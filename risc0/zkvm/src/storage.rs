@@ -0,0 +1,74 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proof of storage: commit the digest of a file streamed through the guest.
+//!
+//! The host provides a file on a file descriptor with
+//! `ExecutorEnvBuilder::stream_file`, and the guest calls
+//! [guest::hash_stream](crate::guest::hash_stream), which reads the file in
+//! chunks of [STREAM_CHUNK_BYTES], hashes it with the SHA accelerator, and
+//! commits a [StreamDigest] to the journal. The hasher state is kept in guest
+//! memory, so a file may span any number of segments.
+//!
+//! ```ignore
+//! // Host
+//! let env = ExecutorEnv::builder()
+//!     .stream_file(STREAM_FD, File::open(path)?)
+//!     .build()?;
+//!
+//! // Guest
+//! risc0_zkvm::guest::hash_stream(STREAM_FD);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::sha::Digest;
+
+/// The number of bytes the guest reads from the host at a time in
+/// [guest::hash_stream](crate::guest::hash_stream).
+pub const STREAM_CHUNK_BYTES: usize = 16 * 1024;
+
+/// The SHA-256 digest and length of a stream, as committed by
+/// [guest::hash_stream](crate::guest::hash_stream).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StreamDigest {
+    /// The SHA-256 digest of the stream.
+    pub digest: Digest,
+
+    /// The number of bytes in the stream.
+    pub len: u64,
+}
+
+#[cfg(feature = "std")]
+impl StreamDigest {
+    /// Compute the [StreamDigest] of the given reader, e.g. to check the
+    /// journal of a receipt against a local copy of a file.
+    pub fn from_reader(mut reader: impl std::io::Read) -> std::io::Result<Self> {
+        use crate::sha::rust_crypto::{Digest as _, Sha256};
+
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; STREAM_CHUNK_BYTES];
+        let mut len = 0;
+        loop {
+            let nread = reader.read(&mut buf)?;
+            if nread == 0 {
+                break;
+            }
+            hasher.update(&buf[..nread]);
+            len += nread as u64;
+        }
+        let digest = Digest::try_from(hasher.finalize().as_slice()).unwrap();
+        Ok(Self { digest, len })
+    }
+}
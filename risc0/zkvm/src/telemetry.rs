@@ -0,0 +1,69 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instrumentation exported through the [metrics](https://docs.rs/metrics)
+//! facade when the `metrics` feature is enabled.
+//!
+//! The following metrics are recorded:
+//!
+//! - `risc0_zkvm_executor_segments_total` (counter): segments produced by the
+//!   executor.
+//! - `risc0_zkvm_executor_cycles_total` (counter): cycles (padded to po2)
+//!   executed.
+//! - `risc0_zkvm_executor_insn_cycles_total` (counter): cycles spent executing
+//!   instructions.
+//! - `risc0_zkvm_prover_segments_total` (counter): segments proven.
+//! - `risc0_zkvm_prover_segment_seconds` (histogram): wall clock seconds to
+//!   prove a segment.
+//! - `risc0_zkvm_prover_seal_bytes` (histogram): size of each segment seal in
+//!   bytes.
+//! - `risc0_zkvm_verifier_segments_total` (counter): segment receipts verified.
+//! - `risc0_zkvm_verifier_segment_seconds` (histogram): wall clock seconds to
+//!   verify a segment.
+//!
+//! Without the `metrics` feature, all of these functions compile to nothing.
+
+#[cfg(feature = "metrics")]
+pub(crate) use std::time::Instant;
+
+#[cfg(feature = "prove")]
+pub(crate) fn segment_executed(po2: usize, insn_cycles: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("risc0_zkvm_executor_segments_total", 1);
+        metrics::counter!("risc0_zkvm_executor_cycles_total", 1 << po2);
+        metrics::counter!("risc0_zkvm_executor_insn_cycles_total", insn_cycles as u64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (po2, insn_cycles);
+}
+
+#[cfg(all(feature = "prove", feature = "metrics"))]
+pub(crate) fn segment_proved(start: Instant, seal_bytes: usize) {
+    metrics::counter!("risc0_zkvm_prover_segments_total", 1);
+    metrics::histogram!(
+        "risc0_zkvm_prover_segment_seconds",
+        start.elapsed().as_secs_f64()
+    );
+    metrics::histogram!("risc0_zkvm_prover_seal_bytes", seal_bytes as f64);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn segment_verified(start: Instant) {
+    metrics::counter!("risc0_zkvm_verifier_segments_total", 1);
+    metrics::histogram!(
+        "risc0_zkvm_verifier_segment_seconds",
+        start.elapsed().as_secs_f64()
+    );
+}
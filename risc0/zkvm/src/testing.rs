@@ -0,0 +1,366 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Unit tests that run inside the zkVM.
+//!
+//! Functions of a guest annotated with [risc0_test] are compiled into the
+//! guest like any other code, and recorded in the `.risc0.tests` section of
+//! the ELF. A guest binary holding tests uses [test_main!](crate::test_main)
+//! as its entry point:
+//!
+//! ```ignore
+//! #![no_main]
+//!
+//! use risc0_zkvm::testing::risc0_test;
+//!
+//! risc0_zkvm::test_main!();
+//!
+//! #[risc0_test]
+//! fn adds() {
+//!     assert_eq!(1 + 1, 2);
+//! }
+//! ```
+//!
+//! On the host, a test target with `harness = false` passes the ELF of the
+//! guest to [main], which runs each test in the executor and reports the
+//! results as `cargo test` does, including filtering by name:
+//!
+//! ```ignore
+//! fn main() -> std::process::ExitCode {
+//!     risc0_zkvm::testing::main(methods::GUEST_TESTS_ELF)
+//! }
+//! ```
+//!
+//! A test passes if the guest halts without panicking. Tests are only
+//! executed by default; with `--prove` or `RISC0_TEST_PROVE=1`, each test is
+//! also proven and its receipt verified.
+
+#[cfg(not(target_os = "zkvm"))]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(not(target_os = "zkvm"))]
+use anyhow::{anyhow, bail, Result};
+pub use risc0_zkvm_derive::risc0_test;
+
+/// The name of the ELF section recording the tests of a guest.
+pub const TESTS_SECTION: &str = ".risc0.tests";
+
+/// The record of a test in the [TESTS_SECTION], written by [risc0_test].
+///
+/// The guest is 32-bit, so a record is the address of the test function, the
+/// length of its name, and the name padded to a multiple of 4 bytes.
+#[doc(hidden)]
+#[repr(C)]
+pub struct TestRecord<const N: usize> {
+    func: fn(),
+    len: u32,
+    name: [u8; N],
+}
+
+impl<const N: usize> TestRecord<N> {
+    #[doc(hidden)]
+    pub const fn new(func: fn(), name: &str) -> Self {
+        let bytes = name.as_bytes();
+        let mut out = [0u8; N];
+        let mut idx = 0;
+        while idx < N {
+            out[idx] = bytes[idx];
+            idx += 1;
+        }
+        Self {
+            func,
+            len: N as u32,
+            name: out,
+        }
+    }
+}
+
+/// The entry point of a guest built by [test_main!](crate::test_main).
+///
+/// Reads the address of a test function, as found by the host in the
+/// [TESTS_SECTION], and calls it.
+#[cfg(target_os = "zkvm")]
+pub fn guest_main() {
+    let addr: u32 = crate::guest::env::read();
+    // SAFETY: the host only passes addresses of functions recorded by
+    // `risc0_test`, which all have the signature `fn()`.
+    let func: fn() = unsafe { core::mem::transmute(addr as usize) };
+    func()
+}
+
+/// Use the test runner as the entry point of a guest holding tests annotated
+/// with [risc0_test].
+#[macro_export]
+macro_rules! test_main {
+    () => {
+        $crate::entry!($crate::testing::guest_main);
+    };
+}
+
+/// A test recorded in the ELF of a guest.
+#[cfg(not(target_os = "zkvm"))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuestTest {
+    /// The path of the test function within the guest crate, e.g.
+    /// `sha::cycle_count`.
+    pub name: String,
+
+    /// The address of the test function in the guest.
+    pub addr: u32,
+}
+
+/// Parse the contents of the [TESTS_SECTION] of a guest.
+#[cfg(not(target_os = "zkvm"))]
+pub fn parse_tests(section: &[u8]) -> Result<Vec<GuestTest>> {
+    let word = |offset: usize| -> Result<u32> {
+        let bytes = section
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("truncated test record"))?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let mut tests = Vec::new();
+    let mut offset = 0;
+    while offset < section.len() {
+        let addr = word(offset)?;
+        let len = word(offset + 4)? as usize;
+        let name = section
+            .get(offset + 8..offset + 8 + len)
+            .ok_or_else(|| anyhow!("truncated test record"))?;
+        let name = core::str::from_utf8(name).map_err(|_| anyhow!("test name is not UTF-8"))?;
+        // Names are recorded with the name of the guest crate, which is the
+        // same for all tests and left out as `cargo test` does.
+        let name = name.split_once("::").map_or(name, |(_, path)| path);
+        if tests.iter().any(|x: &GuestTest| x.name == name) {
+            bail!("test recorded more than once: {name}");
+        }
+        tests.push(GuestTest {
+            name: name.into(),
+            addr,
+        });
+        offset += 8 + crate::align_up(len, 4);
+    }
+    Ok(tests)
+}
+
+/// List the tests recorded in the given guest ELF, sorted by name.
+#[cfg(not(target_os = "zkvm"))]
+pub fn list(elf: &[u8]) -> Result<Vec<GuestTest>> {
+    let mut tests = match risc0_binfmt::read_elf_section(elf, TESTS_SECTION)? {
+        Some(section) => parse_tests(&section)?,
+        None => Vec::new(),
+    };
+    tests.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tests)
+}
+
+/// Run a test of the given guest ELF in the executor, and if `prove` is set,
+/// prove it and verify the receipt.
+///
+/// Returns an error if the guest panics or otherwise fails to halt
+/// successfully.
+#[cfg(feature = "prove")]
+pub fn run(elf: &[u8], test: &GuestTest, prove: bool) -> Result<()> {
+    use crate::{serde::to_vec, Executor, ExecutorEnv, ExitCode};
+
+    let env = ExecutorEnv::builder()
+        .add_input(&to_vec(&test.addr)?)
+        .build()?;
+    let image_id = env.image_id(elf)?;
+    let session = Executor::from_elf(env, elf)?.run()?;
+    if session.exit_code != ExitCode::Halted(0) {
+        bail!("guest exited with {:?}", session.exit_code);
+    }
+    if prove {
+        session.prove()?.verify(image_id)?;
+    }
+    Ok(())
+}
+
+// The options of the libtest harness understood by [main].
+#[cfg(feature = "prove")]
+#[derive(Default)]
+struct Options {
+    filters: Vec<String>,
+    skip: Vec<String>,
+    exact: bool,
+    list: bool,
+    prove: bool,
+}
+
+#[cfg(feature = "prove")]
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut opts = Options {
+            prove: matches!(std::env::var("RISC0_TEST_PROVE").as_deref(), Ok("1")),
+            ..Default::default()
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--exact" => opts.exact = true,
+                "--list" => opts.list = true,
+                "--prove" => opts.prove = true,
+                "--skip" => opts.skip.extend(args.next()),
+                // Options of libtest with a value that do not apply here.
+                "--color" | "--format" | "--logfile" | "--test-threads" | "-Z" => {
+                    args.next();
+                }
+                _ if arg.starts_with('-') => {}
+                _ => opts.filters.push(arg),
+            }
+        }
+        opts
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let matches = |filter: &String| match self.exact {
+            true => name == filter,
+            false => name.contains(filter.as_str()),
+        };
+        (self.filters.is_empty() || self.filters.iter().any(matches))
+            && !self.skip.iter().any(matches)
+    }
+}
+
+/// Run the tests of the given guest ELF as the `main` function of a test
+/// target with `harness = false`, and report the results as `cargo test`
+/// does.
+///
+/// Accepts the filters and the `--exact`, `--skip` and `--list` options of
+/// `cargo test`, and `--prove` to prove each test in addition to executing
+/// it.
+#[cfg(feature = "prove")]
+pub fn main(elf: &[u8]) -> std::process::ExitCode {
+    use std::{process::ExitCode, time::Instant};
+
+    let opts = Options::parse(std::env::args().skip(1));
+    let tests = match list(elf) {
+        Ok(tests) => tests,
+        Err(err) => {
+            eprintln!("error: unable to list the tests of the guest: {err:#}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (tests, filtered): (Vec<_>, Vec<_>) =
+        tests.into_iter().partition(|x| opts.matches(&x.name));
+
+    if opts.list {
+        for test in tests.iter() {
+            println!("{}: test", test.name);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let start = Instant::now();
+    println!();
+    println!("running {} tests", tests.len());
+    let mut failures = Vec::new();
+    for test in tests.iter() {
+        match run(elf, test, opts.prove) {
+            Ok(()) => println!("test {} ... ok", test.name),
+            Err(err) => {
+                println!("test {} ... FAILED", test.name);
+                failures.push((&test.name, err));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!();
+        println!("failures:");
+        for (name, err) in failures.iter() {
+            println!();
+            println!("---- {name} ----");
+            println!("{err:#}");
+        }
+        println!();
+        println!("failures:");
+        for (name, _) in failures.iter() {
+            println!("    {name}");
+        }
+    }
+    println!();
+    println!(
+        "test result: {}. {} passed; {} failed; 0 ignored; 0 measured; {} filtered out; finished in {:.2}s",
+        match failures.is_empty() {
+            true => "ok",
+            false => "FAILED",
+        },
+        tests.len() - failures.len(),
+        failures.len(),
+        filtered.len(),
+        start.elapsed().as_secs_f64()
+    );
+    println!();
+    match failures.is_empty() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_section() {
+        let mut section = Vec::new();
+        for (addr, name) in [(0x1000u32, "guest::a::b"), (0x2000, "guest::c")] {
+            section.extend(addr.to_le_bytes());
+            section.extend((name.len() as u32).to_le_bytes());
+            section.extend(name.as_bytes());
+            section.resize(crate::align_up(section.len(), 4), 0);
+        }
+        let tests = parse_tests(&section).unwrap();
+        assert_eq!(
+            tests,
+            [
+                GuestTest {
+                    name: "a::b".into(),
+                    addr: 0x1000
+                },
+                GuestTest {
+                    name: "c".into(),
+                    addr: 0x2000
+                },
+            ]
+        );
+
+        assert!(parse_tests(&section[..section.len() - 4]).is_err());
+        let twice = [section.as_slice(), section.as_slice()].concat();
+        assert!(parse_tests(&twice).is_err());
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn list_guest_tests() {
+        use risc0_zkvm_methods::{GUEST_TESTS_ELF, MULTI_TEST_ELF};
+
+        let tests = list(GUEST_TESTS_ELF).unwrap();
+        let names: Vec<&str> = tests.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, ["libm_build", "rsa_compat", "sha_cycle_count"]);
+        assert!(list(MULTI_TEST_ELF).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn filter_options() {
+        let args = "sha --skip sha::slow --test-threads 1 --prove".split(' ');
+        let opts = Options::parse(args.map(String::from));
+        assert!(opts.prove);
+        assert_eq!(opts.filters, ["sha"]);
+        assert!(opts.matches("sha::cycle_count"));
+        assert!(!opts.matches("sha::slow"));
+        assert!(!opts.matches("rsa"));
+    }
+}
@@ -0,0 +1,860 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts bound to an RFC 3161 timestamp token.
+//!
+//! A [TimestampedReceipt] wraps a [Receipt] with a timestamp token issued by a
+//! time-stamping authority (TSA) over the
+//! [claim_digest](crate::attest::claim_digest) of the receipt, the same digest
+//! that attestations bind, so that it shows the claim was proven no later
+//! than the time in the token.
+//!
+//! The token is requested with [TimestampedReceipt::request], which encodes
+//! an RFC 3161 `TimeStampReq` and sends it with a [TimestampAuthority], e.g.
+//! an HTTP client posting `application/timestamp-query` to the TSA, and checks
+//! that the token answers the request. [TimestampedReceipt::verify] checks
+//! the proof, has a [TokenVerifier] check the CMS signature of the token, and
+//! then checks that the token covers the claim, returning its time.
+//! [RsaTokenVerifier] checks the signatures of TSAs with pinned RSA keys.
+//!
+//! Tokens are decoded with the `cms` and `x509-cert` crates. The RFC 3161
+//! structures they do not provide are defined here.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cms::{
+    content_info::ContentInfo,
+    signed_data::{SignedAttributes, SignedData},
+};
+use der::{
+    asn1::{Any, BitString, Int, ObjectIdentifier, OctetString},
+    Decode, Encode, Sequence, Tag, Tagged,
+};
+use risc0_zkp::verify::VerificationError;
+use rsa::{
+    pkcs1v15::{Signature, VerifyingKey},
+    pkcs8::DecodePublicKey,
+    sha2::Sha256 as RsaSha256,
+    signature::Verifier,
+    RsaPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use x509_cert::{
+    ext::{pkix::name::GeneralName, Extensions},
+    spki::AlgorithmIdentifierOwned,
+};
+
+use crate::{
+    attest::claim_digest,
+    receipt::{Receipt, VerifierContext},
+    sha::{Digest, Impl, Sha256},
+};
+
+/// The object identifier of SHA-256.
+const ID_SHA_256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+
+/// The object identifier of `rsaEncryption`.
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+/// The object identifier of `sha256WithRSAEncryption`.
+const SHA_256_WITH_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// The object identifier of the CMS `id-signedData` content type.
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+
+/// The object identifier of the CMS `contentType` attribute.
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+
+/// The object identifier of the CMS `messageDigest` attribute.
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+
+/// The object identifier of `id-ct-TSTInfo`.
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// Sends timestamp requests to a time-stamping authority.
+pub trait TimestampAuthority {
+    /// Send the given DER encoded `TimeStampReq` and return the DER encoded
+    /// `TimeStampResp`.
+    fn timestamp(&self, request: &[u8]) -> Result<Vec<u8>, TimestampError>;
+}
+
+/// Checks the signatures of timestamp tokens.
+pub trait TokenVerifier {
+    /// Verify the CMS signature of the given token, a DER encoded
+    /// `ContentInfo`, and that it was signed by a trusted TSA.
+    fn verify(&self, token: &[u8]) -> Result<(), TimestampError>;
+}
+
+/// An error produced while obtaining or checking a timestamp token.
+#[derive(Debug, PartialEq)]
+pub enum TimestampError {
+    /// The TSA could not be reached or rejected the request.
+    Authority(String),
+
+    /// The response or token is not well formed.
+    Malformed(String),
+
+    /// The token was not signed by a trusted TSA.
+    UntrustedToken,
+
+    /// The token covers a different digest than the claim of the receipt.
+    DigestMismatch,
+
+    /// The token does not carry the nonce of the request.
+    NonceMismatch,
+
+    /// The claim of the receipt could not be read.
+    Claim(VerificationError),
+}
+
+impl core::fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TimestampError::Authority(msg) => write!(f, "timestamp authority error: {msg}"),
+            TimestampError::Malformed(msg) => write!(f, "malformed timestamp token: {msg}"),
+            TimestampError::UntrustedToken => write!(f, "timestamp token is not trusted"),
+            TimestampError::DigestMismatch => {
+                write!(f, "timestamp token does not cover the receipt claim")
+            }
+            TimestampError::NonceMismatch => {
+                write!(f, "timestamp token does not answer the request")
+            }
+            TimestampError::Claim(err) => write!(f, "unable to read the receipt claim: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TimestampError {}
+
+impl From<der::Error> for TimestampError {
+    fn from(err: der::Error) -> Self {
+        TimestampError::Malformed(err.to_string())
+    }
+}
+
+/// The error returned by [TimestampedReceipt::verify].
+#[derive(Debug, PartialEq)]
+pub enum TimestampedReceiptError {
+    /// The inner [Receipt] failed to verify.
+    Receipt(VerificationError),
+
+    /// The timestamp token failed to verify.
+    Timestamp(TimestampError),
+}
+
+impl core::fmt::Display for TimestampedReceiptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TimestampedReceiptError::Receipt(err) => write!(f, "receipt failed to verify: {err}"),
+            TimestampedReceiptError::Timestamp(err) => {
+                write!(f, "timestamp failed to verify: {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimestampedReceiptError {}
+
+/// A [Receipt] together with a timestamp token over its claim.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TimestampedReceipt {
+    /// The timestamped receipt.
+    pub receipt: Receipt,
+
+    /// The DER encoded timestamp token, a CMS `ContentInfo`.
+    pub token: Vec<u8>,
+}
+
+// MessageImprint ::= SEQUENCE { hashAlgorithm, hashedMessage }
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct MessageImprint {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetString,
+}
+
+// TimeStampReq of RFC 3161.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TimeStampReq {
+    version: u8,
+    message_imprint: MessageImprint,
+    #[asn1(optional = "true")]
+    req_policy: Option<ObjectIdentifier>,
+    #[asn1(optional = "true")]
+    nonce: Option<u64>,
+    #[asn1(default = "Default::default")]
+    cert_req: bool,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+// PKIStatusInfo of RFC 3161.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct PkiStatusInfo {
+    status: u8,
+    #[asn1(optional = "true")]
+    status_string: Option<Vec<String>>,
+    #[asn1(optional = "true")]
+    fail_info: Option<BitString>,
+}
+
+// TimeStampResp of RFC 3161.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TimeStampResp {
+    status: PkiStatusInfo,
+    #[asn1(optional = "true")]
+    time_stamp_token: Option<ContentInfo>,
+}
+
+// Accuracy of RFC 3161.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct Accuracy {
+    #[asn1(optional = "true")]
+    seconds: Option<u64>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    micros: Option<u16>,
+}
+
+// TSTInfo of RFC 3161.
+//
+// The genTime is kept as it was encoded: RFC 3161 allows fractional seconds,
+// which `der::asn1::GeneralizedTime` does not, so it is parsed by
+// [parse_generalized_time].
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TstInfo {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: MessageImprint,
+    serial_number: Int,
+    gen_time: Any,
+    #[asn1(optional = "true")]
+    accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    ordering: bool,
+    #[asn1(optional = "true")]
+    nonce: Option<Int>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    tsa: Option<GeneralName>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+impl TstInfo {
+    // Check that this TSTInfo covers the given SHA-256 digest.
+    fn check_digest(&self, digest: &Digest) -> Result<(), TimestampError> {
+        if self.message_imprint.hash_algorithm.oid != ID_SHA_256 {
+            return Err(TimestampError::Malformed(
+                "message imprint is not SHA-256".into(),
+            ));
+        }
+        if self.message_imprint.hashed_message.as_bytes() != digest.as_bytes() {
+            return Err(TimestampError::DigestMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Encode an RFC 3161 `TimeStampReq` for the given SHA-256 digest.
+///
+/// The request asks the TSA to include its certificate in the token.
+pub fn timestamp_request(digest: &Digest, nonce: u64) -> Vec<u8> {
+    TimeStampReq {
+        version: 1,
+        message_imprint: MessageImprint {
+            hash_algorithm: sha256_algorithm(),
+            hashed_message: OctetString::new(digest.as_bytes()).unwrap(),
+        },
+        req_policy: None,
+        nonce: Some(nonce),
+        cert_req: true,
+        extensions: None,
+    }
+    .to_der()
+    .unwrap()
+}
+
+/// Extract the timestamp token from a DER encoded `TimeStampResp`.
+///
+/// Fails if the TSA did not grant the request.
+pub fn token_from_response(response: &[u8]) -> Result<Vec<u8>, TimestampError> {
+    let response = TimeStampResp::from_der(response)?;
+    // granted (0) or grantedWithMods (1)
+    if response.status.status > 1 {
+        return Err(TimestampError::Authority(format!(
+            "request was rejected with status {}: {}",
+            response.status.status,
+            response.status.status_string.unwrap_or_default().join("; ")
+        )));
+    }
+    let token = response
+        .time_stamp_token
+        .ok_or_else(|| TimestampError::Malformed("response has no token".into()))?;
+    Ok(token.to_der()?)
+}
+
+/// Check that a token covers the given digest, returning the time it was
+/// issued.
+///
+/// The CMS signature of the token is checked with the given [TokenVerifier].
+pub fn verify_token(
+    token: &[u8],
+    digest: &Digest,
+    verifier: &impl TokenVerifier,
+) -> Result<SystemTime, TimestampError> {
+    verifier.verify(token)?;
+    let (_, tst_info) = signed_content(token)?;
+    let tst_info = TstInfo::from_der(&tst_info)?;
+    tst_info.check_digest(digest)?;
+    if tst_info.gen_time.tag() != Tag::GeneralizedTime {
+        return Err(TimestampError::Malformed(
+            "genTime is not a GeneralizedTime".into(),
+        ));
+    }
+    parse_generalized_time(tst_info.gen_time.value())
+}
+
+/// A [TokenVerifier] for tokens signed with RSA by a TSA whose public key is
+/// pinned.
+///
+/// This checks that the token is signed, with PKCS #1 v1.5 and SHA-256, over
+/// signed attributes whose content type is `id-ct-TSTInfo` and whose message
+/// digest is the SHA-256 digest of the `TSTInfo`, by one of the trusted keys.
+/// The certificates in the token are not used, so the keys are trusted as
+/// given rather than through a certificate chain.
+pub struct RsaTokenVerifier {
+    keys: Vec<RsaPublicKey>,
+}
+
+impl RsaTokenVerifier {
+    /// Construct a verifier trusting the given keys, each a DER encoded
+    /// `SubjectPublicKeyInfo`, as found in the certificate of a TSA.
+    pub fn from_public_keys_der(keys: &[&[u8]]) -> Result<Self, TimestampError> {
+        let keys = keys
+            .iter()
+            .map(|der| {
+                RsaPublicKey::from_public_key_der(der)
+                    .map_err(|err| TimestampError::Malformed(format!("invalid RSA key: {err}")))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { keys })
+    }
+}
+
+impl TokenVerifier for RsaTokenVerifier {
+    fn verify(&self, token: &[u8]) -> Result<(), TimestampError> {
+        let (signed_data, tst_info) = signed_content(token)?;
+        let signer = signed_data
+            .signer_infos
+            .0
+            .iter()
+            .next()
+            .ok_or_else(|| TimestampError::Malformed("token has no signer".into()))?;
+        if signer.digest_alg.oid != ID_SHA_256 {
+            return Err(TimestampError::Malformed(
+                "token is not signed with SHA-256".into(),
+            ));
+        }
+        let oid = signer.signature_algorithm.oid;
+        if oid != RSA_ENCRYPTION && oid != SHA_256_WITH_RSA_ENCRYPTION {
+            return Err(TimestampError::Malformed(
+                "token is not signed with RSA".into(),
+            ));
+        }
+        let signed_attrs = signer
+            .signed_attrs
+            .as_ref()
+            .ok_or_else(|| TimestampError::Malformed("token has no signed attributes".into()))?;
+        let content_type: ObjectIdentifier =
+            signed_attr(signed_attrs, ID_CONTENT_TYPE)?.decode_as()?;
+        if content_type != ID_CT_TST_INFO {
+            return Err(TimestampError::Malformed(
+                "signed attributes are not for a TSTInfo".into(),
+            ));
+        }
+        let message_digest: OctetString =
+            signed_attr(signed_attrs, ID_MESSAGE_DIGEST)?.decode_as()?;
+        if message_digest.as_bytes() != Impl::hash_bytes(&tst_info).as_bytes() {
+            return Err(TimestampError::UntrustedToken);
+        }
+        let signature = Signature::try_from(signer.signature.as_bytes())
+            .map_err(|_| TimestampError::Malformed("invalid RSA signature".into()))?;
+        // The signature covers the DER encoding of the signed attributes as a
+        // SET, rather than with the implicit tag they have in the SignerInfo.
+        let message = signed_attrs.to_der()?;
+        let trusted = self.keys.iter().any(|key| {
+            VerifyingKey::<RsaSha256>::new(key.clone())
+                .verify(&message, &signature)
+                .is_ok()
+        });
+        match trusted {
+            true => Ok(()),
+            false => Err(TimestampError::UntrustedToken),
+        }
+    }
+}
+
+impl TimestampedReceipt {
+    /// Obtain a timestamp token for the given [Receipt] from a
+    /// [TimestampAuthority].
+    ///
+    /// Fails if the token does not cover the claim of the receipt, or does
+    /// not carry the given nonce, i.e. was not issued for this request. The
+    /// signature of the token is only checked by [TimestampedReceipt::verify].
+    pub fn request(
+        receipt: Receipt,
+        tsa: &impl TimestampAuthority,
+        nonce: u64,
+    ) -> Result<Self, TimestampError> {
+        let digest = claim_digest(&receipt).map_err(TimestampError::Claim)?;
+        let request = timestamp_request(&digest, nonce);
+        let token = token_from_response(&tsa.timestamp(&request)?)?;
+        check_token_answers(&token, &digest, nonce)?;
+        Ok(Self { receipt, token })
+    }
+
+    /// Verify the proof of the inner [Receipt] against the given `image_id`,
+    /// and that the timestamp token covers its claim, returning the time the
+    /// token was issued.
+    pub fn verify(
+        &self,
+        image_id: impl Into<Digest>,
+        verifier: &impl TokenVerifier,
+    ) -> Result<SystemTime, TimestampedReceiptError> {
+        self.verify_with_context(&VerifierContext::default(), image_id, verifier)
+    }
+
+    /// Verify the proof of the inner [Receipt] with the given
+    /// [VerifierContext], and that the timestamp token covers its claim,
+    /// returning the time the token was issued.
+    pub fn verify_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        verifier: &impl TokenVerifier,
+    ) -> Result<SystemTime, TimestampedReceiptError> {
+        self.receipt
+            .verify_with_context(ctx, image_id)
+            .map_err(TimestampedReceiptError::Receipt)?;
+        let digest = claim_digest(&self.receipt).map_err(TimestampedReceiptError::Receipt)?;
+        verify_token(&self.token, &digest, verifier).map_err(TimestampedReceiptError::Timestamp)
+    }
+}
+
+// Check that a token covers the given digest and carries the given nonce,
+// i.e. that it answers the request for them.
+fn check_token_answers(token: &[u8], digest: &Digest, nonce: u64) -> Result<(), TimestampError> {
+    let (_, tst_info) = signed_content(token)?;
+    let tst_info = TstInfo::from_der(&tst_info)?;
+    tst_info.check_digest(digest)?;
+    let expected = nonce.to_der()?;
+    match tst_info.nonce.map(|x| x.to_der()).transpose()? {
+        Some(actual) if actual == expected => Ok(()),
+        _ => Err(TimestampError::NonceMismatch),
+    }
+}
+
+// Decode the SignedData of a token, a ContentInfo holding a CMS SignedData,
+// and return it with the DER encoded TSTInfo it encapsulates.
+fn signed_content(token: &[u8]) -> Result<(SignedData, Vec<u8>), TimestampError> {
+    let content_info = ContentInfo::from_der(token)?;
+    if content_info.content_type != ID_SIGNED_DATA {
+        return Err(TimestampError::Malformed(
+            "token does not hold a SignedData".into(),
+        ));
+    }
+    let signed_data = SignedData::from_der(&content_info.content.to_der()?)?;
+    let encap = &signed_data.encap_content_info;
+    if encap.econtent_type != ID_CT_TST_INFO {
+        return Err(TimestampError::Malformed(
+            "token does not hold a TSTInfo".into(),
+        ));
+    }
+    let tst_info: OctetString = encap
+        .econtent
+        .as_ref()
+        .ok_or_else(|| TimestampError::Malformed("token has no content".into()))?
+        .decode_as()?;
+    let tst_info = tst_info.as_bytes().to_vec();
+    Ok((signed_data, tst_info))
+}
+
+// Return the single value of the signed attribute of the given type.
+fn signed_attr(attrs: &SignedAttributes, oid: ObjectIdentifier) -> Result<&Any, TimestampError> {
+    let mut values = attrs
+        .iter()
+        .filter(|attr| attr.oid == oid)
+        .flat_map(|attr| attr.values.iter());
+    match (values.next(), values.next()) {
+        (Some(value), None) => Ok(value),
+        _ => Err(TimestampError::Malformed(format!(
+            "token must have one signed attribute {oid}"
+        ))),
+    }
+}
+
+fn sha256_algorithm() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ID_SHA_256,
+        parameters: Some(Any::null()),
+    }
+}
+
+// Parse a GeneralizedTime of the form YYYYMMDDhhmmss[.f*]Z.
+fn parse_generalized_time(time: &[u8]) -> Result<SystemTime, TimestampError> {
+    let malformed = || TimestampError::Malformed("invalid genTime".into());
+    let time = core::str::from_utf8(time).map_err(|_| malformed())?;
+    let time = time.strip_suffix('Z').ok_or_else(malformed)?;
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    if time.len() != 14 || !time.bytes().all(|x| x.is_ascii_digit()) {
+        return Err(malformed());
+    }
+    let field = |range: core::ops::Range<usize>| time[range].parse::<i64>().unwrap();
+    let (year, month, day) = (field(0..4), field(4..6), field(6..8));
+    let (hour, minute, second) = (field(8..10), field(10..12), field(12..14));
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(malformed());
+    }
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    let secs = u64::try_from(secs).map_err(|_| malformed())?;
+    let nanos = match fraction {
+        "" => 0,
+        _ if fraction.len() <= 9 && fraction.bytes().all(|x| x.is_ascii_digit()) => {
+            fraction.parse::<u32>().unwrap() * 10u32.pow(9 - fraction.len() as u32)
+        }
+        _ => return Err(malformed()),
+    };
+    Ok(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+// The number of days in the given month of the proleptic Gregorian calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// The number of days since 1970-01-01 of the given date in the proleptic
+// Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use cms::{
+        content_info::CmsVersion,
+        signed_data::{EncapsulatedContentInfo, SignerIdentifier, SignerInfo, SignerInfos},
+    };
+    use der::asn1::SetOfVec;
+    use rsa::{
+        pkcs1v15::SigningKey,
+        pkcs8::{DecodePrivateKey, EncodePublicKey},
+        signature::{SignatureEncoding, Signer},
+        RsaPrivateKey,
+    };
+    use x509_cert::{attr::Attribute, ext::pkix::SubjectKeyIdentifier};
+
+    use super::*;
+
+    const GEN_TIME: &str = "20230901123045.5Z";
+
+    // The object identifier of the CMS id-data content type.
+    const ID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+
+    struct AcceptAll;
+
+    impl TokenVerifier for AcceptAll {
+        fn verify(&self, _token: &[u8]) -> Result<(), TimestampError> {
+            Ok(())
+        }
+    }
+
+    fn tsa_key() -> RsaPrivateKey {
+        RsaPrivateKey::from_pkcs8_pem(include_str!("../methods/guest/src/bin/rsa2048-priv.pem"))
+            .unwrap()
+    }
+
+    fn tst_info_with_time(digest: &[u8], nonce: u64, gen_time: &str) -> Vec<u8> {
+        TstInfo {
+            version: 1,
+            policy: ObjectIdentifier::new_unwrap("1.2.3"),
+            message_imprint: MessageImprint {
+                hash_algorithm: sha256_algorithm(),
+                hashed_message: OctetString::new(digest).unwrap(),
+            },
+            serial_number: Int::new(&[7]).unwrap(),
+            gen_time: Any::new(Tag::GeneralizedTime, gen_time.as_bytes()).unwrap(),
+            accuracy: None,
+            ordering: false,
+            nonce: Some(Int::new(&nonce.to_der().unwrap()[2..]).unwrap()),
+            tsa: None,
+            extensions: None,
+        }
+        .to_der()
+        .unwrap()
+    }
+
+    fn tst_info(digest: &[u8], nonce: u64) -> Vec<u8> {
+        tst_info_with_time(digest, nonce, GEN_TIME)
+    }
+
+    // The parts of a token that tests vary.
+    struct TokenSpec<'a> {
+        tst_info: &'a [u8],
+        // The bytes whose digest is the messageDigest attribute.
+        signed: &'a [u8],
+        // The value of the contentType attribute.
+        content_type: ObjectIdentifier,
+        // The content type of the outer ContentInfo.
+        outer_content_type: ObjectIdentifier,
+    }
+
+    impl<'a> TokenSpec<'a> {
+        fn new(tst_info: &'a [u8]) -> Self {
+            Self {
+                tst_info,
+                signed: tst_info,
+                content_type: ID_CT_TST_INFO,
+                outer_content_type: ID_SIGNED_DATA,
+            }
+        }
+
+        // Build the token, signed by the given key.
+        fn build(&self, key: &RsaPrivateKey) -> Vec<u8> {
+            let attribute = |oid: ObjectIdentifier, value: Any| Attribute {
+                oid,
+                values: SetOfVec::try_from(vec![value]).unwrap(),
+            };
+            let signed_attrs: SignedAttributes = SetOfVec::try_from(vec![
+                attribute(
+                    ID_CONTENT_TYPE,
+                    Any::encode_from(&self.content_type).unwrap(),
+                ),
+                attribute(
+                    ID_MESSAGE_DIGEST,
+                    Any::encode_from(
+                        &OctetString::new(Impl::hash_bytes(self.signed).as_bytes()).unwrap(),
+                    )
+                    .unwrap(),
+                ),
+            ])
+            .unwrap();
+            let signature = SigningKey::<RsaSha256>::new(key.clone())
+                .sign(&signed_attrs.to_der().unwrap())
+                .to_vec();
+            let signer_info = SignerInfo {
+                version: CmsVersion::V3,
+                sid: SignerIdentifier::SubjectKeyIdentifier(SubjectKeyIdentifier(
+                    OctetString::new(vec![1]).unwrap(),
+                )),
+                digest_alg: sha256_algorithm(),
+                signed_attrs: Some(signed_attrs),
+                signature_algorithm: AlgorithmIdentifierOwned {
+                    oid: RSA_ENCRYPTION,
+                    parameters: Some(Any::null()),
+                },
+                signature: OctetString::new(signature).unwrap(),
+                unsigned_attrs: None,
+            };
+            let signed_data = SignedData {
+                version: CmsVersion::V3,
+                digest_algorithms: SetOfVec::try_from(vec![sha256_algorithm()]).unwrap(),
+                encap_content_info: EncapsulatedContentInfo {
+                    econtent_type: ID_CT_TST_INFO,
+                    econtent: Some(
+                        Any::encode_from(&OctetString::new(self.tst_info).unwrap()).unwrap(),
+                    ),
+                },
+                certificates: None,
+                crls: None,
+                signer_infos: SignerInfos(SetOfVec::try_from(vec![signer_info]).unwrap()),
+            };
+            ContentInfo {
+                content_type: self.outer_content_type,
+                content: Any::encode_from(&signed_data).unwrap(),
+            }
+            .to_der()
+            .unwrap()
+        }
+    }
+
+    fn token(tst_info: &[u8], signed: &[u8], key: &RsaPrivateKey) -> Vec<u8> {
+        TokenSpec {
+            signed,
+            ..TokenSpec::new(tst_info)
+        }
+        .build(key)
+    }
+
+    // Build a TimeStampResp granting the given token.
+    fn response(token: &[u8]) -> Vec<u8> {
+        TimeStampResp {
+            status: PkiStatusInfo {
+                status: 0,
+                status_string: None,
+                fail_info: None,
+            },
+            time_stamp_token: Some(ContentInfo::from_der(token).unwrap()),
+        }
+        .to_der()
+        .unwrap()
+    }
+
+    fn verifier(key: &RsaPrivateKey) -> RsaTokenVerifier {
+        let spki = key.to_public_key().to_public_key_der().unwrap();
+        RsaTokenVerifier::from_public_keys_der(&[spki.as_bytes()]).unwrap()
+    }
+
+    #[test]
+    fn timestamp_token() {
+        let key = tsa_key();
+        let digest = *Impl::hash_bytes(b"claim");
+        let request = TimeStampReq::from_der(&timestamp_request(&digest, 0x80)).unwrap();
+        assert_eq!(request.nonce, Some(0x80));
+        assert!(request.cert_req);
+
+        let info = tst_info(digest.as_bytes(), 0x80);
+        let token = token_from_response(&response(&token(&info, &info, &key))).unwrap();
+        let time = verify_token(&token, &digest, &verifier(&key)).unwrap();
+        assert_eq!(time, UNIX_EPOCH + Duration::new(1693571445, 500_000_000));
+
+        let other = *Impl::hash_bytes(b"other");
+        assert_eq!(
+            verify_token(&token, &other, &AcceptAll).unwrap_err(),
+            TimestampError::DigestMismatch
+        );
+
+        // The token answers only the request with its digest and nonce.
+        check_token_answers(&token, &digest, 0x80).unwrap();
+        assert_eq!(
+            check_token_answers(&token, &digest, 0x81).unwrap_err(),
+            TimestampError::NonceMismatch
+        );
+        assert_eq!(
+            check_token_answers(&token, &other, 0x80).unwrap_err(),
+            TimestampError::DigestMismatch
+        );
+    }
+
+    #[test]
+    fn rsa_token_verifier() {
+        let key = tsa_key();
+        let digest = *Impl::hash_bytes(b"claim");
+        let info = tst_info(digest.as_bytes(), 1);
+        let token = token(&info, &info, &key);
+        verifier(&key).verify(&token).unwrap();
+
+        // A verifier that does not trust the key rejects the token.
+        let untrusted = RsaTokenVerifier::from_public_keys_der(&[]).unwrap();
+        assert_eq!(
+            untrusted.verify(&token).unwrap_err(),
+            TimestampError::UntrustedToken
+        );
+
+        // So does a verifier of a token with a flipped signature bit.
+        let mut forged = token.clone();
+        *forged.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verifier(&key).verify(&forged).unwrap_err(),
+            TimestampError::UntrustedToken
+        );
+
+        // And of a signed token holding another TSTInfo.
+        let other = tst_info(Impl::hash_bytes(b"other").as_bytes(), 1);
+        assert_eq!(
+            verifier(&key)
+                .verify(&token(&other, &info, &key))
+                .unwrap_err(),
+            TimestampError::UntrustedToken
+        );
+    }
+
+    #[test]
+    fn content_types() {
+        let key = tsa_key();
+        let info = tst_info(Impl::hash_bytes(b"claim").as_bytes(), 1);
+
+        // The outer ContentInfo must hold a SignedData.
+        let token = TokenSpec {
+            outer_content_type: ID_CT_TST_INFO,
+            ..TokenSpec::new(&info)
+        }
+        .build(&key);
+        assert!(matches!(
+            verifier(&key).verify(&token),
+            Err(TimestampError::Malformed(_))
+        ));
+
+        // The signed attributes must be for a TSTInfo, even if signed.
+        let token = TokenSpec {
+            content_type: ID_DATA,
+            ..TokenSpec::new(&info)
+        }
+        .build(&key);
+        assert!(matches!(
+            verifier(&key).verify(&token),
+            Err(TimestampError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn generalized_time() {
+        let parse = |time: &str| parse_generalized_time(time.as_bytes());
+        assert_eq!(parse("19700101000000Z").unwrap(), UNIX_EPOCH);
+        assert_eq!(
+            parse("20240229235959Z").unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1709251199)
+        );
+        for time in [
+            "20231301000000Z",
+            "20230001000000Z",
+            "20230100000000Z",
+            "20230132000000Z",
+            "20230229000000Z",
+            "20230431000000Z",
+            "20230101240000Z",
+            "20230101006000Z",
+            "20230101000060Z",
+            "20230101000000",
+            "20230101000000.Z1",
+        ] {
+            assert!(parse(time).is_err(), "{time}");
+        }
+
+        // The genTime of a token is checked as well.
+        let digest = *Impl::hash_bytes(b"claim");
+        let info = tst_info_with_time(digest.as_bytes(), 1, "20231332000000Z");
+        let token = token(&info, &info, &tsa_key());
+        assert!(matches!(
+            verify_token(&token, &digest, &AcceptAll),
+            Err(TimestampError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,23 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runs the `#[risc0_test]` functions of the `guest_tests` guest.
+
+use std::process::ExitCode;
+
+use risc0_zkvm_methods::GUEST_TESTS_ELF;
+
+fn main() -> ExitCode {
+    risc0_zkvm::testing::main(GUEST_TESTS_ELF)
+}
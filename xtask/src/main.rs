@@ -13,8 +13,9 @@
 // limitations under the License.
 
 use clap::{Parser, Subcommand};
-use risc0_zkvm::{prove::default_prover, ExecutorEnv};
-use risc0_zkvm_methods::{FIB_ELF, FIB_ID};
+use risc0_zkvm::prove::default_prover;
+use risc0_zkvm_methods::FIB_ELF;
+use risc0_zkvm_receipts::generate::Generator;
 use semver::Version;
 use which::which;
 use xshell::{cmd, Shell};
@@ -29,6 +30,12 @@ struct Cli {
 enum Commands {
     Install,
     GenReceipt,
+    /// Build the wasm verifier for release and enforce a size budget.
+    WasmRelease {
+        /// Maximum size of the optimized wasm artifact, in KiB.
+        #[arg(long, default_value_t = 400)]
+        budget_kb: u64,
+    },
 }
 
 impl Commands {
@@ -36,6 +43,7 @@ impl Commands {
         match self {
             Commands::Install => self.cmd_install(),
             Commands::GenReceipt => self.cmd_gen_receipt(),
+            Commands::WasmRelease { budget_kb } => self.cmd_wasm_release(*budget_kb),
         }
     }
 
@@ -45,22 +53,60 @@ impl Commands {
     }
 
     fn cmd_gen_receipt(&self) {
-        let iterations = 100;
-        let env = ExecutorEnv::builder()
-            .add_input(&[iterations])
-            .build()
+        let iterations: u32 = 100;
+        Generator::new()
+            .add("FIB", FIB_ELF, &[iterations])
+            .write(
+                default_prover().as_ref(),
+                "risc0/zkvm/receipts/src/receipts.rs",
+            )
             .unwrap();
-        let receipt = default_prover().prove_elf(env, FIB_ELF).unwrap();
-        let receipt_bytes = bincode::serialize(&receipt).unwrap();
+    }
+
+    fn cmd_wasm_release(&self, budget_kb: u64) {
+        const CRATE: &str = "browser_verify";
+        const TARGET: &str = "wasm32-unknown-unknown";
+        const PROFILE: &str = "wasm-release";
+
+        let sh = Shell::new().unwrap();
+        let root = sh.current_dir();
+        let root = root.display().to_string();
+
+        // Remap absolute paths out of the artifact so that the build output does
+        // not depend on where the repository or cargo home are checked out.
+        let cargo_home = std::env::var("CARGO_HOME")
+            .unwrap_or_else(|_| format!("{}/.cargo", std::env::var("HOME").unwrap()));
+        let rustflags =
+            format!("--remap-path-prefix={root}=. --remap-path-prefix={cargo_home}=/cargo");
 
-        let rust_code = format!(
-            r##"
-pub const FIB_ID: [u32; 8] = {FIB_ID:?};
-pub const FIB_RECEIPT: &[u8] = &{receipt_bytes:?};
-"##
-        );
+        cmd!(
+            sh,
+            "cargo build --manifest-path examples/Cargo.toml -p browser-verify --locked --target {TARGET} --profile {PROFILE}"
+        )
+        .env("RUSTFLAGS", rustflags)
+        .env("SOURCE_DATE_EPOCH", "0")
+        .run()
+        .unwrap();
 
-        std::fs::write("risc0/zkvm/receipts/src/receipts.rs", rust_code).unwrap();
+        let input = format!("examples/target/{TARGET}/{PROFILE}/{CRATE}.wasm");
+        let output = format!("examples/target/{TARGET}/{PROFILE}/{CRATE}.opt.wasm");
+        cmd!(
+            sh,
+            "wasm-opt -Oz --strip-debug --strip-producers {input} -o {output}"
+        )
+        .run()
+        .unwrap();
+
+        let size = std::fs::metadata(&output).unwrap().len();
+        let budget = budget_kb * 1024;
+        println!("{output}: {size} bytes (budget: {budget} bytes)");
+        if size > budget {
+            eprintln!(
+                "error: wasm artifact exceeds size budget by {} bytes",
+                size - budget
+            );
+            std::process::exit(1);
+        }
     }
 }
 
@@ -85,10 +131,13 @@ fn install_solc() {
 }
 
 fn install_wasm_tools() {
+    let sh = Shell::new().unwrap();
     if which("wask-pack").is_err() {
-        let sh = Shell::new().unwrap();
         cmd!(sh, "cargo install --locked wasm-pack").run().unwrap();
     }
+    if which("wasm-opt").is_err() {
+        cmd!(sh, "cargo install --locked wasm-opt").run().unwrap();
+    }
 }
 
 fn main() {